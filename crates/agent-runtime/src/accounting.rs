@@ -0,0 +1,195 @@
+//! Token and cost accounting.
+//!
+//! Every provider call costs money; sessions record how much. A
+//! [`UsageEvent`] captures one call's token counts with the cost computed
+//! from the parser's pricing table at record time (prices drift, so the
+//! number is locked in when the call happens, not when the report runs).
+//! [`SessionStore::spend_report`] aggregates the stored events per
+//! session, per prompt name, and per day.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AgentError;
+use crate::provider::Usage;
+use crate::session::SessionStore;
+
+/// One provider call's recorded usage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEvent {
+    /// The resolved `provider/model` the call went to.
+    pub client: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// USD, computed from the pricing table when recorded. `None` when the
+    /// model has no pricing entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Unix seconds.
+    pub at: i64,
+}
+
+impl UsageEvent {
+    /// Record one call made now, pricing it via
+    /// [`pricing_for`](prompt_parser::pricing_for).
+    pub fn record(client: &str, usage: Usage) -> Self {
+        let cost_usd = prompt_parser::pricing_for(client).map(|p| {
+            usage.input_tokens as f64 / 1_000_000.0 * p.input_per_mtok
+                + usage.output_tokens as f64 / 1_000_000.0 * p.output_per_mtok
+        });
+        UsageEvent {
+            client: client.to_string(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost_usd,
+            at: crate::session::now_unix(),
+        }
+    }
+}
+
+/// Summed usage over some grouping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Sum of the priced events; unpriced events count tokens but no cost.
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, event: &UsageEvent) {
+        self.calls += 1;
+        self.input_tokens += event.input_tokens;
+        self.output_tokens += event.output_tokens;
+        self.cost_usd += event.cost_usd.unwrap_or_default();
+    }
+}
+
+/// Spend aggregated across every stored session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SpendReport {
+    pub total: UsageTotals,
+    pub by_session: BTreeMap<String, UsageTotals>,
+    pub by_prompt: BTreeMap<String, UsageTotals>,
+    /// Keyed `YYYY-MM-DD` (UTC).
+    pub by_day: BTreeMap<String, UsageTotals>,
+}
+
+/// `YYYY-MM-DD` (UTC) for a unix timestamp. Same civil-date math the
+/// parser's clock uses.
+fn day_of(unix: i64) -> String {
+    let days = unix.div_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let year = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = if month <= 2 { year + 1 } else { year };
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+impl SessionStore {
+    /// Aggregate every stored session's usage events. Sessions that fail
+    /// to load are skipped — a corrupt document shouldn't hide the rest of
+    /// the spend.
+    pub fn spend_report(&self) -> Result<SpendReport, AgentError> {
+        let mut report = SpendReport::default();
+        for id in self.list()? {
+            let Ok(session) = self.load(&id) else {
+                continue;
+            };
+            for event in &session.usage {
+                report.total.add(event);
+                report.by_session.entry(id.clone()).or_default().add(event);
+                report
+                    .by_prompt
+                    .entry(session.prompt_name.clone())
+                    .or_default()
+                    .add(event);
+                report.by_day.entry(day_of(event.at)).or_default().add(event);
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn usage(input: u64, output: u64) -> Usage {
+        Usage {
+            input_tokens: input,
+            output_tokens: output,
+        }
+    }
+
+    #[test]
+    fn events_price_from_the_table_at_record_time() {
+        // claude-sonnet-4: $3/M in, $15/M out.
+        let event = UsageEvent::record("anthropic/claude-sonnet-4", usage(1_000_000, 200_000));
+        assert_eq!(event.cost_usd, Some(3.0 + 3.0));
+        let unpriced = UsageEvent::record("local/llama-3", usage(5, 5));
+        assert_eq!(unpriced.cost_usd, None);
+    }
+
+    #[test]
+    fn reports_aggregate_per_session_prompt_and_day() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-spend-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = SessionStore::open(dir).unwrap();
+
+        let mut a = store.create("triage", json!({})).unwrap();
+        a.record_usage(UsageEvent {
+            client: "anthropic/claude-sonnet-4".into(),
+            input_tokens: 100,
+            output_tokens: 50,
+            cost_usd: Some(0.01),
+            at: 86_400, // 1970-01-02
+        });
+        a.record_usage(UsageEvent {
+            client: "anthropic/claude-sonnet-4".into(),
+            input_tokens: 30,
+            output_tokens: 10,
+            cost_usd: None,
+            at: 86_400,
+        });
+        store.save(&a).unwrap();
+        let mut b = store.create("review", json!({})).unwrap();
+        b.record_usage(UsageEvent {
+            client: "openai/gpt-4o".into(),
+            input_tokens: 10,
+            output_tokens: 5,
+            cost_usd: Some(0.002),
+            at: 200_000, // 1970-01-03
+        });
+        store.save(&b).unwrap();
+
+        let report = store.spend_report().unwrap();
+        assert_eq!(report.total.calls, 3);
+        assert_eq!(report.total.input_tokens, 140);
+        assert_eq!(report.total.cost_usd, 0.012);
+        assert_eq!(report.by_prompt["triage"].calls, 2);
+        assert_eq!(report.by_session[&a.id].output_tokens, 60);
+        assert_eq!(report.by_day["1970-01-02"].calls, 2);
+        assert_eq!(report.by_day["1970-01-03"].cost_usd, 0.002);
+    }
+
+    #[test]
+    fn day_bucketing_handles_negative_and_leap_dates() {
+        assert_eq!(day_of(0), "1970-01-01");
+        assert_eq!(day_of(-1), "1969-12-31");
+        assert_eq!(day_of(951_782_400), "2000-02-29");
+    }
+}
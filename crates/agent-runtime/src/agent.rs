@@ -0,0 +1,546 @@
+//! The agent loop.
+//!
+//! A `type: agent` prompt doesn't end at the first response: the model
+//! calls tools, reads their results, and keeps going. [`run_agent`] drives
+//! that loop — render, complete, dispatch, append, repeat — up to
+//! `max_turns`, and returns an [`AgentRun`] recording every turn and tool
+//! call plus the final output, validated against the prompt's `output`
+//! schema the same way a single-shot response would be.
+
+use serde_json::Value;
+
+use prompt_parser::{Message, OutputMode, PromptDefinition, PromptError};
+
+use crate::error::AgentError;
+use crate::provider::{Provider, ProviderRequest, StopReason, Usage};
+use crate::runner::ToolRunner;
+use crate::session::ToolCallRecord;
+
+/// When no `max_turns` is declared, stop the loop here rather than letting
+/// a tool-happy model run forever.
+const DEFAULT_MAX_TURNS: u32 = 10;
+
+/// How a run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// The model finished on its own.
+    Completed,
+    /// The loop hit `max_turns` with the model still asking for tools.
+    MaxTurnsExhausted,
+    /// A [`RunLimits`] budget ran out mid-run. The [`AgentRun`] still
+    /// carries every turn and tool call up to the abort.
+    BudgetExceeded(LimitKind),
+}
+
+/// Which budget ran out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    Tokens,
+    Cost,
+    WallClock,
+    ToolCalls,
+}
+
+/// Hard budgets checked inside the loop; `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RunLimits {
+    /// Input plus output tokens, summed over the run.
+    pub max_tokens: Option<u64>,
+    /// Estimated spend in dollars, priced per the model's pricing table;
+    /// models without pricing never trip this limit.
+    pub max_cost_usd: Option<f64>,
+    pub max_duration: Option<std::time::Duration>,
+    /// Tool calls dispatched, summed over the run.
+    pub max_tool_calls: Option<u32>,
+}
+
+impl RunLimits {
+    /// The first exceeded limit, if any.
+    fn exceeded(
+        &self,
+        usage: Usage,
+        cost_usd: f64,
+        elapsed: std::time::Duration,
+        tool_calls: u32,
+    ) -> Option<LimitKind> {
+        if let Some(max) = self.max_tokens
+            && usage.input_tokens + usage.output_tokens > max
+        {
+            return Some(LimitKind::Tokens);
+        }
+        if let Some(max) = self.max_cost_usd
+            && cost_usd > max
+        {
+            return Some(LimitKind::Cost);
+        }
+        if let Some(max) = self.max_duration
+            && elapsed > max
+        {
+            return Some(LimitKind::WallClock);
+        }
+        if let Some(max) = self.max_tool_calls
+            && tool_calls > max
+        {
+            return Some(LimitKind::ToolCalls);
+        }
+        None
+    }
+}
+
+/// One provider round-trip and the tool calls it triggered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnRecord {
+    /// 1-based.
+    pub turn: u32,
+    /// Text the model produced this turn.
+    pub text: String,
+    pub tool_calls: Vec<ToolCallRecord>,
+}
+
+/// The structured record [`run_agent`] returns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentRun {
+    pub prompt_name: String,
+    pub turns: Vec<TurnRecord>,
+    /// The last turn's text.
+    pub final_text: String,
+    /// The final text parsed as JSON, when the prompt's `output_mode` is
+    /// `json` and an `output` schema is declared. Validated against that
+    /// schema.
+    pub output: Option<Value>,
+    /// Token usage summed over every turn.
+    pub usage: Usage,
+    pub outcome: RunOutcome,
+}
+
+/// Execute a `type: agent` prompt to completion.
+///
+/// Each iteration sends the conversation so far, dispatches any tool calls
+/// through `tools`, and appends the results as tool messages. The loop
+/// ends when the model stops asking for tools or `max_turns` is reached.
+pub fn run_agent(
+    def: &PromptDefinition,
+    data: &Value,
+    provider: &dyn Provider,
+    tools: &ToolRunner,
+) -> Result<AgentRun, AgentError> {
+    run_agent_inner(def, data, provider, tools, &RunLimits::default(), |_, _| Ok(()))
+}
+
+/// [`run_agent`] under hard budgets. When a budget runs out the run ends
+/// with [`RunOutcome::BudgetExceeded`] and everything done so far — not
+/// an error, so callers keep the partial work.
+pub fn run_agent_limited(
+    def: &PromptDefinition,
+    data: &Value,
+    provider: &dyn Provider,
+    tools: &ToolRunner,
+    limits: &RunLimits,
+) -> Result<AgentRun, AgentError> {
+    run_agent_inner(def, data, provider, tools, limits, |_, _| Ok(()))
+}
+
+/// [`run_agent`] with jj checkpointing: before each write-class tool call
+/// the working copy is snapshotted and the checkpoint recorded on
+/// `session`, so [`Checkpointer::rollback_to_turn`](crate::checkpoint::Checkpointer::rollback_to_turn)
+/// can undo the run turn by turn.
+pub fn run_agent_checkpointed(
+    def: &PromptDefinition,
+    data: &Value,
+    provider: &dyn Provider,
+    tools: &ToolRunner,
+    checkpointer: &crate::checkpoint::Checkpointer,
+    session: &mut crate::session::Session,
+) -> Result<AgentRun, AgentError> {
+    run_agent_inner(def, data, provider, tools, &RunLimits::default(), |turn, tool| {
+        checkpointer.before_tool(session, turn, tool).map(|_| ())
+    })
+}
+
+fn run_agent_inner(
+    def: &PromptDefinition,
+    data: &Value,
+    provider: &dyn Provider,
+    tools: &ToolRunner,
+    limits: &RunLimits,
+    mut before_tool: impl FnMut(u32, &str) -> Result<(), AgentError>,
+) -> Result<AgentRun, AgentError> {
+    if def.prompt_type.as_deref() != Some("agent") {
+        return Err(AgentError::Prompt(PromptError::Frontmatter(format!(
+            "prompt `{}` is not `type: agent`",
+            def.name
+        ))));
+    }
+    let mut request = ProviderRequest::from_definition(def, data)?;
+    let declared: Vec<String> = request.tools.iter().map(|t| t.name.clone()).collect();
+    // Swap the parser's name-only specs for the runner's real schemas; this
+    // also fails fast on a declared tool nothing can execute.
+    request.tools = tools.specs(&declared)?;
+
+    let max_turns = def.max_turns.unwrap_or(DEFAULT_MAX_TURNS);
+    let pricing = prompt_parser::pricing_for(&request.client.to_string());
+    let started = std::time::Instant::now();
+    let mut turns = Vec::new();
+    let mut usage = Usage::default();
+    let mut total_tool_calls: u32 = 0;
+    let mut outcome = RunOutcome::MaxTurnsExhausted;
+
+    for turn in 1..=max_turns {
+        let response = provider.complete(&request)?;
+        usage.input_tokens += response.usage.input_tokens;
+        usage.output_tokens += response.usage.output_tokens;
+        request.messages.push(Message {
+            role: "assistant".to_string(),
+            content: response.text.clone(),
+            attachments: Vec::new(),
+        });
+
+        let mut tool_calls = Vec::with_capacity(response.tool_calls.len());
+        for call in &response.tool_calls {
+            before_tool(turn, &call.name)?;
+            let result = tools.dispatch(call)?;
+            tool_calls.push(ToolCallRecord {
+                turn,
+                tool: call.name.clone(),
+                arguments: call.arguments.clone(),
+                result: result.result.clone(),
+            });
+            request.messages.push(result.into_message());
+        }
+        total_tool_calls += response.tool_calls.len() as u32;
+        let done = response.tool_calls.is_empty() && response.stop_reason != StopReason::ToolUse;
+        turns.push(TurnRecord {
+            turn,
+            text: response.text,
+            tool_calls,
+        });
+        if done {
+            outcome = RunOutcome::Completed;
+            break;
+        }
+        let cost_usd = pricing.map_or(0.0, |p| {
+            usage.input_tokens as f64 / 1_000_000.0 * p.input_per_mtok
+                + usage.output_tokens as f64 / 1_000_000.0 * p.output_per_mtok
+        });
+        if let Some(limit) = limits.exceeded(usage, cost_usd, started.elapsed(), total_tool_calls) {
+            outcome = RunOutcome::BudgetExceeded(limit);
+            break;
+        }
+    }
+
+    let final_text = turns.last().map(|t| t.text.clone()).unwrap_or_default();
+    // A budget abort returns whatever the model had; only completed runs
+    // owe valid structured output.
+    let validate_output = !matches!(outcome, RunOutcome::BudgetExceeded(_));
+    let output = if validate_output
+        && def.effective_output_mode() == OutputMode::Json
+        && def.output.is_some()
+    {
+        let value: Value = serde_json::from_str(&final_text).map_err(|e| {
+            AgentError::Prompt(PromptError::Frontmatter(format!(
+                "agent output is not valid JSON: {e}"
+            )))
+        })?;
+        def.validate_output(&value)?;
+        Some(value)
+    } else {
+        None
+    };
+
+    Ok(AgentRun {
+        prompt_name: def.name.clone(),
+        turns,
+        final_text,
+        output,
+        usage,
+        outcome,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{ProviderResponse, ToolCallRequest};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    /// Plays back scripted responses, recording what it was asked.
+    struct ScriptedProvider {
+        responses: RefCell<VecDeque<ProviderResponse>>,
+        requests: RefCell<Vec<ProviderRequest>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(responses: Vec<ProviderResponse>) -> Self {
+            ScriptedProvider {
+                responses: RefCell::new(responses.into()),
+                requests: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl Provider for ScriptedProvider {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn complete(&self, request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+            self.requests.borrow_mut().push(request.clone());
+            self.responses.borrow_mut().pop_front().ok_or_else(|| {
+                AgentError::Provider {
+                    provider: "scripted".to_string(),
+                    message: "script exhausted".to_string(),
+                }
+            })
+        }
+
+        fn complete_stream(
+            &self,
+            request: &ProviderRequest,
+            _on_event: &mut dyn FnMut(&crate::stream::StreamEvent),
+        ) -> Result<ProviderResponse, AgentError> {
+            self.complete(request)
+        }
+    }
+
+    fn text_response(text: &str) -> ProviderResponse {
+        ProviderResponse {
+            text: text.to_string(),
+            tool_calls: Vec::new(),
+            usage: Usage {
+                input_tokens: 10,
+                output_tokens: 5,
+            },
+            stop_reason: StopReason::EndTurn,
+        }
+    }
+
+    fn tool_response(name: &str, arguments: Value) -> ProviderResponse {
+        ProviderResponse {
+            text: String::new(),
+            tool_calls: vec![ToolCallRequest {
+                id: "call_1".into(),
+                name: name.into(),
+                arguments,
+            }],
+            usage: Usage {
+                input_tokens: 10,
+                output_tokens: 5,
+            },
+            stop_reason: StopReason::ToolUse,
+        }
+    }
+
+    fn tools() -> ToolRunner {
+        let mut runner = ToolRunner::new();
+        runner
+            .register(
+                "lookup",
+                Some("Look a thing up"),
+                json!({ "type": "object" }),
+                |args| Ok(json!(format!("found {}", args["q"].as_str().unwrap_or("?")))),
+            )
+            .unwrap();
+        runner
+    }
+
+    fn agent_def() -> PromptDefinition {
+        PromptDefinition::parse(
+            "---\n\
+             name: researcher\n\
+             type: agent\n\
+             client: anthropic/claude-sonnet-4\n\
+             max_turns: 3\n\
+             tools:\n\
+             \x20 - lookup\n\
+             output:\n\
+             \x20 type: object\n\
+             \x20 properties:\n\
+             \x20   answer: { type: string }\n\
+             \x20 required: [answer]\n\
+             ---\n\
+             Research {{ topic }}.",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn the_loop_dispatches_tools_and_validates_final_output() {
+        let provider = ScriptedProvider::new(vec![
+            tool_response("lookup", json!({ "q": "zig" })),
+            text_response("{\"answer\":\"found zig\"}"),
+        ]);
+        let run = run_agent(&agent_def(), &json!({ "topic": "zig" }), &provider, &tools()).unwrap();
+
+        assert_eq!(run.outcome, RunOutcome::Completed);
+        assert_eq!(run.turns.len(), 2);
+        assert_eq!(run.turns[0].tool_calls[0].tool, "lookup");
+        assert_eq!(run.turns[0].tool_calls[0].result, json!("found zig"));
+        assert_eq!(run.output, Some(json!({ "answer": "found zig" })));
+        assert_eq!(run.usage.input_tokens, 20);
+
+        // The second request must carry the tool result back.
+        let requests = provider.requests.borrow();
+        assert_eq!(requests[1].messages.last().unwrap().role, "tool");
+        assert_eq!(requests[1].messages.last().unwrap().content, "found zig");
+        // And the runner's real schema replaced the name-only spec.
+        assert_eq!(requests[0].tools[0].parameters, json!({ "type": "object" }));
+    }
+
+    #[test]
+    fn max_turns_caps_a_model_that_keeps_asking_for_tools() {
+        let provider = ScriptedProvider::new(vec![
+            tool_response("lookup", json!({ "q": "a" })),
+            tool_response("lookup", json!({ "q": "b" })),
+            tool_response("lookup", json!({ "q": "c" })),
+            tool_response("lookup", json!({ "q": "d" })),
+        ]);
+        let mut def = agent_def();
+        def.output = None; // empty tool-turn text is not JSON
+        let run = run_agent(&def, &json!({ "topic": "x" }), &provider, &tools()).unwrap();
+        assert_eq!(run.outcome, RunOutcome::MaxTurnsExhausted);
+        assert_eq!(run.turns.len(), 3);
+    }
+
+    #[test]
+    fn budgets_abort_the_loop_and_keep_the_partial_run() {
+        let responses = || {
+            vec![
+                tool_response("lookup", json!({ "q": "a" })),
+                tool_response("lookup", json!({ "q": "b" })),
+                tool_response("lookup", json!({ "q": "c" })),
+            ]
+        };
+        let data = json!({ "topic": "x" });
+
+        // One tool call allowed: the second turn trips the limit.
+        let limits = RunLimits {
+            max_tool_calls: Some(1),
+            ..RunLimits::default()
+        };
+        let run = run_agent_limited(
+            &agent_def(),
+            &data,
+            &ScriptedProvider::new(responses()),
+            &tools(),
+            &limits,
+        )
+        .unwrap();
+        assert_eq!(run.outcome, RunOutcome::BudgetExceeded(LimitKind::ToolCalls));
+        assert_eq!(run.turns.len(), 2);
+        // The partial work survives; output validation is not attempted.
+        assert_eq!(run.output, None);
+        assert_eq!(run.turns[0].tool_calls[0].result, json!("found a"));
+
+        // Each scripted turn costs 15 tokens.
+        let limits = RunLimits {
+            max_tokens: Some(20),
+            ..RunLimits::default()
+        };
+        let run = run_agent_limited(
+            &agent_def(),
+            &data,
+            &ScriptedProvider::new(responses()),
+            &tools(),
+            &limits,
+        )
+        .unwrap();
+        assert_eq!(run.outcome, RunOutcome::BudgetExceeded(LimitKind::Tokens));
+
+        // A zero wall-clock budget stops after the first turn.
+        let limits = RunLimits {
+            max_duration: Some(std::time::Duration::ZERO),
+            ..RunLimits::default()
+        };
+        let run = run_agent_limited(
+            &agent_def(),
+            &data,
+            &ScriptedProvider::new(responses()),
+            &tools(),
+            &limits,
+        )
+        .unwrap();
+        assert_eq!(run.outcome, RunOutcome::BudgetExceeded(LimitKind::WallClock));
+        assert_eq!(run.turns.len(), 1);
+    }
+
+    #[test]
+    fn a_model_that_finishes_within_budget_is_unaffected() {
+        let provider = ScriptedProvider::new(vec![
+            tool_response("lookup", json!({ "q": "zig" })),
+            text_response("{\"answer\":\"found zig\"}"),
+        ]);
+        let limits = RunLimits {
+            max_tool_calls: Some(5),
+            max_tokens: Some(1000),
+            ..RunLimits::default()
+        };
+        let run = run_agent_limited(&agent_def(), &json!({ "topic": "zig" }), &provider, &tools(), &limits)
+            .unwrap();
+        assert_eq!(run.outcome, RunOutcome::Completed);
+        assert_eq!(run.output, Some(json!({ "answer": "found zig" })));
+    }
+
+    #[test]
+    fn output_failing_the_schema_is_an_error() {
+        let provider = ScriptedProvider::new(vec![text_response("{\"wrong\":1}")]);
+        let err =
+            run_agent(&agent_def(), &json!({ "topic": "x" }), &provider, &tools()).unwrap_err();
+        assert!(err.to_string().contains("answer"), "{err}");
+    }
+
+    #[test]
+    fn checkpointed_runs_snapshot_before_write_tools() {
+        use crate::checkpoint::{Checkpointer, WorkspaceVcs};
+        struct CountingVcs;
+        impl WorkspaceVcs for CountingVcs {
+            fn snapshot(&self, tag: &str) -> Result<String, AgentError> {
+                Ok(format!("op-{tag}"))
+            }
+            fn restore(&self, _snapshot_id: &str) -> Result<(), AgentError> {
+                Ok(())
+            }
+        }
+
+        let provider = ScriptedProvider::new(vec![
+            tool_response("lookup", json!({ "q": "zig" })),
+            text_response("{\"answer\":\"found zig\"}"),
+        ]);
+        let checkpointer = Checkpointer::new(CountingVcs, ["lookup"]);
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-agent-ckpt-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut session = crate::session::SessionStore::open(dir)
+            .unwrap()
+            .create("researcher", json!({}))
+            .unwrap();
+
+        run_agent_checkpointed(
+            &agent_def(),
+            &json!({ "topic": "zig" }),
+            &provider,
+            &tools(),
+            &checkpointer,
+            &mut session,
+        )
+        .unwrap();
+        assert_eq!(session.checkpoints.len(), 1);
+        assert_eq!(session.checkpoints[0].turn, 1);
+        assert_eq!(session.checkpoints[0].tool, "lookup");
+    }
+
+    #[test]
+    fn non_agent_prompts_are_rejected() {
+        let def = PromptDefinition::parse(
+            "---\nname: x\nclient: anthropic/claude-sonnet-4\n---\nhi",
+        )
+        .unwrap();
+        let provider = ScriptedProvider::new(vec![]);
+        let err = run_agent(&def, &json!({}), &provider, &tools()).unwrap_err();
+        assert!(err.to_string().contains("not `type: agent`"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Repository activity analytics.
+//!
+//! The insights dashboard wants "who commits, when, and where" — not a
+//! raw log to fold over in the frontend. [`analyze`] turns structured
+//! commit rows into exactly that: contributor totals, commit counts per
+//! time bucket, and per-directory churn, all serializable straight into
+//! the HTTP layer. [`JjCli::activity`] produces the rows for a revset
+//! via one `jj log` template call.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::checkpoint::JjCli;
+use crate::error::AgentError;
+
+/// One commit's analytics-relevant facts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitActivity {
+    /// Author email, as jj records it.
+    pub author: String,
+    /// Commit timestamp, unix seconds.
+    pub timestamp: i64,
+    /// Paths the commit touched.
+    pub paths: Vec<String>,
+}
+
+/// Commit count for one author.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ContributorStats {
+    pub author: String,
+    pub commits: usize,
+}
+
+/// Commit count in one time bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimeBucket {
+    /// Bucket start, unix seconds (aligned to the bucket width).
+    pub start: i64,
+    pub commits: usize,
+}
+
+/// Touched-file count under one directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DirChurn {
+    /// The file's immediate parent directory; `""` for the repo root.
+    pub dir: String,
+    pub changes: usize,
+}
+
+/// Everything the dashboard draws, from one pass over the commits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActivityReport {
+    /// Busiest contributor first; ties break alphabetically.
+    pub contributors: Vec<ContributorStats>,
+    /// Non-empty buckets only, oldest first.
+    pub buckets: Vec<TimeBucket>,
+    /// Churniest directory first; ties break alphabetically.
+    pub churn: Vec<DirChurn>,
+}
+
+/// Fold `commits` into an [`ActivityReport`], bucketing timestamps by
+/// `bucket_secs` (86 400 for daily, 604 800 for weekly).
+pub fn analyze(commits: &[CommitActivity], bucket_secs: i64) -> ActivityReport {
+    let bucket_secs = bucket_secs.max(1);
+    let mut by_author: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut by_bucket: BTreeMap<i64, usize> = BTreeMap::new();
+    let mut by_dir: BTreeMap<String, usize> = BTreeMap::new();
+    for commit in commits {
+        *by_author.entry(&commit.author).or_default() += 1;
+        let start = commit.timestamp - commit.timestamp.rem_euclid(bucket_secs);
+        *by_bucket.entry(start).or_default() += 1;
+        for path in &commit.paths {
+            let dir = path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+            *by_dir.entry(dir.to_string()).or_default() += 1;
+        }
+    }
+    let mut contributors: Vec<ContributorStats> = by_author
+        .into_iter()
+        .map(|(author, commits)| ContributorStats {
+            author: author.to_string(),
+            commits,
+        })
+        .collect();
+    contributors.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+    let buckets = by_bucket
+        .into_iter()
+        .map(|(start, commits)| TimeBucket { start, commits })
+        .collect();
+    let mut churn: Vec<DirChurn> = by_dir
+        .into_iter()
+        .map(|(dir, changes)| DirChurn { dir, changes })
+        .collect();
+    churn.sort_by(|a, b| b.changes.cmp(&a.changes).then(a.dir.cmp(&b.dir)));
+    ActivityReport {
+        contributors,
+        buckets,
+        churn,
+    }
+}
+
+/// The `-T` template behind [`JjCli::activity`]: tab-separated author,
+/// unix timestamp, then the touched paths.
+const ACTIVITY_TEMPLATE: &str = concat!(
+    r#"author.email() ++ "\t" ++ committer.timestamp().utc().format("%s") ++ "\t" ++ "#,
+    r#"diff.files().map(|f| f.path()).join("\t") ++ "\n""#,
+);
+
+impl JjCli {
+    /// One [`CommitActivity`] row per commit in `revset`.
+    pub fn activity(&self, revset: &str) -> Result<Vec<CommitActivity>, AgentError> {
+        let out = self.jj(&["log", "-r", revset, "--no-graph", "-T", ACTIVITY_TEMPLATE])?;
+        parse_activity_lines(&out)
+    }
+}
+
+/// Parse the template output: `<author>\t<unix-ts>[\t<path>…]` per line.
+pub(crate) fn parse_activity_lines(out: &str) -> Result<Vec<CommitActivity>, AgentError> {
+    out.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.split('\t');
+            let author = fields.next().unwrap_or_default().to_string();
+            let timestamp = fields
+                .next()
+                .and_then(|ts| ts.parse().ok())
+                .ok_or_else(|| AgentError::Vcs(format!("unparseable activity line: `{line}`")))?;
+            let paths = fields.filter(|p| !p.is_empty()).map(String::from).collect();
+            Ok(CommitActivity {
+                author,
+                timestamp,
+                paths,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn commit(author: &str, timestamp: i64, paths: &[&str]) -> CommitActivity {
+        CommitActivity {
+            author: author.to_string(),
+            timestamp,
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn the_report_ranks_contributors_buckets_and_directories() {
+        let commits = [
+            commit("ada@example.com", 100, &["src/lib.rs", "src/http.rs"]),
+            commit("ada@example.com", 86_500, &["src/lib.rs"]),
+            commit("grace@example.com", 86_600, &["docs/guide.md", "README.md"]),
+        ];
+        let report = analyze(&commits, 86_400);
+        assert_eq!(
+            report.contributors,
+            [
+                ContributorStats { author: "ada@example.com".into(), commits: 2 },
+                ContributorStats { author: "grace@example.com".into(), commits: 1 },
+            ]
+        );
+        assert_eq!(
+            report.buckets,
+            [
+                TimeBucket { start: 0, commits: 1 },
+                TimeBucket { start: 86_400, commits: 2 },
+            ]
+        );
+        assert_eq!(
+            report.churn,
+            [
+                DirChurn { dir: "src".into(), changes: 3 },
+                DirChurn { dir: "".into(), changes: 1 },
+                DirChurn { dir: "docs".into(), changes: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn template_lines_parse_with_and_without_paths() {
+        let rows = parse_activity_lines(
+            "ada@example.com\t100\tsrc/lib.rs\tsrc/http.rs\ngrace@example.com\t200\n",
+        )
+        .unwrap();
+        assert_eq!(rows[0], commit("ada@example.com", 100, &["src/lib.rs", "src/http.rs"]));
+        assert_eq!(rows[1], commit("grace@example.com", 200, &[]));
+
+        let err = parse_activity_lines("ada@example.com\tyesterday\n").unwrap_err();
+        assert!(err.to_string().contains("unparseable activity line"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Bearer-token auth for the HTTP API.
+//!
+//! A single-user desktop instance runs open (no auth configured, matching
+//! the local-dev posture); a multi-user deployment configures a
+//! [`TokenAuth`] table. Each token carries scopes (read/write) and the
+//! workspaces it may touch, and [`TokenAuth::authorize`] answers with the
+//! HTTP status the route should return: 401 for a missing or unknown
+//! token, 403 for a known token short on scope or workspace.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+/// What a token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Write,
+}
+
+/// One token's grant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Grant {
+    scopes: BTreeSet<Scope>,
+    /// `None` grants every workspace.
+    workspaces: Option<BTreeSet<String>>,
+}
+
+/// The token table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenAuth {
+    grants: HashMap<String, Grant>,
+}
+
+impl TokenAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grant `token` the given scopes on every workspace.
+    pub fn grant_all_workspaces(
+        mut self,
+        token: impl Into<String>,
+        scopes: impl IntoIterator<Item = Scope>,
+    ) -> Self {
+        self.grants.insert(
+            token.into(),
+            Grant {
+                scopes: scopes.into_iter().collect(),
+                workspaces: None,
+            },
+        );
+        self
+    }
+
+    /// Grant `token` the given scopes on the named workspaces only.
+    pub fn grant(
+        mut self,
+        token: impl Into<String>,
+        scopes: impl IntoIterator<Item = Scope>,
+        workspaces: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.grants.insert(
+            token.into(),
+            Grant {
+                scopes: scopes.into_iter().collect(),
+                workspaces: Some(workspaces.into_iter().map(Into::into).collect()),
+            },
+        );
+        self
+    }
+
+    /// Authorize one request. `authorization` is the raw header value
+    /// (`Bearer <token>`); errors are `(status, message)` for the route to
+    /// return.
+    pub fn authorize(
+        &self,
+        authorization: Option<&str>,
+        workspace: &str,
+        scope: Scope,
+    ) -> Result<(), (u16, String)> {
+        let token = authorization
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .ok_or((401, "missing bearer token".to_string()))?;
+        let grant = self
+            .grants
+            .get(token)
+            .ok_or((401, "unknown token".to_string()))?;
+        if !grant.scopes.contains(&scope) {
+            return Err((403, format!("token lacks the `{scope:?}` scope").to_lowercase()));
+        }
+        if let Some(workspaces) = &grant.workspaces
+            && !workspaces.contains(workspace)
+        {
+            return Err((403, format!("token has no access to workspace `{workspace}`")));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn auth() -> TokenAuth {
+        TokenAuth::new()
+            .grant_all_workspaces("admin-token", [Scope::Read, Scope::Write])
+            .grant("viewer-token", [Scope::Read], ["smithers"])
+    }
+
+    #[test]
+    fn missing_and_unknown_tokens_are_401() {
+        let auth = auth();
+        assert_eq!(auth.authorize(None, "smithers", Scope::Read).unwrap_err().0, 401);
+        assert_eq!(
+            auth.authorize(Some("Bearer bogus"), "smithers", Scope::Read).unwrap_err().0,
+            401
+        );
+        // A token sent without the Bearer prefix is equally missing.
+        assert_eq!(
+            auth.authorize(Some("viewer-token"), "smithers", Scope::Read).unwrap_err().0,
+            401
+        );
+    }
+
+    #[test]
+    fn scope_and_workspace_violations_are_403() {
+        let auth = auth();
+        let err = auth
+            .authorize(Some("Bearer viewer-token"), "smithers", Scope::Write)
+            .unwrap_err();
+        assert_eq!(err.0, 403);
+        assert!(err.1.contains("scope"));
+
+        let err = auth
+            .authorize(Some("Bearer viewer-token"), "other", Scope::Read)
+            .unwrap_err();
+        assert_eq!(err.0, 403);
+        assert!(err.1.contains("workspace `other`"));
+    }
+
+    #[test]
+    fn valid_grants_pass() {
+        let auth = auth();
+        assert!(auth.authorize(Some("Bearer viewer-token"), "smithers", Scope::Read).is_ok());
+        assert!(auth.authorize(Some("Bearer admin-token"), "anything", Scope::Write).is_ok());
+    }
+}
@@ -0,0 +1,154 @@
+//! Bounded-parallel batch reads against a workspace.
+//!
+//! Reading many files through [`WorkspaceQueries`] is strictly
+//! sequential: each call shells out, waits, parses, returns. Exporting a
+//! snapshot or hydrating a context window reads dozens of files, and the
+//! latency adds up linearly. [`fetch_files`] runs the reads across a
+//! bounded pool of threads and hands back results in request order, so
+//! callers keep the simple one-call-per-file mental model while the
+//! wall-clock cost amortizes.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::AgentError;
+use crate::http::WorkspaceQueries;
+
+/// Concurrent reads in flight, by default. High enough to hide process
+/// spawn latency, low enough not to thrash a laptop.
+pub const DEFAULT_FETCH_PARALLELISM: usize = 8;
+
+/// Run `fetch` over every request with at most `parallelism` in flight.
+/// Results come back in request order, one per request; what "failure"
+/// means is up to the result type, so one bad item never sinks the batch.
+pub fn fetch_with<R: Sync, T: Send>(
+    requests: &[R],
+    parallelism: usize,
+    fetch: impl Fn(&R) -> T + Sync,
+) -> Vec<T> {
+    let results = Mutex::new(Vec::from_iter(requests.iter().map(|_| None)));
+    let next = AtomicUsize::new(0);
+    let workers = parallelism.max(1).min(requests.len().max(1));
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(request) = requests.get(index) else {
+                        break;
+                    };
+                    let result = fetch(request);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|slot| slot.expect("every slot is filled by a worker"))
+        .collect()
+}
+
+/// Fetch `(revision, path)` pairs concurrently with at most
+/// `parallelism` reads in flight. Results come back in request order,
+/// one per pair; a failed read fails its own slot, not the batch.
+pub fn fetch_files(
+    workspace: &(impl WorkspaceQueries + Sync),
+    requests: &[(String, String)],
+    parallelism: usize,
+) -> Vec<Result<String, AgentError>> {
+    fetch_with(requests, parallelism, |(revision, path)| {
+        workspace.file(revision, path)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{BookmarkInfo, CommitInfo};
+    use pretty_assertions::assert_eq;
+
+    struct Slow {
+        peak: AtomicUsize,
+        in_flight: AtomicUsize,
+    }
+
+    impl WorkspaceQueries for Slow {
+        fn commits(&self, _limit: usize) -> Result<Vec<CommitInfo>, AgentError> {
+            Ok(Vec::new())
+        }
+
+        fn file(&self, revision: &str, path: &str) -> Result<String, AgentError> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if path == "missing" {
+                return Err(AgentError::Io {
+                    path: path.to_string(),
+                    message: "no such file".to_string(),
+                });
+            }
+            Ok(format!("{path} at {revision}"))
+        }
+
+        fn diff(&self, _from: &str, _to: &str) -> Result<String, AgentError> {
+            Ok(String::new())
+        }
+
+        fn bookmarks(&self) -> Result<Vec<BookmarkInfo>, AgentError> {
+            Ok(Vec::new())
+        }
+
+        fn status(&self) -> Result<String, AgentError> {
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn results_come_back_in_request_order_with_per_slot_errors() {
+        let workspace = Slow {
+            peak: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+        };
+        let requests: Vec<(String, String)> = ["a", "missing", "b"]
+            .iter()
+            .map(|p| ("r1".to_string(), p.to_string()))
+            .collect();
+
+        let results = fetch_files(&workspace, &requests, 2);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_deref().unwrap(), "a at r1");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_deref().unwrap(), "b at r1");
+    }
+
+    #[test]
+    fn parallelism_is_used_but_bounded() {
+        let workspace = Slow {
+            peak: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+        };
+        let requests: Vec<(String, String)> = (0..12)
+            .map(|i| ("r1".to_string(), format!("file-{i}")))
+            .collect();
+
+        fetch_files(&workspace, &requests, 4);
+        let peak = workspace.peak.load(Ordering::SeqCst);
+        assert!(peak > 1, "reads never overlapped");
+        assert!(peak <= 4, "parallelism bound exceeded: {peak}");
+    }
+
+    #[test]
+    fn an_empty_batch_is_a_no_op() {
+        let workspace = Slow {
+            peak: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+        };
+        assert!(fetch_files(&workspace, &[], 4).is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+//! Automated bisection over a commit range.
+//!
+//! The host (or an agent tool) owns running the test; this drives the
+//! search. Build a [`Bisector`] over the commits between a known-good
+//! and known-bad endpoint, then loop: [`Bisector::next_candidate`],
+//! check it out, run the test, [`Bisector::report`] the result. When
+//! `next_candidate` returns `None` the range has collapsed and
+//! [`Bisector::culprit`] names the first bad commit. Skips (commits
+//! that don't build, say) are stepped around; a culprit adjacent to
+//! skipped commits is reported with that ambiguity intact.
+
+use std::collections::BTreeSet;
+
+use crate::checkpoint::JjCli;
+use crate::error::AgentError;
+
+/// What the host's test said about one candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BisectResult {
+    Good,
+    Bad,
+    /// Untestable (broken build, flaky infra); try a neighbor instead.
+    Skip,
+}
+
+/// Binary search state over an ordered commit range.
+pub struct Bisector {
+    /// Oldest first; the first entry is known good, the last known bad.
+    commits: Vec<String>,
+    good: usize,
+    bad: usize,
+    skipped: BTreeSet<usize>,
+    /// The candidate handed out and not yet reported on.
+    pending: Option<usize>,
+}
+
+impl Bisector {
+    /// `commits` oldest first, with `commits[0]` the known-good endpoint
+    /// and the last entry the known-bad one. Fails when the range has no
+    /// two endpoints to disagree.
+    pub fn new(commits: Vec<String>) -> Result<Self, AgentError> {
+        if commits.len() < 2 {
+            return Err(AgentError::Vcs(
+                "bisection needs at least a good and a bad endpoint".to_string(),
+            ));
+        }
+        let bad = commits.len() - 1;
+        Ok(Bisector {
+            commits,
+            good: 0,
+            bad,
+            skipped: BTreeSet::new(),
+            pending: None,
+        })
+    }
+
+    /// The next commit to test, or `None` when the search is finished
+    /// (either collapsed to one suspect or everything left is skipped).
+    /// Asking again before [`Self::report`] returns the same candidate.
+    pub fn next_candidate(&mut self) -> Option<&str> {
+        if let Some(pending) = self.pending {
+            return Some(&self.commits[pending]);
+        }
+        let mid = (self.good + self.bad) / 2;
+        // The midpoint, or its nearest untested neighbor when skipped.
+        let candidate = (0..)
+            .map(|distance| [mid.saturating_sub(distance), mid + distance])
+            .take_while(|[low, high]| *low > self.good || *high < self.bad)
+            .flatten()
+            .find(|i| *i > self.good && *i < self.bad && !self.skipped.contains(i))?;
+        self.pending = Some(candidate);
+        Some(&self.commits[candidate])
+    }
+
+    /// Record the test result for the current candidate.
+    pub fn report(&mut self, result: BisectResult) -> Result<(), AgentError> {
+        let pending = self.pending.take().ok_or_else(|| {
+            AgentError::Vcs("nothing to report: call next_candidate() first".to_string())
+        })?;
+        match result {
+            BisectResult::Good => self.good = pending,
+            BisectResult::Bad => self.bad = pending,
+            BisectResult::Skip => {
+                self.skipped.insert(pending);
+            }
+        }
+        Ok(())
+    }
+
+    /// The first bad commit, once [`Self::next_candidate`] has returned
+    /// `None`. With skipped commits directly above the last good one,
+    /// this is the earliest commit *known* bad — the true culprit may be
+    /// among the skips.
+    pub fn culprit(&self) -> &str {
+        &self.commits[self.bad]
+    }
+
+    /// Commits still in the untested gap.
+    pub fn remaining(&self) -> usize {
+        (self.good + 1..self.bad)
+            .filter(|i| !self.skipped.contains(i))
+            .count()
+    }
+}
+
+impl JjCli {
+    /// A [`Bisector`] over `good::bad`, resolved through `jj log`.
+    pub fn bisect_range(&self, good: &str, bad: &str) -> Result<Bisector, AgentError> {
+        let out = self.jj(&[
+            "log",
+            "-r",
+            &format!("{good}::{bad}"),
+            "--reversed",
+            "--no-graph",
+            "-T",
+            r#"commit_id.short() ++ "\n""#,
+        ])?;
+        Bisector::new(out.lines().map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bisector(n: usize) -> Bisector {
+        Bisector::new((0..n).map(|i| format!("c{i}")).collect()).unwrap()
+    }
+
+    /// Drive a full bisection where commits at `first_bad` and later
+    /// fail, returning the culprit and how many tests it took.
+    fn hunt(n: usize, first_bad: usize) -> (String, usize) {
+        let mut bisect = bisector(n);
+        let mut tests = 0;
+        while let Some(candidate) = bisect.next_candidate() {
+            let index: usize = candidate[1..].parse().unwrap();
+            tests += 1;
+            let result = if index >= first_bad { BisectResult::Bad } else { BisectResult::Good };
+            bisect.report(result).unwrap();
+        }
+        (bisect.culprit().to_string(), tests)
+    }
+
+    #[test]
+    fn the_search_converges_on_the_first_bad_commit_in_log_steps() {
+        for first_bad in 1..16 {
+            let (culprit, tests) = hunt(16, first_bad);
+            assert_eq!(culprit, format!("c{first_bad}"), "first bad {first_bad}");
+            assert!(tests <= 4, "took {tests} tests");
+        }
+    }
+
+    #[test]
+    fn skipped_commits_are_stepped_around() {
+        let mut bisect = bisector(8);
+        // The midpoint doesn't build; the driver tries a neighbor instead.
+        assert_eq!(bisect.next_candidate(), Some("c3"));
+        bisect.report(BisectResult::Skip).unwrap();
+        let neighbor = bisect.next_candidate().unwrap().to_string();
+        assert_ne!(neighbor, "c3");
+
+        // Same candidate until it's reported on.
+        assert_eq!(bisect.next_candidate(), Some(neighbor.as_str()));
+        bisect.report(BisectResult::Good).unwrap();
+        assert!(bisect.remaining() < 6);
+    }
+
+    #[test]
+    fn degenerate_ranges_and_misuse_are_refused() {
+        assert!(Bisector::new(vec!["only".to_string()]).is_err());
+
+        // Two endpoints: nothing between them to test.
+        let mut tight = bisector(2);
+        assert_eq!(tight.next_candidate(), None);
+        assert_eq!(tight.culprit(), "c1");
+        let err = tight.report(BisectResult::Good).unwrap_err();
+        assert!(err.to_string().contains("next_candidate"));
+    }
+}
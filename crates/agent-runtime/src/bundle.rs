@@ -0,0 +1,211 @@
+//! Portable snapshot bundles.
+//!
+//! Agent work often happens in a sandbox VM with no git remote in sight.
+//! A [`SnapshotBundle`] moves that work anyway: a self-contained JSON
+//! file holding one or more captured trees — each with the revision it
+//! came from, the description of the change, and a [`SnapshotManifest`]
+//! root so the receiving side can prove nothing was mangled in transit.
+//! Export on one machine, copy the file however you like, import on the
+//! other and materialize the tree into a workspace (whose own vcs then
+//! snapshots it as a normal commit).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AgentError;
+use crate::manifest::SnapshotManifest;
+use crate::patch::TreeSnapshot;
+
+/// Bumped when the on-disk shape changes; imports refuse newer versions.
+const BUNDLE_VERSION: u32 = 1;
+
+/// One captured tree inside a bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    /// The revision this tree was captured at on the exporting machine.
+    pub revision: String,
+    /// The change description, carried verbatim.
+    pub description: String,
+    /// Manifest root of `files`, checked on import.
+    pub root: String,
+    /// Path → content for the whole tree.
+    pub files: BTreeMap<String, String>,
+}
+
+impl BundleEntry {
+    /// The tree this entry carries.
+    pub fn snapshot(&self) -> TreeSnapshot {
+        TreeSnapshot::from_files(self.files.clone())
+    }
+
+    /// Write this entry's tree under `dir`, creating directories as
+    /// needed. Returns the written paths, sorted. Files already in `dir`
+    /// but not in the bundle are left alone — materializing is additive;
+    /// the receiving workspace's vcs shows the resulting diff.
+    pub fn materialize(&self, dir: impl AsRef<Path>) -> Result<Vec<String>, AgentError> {
+        let dir = dir.as_ref();
+        for (path, content) in &self.files {
+            let target = dir.join(path);
+            let io_err = |e: std::io::Error| AgentError::Io {
+                path: target.display().to_string(),
+                message: e.to_string(),
+            };
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(io_err)?;
+            }
+            fs::write(&target, content).map_err(io_err)?;
+        }
+        Ok(self.files.keys().cloned().collect())
+    }
+}
+
+/// A portable set of snapshots, exported to and imported from one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotBundle {
+    version: u32,
+    /// Entries in export order (typically oldest first).
+    pub entries: Vec<BundleEntry>,
+}
+
+impl Default for SnapshotBundle {
+    fn default() -> Self {
+        SnapshotBundle {
+            version: BUNDLE_VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl SnapshotBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one captured tree; the manifest root is computed here so the
+    /// importing side has something to check.
+    pub fn push(
+        &mut self,
+        revision: impl Into<String>,
+        description: impl Into<String>,
+        snapshot: &TreeSnapshot,
+    ) {
+        let manifest = SnapshotManifest::from_snapshot(snapshot);
+        self.entries.push(BundleEntry {
+            revision: revision.into(),
+            description: description.into(),
+            root: manifest.root,
+            files: snapshot.files().clone(),
+        });
+    }
+
+    /// Write the bundle to `path` atomically (write-then-rename), so a
+    /// half-copied file never masquerades as a bundle.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<(), AgentError> {
+        let path = path.as_ref();
+        let io_err = |e: std::io::Error| AgentError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        };
+        let json = serde_json::to_string_pretty(self).expect("bundle serializes");
+        let tmp = path.with_extension("bundle.tmp");
+        fs::write(&tmp, json).map_err(io_err)?;
+        fs::rename(&tmp, path).map_err(io_err)
+    }
+
+    /// Read and verify a bundle from `path`. Every entry's tree must
+    /// hash back to its recorded root — a failed check names the entry
+    /// rather than handing the caller a silently corrupted tree.
+    pub fn import(path: impl AsRef<Path>) -> Result<Self, AgentError> {
+        let path = path.as_ref();
+        let io_err = |message: String| AgentError::Io {
+            path: path.display().to_string(),
+            message,
+        };
+        let json = fs::read_to_string(path).map_err(|e| io_err(e.to_string()))?;
+        let bundle: SnapshotBundle =
+            serde_json::from_str(&json).map_err(|e| io_err(format!("not a bundle: {e}")))?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(io_err(format!(
+                "bundle version {} is newer than this runtime understands ({BUNDLE_VERSION})",
+                bundle.version
+            )));
+        }
+        for entry in &bundle.entries {
+            let manifest = SnapshotManifest::from_snapshot(&entry.snapshot());
+            if manifest.root != entry.root {
+                return Err(io_err(format!(
+                    "bundle entry `{}` failed verification: content does not match its manifest root",
+                    entry.revision
+                )));
+            }
+        }
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn temp_dir(label: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("agent-runtime-{label}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn snapshot(files: &[(&str, &str)]) -> TreeSnapshot {
+        TreeSnapshot::from_files(files.iter().map(|(p, c)| (p.to_string(), c.to_string())))
+    }
+
+    #[test]
+    fn bundles_round_trip_through_a_file_and_materialize() {
+        let dir = temp_dir("bundle-roundtrip");
+        let mut bundle = SnapshotBundle::new();
+        bundle.push(
+            "zx1",
+            "add greeting",
+            &snapshot(&[("src/lib.rs", "pub fn hi() {}\n"), ("notes.md", "wip\n")]),
+        );
+
+        let path = dir.join("work.bundle");
+        bundle.export(&path).unwrap();
+        let imported = SnapshotBundle::import(&path).unwrap();
+        assert_eq!(imported, bundle);
+
+        let target = dir.join("other-workspace");
+        let written = imported.entries[0].materialize(&target).unwrap();
+        assert_eq!(written, ["notes.md", "src/lib.rs"]);
+        assert_eq!(
+            fs::read_to_string(target.join("src/lib.rs")).unwrap(),
+            "pub fn hi() {}\n"
+        );
+    }
+
+    #[test]
+    fn tampered_bundles_are_refused_by_name() {
+        let dir = temp_dir("bundle-tamper");
+        let mut bundle = SnapshotBundle::new();
+        bundle.push("zx2", "fix", &snapshot(&[("a.txt", "one\n")]));
+        let path = dir.join("work.bundle");
+        bundle.export(&path).unwrap();
+
+        let doctored = fs::read_to_string(&path).unwrap().replace("one", "two");
+        fs::write(&path, doctored).unwrap();
+        let err = SnapshotBundle::import(&path).unwrap_err();
+        assert!(err.to_string().contains("entry `zx2` failed verification"));
+    }
+
+    #[test]
+    fn bundles_from_the_future_are_not_guessed_at() {
+        let dir = temp_dir("bundle-version");
+        let path = dir.join("work.bundle");
+        fs::write(&path, r#"{"version": 99, "entries": []}"#).unwrap();
+        let err = SnapshotBundle::import(&path).unwrap_err();
+        assert!(err.to_string().contains("version 99 is newer"));
+    }
+}
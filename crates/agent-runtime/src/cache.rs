@@ -0,0 +1,292 @@
+//! Execution cache for deterministic provider calls.
+//!
+//! A temperature-0 completion is a pure function of its request: same
+//! prompt, same rendered inputs, same model and params — same response.
+//! During development and in tests the same request runs over and over,
+//! and every run is paid for. [`ExecutionCache`] keys on a content hash
+//! of the whole request (which covers the prompt, the rendered messages,
+//! the model, and the sampling params), bounded by a TTL and an entry
+//! limit. Requests with any nonzero temperature never enter the
+//! cache. [`CachingProvider`] wraps any [`Provider`] with it.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::AgentError;
+use crate::provider::{Provider, ProviderRequest, ProviderResponse};
+use crate::stream::StreamEvent;
+
+/// The cache key for a request, or `None` when the request isn't
+/// deterministic and must not be cached.
+pub fn cache_key(request: &ProviderRequest) -> Option<u64> {
+    if request.temperature != Some(0.0) {
+        return None;
+    }
+    Some(request_fingerprint(request))
+}
+
+/// A content hash of every request field. Unlike [`cache_key`] it ignores
+/// determinism — the mock provider keys canned responses with it.
+pub fn request_fingerprint(request: &ProviderRequest) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    request.client.hash(&mut hasher);
+    request.system.hash(&mut hasher);
+    serde_json::to_string(&request.messages)
+        .expect("messages serialize")
+        .hash(&mut hasher);
+    for tool in &request.tools {
+        tool.name.hash(&mut hasher);
+        tool.description.hash(&mut hasher);
+        tool.parameters.to_string().hash(&mut hasher);
+    }
+    request.temperature.map(f64::to_bits).hash(&mut hasher);
+    request.top_p.map(f64::to_bits).hash(&mut hasher);
+    request.max_tokens.hash(&mut hasher);
+    request.stop.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct CacheEntry {
+    response: ProviderResponse,
+    stored_at: Instant,
+}
+
+/// A point-in-time snapshot of cache effectiveness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Bounded response cache: entries expire after `ttl` and the oldest
+/// entry is evicted when `max_entries` is reached.
+pub struct ExecutionCache {
+    entries: Mutex<HashMap<u64, CacheEntry>>,
+    ttl: Duration,
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ExecutionCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        ExecutionCache {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+            max_entries,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The cached response for `key`, unless it has expired.
+    pub fn get(&self, key: u64) -> Option<ProviderResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get(&key) {
+            if entry.stored_at.elapsed() <= self.ttl {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(entry.response.clone());
+            }
+            entries.remove(&key);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Store a response, evicting the oldest entry at capacity.
+    pub fn put(&self, key: u64, response: ProviderResponse) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(k, _)| *k);
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                response,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Current cache statistics.
+    pub fn stats(&self) -> ExecutionCacheStats {
+        ExecutionCacheStats {
+            entries: self.entries.lock().unwrap().len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A [`Provider`] that consults an [`ExecutionCache`] before calling the
+/// wrapped provider. Streaming calls pass straight through — a replayed
+/// response would fake its own event sequence, and callers streaming for
+/// UI feedback want the real one.
+pub struct CachingProvider<P> {
+    inner: P,
+    cache: std::sync::Arc<ExecutionCache>,
+}
+
+impl<P: Provider> CachingProvider<P> {
+    pub fn new(inner: P, cache: std::sync::Arc<ExecutionCache>) -> Self {
+        CachingProvider { inner, cache }
+    }
+}
+
+impl<P: Provider> Provider for CachingProvider<P> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn complete(&self, request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+        let Some(key) = cache_key(request) else {
+            return self.inner.complete(request);
+        };
+        if let Some(cached) = self.cache.get(key) {
+            return Ok(cached);
+        }
+        let response = self.inner.complete(request)?;
+        self.cache.put(key, response.clone());
+        Ok(response)
+    }
+
+    fn complete_stream(
+        &self,
+        request: &ProviderRequest,
+        on_event: &mut dyn FnMut(&StreamEvent),
+    ) -> Result<ProviderResponse, AgentError> {
+        self.inner.complete_stream(request, on_event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{StopReason, Usage};
+    use pretty_assertions::assert_eq;
+    use prompt_parser::ClientId;
+    use std::cell::Cell;
+    use std::sync::Arc;
+
+    struct CountingProvider {
+        calls: Cell<u64>,
+    }
+
+    impl Provider for CountingProvider {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn complete(&self, _request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(ProviderResponse {
+                text: format!("call {}", self.calls.get()),
+                tool_calls: Vec::new(),
+                usage: Usage::default(),
+                stop_reason: StopReason::EndTurn,
+            })
+        }
+
+        fn complete_stream(
+            &self,
+            request: &ProviderRequest,
+            _on_event: &mut dyn FnMut(&StreamEvent),
+        ) -> Result<ProviderResponse, AgentError> {
+            self.complete(request)
+        }
+    }
+
+    fn request(temperature: Option<f64>) -> ProviderRequest {
+        ProviderRequest {
+            client: ClientId {
+                provider: "anthropic".to_string(),
+                model: "claude".to_string(),
+                version: None,
+            },
+            system: None,
+            messages: Vec::new(),
+            tools: Vec::new(),
+            temperature,
+            top_p: None,
+            max_tokens: None,
+            stop: Vec::new(),
+        }
+    }
+
+    fn caching(ttl: Duration, max: usize) -> (Arc<ExecutionCache>, CachingProvider<CountingProvider>) {
+        let cache = Arc::new(ExecutionCache::new(ttl, max));
+        let provider = CachingProvider::new(
+            CountingProvider { calls: Cell::new(0) },
+            Arc::clone(&cache),
+        );
+        (cache, provider)
+    }
+
+    #[test]
+    fn temperature_zero_requests_reuse_the_first_response() {
+        let (cache, provider) = caching(Duration::from_secs(60), 16);
+        let request = request(Some(0.0));
+        assert_eq!(provider.complete(&request).unwrap().text, "call 1");
+        assert_eq!(provider.complete(&request).unwrap().text, "call 1");
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[test]
+    fn sampling_requests_are_never_cached() {
+        let (cache, provider) = caching(Duration::from_secs(60), 16);
+        let request = request(Some(0.7));
+        assert_eq!(provider.complete(&request).unwrap().text, "call 1");
+        assert_eq!(provider.complete(&request).unwrap().text, "call 2");
+        assert_eq!(cache.stats().entries, 0);
+        assert!(cache_key(&request).is_none());
+        assert!(cache_key(&self::request(None)).is_none());
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let (_, provider) = caching(Duration::ZERO, 16);
+        let request = request(Some(0.0));
+        assert_eq!(provider.complete(&request).unwrap().text, "call 1");
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(provider.complete(&request).unwrap().text, "call 2");
+    }
+
+    #[test]
+    fn the_oldest_entry_is_evicted_at_capacity() {
+        let cache = ExecutionCache::new(Duration::from_secs(60), 2);
+        let response = ProviderResponse {
+            text: "r".to_string(),
+            tool_calls: Vec::new(),
+            usage: Usage::default(),
+            stop_reason: StopReason::EndTurn,
+        };
+        cache.put(1, response.clone());
+        std::thread::sleep(Duration::from_millis(5));
+        cache.put(2, response.clone());
+        std::thread::sleep(Duration::from_millis(5));
+        cache.put(3, response);
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(2).is_some());
+        assert!(cache.get(3).is_some());
+    }
+
+    #[test]
+    fn different_params_get_different_keys() {
+        let mut a = request(Some(0.0));
+        let b = request(Some(0.0));
+        assert_eq!(cache_key(&a), cache_key(&b));
+        a.max_tokens = Some(100);
+        assert_ne!(cache_key(&a), cache_key(&b));
+    }
+}
@@ -0,0 +1,471 @@
+//! jj-backed checkpoints around write actions.
+//!
+//! Agents edit files; operators need an undo. Before each file-writing
+//! tool call the runtime snapshots the jj working copy and records the
+//! operation id on the session, tagged with the turn that triggered it.
+//! [`Checkpointer::rollback_to_turn`] then restores the tree to the state
+//! *before* a given turn's first write. The jj integration shells out to
+//! the `jj` CLI (the workspace layer owns the real jj build); tests use an
+//! in-memory [`WorkspaceVcs`].
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AgentError;
+use crate::hooks::{HookOutcome, HookStage, Hooks};
+use crate::patch::SessionPatch;
+use crate::session::Session;
+
+/// One snapshot taken before a write, stored on the session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The agent-loop turn whose write triggered the snapshot.
+    pub turn: u32,
+    /// The tool that was about to write.
+    pub tool: String,
+    /// The jj operation id (or backend-equivalent) to restore.
+    pub snapshot_id: String,
+}
+
+/// The version-control operations checkpointing needs.
+pub trait WorkspaceVcs {
+    /// Snapshot the working copy and return an id that [`Self::restore`]
+    /// accepts. `tag` describes why (session/turn); backends that can't
+    /// label snapshots may ignore it.
+    fn snapshot(&self, tag: &str) -> Result<String, AgentError>;
+
+    /// Restore the working copy to a previously returned snapshot id.
+    fn restore(&self, snapshot_id: &str) -> Result<(), AgentError>;
+}
+
+/// [`WorkspaceVcs`] via the `jj` CLI against one workspace.
+///
+/// Configuration layers the way the jj CLI does, because it *is* the jj
+/// CLI: user config (`~/.config/jj/config.toml`), then repo config, then
+/// env overrides all apply to every invocation — author identity, revset
+/// aliases, and backend settings behave exactly as in the user's shell.
+/// [`Self::with_config`] stacks programmatic overrides on top of all of
+/// that.
+pub struct JjCli {
+    workspace: PathBuf,
+    /// Snapshot via watchman instead of stat-ing every file. Only set
+    /// when watchman actually answered; see [`Self::with_fsmonitor`].
+    fsmonitor: bool,
+    /// TOML lines passed as `--config-toml`, strongest layer last.
+    overrides: Vec<String>,
+}
+
+impl JjCli {
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        JjCli {
+            workspace: workspace.into(),
+            fsmonitor: false,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Override one config key for every invocation, on top of the
+    /// user's and repo's own config. `value` is a TOML value, so quote
+    /// strings: `with_config("user.name", "\"agent\"")`.
+    pub fn with_config(mut self, key: &str, value: &str) -> Self {
+        self.overrides.push(format!("{key} = {value}"));
+        self
+    }
+
+    /// Ask jj to consult watchman when snapshotting the working copy, so
+    /// snapshots on huge repos stop scaling with tree size. When watchman
+    /// isn't installed (or doesn't answer) this quietly stays on the
+    /// stat-everything path — a missing daemon shouldn't break writes.
+    pub fn with_fsmonitor(mut self) -> Self {
+        self.fsmonitor = watchman_available();
+        if !self.fsmonitor {
+            tracing::debug!("watchman unavailable; fsmonitor stays off");
+        }
+        self
+    }
+
+    /// The `--config-toml` arguments every invocation carries: the
+    /// fsmonitor switch first, then [`Self::with_config`] overrides in
+    /// registration order (later entries win, as in jj's own layering).
+    fn config_args(&self) -> Vec<&str> {
+        let mut args: Vec<&str> = fsmonitor_args(self.fsmonitor).to_vec();
+        for line in &self.overrides {
+            args.push("--config-toml");
+            args.push(line);
+        }
+        args
+    }
+
+    pub(crate) fn jj(&self, args: &[&str]) -> Result<String, AgentError> {
+        tracing::trace!(args = args.join(" "), "running jj");
+        let output = Command::new("jj")
+            .arg("--repository")
+            .arg(&self.workspace)
+            .args(self.config_args())
+            .args(args)
+            .output()
+            .map_err(|e| AgentError::Vcs(format!("failed to run jj: {e}")))?;
+        if !output.status.success() {
+            return Err(AgentError::Vcs(format!(
+                "jj {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// The extra jj arguments the fsmonitor switch turns on.
+fn fsmonitor_args(enabled: bool) -> &'static [&'static str] {
+    if enabled {
+        &["--config-toml", r#"core.fsmonitor = "watchman""#]
+    } else {
+        &[]
+    }
+}
+
+/// Whether a watchman daemon answers on this machine.
+fn watchman_available() -> bool {
+    Command::new("watchman")
+        .arg("version")
+        .output()
+        .map(|out| out.status.success())
+        .unwrap_or(false)
+}
+
+impl WorkspaceVcs for JjCli {
+    fn snapshot(&self, _tag: &str) -> Result<String, AgentError> {
+        // Any jj command snapshots the working copy; `status` is the
+        // cheapest. The resulting operation id is the checkpoint. jj has
+        // no per-operation labels, so the tag lives on the session only.
+        self.jj(&["status"])?;
+        self.jj(&["operation", "log", "--no-graph", "-n", "1", "-T", "id"])
+    }
+
+    fn restore(&self, snapshot_id: &str) -> Result<(), AgentError> {
+        self.jj(&["operation", "restore", snapshot_id]).map(|_| ())
+    }
+}
+
+/// Builds a [`WorkspaceVcs`] for a workspace path.
+type VcsFactory = Box<dyn Fn(&Path) -> Result<Box<dyn WorkspaceVcs>, AgentError> + Send + Sync>;
+
+/// Named backend factories, so hosts pick (or supply) the working-copy
+/// backend instead of the runtime hardcoding jj. The default table knows
+/// `jj`; an embedder experimenting with in-memory or remote stores for
+/// ephemeral workspaces registers its own factory under a new name and
+/// everything downstream — [`Checkpointer`], sessions, rollback — is
+/// none the wiser.
+pub struct VcsBackends {
+    factories: BTreeMap<String, VcsFactory>,
+}
+
+impl Default for VcsBackends {
+    fn default() -> Self {
+        let mut backends = VcsBackends {
+            factories: BTreeMap::new(),
+        };
+        backends.register("jj", |workspace| Ok(Box::new(JjCli::new(workspace))));
+        backends
+    }
+}
+
+impl VcsBackends {
+    /// The built-in table: just `jj`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `factory` under `name`, replacing any previous entry —
+    /// which is also how a host swaps out the built-in `jj`.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        factory: impl Fn(&Path) -> Result<Box<dyn WorkspaceVcs>, AgentError> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(name.into(), Box::new(factory));
+    }
+
+    /// Build the backend registered under `name` for `workspace`.
+    pub fn open(
+        &self,
+        name: &str,
+        workspace: impl AsRef<Path>,
+    ) -> Result<Box<dyn WorkspaceVcs>, AgentError> {
+        let factory = self.factories.get(name).ok_or_else(|| {
+            AgentError::Vcs(format!(
+                "no registered vcs backend `{name}` (available: {})",
+                self.names().join(", ")
+            ))
+        })?;
+        factory(workspace.as_ref())
+    }
+
+    /// Registered backend names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+}
+
+/// Snapshots before write-class tools and rolls sessions back.
+pub struct Checkpointer {
+    vcs: Box<dyn WorkspaceVcs>,
+    /// Tool names that mutate the tree and therefore checkpoint first.
+    write_tools: BTreeSet<String>,
+    /// Host hooks consulted at `PreSnapshot`, when the host set any.
+    hooks: Option<Arc<Mutex<Hooks>>>,
+}
+
+impl Checkpointer {
+    pub fn new(
+        vcs: impl WorkspaceVcs + 'static,
+        write_tools: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Checkpointer {
+            vcs: Box::new(vcs),
+            write_tools: write_tools.into_iter().map(Into::into).collect(),
+            hooks: None,
+        }
+    }
+
+    /// Consult `hooks` before each write-tool snapshot; a `PreSnapshot`
+    /// veto fails the tool call instead of snapshotting. Hooks the host
+    /// registered over the FFI (the global registry) are consulted
+    /// regardless.
+    pub fn with_hooks(mut self, hooks: Arc<Mutex<Hooks>>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Snapshot ahead of `tool` running in `turn`, when it's write-class.
+    /// The checkpoint is recorded on the session; read-only tools are a
+    /// no-op.
+    pub fn before_tool(
+        &self,
+        session: &mut Session,
+        turn: u32,
+        tool: &str,
+    ) -> Result<Option<String>, AgentError> {
+        if !self.write_tools.contains(tool) {
+            return Ok(None);
+        }
+        let tag = format!("{}/turn-{turn}/{tool}", session.id);
+        let hooks = self.pre_snapshot_hooks(&tag);
+        if hooks.is_vetoed() {
+            return Err(AgentError::Vcs(format!(
+                "pre-snapshot hook vetoed `{tool}`: {}",
+                hooks.vetoes.join("; ")
+            )));
+        }
+        let snapshot_id = self.vcs.snapshot(&tag)?;
+        session.checkpoints.push(Checkpoint {
+            turn,
+            tool: tool.to_string(),
+            snapshot_id: snapshot_id.clone(),
+        });
+        Ok(Some(snapshot_id))
+    }
+
+    /// Run `PreSnapshot` hooks — the FFI-registered global ones, then
+    /// this checkpointer's own. There is no pending patch yet at
+    /// snapshot time, so hooks see the tag and an empty change set.
+    fn pre_snapshot_hooks(&self, tag: &str) -> HookOutcome {
+        let empty = SessionPatch::default();
+        let mut outcome = crate::hooks::global()
+            .lock()
+            .expect("hook registry lock")
+            .run(HookStage::PreSnapshot, tag, &empty);
+        if let Some(hooks) = &self.hooks {
+            let own = hooks
+                .lock()
+                .expect("hook registry lock")
+                .run(HookStage::PreSnapshot, tag, &empty);
+            outcome.vetoes.extend(own.vetoes);
+            outcome.annotations.extend(own.annotations);
+        }
+        outcome
+    }
+
+    /// Restore the tree to before `turn`'s first write: the earliest
+    /// checkpoint at or after that turn. Fails when the session never
+    /// checkpointed there.
+    pub fn rollback_to_turn(&self, session: &Session, turn: u32) -> Result<String, AgentError> {
+        let checkpoint = session
+            .checkpoints
+            .iter()
+            .filter(|c| c.turn >= turn)
+            .min_by_key(|c| c.turn)
+            .ok_or_else(|| {
+                AgentError::Vcs(format!(
+                    "session `{}` has no checkpoint at or after turn {turn}",
+                    session.id
+                ))
+            })?;
+        self.vcs.restore(&checkpoint.snapshot_id)?;
+        Ok(checkpoint.snapshot_id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::SessionStore;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Counts snapshots, records restores.
+    #[derive(Default)]
+    struct FakeVcs {
+        snapshots: Rc<RefCell<Vec<String>>>,
+        restored: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl WorkspaceVcs for FakeVcs {
+        fn snapshot(&self, tag: &str) -> Result<String, AgentError> {
+            let mut snapshots = self.snapshots.borrow_mut();
+            snapshots.push(tag.to_string());
+            Ok(format!("op{}", snapshots.len()))
+        }
+
+        fn restore(&self, snapshot_id: &str) -> Result<(), AgentError> {
+            self.restored.borrow_mut().push(snapshot_id.to_string());
+            Ok(())
+        }
+    }
+
+    fn session() -> Session {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-checkpoints-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        SessionStore::open(dir)
+            .unwrap()
+            .create("editor", json!({}))
+            .unwrap()
+    }
+
+    #[test]
+    fn only_write_tools_checkpoint_and_tags_name_the_turn() {
+        let vcs = FakeVcs::default();
+        let snapshots = Rc::clone(&vcs.snapshots);
+        let checkpointer = Checkpointer::new(vcs, ["write_file", "shell"]);
+        let mut session = session();
+
+        assert!(checkpointer.before_tool(&mut session, 1, "read_file").unwrap().is_none());
+        let id = checkpointer.before_tool(&mut session, 2, "write_file").unwrap();
+        assert_eq!(id.as_deref(), Some("op1"));
+        assert_eq!(session.checkpoints.len(), 1);
+        assert_eq!(session.checkpoints[0].turn, 2);
+        assert!(snapshots.borrow()[0].ends_with("/turn-2/write_file"));
+    }
+
+    #[test]
+    fn rollback_restores_the_state_before_the_requested_turn() {
+        let vcs = FakeVcs::default();
+        let restored = Rc::clone(&vcs.restored);
+        let checkpointer = Checkpointer::new(vcs, ["write_file"]);
+        let mut session = session();
+        checkpointer.before_tool(&mut session, 2, "write_file").unwrap();
+        checkpointer.before_tool(&mut session, 4, "write_file").unwrap();
+
+        // Turn 3 had no write; the next write's checkpoint (turn 4) still
+        // holds the tree as turn 3 left it.
+        let id = checkpointer.rollback_to_turn(&session, 3).unwrap();
+        assert_eq!(id, "op2");
+        assert_eq!(restored.borrow().as_slice(), ["op2"]);
+
+        let err = checkpointer.rollback_to_turn(&session, 5).unwrap_err();
+        assert!(err.to_string().contains("no checkpoint"));
+    }
+
+    #[test]
+    fn a_pre_snapshot_veto_fails_the_write_before_anything_happens() {
+        use crate::hooks::{HookStage, HookVerdict, Hooks};
+
+        let vcs = FakeVcs::default();
+        let snapshots = Rc::clone(&vcs.snapshots);
+        let mut hooks = Hooks::new();
+        hooks.register(HookStage::PreSnapshot, |tag: &str, _: &SessionPatch| {
+            if tag.contains("/shell") {
+                HookVerdict::Veto("shell writes are frozen".into())
+            } else {
+                HookVerdict::Allow
+            }
+        });
+        let checkpointer = Checkpointer::new(vcs, ["write_file", "shell"])
+            .with_hooks(Arc::new(Mutex::new(hooks)));
+        let mut session = session();
+
+        let err = checkpointer.before_tool(&mut session, 1, "shell").unwrap_err();
+        assert!(err.to_string().contains("vetoed `shell`: shell writes are frozen"));
+        assert!(snapshots.borrow().is_empty());
+        assert!(session.checkpoints.is_empty());
+
+        // Other write tools still checkpoint normally.
+        assert!(checkpointer.before_tool(&mut session, 2, "write_file").unwrap().is_some());
+    }
+
+    /// In-memory backend a host might register for ephemeral workspaces.
+    struct MemVcs;
+
+    impl WorkspaceVcs for MemVcs {
+        fn snapshot(&self, tag: &str) -> Result<String, AgentError> {
+            Ok(format!("mem:{tag}"))
+        }
+
+        fn restore(&self, _snapshot_id: &str) -> Result<(), AgentError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn the_fsmonitor_switch_maps_to_jj_config_and_degrades_to_nothing() {
+        assert_eq!(
+            fsmonitor_args(true),
+            ["--config-toml", r#"core.fsmonitor = "watchman""#]
+        );
+        assert!(fsmonitor_args(false).is_empty());
+        // Never enabled without a live daemon, whatever the host asked for.
+        let cli = JjCli::new("/tmp/ws").with_fsmonitor();
+        assert_eq!(cli.fsmonitor, watchman_available());
+    }
+
+    #[test]
+    fn config_overrides_stack_in_order_on_top_of_the_cli_layers() {
+        let cli = JjCli::new("/tmp/ws")
+            .with_config("user.name", "\"agent\"")
+            .with_config("ui.color", "\"never\"");
+        assert_eq!(
+            cli.config_args(),
+            [
+                "--config-toml",
+                "user.name = \"agent\"",
+                "--config-toml",
+                "ui.color = \"never\"",
+            ]
+        );
+        assert!(JjCli::new("/tmp/ws").config_args().is_empty());
+    }
+
+    #[test]
+    fn hosts_register_their_own_backends_next_to_jj() {
+        let mut backends = VcsBackends::new();
+        assert_eq!(backends.names(), ["jj"]);
+        backends.register("mem", |_| Ok(Box::new(MemVcs)));
+
+        let vcs = backends.open("mem", "/tmp/ws").unwrap();
+        assert_eq!(vcs.snapshot("s1/turn-1/write_file").unwrap(), "mem:s1/turn-1/write_file");
+
+        let err = backends.open("git", "/tmp/ws").err().expect("unregistered backend");
+        assert!(err.to_string().contains("no registered vcs backend `git`"));
+        assert!(err.to_string().contains("jj, mem"));
+    }
+}
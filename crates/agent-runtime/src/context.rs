@@ -0,0 +1,265 @@
+//! Context packing: the most relevant snippets under a token budget.
+//!
+//! Retrieval-augmented prompts get one input slot for repository context,
+//! and it has a hard size. The [`ContextPacker`] scores every chunk of a
+//! [`TreeSnapshot`] against the task description — path mentions, term
+//! overlap, recency of the file in jj history, and (when an index is
+//! supplied) embedding similarity — then packs the best chunks greedily
+//! until the token budget is spent. The output is a single formatted
+//! string ready for a prompt input, each snippet fenced and labelled with
+//! its path and line range.
+
+use prompt_parser::{BpeTokenCounter, TokenCounter};
+use serde::Serialize;
+
+use crate::embed::{EmbeddingIndex, EmbeddingProvider, chunk_lines};
+use crate::error::AgentError;
+use crate::patch::TreeSnapshot;
+
+/// Chunk geometry mirrors the embedding index so embedding scores line up
+/// with the chunks being packed.
+const CHUNK_LINES: usize = 40;
+const CHUNK_OVERLAP: usize = 8;
+
+/// One packed snippet, in pack order (best first).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackedSnippet {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub score: f32,
+    pub text: String,
+}
+
+/// The packer's output.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackedContext {
+    pub snippets: Vec<PackedSnippet>,
+    /// Tokens the formatted output costs.
+    pub tokens: usize,
+}
+
+impl PackedContext {
+    /// The snippets as one string for a prompt input: a fenced block per
+    /// snippet, labelled `path:start-end`.
+    pub fn formatted(&self) -> String {
+        self.snippets
+            .iter()
+            .map(|s| {
+                format!(
+                    "{}:{}-{}\n```\n{}\n```\n",
+                    s.path, s.start_line, s.end_line, s.text
+                )
+            })
+            .collect()
+    }
+}
+
+/// Selects and formats snippets for a task under a token budget.
+pub struct ContextPacker {
+    token_budget: usize,
+    /// Paths touched recently, most recent first — typically the files of
+    /// the last few jj changes.
+    recent_paths: Vec<String>,
+}
+
+impl ContextPacker {
+    pub fn new(token_budget: usize) -> Self {
+        ContextPacker {
+            token_budget,
+            recent_paths: Vec::new(),
+        }
+    }
+
+    /// Boost files that changed recently, most recent first.
+    pub fn with_recent_paths(mut self, paths: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.recent_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Pack `snapshot` for `task`. With an embedding index the score mixes
+    /// in cosine similarity; without one the heuristics stand alone.
+    pub fn pack(
+        &self,
+        task: &str,
+        snapshot: &TreeSnapshot,
+        embeddings: Option<(&EmbeddingIndex, &dyn EmbeddingProvider)>,
+    ) -> Result<PackedContext, AgentError> {
+        let embed_scores = match embeddings {
+            Some((index, provider)) => index
+                .search(provider, task, usize::MAX)?
+                .into_iter()
+                .map(|hit| ((hit.path, hit.start_line), hit.score))
+                .collect(),
+            None => std::collections::HashMap::new(),
+        };
+
+        let mut candidates = Vec::new();
+        for (path, content) in snapshot.files() {
+            let path_score = path_relevance(task, path);
+            let recency = self.recency_score(path);
+            for (start_line, text) in chunk_lines(content, CHUNK_LINES, CHUNK_OVERLAP) {
+                let embed = embed_scores
+                    .get(&(path.clone(), start_line))
+                    .copied()
+                    .unwrap_or(0.0);
+                let end_line = start_line + text.lines().count().max(1) - 1;
+                candidates.push(PackedSnippet {
+                    path: path.clone(),
+                    start_line,
+                    end_line,
+                    score: path_score + recency + term_overlap(task, &text) + embed,
+                    text,
+                });
+            }
+        }
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        // Greedy fill: a snippet that doesn't fit is skipped, a smaller
+        // one further down may still squeeze in.
+        let counter = BpeTokenCounter::cl100k();
+        let mut snippets = Vec::new();
+        let mut tokens = 0;
+        for snippet in candidates {
+            let cost = counter.count(&format!(
+                "{}:{}-{}\n```\n{}\n```\n",
+                snippet.path, snippet.start_line, snippet.end_line, snippet.text
+            ));
+            if tokens + cost > self.token_budget {
+                continue;
+            }
+            tokens += cost;
+            snippets.push(snippet);
+        }
+        Ok(PackedContext { snippets, tokens })
+    }
+
+    fn recency_score(&self, path: &str) -> f32 {
+        match self.recent_paths.iter().position(|p| p == path) {
+            Some(index) => 1.0 - index as f32 / self.recent_paths.len() as f32,
+            None => 0.0,
+        }
+    }
+}
+
+/// How strongly the task text points at this path: the file stem named
+/// outright counts most, any path component mentioned counts some.
+fn path_relevance(task: &str, path: &str) -> f32 {
+    let task = task.to_lowercase();
+    let mut score = 0.0;
+    if let Some(name) = path.rsplit('/').next() {
+        if task.contains(&name.to_lowercase()) {
+            score += 2.0;
+        } else if let Some(stem) = name.split('.').next()
+            && stem.len() >= 3
+            && task.contains(&stem.to_lowercase())
+        {
+            score += 1.5;
+        }
+    }
+    for component in path.split('/').rev().skip(1) {
+        if component.len() >= 3 && task.contains(&component.to_lowercase()) {
+            score += 0.5;
+        }
+    }
+    score
+}
+
+/// Fraction of the task's significant words that appear in the text.
+fn term_overlap(task: &str, text: &str) -> f32 {
+    let text = text.to_lowercase();
+    let words: Vec<String> = task
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.len() >= 4)
+        .map(str::to_string)
+        .collect();
+    if words.is_empty() {
+        return 0.0;
+    }
+    let hits = words.iter().filter(|w| text.contains(w.as_str())).count();
+    hits as f32 / words.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn snapshot() -> TreeSnapshot {
+        TreeSnapshot::from_files([
+            (
+                "src/parser.rs".to_string(),
+                "fn parse(source: &str) {}\n".to_string(),
+            ),
+            (
+                "src/network.rs".to_string(),
+                "fn connect(addr: &str) {}\n".to_string(),
+            ),
+            (
+                "docs/notes.md".to_string(),
+                "meeting notes, nothing useful\n".to_string(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn path_mentions_dominate_the_ranking() {
+        let packer = ContextPacker::new(4000);
+        let packed = packer
+            .pack("fix the bug in parser.rs where parse fails", &snapshot(), None)
+            .unwrap();
+        assert_eq!(packed.snippets[0].path, "src/parser.rs");
+        assert!(packed.tokens > 0);
+    }
+
+    #[test]
+    fn recency_breaks_ties() {
+        let packer = ContextPacker::new(4000).with_recent_paths(["docs/notes.md"]);
+        let packed = packer.pack("look around", &snapshot(), None).unwrap();
+        assert_eq!(packed.snippets[0].path, "docs/notes.md");
+    }
+
+    #[test]
+    fn the_budget_is_a_hard_limit() {
+        let roomy = ContextPacker::new(4000).pack("parse", &snapshot(), None).unwrap();
+        assert_eq!(roomy.snippets.len(), 3);
+
+        let tight = ContextPacker::new(30).pack("parse", &snapshot(), None).unwrap();
+        assert!(tight.snippets.len() < 3);
+        assert!(tight.tokens <= 30);
+
+        let none = ContextPacker::new(0).pack("parse", &snapshot(), None).unwrap();
+        assert!(none.snippets.is_empty());
+        assert_eq!(none.tokens, 0);
+    }
+
+    #[test]
+    fn formatted_output_labels_each_snippet() {
+        let packed = ContextPacker::new(4000)
+            .pack("parser.rs", &snapshot(), None)
+            .unwrap();
+        let formatted = packed.formatted();
+        assert!(formatted.starts_with("src/parser.rs:1-1\n```\n"));
+        assert!(formatted.contains("fn parse(source: &str) {}"));
+    }
+
+    #[test]
+    fn embedding_scores_mix_into_the_ranking() {
+        struct ConnectEmbedder;
+        impl EmbeddingProvider for ConnectEmbedder {
+            fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AgentError> {
+                Ok(texts
+                    .iter()
+                    .map(|t| vec![t.matches("connect").count() as f32, 1.0])
+                    .collect())
+            }
+        }
+        let mut index = EmbeddingIndex::new();
+        index.update(&snapshot(), &ConnectEmbedder).unwrap();
+        let packed = ContextPacker::new(4000)
+            .pack("connect", &snapshot(), Some((&index, &ConnectEmbedder)))
+            .unwrap();
+        assert_eq!(packed.snippets[0].path, "src/network.rs");
+    }
+}
@@ -0,0 +1,253 @@
+//! Conventional-commit parsing and linting.
+//!
+//! `feat(parser)!: subject` — the structure review UIs sort by and
+//! changelogs are generated from. This module parses a description into
+//! its type/scope/subject/breaking fields and validates it against a
+//! [`CommitConvention`], so the same lint runs everywhere a description
+//! is written: the UI calls [`agent_runtime_lint_commit`] over the C
+//! boundary, the agent gets it as the `lint_commit` MCP tool, and Rust
+//! callers use the types directly.
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::trailer::CommitDescription;
+
+/// The structured first line (plus breaking-change markers) of a
+/// conventional commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConventionalCommit {
+    /// `feat`, `fix`, `refactor`, …
+    pub commit_type: String,
+    /// The parenthesized scope, when present.
+    pub scope: Option<String>,
+    /// Everything after the `: `.
+    pub subject: String,
+    /// A `!` before the colon, or a `BREAKING-CHANGE:` trailer.
+    pub breaking: bool,
+}
+
+impl ConventionalCommit {
+    /// Parse `description`. `None` when the first line doesn't have the
+    /// `type[(scope)][!]: subject` shape at all — that's a convention
+    /// violation, not a parse bug, and [`CommitConvention::validate`]
+    /// reports it as one.
+    pub fn parse(description: &str) -> Option<Self> {
+        let first = description.lines().next().unwrap_or_default();
+        let (head, subject) = first.split_once(": ")?;
+        let (head, breaking_mark) = match head.strip_suffix('!') {
+            Some(head) => (head, true),
+            None => (head, false),
+        };
+        let (commit_type, scope) = match head.split_once('(') {
+            Some((commit_type, rest)) => (commit_type, Some(rest.strip_suffix(')')?)),
+            None => (head, None),
+        };
+        if commit_type.is_empty()
+            || !commit_type.chars().all(|c| c.is_ascii_lowercase())
+            || scope.is_some_and(str::is_empty)
+        {
+            return None;
+        }
+        let trailers = CommitDescription::parse(description);
+        let breaking = breaking_mark || !trailers.values("Breaking-Change").is_empty();
+        Some(ConventionalCommit {
+            commit_type: commit_type.to_string(),
+            scope: scope.map(String::from),
+            subject: subject.to_string(),
+            breaking,
+        })
+    }
+}
+
+/// What a repo accepts as a well-formed description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitConvention {
+    /// Allowed commit types, sorted.
+    pub types: Vec<String>,
+    /// Whether every commit must name a scope.
+    pub require_scope: bool,
+    /// Maximum length of the whole first line.
+    pub max_subject_len: usize,
+}
+
+impl Default for CommitConvention {
+    /// The conventionalcommits.org types, scope optional, 72-column
+    /// first line.
+    fn default() -> Self {
+        CommitConvention {
+            types: ["build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test"]
+                .map(String::from)
+                .to_vec(),
+            require_scope: false,
+            max_subject_len: 72,
+        }
+    }
+}
+
+impl CommitConvention {
+    /// Every way `description` falls short, as human-readable lines.
+    /// Empty means the description passes.
+    pub fn validate(&self, description: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        let first = description.lines().next().unwrap_or_default();
+        let Some(parsed) = ConventionalCommit::parse(description) else {
+            return vec![
+                "first line is not `type(scope): subject` — expected a conventional commit header"
+                    .to_string(),
+            ];
+        };
+        if !self.types.contains(&parsed.commit_type) {
+            violations.push(format!(
+                "unknown type `{}` (allowed: {})",
+                parsed.commit_type,
+                self.types.join(", ")
+            ));
+        }
+        if self.require_scope && parsed.scope.is_none() {
+            violations.push("missing scope — this repo requires `type(scope): subject`".to_string());
+        }
+        if parsed.subject.is_empty() {
+            violations.push("empty subject".to_string());
+        } else if parsed.subject.ends_with('.') {
+            violations.push("subject ends with a period".to_string());
+        }
+        if first.chars().count() > self.max_subject_len {
+            violations.push(format!(
+                "first line is {} characters (limit {})",
+                first.chars().count(),
+                self.max_subject_len
+            ));
+        }
+        violations
+    }
+}
+
+/// Lint `description` against the default convention — the shared body
+/// behind the MCP tool and the FFI export.
+pub(crate) fn lint_report(description: &str) -> serde_json::Value {
+    let convention = CommitConvention::default();
+    json!({
+        "parsed": ConventionalCommit::parse(description),
+        "violations": convention.validate(description),
+    })
+}
+
+/// Lint a commit description. Returns a heap-allocated JSON envelope
+/// (`{"ok":true,"parsed":...,"violations":[...]}`) the caller must
+/// release with [`agent_runtime_free`].
+///
+/// # Safety
+/// `description` must be a valid NUL-terminated string or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_runtime_lint_commit(description: *const c_char) -> *mut c_char {
+    let report = if description.is_null() {
+        json!({ "ok": false, "error": "`description` is null" })
+    } else {
+        match unsafe { std::ffi::CStr::from_ptr(description) }.to_str() {
+            Ok(text) => {
+                let mut report = json!({ "ok": true });
+                if let (serde_json::Value::Object(dst), serde_json::Value::Object(src)) =
+                    (&mut report, lint_report(text))
+                {
+                    dst.extend(src);
+                }
+                report
+            }
+            Err(_) => json!({ "ok": false, "error": "`description` is not valid UTF-8" }),
+        }
+    };
+    // Serialized JSON has no raw NUL; escape rather than abort the host
+    // if that ever stops holding (same posture as the log sink).
+    let c = match CString::new(report.to_string()) {
+        Ok(c) => c,
+        Err(err) => {
+            let escaped = String::from_utf8_lossy(&err.into_vec()).replace('\0', "\\u0000");
+            CString::new(escaped).expect("interior NULs were just escaped")
+        }
+    };
+    c.into_raw()
+}
+
+/// Release a string returned by this crate's FFI functions.
+///
+/// # Safety
+/// `ptr` must have come from this crate's FFI and not be freed twice.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_runtime_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn headers_parse_into_type_scope_subject_and_breaking() {
+        let parsed = ConventionalCommit::parse("feat(parser)!: allow nested includes").unwrap();
+        assert_eq!(parsed.commit_type, "feat");
+        assert_eq!(parsed.scope.as_deref(), Some("parser"));
+        assert_eq!(parsed.subject, "allow nested includes");
+        assert!(parsed.breaking);
+
+        let plain = ConventionalCommit::parse("fix: stop the watcher race").unwrap();
+        assert_eq!(plain.scope, None);
+        assert!(!plain.breaking);
+
+        // The trailer form of breaking, without the `!`.
+        let trailer = ConventionalCommit::parse(
+            "refactor: rework the loop\n\nBreaking-Change: run() now takes limits\n",
+        )
+        .unwrap();
+        assert!(trailer.breaking);
+
+        assert_eq!(ConventionalCommit::parse("just a sentence"), None);
+        assert_eq!(ConventionalCommit::parse("Feat: uppercase type"), None);
+        assert_eq!(ConventionalCommit::parse("feat(): empty scope"), None);
+    }
+
+    #[test]
+    fn validation_names_each_violation() {
+        let convention = CommitConvention {
+            require_scope: true,
+            ..CommitConvention::default()
+        };
+        let violations = convention.validate(&format!("wat: {}.", "x".repeat(80)));
+        assert_eq!(violations.len(), 4);
+        assert!(violations[0].contains("unknown type `wat`"));
+        assert!(violations[1].contains("missing scope"));
+        assert!(violations[2].contains("period"));
+        assert!(violations[3].contains("limit 72"));
+
+        assert!(convention.validate("feat(core): add thing").is_empty());
+        assert_eq!(
+            CommitConvention::default().validate("not conventional at all"),
+            ["first line is not `type(scope): subject` — expected a conventional commit header"]
+        );
+    }
+
+    #[test]
+    fn the_ffi_export_lints_and_the_envelope_round_trips() {
+        let description = CString::new("feat: add linting").unwrap();
+        let out = unsafe { agent_runtime_lint_commit(description.as_ptr()) };
+        let json: serde_json::Value = serde_json::from_str(
+            unsafe { std::ffi::CStr::from_ptr(out) }.to_str().unwrap(),
+        )
+        .unwrap();
+        unsafe { agent_runtime_free(out) };
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["parsed"]["commit_type"], "feat");
+        assert_eq!(json["violations"], json!([]));
+
+        let err = unsafe { agent_runtime_lint_commit(std::ptr::null()) };
+        let envelope = unsafe { std::ffi::CStr::from_ptr(err) }.to_str().unwrap().to_string();
+        unsafe { agent_runtime_free(err) };
+        assert!(envelope.contains("\"ok\":false"));
+    }
+}
@@ -0,0 +1,447 @@
+//! Embedding index over repository files.
+//!
+//! Semantic search needs vectors; this module produces and maintains
+//! them. Files from a [`TreeSnapshot`] (captured at a pinned revision)
+//! are split into overlapping line chunks, embedded through a pluggable
+//! [`EmbeddingProvider`], and stored on disk as a JSON index. Re-indexing
+//! is incremental: each file's content hash is recorded, and an update
+//! only re-embeds files whose hash changed since the last pass — the
+//! expensive provider call is skipped for the unchanged bulk of the tree.
+
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AgentError;
+use crate::patch::TreeSnapshot;
+
+/// Lines per chunk and the overlap carried between consecutive chunks.
+const CHUNK_LINES: usize = 40;
+const CHUNK_OVERLAP: usize = 8;
+
+/// Produces embedding vectors for a batch of texts, one vector per text,
+/// all the same dimension.
+pub trait EmbeddingProvider {
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AgentError>;
+}
+
+/// One embedded chunk of one file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    pub path: String,
+    /// Chunk ordinal within the file.
+    pub chunk: usize,
+    /// 1-based first line of the chunk.
+    pub start_line: usize,
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+/// What one [`EmbeddingIndex::update`] pass did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexReport {
+    pub embedded_files: usize,
+    pub unchanged_files: usize,
+    pub removed_files: usize,
+    pub chunks: usize,
+}
+
+/// The on-disk index: chunk vectors plus the per-file content hashes that
+/// make updates incremental.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingIndex {
+    /// The revision the index was last updated at, when the caller pins
+    /// one; informational.
+    pub revision: Option<String>,
+    chunks: Vec<ChunkRecord>,
+    file_hashes: BTreeMap<String, u64>,
+}
+
+impl EmbeddingIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bring the index in line with `snapshot`, embedding only files whose
+    /// content changed and dropping files that are gone.
+    pub fn update(
+        &mut self,
+        snapshot: &TreeSnapshot,
+        provider: &dyn EmbeddingProvider,
+    ) -> Result<IndexReport, AgentError> {
+        let mut report = IndexReport::default();
+        let files = snapshot.files();
+
+        let removed: Vec<String> = self
+            .file_hashes
+            .keys()
+            .filter(|path| !files.contains_key(*path))
+            .cloned()
+            .collect();
+        report.removed_files = removed.len();
+        for path in removed {
+            self.file_hashes.remove(&path);
+            self.chunks.retain(|c| c.path != path);
+        }
+
+        for (path, content) in files {
+            let hash = content_hash(content);
+            if self.file_hashes.get(path) == Some(&hash) {
+                report.unchanged_files += 1;
+                continue;
+            }
+            self.chunks.retain(|c| &c.path != path);
+            let chunks = chunk_lines(content, CHUNK_LINES, CHUNK_OVERLAP);
+            let texts: Vec<&str> = chunks.iter().map(|(_, text)| text.as_str()).collect();
+            let vectors = provider.embed(&texts)?;
+            for (ordinal, ((start_line, text), vector)) in
+                chunks.into_iter().zip(vectors).enumerate()
+            {
+                self.chunks.push(ChunkRecord {
+                    path: path.clone(),
+                    chunk: ordinal,
+                    start_line,
+                    text,
+                    vector,
+                });
+            }
+            self.file_hashes.insert(path.clone(), hash);
+            report.embedded_files += 1;
+        }
+        report.chunks = self.chunks.len();
+        Ok(report)
+    }
+
+    /// [`Self::update`], recording the revision the snapshot was taken at.
+    pub fn update_at(
+        &mut self,
+        revision: impl Into<String>,
+        snapshot: &TreeSnapshot,
+        provider: &dyn EmbeddingProvider,
+    ) -> Result<IndexReport, AgentError> {
+        let report = self.update(snapshot, provider)?;
+        self.revision = Some(revision.into());
+        Ok(report)
+    }
+
+    pub fn chunks(&self) -> &[ChunkRecord] {
+        &self.chunks
+    }
+
+    /// Write the index as JSON, atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AgentError> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_string(self).expect("indexes serialize");
+        std::fs::write(&tmp, json)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|e| AgentError::Io {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Load a previously saved index.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AgentError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| AgentError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        serde_json::from_str(&json).map_err(|e| AgentError::Io {
+            path: path.display().to_string(),
+            message: format!("corrupt embedding index: {e}"),
+        })
+    }
+}
+
+/// One ranked [`EmbeddingIndex::search`] result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Cosine similarity to the query, higher is closer.
+    pub score: f32,
+    pub snippet: String,
+    /// The revision the index is pinned to, when one was recorded.
+    pub revision: Option<String>,
+}
+
+impl EmbeddingIndex {
+    /// The `k` chunks closest to `query`, ranked by cosine similarity.
+    pub fn search(
+        &self,
+        provider: &dyn EmbeddingProvider,
+        query: &str,
+        k: usize,
+    ) -> Result<Vec<SearchHit>, AgentError> {
+        let query_vector = provider.embed(&[query])?.into_iter().next().ok_or_else(|| {
+            AgentError::Provider {
+                provider: "embedding".to_string(),
+                message: "provider returned no vector for the query".to_string(),
+            }
+        })?;
+        let mut hits: Vec<SearchHit> = self
+            .chunks
+            .iter()
+            .map(|chunk| SearchHit {
+                path: chunk.path.clone(),
+                start_line: chunk.start_line,
+                end_line: chunk.start_line + chunk.text.lines().count().max(1) - 1,
+                score: cosine(&query_vector, &chunk.vector),
+                snippet: chunk.text.clone(),
+                revision: self.revision.clone(),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(k);
+        Ok(hits)
+    }
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 { 0.0 } else { dot / denom }
+}
+
+/// Install a `semantic_search` tool over a built index. A `revision`
+/// argument that doesn't match the index's pinned revision comes back as
+/// an error result — the model asked about a tree the index doesn't hold.
+pub fn register_semantic_search(
+    runner: &mut crate::runner::ToolRunner,
+    index: std::sync::Arc<EmbeddingIndex>,
+    provider: std::sync::Arc<dyn EmbeddingProvider + Send + Sync>,
+) -> Result<(), AgentError> {
+    runner.register(
+        "semantic_search",
+        Some("Search the repository by meaning; returns ranked file snippets"),
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "revision": { "type": "string" },
+                "k": { "type": "integer", "minimum": 1 },
+            },
+            "required": ["query"],
+            "additionalProperties": false,
+        }),
+        move |args| {
+            let query = args["query"].as_str().unwrap_or_default();
+            if let Some(revision) = args["revision"].as_str()
+                && index.revision.as_deref() != Some(revision)
+            {
+                return Err(format!(
+                    "index is pinned to {}, not `{revision}`; re-index first",
+                    index.revision.as_deref().unwrap_or("no revision"),
+                ));
+            }
+            let k = args["k"].as_u64().unwrap_or(5) as usize;
+            let hits = index
+                .search(provider.as_ref(), query, k)
+                .map_err(|e| e.to_string())?;
+            Ok(serde_json::to_value(hits).expect("hits serialize"))
+        },
+    )
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Split `content` into chunks of at most `max_lines` lines, each carrying
+/// the last `overlap` lines of its predecessor so matches near a boundary
+/// land in some chunk whole. Returns `(1-based start line, text)` pairs;
+/// empty content produces no chunks.
+pub fn chunk_lines(content: &str, max_lines: usize, overlap: usize) -> Vec<(usize, String)> {
+    debug_assert!(overlap < max_lines);
+    let lines: Vec<&str> = content.lines().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + max_lines).min(lines.len());
+        chunks.push((start + 1, lines[start..end].join("\n")));
+        if end == lines.len() {
+            break;
+        }
+        start = end - overlap;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::cell::RefCell;
+
+    /// Deterministic, call-counting stand-in for a real embedding API.
+    pub(crate) struct FakeEmbedder {
+        pub calls: RefCell<Vec<usize>>,
+    }
+
+    impl FakeEmbedder {
+        pub(crate) fn new() -> Self {
+            FakeEmbedder {
+                calls: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl EmbeddingProvider for FakeEmbedder {
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AgentError> {
+            self.calls.borrow_mut().push(texts.len());
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let h = content_hash(text);
+                    vec![(h % 97) as f32, (h % 89) as f32, text.len() as f32]
+                })
+                .collect())
+        }
+    }
+
+    fn snapshot(files: &[(&str, &str)]) -> TreeSnapshot {
+        TreeSnapshot::from_files(
+            files
+                .iter()
+                .map(|(p, c)| (p.to_string(), c.to_string())),
+        )
+    }
+
+    #[test]
+    fn chunks_overlap_and_cover_the_file() {
+        let content = (1..=100).map(|i| format!("line {i}")).collect::<Vec<_>>().join("\n");
+        let chunks = chunk_lines(&content, 40, 8);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].0, 1);
+        assert_eq!(chunks[1].0, 33);
+        assert_eq!(chunks[2].0, 65);
+        assert!(chunks[2].1.ends_with("line 100"));
+        assert!(chunk_lines("", 40, 8).is_empty());
+    }
+
+    #[test]
+    fn updates_only_embed_changed_files() {
+        let embedder = FakeEmbedder::new();
+        let mut index = EmbeddingIndex::new();
+        let report = index
+            .update_at("rev1", &snapshot(&[("a.rs", "fn a() {}"), ("b.rs", "fn b() {}")]), &embedder)
+            .unwrap();
+        assert_eq!(report.embedded_files, 2);
+        assert_eq!(index.revision.as_deref(), Some("rev1"));
+
+        // Only `b.rs` changed: one embed call, `a.rs` untouched.
+        let report = index
+            .update_at("rev2", &snapshot(&[("a.rs", "fn a() {}"), ("b.rs", "fn b2() {}")]), &embedder)
+            .unwrap();
+        assert_eq!(report.embedded_files, 1);
+        assert_eq!(report.unchanged_files, 1);
+        assert_eq!(embedder.calls.borrow().len(), 3);
+    }
+
+    #[test]
+    fn deleted_files_leave_the_index() {
+        let embedder = FakeEmbedder::new();
+        let mut index = EmbeddingIndex::new();
+        index
+            .update(&snapshot(&[("a.rs", "fn a() {}"), ("b.rs", "fn b() {}")]), &embedder)
+            .unwrap();
+        let report = index.update(&snapshot(&[("a.rs", "fn a() {}")]), &embedder).unwrap();
+        assert_eq!(report.removed_files, 1);
+        assert!(index.chunks().iter().all(|c| c.path == "a.rs"));
+    }
+
+    /// Embeds by keyword counts, so similarity is predictable.
+    struct KeywordEmbedder;
+
+    impl EmbeddingProvider for KeywordEmbedder {
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AgentError> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    vec![
+                        text.matches("parser").count() as f32,
+                        text.matches("socket").count() as f32,
+                    ]
+                })
+                .collect())
+        }
+    }
+
+    fn keyword_index() -> EmbeddingIndex {
+        let mut index = EmbeddingIndex::new();
+        index
+            .update_at(
+                "zx0",
+                &snapshot(&[
+                    ("parse.rs", "parser parser parser"),
+                    ("net.rs", "socket socket"),
+                ]),
+                &KeywordEmbedder,
+            )
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn search_ranks_by_similarity_and_pins_the_revision() {
+        let index = keyword_index();
+        let hits = index.search(&KeywordEmbedder, "the parser", 1).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "parse.rs");
+        assert_eq!((hits[0].start_line, hits[0].end_line), (1, 1));
+        assert_eq!(hits[0].revision.as_deref(), Some("zx0"));
+
+        let hits = index.search(&KeywordEmbedder, "socket", 5).unwrap();
+        assert_eq!(hits[0].path, "net.rs");
+    }
+
+    #[test]
+    fn the_tool_answers_and_rejects_stale_revisions() {
+        use crate::provider::ToolCallRequest;
+        use std::sync::Arc;
+        let mut runner = crate::runner::ToolRunner::new();
+        register_semantic_search(&mut runner, Arc::new(keyword_index()), Arc::new(KeywordEmbedder))
+            .unwrap();
+
+        let call = |arguments| ToolCallRequest {
+            id: "t1".to_string(),
+            name: "semantic_search".to_string(),
+            arguments,
+        };
+        let result = runner
+            .dispatch(&call(serde_json::json!({ "query": "parser", "k": 1 })))
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(result.result[0]["path"], "parse.rs");
+
+        let result = runner
+            .dispatch(&call(serde_json::json!({ "query": "parser", "revision": "zx9" })))
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.result.as_str().unwrap().contains("pinned to zx0"));
+    }
+
+    #[test]
+    fn the_index_round_trips_through_disk() {
+        let embedder = FakeEmbedder::new();
+        let mut index = EmbeddingIndex::new();
+        index
+            .update_at("rev1", &snapshot(&[("a.rs", "fn a() {}")]), &embedder)
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("agent-runtime-embed-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("index.json");
+        index.save(&path).unwrap();
+        assert_eq!(EmbeddingIndex::load(&path).unwrap(), index);
+    }
+}
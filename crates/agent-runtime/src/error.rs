@@ -0,0 +1,51 @@
+//! The runtime error type.
+
+use thiserror::Error;
+
+/// Everything that can go wrong while executing an agent session.
+#[derive(Debug, Error)]
+pub enum AgentError {
+    /// Filesystem trouble (session store, workspace access).
+    #[error("io error at {path}: {message}")]
+    Io { path: String, message: String },
+
+    /// A stored session document that no longer deserializes.
+    #[error("corrupt session `{id}`: {message}")]
+    CorruptSession { id: String, message: String },
+
+    /// A session id with nothing stored under it.
+    #[error("unknown session `{0}`")]
+    UnknownSession(String),
+
+    /// A failed workspace version-control operation (jj snapshot/restore).
+    #[error("workspace vcs error: {0}")]
+    Vcs(String),
+
+    /// SQLite trouble in the history store.
+    #[cfg(feature = "history")]
+    #[error("history store error: {0}")]
+    History(#[from] rusqlite::Error),
+
+    /// A write or read that the workspace sandbox refused: an escape via
+    /// `..` or symlinks, or a denylisted name.
+    #[error("sandbox policy violation: {0}")]
+    PolicyViolation(String),
+
+    /// A provider request that failed: missing key, transport error, or a
+    /// response the parser couldn't make sense of.
+    #[error("provider `{provider}` error: {message}")]
+    Provider { provider: String, message: String },
+
+    /// A `client` provider this crate has no HTTP client for.
+    #[error("no provider client for `{0}`")]
+    UnsupportedProvider(String),
+
+    /// Structured output still failing schema validation after every
+    /// repair attempt (see `complete_structured`).
+    #[error("output failed schema validation after {attempts} attempts: {message}")]
+    InvalidOutput { attempts: u32, message: String },
+
+    /// Parsing/rendering/validation errors from the prompt layer.
+    #[error(transparent)]
+    Prompt(#[from] prompt_parser::PromptError),
+}
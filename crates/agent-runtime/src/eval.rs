@@ -0,0 +1,291 @@
+//! Evaluation harness for prompt suites.
+//!
+//! prompt-parser's embedded golden tests stop at the render: they prove
+//! the template, not the model. An [`EvalSuite`] goes the rest of the
+//! way — each case executes its prompt against a [`Provider`] (real or
+//! scripted), the response is checked against the prompt's `output`
+//! schema and the case's expectations, and the whole run comes back as a
+//! serializable [`EvalReport`] for CI to parse. Agent prompts run the
+//! full tool loop; everything else is a single completion.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use prompt_parser::{OutputMode, PromptDefinition};
+
+use crate::error::AgentError;
+use crate::provider::{Provider, ProviderRequest};
+use crate::runner::ToolRunner;
+
+/// One eval case: inputs plus what the response must look like. The
+/// prompt's `output` schema is always enforced on top of these.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalCase {
+    pub name: String,
+    pub inputs: Value,
+    /// Exact expected response text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect: Option<String>,
+    /// Substrings the response must contain.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expect_contains: Vec<String>,
+}
+
+impl EvalCase {
+    pub fn new(name: impl Into<String>, inputs: Value) -> Self {
+        EvalCase {
+            name: name.into(),
+            inputs,
+            expect: None,
+            expect_contains: Vec::new(),
+        }
+    }
+
+    pub fn expecting(mut self, text: impl Into<String>) -> Self {
+        self.expect = Some(text.into());
+        self
+    }
+
+    pub fn expecting_contains(mut self, text: impl Into<String>) -> Self {
+        self.expect_contains.push(text.into());
+        self
+    }
+}
+
+/// Outcome of one executed case.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalOutcome {
+    pub prompt: String,
+    pub case: String,
+    /// `None` on pass, otherwise the failure reason.
+    pub failure: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl EvalOutcome {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// The machine-readable run report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub outcomes: Vec<EvalOutcome>,
+}
+
+/// A suite of prompts with their cases.
+#[derive(Default)]
+pub struct EvalSuite {
+    entries: Vec<(PromptDefinition, Vec<EvalCase>)>,
+}
+
+impl EvalSuite {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, def: PromptDefinition, cases: Vec<EvalCase>) {
+        self.entries.push((def, cases));
+    }
+
+    /// Add a prompt with cases lifted from its embedded `tests:` block —
+    /// the same inputs, with render-level `expect` reused as an exact
+    /// expectation on the response. Cases that expect a render error are
+    /// skipped; the golden runner already covers those.
+    pub fn add_with_embedded_tests(&mut self, def: PromptDefinition) {
+        let cases = def
+            .test_cases()
+            .iter()
+            .filter(|case| case.expect_error.is_none())
+            .map(|case| EvalCase {
+                name: case.name.clone(),
+                inputs: case.inputs.clone(),
+                expect: case.expect.clone(),
+                expect_contains: Vec::new(),
+            })
+            .collect();
+        self.add(def, cases);
+    }
+
+    /// Execute every case against `provider`. Agent prompts get the full
+    /// loop with `tools`; other prompts are one completion. Provider and
+    /// validation errors fail the case, not the run.
+    pub fn run(&self, provider: &dyn Provider, tools: &ToolRunner) -> EvalReport {
+        let mut outcomes = Vec::new();
+        for (def, cases) in &self.entries {
+            for case in cases {
+                let started = std::time::Instant::now();
+                let failure = match execute(def, case, provider, tools) {
+                    Ok(text) => check(def, case, &text).err(),
+                    Err(e) => Some(e.to_string()),
+                };
+                outcomes.push(EvalOutcome {
+                    prompt: def.name.clone(),
+                    case: case.name.clone(),
+                    failure,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                });
+            }
+        }
+        let passed = outcomes.iter().filter(|o| o.passed()).count();
+        EvalReport {
+            passed,
+            failed: outcomes.len() - passed,
+            outcomes,
+        }
+    }
+}
+
+fn execute(
+    def: &PromptDefinition,
+    case: &EvalCase,
+    provider: &dyn Provider,
+    tools: &ToolRunner,
+) -> Result<String, AgentError> {
+    if def.prompt_type.as_deref() == Some("agent") {
+        let run = crate::agent::run_agent(def, &case.inputs, provider, tools)?;
+        return Ok(run.final_text);
+    }
+    let request = ProviderRequest::from_definition(def, &case.inputs)?;
+    Ok(provider.complete(&request)?.text)
+}
+
+fn check(def: &PromptDefinition, case: &EvalCase, text: &str) -> Result<(), String> {
+    if def.effective_output_mode() == OutputMode::Json && def.output.is_some() {
+        let value: Value = serde_json::from_str(text)
+            .map_err(|_| format!("response is not valid JSON: {text:?}"))?;
+        def.validate_output(&value)
+            .map_err(|e| format!("response fails the output schema: {e}"))?;
+    }
+    if let Some(expect) = &case.expect
+        && text != expect
+    {
+        return Err(format!("expected {expect:?}, got {text:?}"));
+    }
+    for needle in &case.expect_contains {
+        if !text.contains(needle) {
+            return Err(format!("response does not contain {needle:?}: {text:?}"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{ProviderResponse, StopReason, Usage};
+    use crate::stream::StreamEvent;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    struct ScriptedProvider {
+        responses: RefCell<VecDeque<&'static str>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(responses: &[&'static str]) -> Self {
+            ScriptedProvider {
+                responses: RefCell::new(responses.iter().copied().collect()),
+            }
+        }
+    }
+
+    impl Provider for ScriptedProvider {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn complete(&self, _request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+            let text = self.responses.borrow_mut().pop_front().expect("a scripted response");
+            Ok(ProviderResponse {
+                text: text.to_string(),
+                tool_calls: Vec::new(),
+                usage: Usage::default(),
+                stop_reason: StopReason::EndTurn,
+            })
+        }
+
+        fn complete_stream(
+            &self,
+            request: &ProviderRequest,
+            _on_event: &mut dyn FnMut(&StreamEvent),
+        ) -> Result<ProviderResponse, AgentError> {
+            self.complete(request)
+        }
+    }
+
+    fn def(source: &str) -> PromptDefinition {
+        prompt_parser::parse(source).unwrap()
+    }
+
+    #[test]
+    fn expectations_and_schemas_both_score() {
+        let mut suite = EvalSuite::new();
+        suite.add(
+            def("---\nname: greet\nclient: anthropic/claude-sonnet-4\ninputs:\n  who: string\n---\nHello {{ who }}"),
+            vec![
+                EvalCase::new("exact", json!({ "who": "a" })).expecting("hi a"),
+                EvalCase::new("substring", json!({ "who": "b" })).expecting_contains("bye"),
+            ],
+        );
+        let provider = ScriptedProvider::new(&["hi a", "hi b"]);
+        let report = suite.run(&provider, &ToolRunner::new());
+        assert_eq!((report.passed, report.failed), (1, 1));
+        assert!(report.outcomes[0].passed());
+        assert!(report.outcomes[1].failure.as_ref().unwrap().contains("does not contain"));
+    }
+
+    #[test]
+    fn json_prompts_enforce_the_output_schema() {
+        let source = "---\nname: classify\nclient: anthropic/claude-sonnet-4\noutput_mode: json\noutput:\n  type: object\n  properties:\n    label: { type: string }\n  required: [label]\n---\nclassify";
+        let mut suite = EvalSuite::new();
+        suite.add(
+            def(source),
+            vec![EvalCase::new("valid", json!({})), EvalCase::new("invalid", json!({}))],
+        );
+        let provider = ScriptedProvider::new(&[r#"{"label":"bug"}"#, r#"{"wrong":1}"#]);
+        let report = suite.run(&provider, &ToolRunner::new());
+        assert_eq!((report.passed, report.failed), (1, 1));
+        assert!(
+            report.outcomes[1]
+                .failure
+                .as_ref()
+                .unwrap()
+                .contains("output schema")
+        );
+    }
+
+    #[test]
+    fn embedded_tests_become_cases() {
+        let source = "---\nname: greet\nclient: anthropic/claude-sonnet-4\ninputs:\n  who: string\ntests:\n  - name: greets\n    inputs: { who: world }\n    expect: \"Hello world\"\n  - name: rejects\n    inputs: {}\n    expect_error: validation\n---\nHello {{ who }}";
+        let mut suite = EvalSuite::new();
+        suite.add_with_embedded_tests(def(source));
+        let provider = ScriptedProvider::new(&["Hello world"]);
+        let report = suite.run(&provider, &ToolRunner::new());
+        // The expect_error case is the golden runner's job, not ours.
+        assert_eq!((report.passed, report.failed), (1, 0));
+        assert_eq!(report.outcomes[0].case, "greets");
+    }
+
+    #[test]
+    fn the_report_serializes_for_ci() {
+        let report = EvalReport {
+            passed: 1,
+            failed: 0,
+            outcomes: vec![EvalOutcome {
+                prompt: "greet".to_string(),
+                case: "greets".to_string(),
+                failure: None,
+                duration_ms: 3,
+            }],
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["passed"], 1);
+        assert_eq!(json["outcomes"][0]["case"], "greets");
+    }
+}
@@ -0,0 +1,261 @@
+//! Repo change notifications.
+//!
+//! Connected UIs shouldn't poll: an [`EventBus`] fans out [`RepoEvent`]s —
+//! the operation log advanced, a bookmark moved, a session changed state —
+//! to any number of subscribers, and the HTTP server streams them as
+//! server-sent events from `/api/events`. A [`RepoWatcher`] produces the
+//! jj-side events by diffing successive observations of the op head and
+//! bookmark list, so it works the same against the real CLI and in tests.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::error::AgentError;
+use crate::http::BookmarkInfo;
+use crate::session::Session;
+
+/// What changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepoEventKind {
+    OperationLogAdvanced,
+    BookmarkMoved,
+    SessionStatusChanged,
+}
+
+impl RepoEventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RepoEventKind::OperationLogAdvanced => "operation_log_advanced",
+            RepoEventKind::BookmarkMoved => "bookmark_moved",
+            RepoEventKind::SessionStatusChanged => "session_status_changed",
+        }
+    }
+}
+
+/// One pushed event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RepoEvent {
+    pub kind: RepoEventKind,
+    pub data: Value,
+    /// Unix seconds.
+    pub at: i64,
+}
+
+impl RepoEvent {
+    fn new(kind: RepoEventKind, data: Value) -> Self {
+        RepoEvent {
+            kind,
+            data,
+            at: crate::session::now_unix(),
+        }
+    }
+}
+
+/// The SSE wire form of one event.
+pub fn sse_frame(event: &RepoEvent) -> String {
+    format!(
+        "event: {}\ndata: {}\n\n",
+        event.kind.as_str(),
+        json!({ "data": event.data, "at": event.at })
+    )
+}
+
+/// Fans events out to subscribers; closed subscribers are dropped on the
+/// next publish.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<Sender<RepoEvent>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new subscription receiving every event published from now on.
+    pub fn subscribe(&self) -> Receiver<RepoEvent> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Deliver `event` to every live subscriber.
+    pub fn publish(&self, event: RepoEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+    /// Publish a session state change.
+    pub fn session_changed(&self, session: &Session) {
+        self.publish(RepoEvent::new(
+            RepoEventKind::SessionStatusChanged,
+            json!({
+                "session_id": session.id,
+                "prompt_name": session.prompt_name,
+                "status": session.status,
+            }),
+        ));
+    }
+}
+
+/// Produces jj-side events by diffing successive observations.
+pub struct RepoWatcher {
+    last_op: Option<String>,
+    last_bookmarks: BTreeMap<String, String>,
+}
+
+impl RepoWatcher {
+    pub fn new() -> Self {
+        RepoWatcher {
+            last_op: None,
+            last_bookmarks: BTreeMap::new(),
+        }
+    }
+
+    /// Compare one observation against the previous one, publishing what
+    /// moved. The first observation seeds state silently.
+    pub fn observe(&mut self, bus: &EventBus, op_head: &str, bookmarks: &[BookmarkInfo]) {
+        let first = self.last_op.is_none();
+        if !first && self.last_op.as_deref() != Some(op_head) {
+            bus.publish(RepoEvent::new(
+                RepoEventKind::OperationLogAdvanced,
+                json!({ "operation": op_head }),
+            ));
+        }
+        self.last_op = Some(op_head.to_string());
+
+        let current: BTreeMap<String, String> = bookmarks
+            .iter()
+            .map(|b| (b.name.clone(), b.target.clone()))
+            .collect();
+        if !first {
+            for (name, target) in &current {
+                if self.last_bookmarks.get(name) != Some(target) {
+                    bus.publish(RepoEvent::new(
+                        RepoEventKind::BookmarkMoved,
+                        json!({ "name": name, "target": target }),
+                    ));
+                }
+            }
+            for name in self.last_bookmarks.keys() {
+                if !current.contains_key(name) {
+                    bus.publish(RepoEvent::new(
+                        RepoEventKind::BookmarkMoved,
+                        json!({ "name": name, "target": Value::Null }),
+                    ));
+                }
+            }
+        }
+        self.last_bookmarks = current;
+    }
+
+    /// One polling step against the real workspace.
+    pub fn poll(
+        &mut self,
+        bus: &EventBus,
+        vcs: &crate::checkpoint::JjCli,
+    ) -> Result<(), AgentError> {
+        use crate::http::WorkspaceQueries;
+        let op_head = vcs.jj(&["operation", "log", "--no-graph", "-n", "1", "-T", "id"])?;
+        let bookmarks = vcs.bookmarks()?;
+        self.observe(bus, &op_head, &bookmarks);
+        Ok(())
+    }
+}
+
+impl Default for RepoWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bookmark(name: &str, target: &str) -> BookmarkInfo {
+        BookmarkInfo {
+            name: name.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn the_bus_fans_out_and_drops_dead_subscribers() {
+        let bus = EventBus::new();
+        let alive = bus.subscribe();
+        let dead = bus.subscribe();
+        drop(dead);
+        bus.publish(RepoEvent::new(
+            RepoEventKind::OperationLogAdvanced,
+            json!({ "operation": "op2" }),
+        ));
+        assert_eq!(alive.try_recv().unwrap().data["operation"], "op2");
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn the_watcher_publishes_diffs_not_observations() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe();
+        let mut watcher = RepoWatcher::new();
+
+        // Seeding is silent.
+        watcher.observe(&bus, "op1", &[bookmark("main", "zx0")]);
+        assert!(rx.try_recv().is_err());
+
+        // Nothing moved: still silent.
+        watcher.observe(&bus, "op1", &[bookmark("main", "zx0")]);
+        assert!(rx.try_recv().is_err());
+
+        // Op advanced, bookmark moved, bookmark deleted — three events.
+        watcher.observe(&bus, "op2", &[bookmark("feature", "zx9")]);
+        let events: Vec<RepoEvent> = rx.try_iter().collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, RepoEventKind::OperationLogAdvanced);
+        assert_eq!(events[1].data, json!({ "name": "feature", "target": "zx9" }));
+        assert_eq!(events[2].data["name"], "main");
+        assert_eq!(events[2].data["target"], Value::Null);
+    }
+
+    #[test]
+    fn session_changes_publish_with_status() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-events-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let session = crate::session::SessionStore::open(dir)
+            .unwrap()
+            .create("triage", json!({}))
+            .unwrap();
+
+        let bus = EventBus::new();
+        let rx = bus.subscribe();
+        bus.session_changed(&session);
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.kind, RepoEventKind::SessionStatusChanged);
+        assert_eq!(event.data["status"], "running");
+    }
+
+    #[test]
+    fn sse_frames_follow_the_wire_format() {
+        let event = RepoEvent {
+            kind: RepoEventKind::BookmarkMoved,
+            data: json!({ "name": "main", "target": "zx1" }),
+            at: 42,
+        };
+        assert_eq!(
+            sse_frame(&event),
+            "event: bookmark_moved\ndata: {\"at\":42,\"data\":{\"name\":\"main\",\"target\":\"zx1\"}}\n\n"
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! SQLite-backed conversation history.
+//!
+//! Session documents are the durable unit of execution; history is the
+//! durable unit of *recall*. A [`HistoryStore`] ingests finished (or
+//! in-flight) sessions into SQLite — messages, tool calls, outcome — and
+//! answers the questions the JSON documents can't without a full scan: by
+//! session, by prompt name, by time range, and full-text over message
+//! content (FTS5). Ingest is idempotent per session, so re-ingesting after
+//! a resume replaces rather than duplicates.
+
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::error::AgentError;
+use crate::session::Session;
+
+/// One stored message row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryMessage {
+    pub session_id: String,
+    pub prompt_name: String,
+    /// Position within the session's transcript, 0-based.
+    pub position: u32,
+    pub role: String,
+    pub content: String,
+    /// The session's `updated_at` when ingested; unix seconds.
+    pub at: i64,
+}
+
+/// One stored tool-call row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryToolCall {
+    pub session_id: String,
+    pub turn: u32,
+    pub tool: String,
+    pub arguments: String,
+    pub result: String,
+}
+
+/// One stored session outcome.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryOutcome {
+    pub session_id: String,
+    pub prompt_name: String,
+    pub status: String,
+    pub at: i64,
+}
+
+/// The SQLite store. One connection; callers wrap it for sharing.
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS messages (
+    id          INTEGER PRIMARY KEY,
+    session_id  TEXT NOT NULL,
+    prompt_name TEXT NOT NULL,
+    position    INTEGER NOT NULL,
+    role        TEXT NOT NULL,
+    content     TEXT NOT NULL,
+    at          INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS messages_session ON messages (session_id, position);
+CREATE INDEX IF NOT EXISTS messages_prompt ON messages (prompt_name, at);
+CREATE TABLE IF NOT EXISTS tool_calls (
+    id          INTEGER PRIMARY KEY,
+    session_id  TEXT NOT NULL,
+    turn        INTEGER NOT NULL,
+    tool        TEXT NOT NULL,
+    arguments   TEXT NOT NULL,
+    result      TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tool_calls_session ON tool_calls (session_id, turn);
+CREATE TABLE IF NOT EXISTS outcomes (
+    session_id  TEXT PRIMARY KEY,
+    prompt_name TEXT NOT NULL,
+    status      TEXT NOT NULL,
+    at          INTEGER NOT NULL
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+    content, content='messages', content_rowid='id'
+);
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, content) VALUES (new.id, new.content);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content)
+        VALUES ('delete', old.id, old.content);
+END;
+";
+
+impl HistoryStore {
+    /// Open (creating schema if needed) a store at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AgentError> {
+        let conn = Connection::open(path.as_ref())?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(HistoryStore { conn })
+    }
+
+    /// An in-memory store, for tests and ephemeral hosts.
+    pub fn open_in_memory() -> Result<Self, AgentError> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(HistoryStore { conn })
+    }
+
+    /// Ingest one session, replacing anything previously stored under its
+    /// id.
+    pub fn ingest(&mut self, session: &Session) -> Result<(), AgentError> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM messages WHERE session_id = ?1", [&session.id])?;
+        tx.execute("DELETE FROM tool_calls WHERE session_id = ?1", [&session.id])?;
+        for (position, message) in session.messages.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO messages (session_id, prompt_name, position, role, content, at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    session.id,
+                    session.prompt_name,
+                    position as u32,
+                    message.role,
+                    message.content,
+                    session.updated_at,
+                ],
+            )?;
+        }
+        for call in &session.tool_calls {
+            tx.execute(
+                "INSERT INTO tool_calls (session_id, turn, tool, arguments, result)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    session.id,
+                    call.turn,
+                    call.tool,
+                    call.arguments.to_string(),
+                    call.result.to_string(),
+                ],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO outcomes (session_id, prompt_name, status, at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (session_id) DO UPDATE SET status = ?3, at = ?4",
+            rusqlite::params![
+                session.id,
+                session.prompt_name,
+                serde_json::to_value(session.status)
+                    .expect("status serializes")
+                    .as_str()
+                    .expect("status is a string")
+                    .to_string(),
+                session.updated_at,
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn collect_messages(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<HistoryMessage>, AgentError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok(HistoryMessage {
+                session_id: row.get(0)?,
+                prompt_name: row.get(1)?,
+                position: row.get(2)?,
+                role: row.get(3)?,
+                content: row.get(4)?,
+                at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// A session's transcript in order.
+    pub fn messages_for_session(&self, id: &str) -> Result<Vec<HistoryMessage>, AgentError> {
+        self.collect_messages(
+            "SELECT session_id, prompt_name, position, role, content, at
+             FROM messages WHERE session_id = ?1 ORDER BY position",
+            [id],
+        )
+    }
+
+    /// Every stored message for a prompt name, newest session first.
+    pub fn messages_for_prompt(&self, name: &str) -> Result<Vec<HistoryMessage>, AgentError> {
+        self.collect_messages(
+            "SELECT session_id, prompt_name, position, role, content, at
+             FROM messages WHERE prompt_name = ?1 ORDER BY at DESC, session_id, position",
+            [name],
+        )
+    }
+
+    /// Messages ingested in `[from, to)` unix seconds.
+    pub fn messages_in_range(&self, from: i64, to: i64) -> Result<Vec<HistoryMessage>, AgentError> {
+        self.collect_messages(
+            "SELECT session_id, prompt_name, position, role, content, at
+             FROM messages WHERE at >= ?1 AND at < ?2 ORDER BY at, session_id, position",
+            [from, to],
+        )
+    }
+
+    /// Full-text search over message content (FTS5 query syntax), best
+    /// match first.
+    pub fn search(&self, query: &str) -> Result<Vec<HistoryMessage>, AgentError> {
+        self.collect_messages(
+            "SELECT m.session_id, m.prompt_name, m.position, m.role, m.content, m.at
+             FROM messages_fts f JOIN messages m ON m.id = f.rowid
+             WHERE messages_fts MATCH ?1 ORDER BY rank",
+            [query],
+        )
+    }
+
+    /// A session's tool calls in turn order.
+    pub fn tool_calls_for_session(&self, id: &str) -> Result<Vec<HistoryToolCall>, AgentError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT session_id, turn, tool, arguments, result
+             FROM tool_calls WHERE session_id = ?1 ORDER BY turn, id",
+        )?;
+        let rows = stmt.query_map([id], |row| {
+            Ok(HistoryToolCall {
+                session_id: row.get(0)?,
+                turn: row.get(1)?,
+                tool: row.get(2)?,
+                arguments: row.get(3)?,
+                result: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Stored outcomes for a prompt name, newest first.
+    pub fn outcomes_for_prompt(&self, name: &str) -> Result<Vec<HistoryOutcome>, AgentError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT session_id, prompt_name, status, at
+             FROM outcomes WHERE prompt_name = ?1 ORDER BY at DESC",
+        )?;
+        let rows = stmt.query_map([name], |row| {
+            Ok(HistoryOutcome {
+                session_id: row.get(0)?,
+                prompt_name: row.get(1)?,
+                status: row.get(2)?,
+                at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionStatus, SessionStore, ToolCallRecord};
+    use pretty_assertions::assert_eq;
+    use prompt_parser::Message;
+    use serde_json::json;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.into(),
+            content: content.into(),
+            attachments: Vec::new(),
+        }
+    }
+
+    fn stored_session(prompt: &str, content: &str) -> Session {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-history-{}-{}",
+            prompt,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = SessionStore::open(dir).unwrap();
+        let mut session = store.create(prompt, json!({})).unwrap();
+        session.push_message(message("user", content));
+        session.push_message(message("assistant", "done"));
+        session.record_tool_call(ToolCallRecord {
+            turn: 1,
+            tool: "read_file".into(),
+            arguments: json!({ "path": "x" }),
+            result: json!("ok"),
+        });
+        session.status = SessionStatus::Completed;
+        session
+    }
+
+    #[test]
+    fn sessions_ingest_and_query_back() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        let session = stored_session("triage", "please fix the flaky watcher test");
+        history.ingest(&session).unwrap();
+
+        let messages = history.messages_for_session(&session.id).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[1].content, "done");
+
+        let calls = history.tool_calls_for_session(&session.id).unwrap();
+        assert_eq!(calls[0].tool, "read_file");
+        assert_eq!(calls[0].arguments, "{\"path\":\"x\"}");
+
+        let outcomes = history.outcomes_for_prompt("triage").unwrap();
+        assert_eq!(outcomes[0].status, "completed");
+
+        assert_eq!(history.messages_for_prompt("triage").unwrap().len(), 2);
+        assert!(history.messages_for_prompt("other").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reingest_replaces_instead_of_duplicating() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        let mut session = stored_session("triage", "first pass");
+        history.ingest(&session).unwrap();
+        session.push_message(message("user", "second pass"));
+        history.ingest(&session).unwrap();
+        assert_eq!(history.messages_for_session(&session.id).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn full_text_search_finds_content_across_sessions() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        history
+            .ingest(&stored_session("triage", "the watcher test is flaky"))
+            .unwrap();
+        history
+            .ingest(&stored_session("review", "rename the pricing table"))
+            .unwrap();
+
+        let hits = history.search("flaky").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].prompt_name, "triage");
+        assert!(history.search("nonexistent").unwrap().is_empty());
+    }
+
+    #[test]
+    fn time_range_queries_use_ingest_time() {
+        let mut history = HistoryStore::open_in_memory().unwrap();
+        let mut session = stored_session("triage", "hello");
+        session.updated_at = 1_000;
+        history.ingest(&session).unwrap();
+        assert_eq!(history.messages_in_range(500, 1_500).unwrap().len(), 2);
+        assert!(history.messages_in_range(2_000, 3_000).unwrap().is_empty());
+    }
+}
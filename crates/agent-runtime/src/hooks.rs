@@ -0,0 +1,241 @@
+//! Host hooks around repo mutations.
+//!
+//! Hosts get a say before the runtime touches the tree: hooks registered
+//! for a [`HookStage`] see the pending change set and either let it
+//! through, pin a note on it, or veto it with a reason the agent loop
+//! can surface. Rust hosts register closures on a [`Hooks`] instance;
+//! C hosts register an [`agent_runtime_register_hook`] callback into the
+//! process-global registry, mirroring how the log sink works. The
+//! [`Checkpointer`](crate::checkpoint::Checkpointer) consults
+//! `PreSnapshot` hooks before every write-tool snapshot; commit paths
+//! run `PreCommit`/`PostCommit` around writing a change.
+
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::{Mutex, OnceLock};
+
+use serde_json::{Value, json};
+
+use crate::patch::SessionPatch;
+
+/// Where in a mutation a hook runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HookStage {
+    /// Before the working copy is snapshotted for a write tool.
+    PreSnapshot,
+    /// Before a pending change set becomes a commit.
+    PreCommit,
+    /// After a commit lands. Vetoes here are too late and are reported
+    /// as annotations instead.
+    PostCommit,
+}
+
+/// One hook's answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookVerdict {
+    Allow,
+    /// Let it through, but attach a note (shown in review, logged).
+    Annotate(String),
+    /// Block the mutation, with a reason the model can act on.
+    Veto(String),
+}
+
+/// What a whole stage of hooks decided.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookOutcome {
+    /// Veto reasons, in registration order. Empty means allowed.
+    pub vetoes: Vec<String>,
+    pub annotations: Vec<String>,
+}
+
+impl HookOutcome {
+    pub fn is_vetoed(&self) -> bool {
+        !self.vetoes.is_empty()
+    }
+}
+
+type Hook = Box<dyn Fn(&str, &SessionPatch) -> HookVerdict + Send + Sync>;
+
+/// A registry of hooks, grouped by stage.
+#[derive(Default)]
+pub struct Hooks {
+    by_stage: BTreeMap<HookStage, Vec<Hook>>,
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `hook` at `stage`; hooks run in registration order and
+    /// all of them run even after a veto, so the outcome carries every
+    /// objection at once.
+    pub fn register(
+        &mut self,
+        stage: HookStage,
+        hook: impl Fn(&str, &SessionPatch) -> HookVerdict + Send + Sync + 'static,
+    ) {
+        self.by_stage.entry(stage).or_default().push(Box::new(hook));
+    }
+
+    /// Run every hook at `stage` against the pending change set.
+    /// `description` is the change description (or the snapshot tag for
+    /// `PreSnapshot`). At `PostCommit` a veto arrives too late to mean
+    /// anything, so it is downgraded to an annotation.
+    pub fn run(&self, stage: HookStage, description: &str, patch: &SessionPatch) -> HookOutcome {
+        let mut outcome = HookOutcome::default();
+        for hook in self.by_stage.get(&stage).into_iter().flatten() {
+            match hook(description, patch) {
+                HookVerdict::Allow => {}
+                HookVerdict::Annotate(note) => outcome.annotations.push(note),
+                HookVerdict::Veto(reason) if stage == HookStage::PostCommit => {
+                    outcome.annotations.push(format!("late veto (ignored): {reason}"));
+                }
+                HookVerdict::Veto(reason) => outcome.vetoes.push(reason),
+            }
+        }
+        outcome
+    }
+}
+
+/// The process-global registry the FFI feeds. Rust hosts normally build
+/// their own [`Hooks`]; embedded hosts registering callbacks before the
+/// runtime exists need somewhere shared to put them.
+pub fn global() -> &'static Mutex<Hooks> {
+    static GLOBAL: OnceLock<Mutex<Hooks>> = OnceLock::new();
+    GLOBAL.get_or_init(|| Mutex::new(Hooks::new()))
+}
+
+/// A host-side hook. Receives the event as a JSON C string
+/// (`{"stage":"pre_commit","description":"...","paths":[...]}`) valid
+/// only for the duration of the call, and answers with either null
+/// (allow) or a verdict JSON (`{"veto":"..."}` or `{"annotate":"..."}`)
+/// in memory the host owns — the runtime copies it before returning, so
+/// a thread-local buffer on the host side is fine.
+pub type HostHook = extern "C" fn(event_json: *const c_char) -> *const c_char;
+
+/// Wrap `hook` so it speaks the C calling convention described on
+/// [`HostHook`].
+fn host_hook_adapter(hook: HostHook, stage: HookStage) -> impl Fn(&str, &SessionPatch) -> HookVerdict {
+    move |description, patch| {
+        let stage_name = match stage {
+            HookStage::PreSnapshot => "pre_snapshot",
+            HookStage::PreCommit => "pre_commit",
+            HookStage::PostCommit => "post_commit",
+        };
+        let paths: Vec<&str> = patch.changes.iter().map(|c| c.path.as_str()).collect();
+        let event = json!({
+            "stage": stage_name,
+            "description": description,
+            "paths": paths,
+        });
+        let event = CString::new(event.to_string()).expect("serialized JSON has no NUL");
+        let answer = hook(event.as_ptr());
+        if answer.is_null() {
+            return HookVerdict::Allow;
+        }
+        let verdict: Value = match unsafe { CStr::from_ptr(answer) }
+            .to_str()
+            .ok()
+            .and_then(|s| serde_json::from_str(s).ok())
+        {
+            Some(v) => v,
+            // An unparseable answer fails closed: a policy callback that
+            // is misbehaving should not silently wave changes through.
+            None => return HookVerdict::Veto("host hook returned an unparseable verdict".into()),
+        };
+        if let Some(reason) = verdict["veto"].as_str() {
+            HookVerdict::Veto(reason.to_string())
+        } else if let Some(note) = verdict["annotate"].as_str() {
+            HookVerdict::Annotate(note.to_string())
+        } else {
+            HookVerdict::Allow
+        }
+    }
+}
+
+/// Register a host callback in the global registry. `stage` is 0
+/// (pre-snapshot), 1 (pre-commit), or 2 (post-commit); anything else
+/// returns false.
+#[unsafe(no_mangle)]
+pub extern "C" fn agent_runtime_register_hook(stage: u8, hook: HostHook) -> bool {
+    let stage = match stage {
+        0 => HookStage::PreSnapshot,
+        1 => HookStage::PreCommit,
+        2 => HookStage::PostCommit,
+        _ => return false,
+    };
+    global()
+        .lock()
+        .expect("hook registry lock")
+        .register(stage, host_hook_adapter(hook, stage));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch::{TreeSnapshot, session_patch};
+    use pretty_assertions::assert_eq;
+
+    fn patch(paths: &[&str]) -> SessionPatch {
+        let after = TreeSnapshot::from_files(
+            paths.iter().map(|p| (p.to_string(), "new\n".to_string())),
+        );
+        session_patch(&TreeSnapshot::default(), &after)
+    }
+
+    #[test]
+    fn every_hook_runs_and_the_outcome_collects_all_objections() {
+        let mut hooks = Hooks::new();
+        hooks.register(HookStage::PreCommit, |_, patch| {
+            if patch.changes.iter().any(|c| c.path.starts_with("secrets/")) {
+                HookVerdict::Veto("touches secrets/".into())
+            } else {
+                HookVerdict::Allow
+            }
+        });
+        hooks.register(HookStage::PreCommit, |description, _| {
+            HookVerdict::Annotate(format!("reviewed: {description}"))
+        });
+
+        let outcome = hooks.run(HookStage::PreCommit, "add config", &patch(&["secrets/key.pem"]));
+        assert!(outcome.is_vetoed());
+        assert_eq!(outcome.vetoes, ["touches secrets/"]);
+        assert_eq!(outcome.annotations, ["reviewed: add config"]);
+
+        let clean = hooks.run(HookStage::PreCommit, "add docs", &patch(&["docs/a.md"]));
+        assert!(!clean.is_vetoed());
+        // A stage with no hooks allows by default.
+        assert!(!hooks.run(HookStage::PreSnapshot, "x", &patch(&[])).is_vetoed());
+    }
+
+    #[test]
+    fn post_commit_vetoes_downgrade_to_annotations() {
+        let mut hooks = Hooks::new();
+        hooks.register(HookStage::PostCommit, |_, _| HookVerdict::Veto("too late".into()));
+        let outcome = hooks.run(HookStage::PostCommit, "done", &patch(&[]));
+        assert!(!outcome.is_vetoed());
+        assert_eq!(outcome.annotations, ["late veto (ignored): too late"]);
+    }
+
+    extern "C" fn vetoing_host_hook(event_json: *const c_char) -> *const c_char {
+        let event = unsafe { CStr::from_ptr(event_json) }.to_str().unwrap();
+        assert!(event.contains("\"stage\":\"pre_commit\""));
+        c"{\"veto\":\"host said no\"}".as_ptr()
+    }
+
+    #[test]
+    fn host_callbacks_speak_json_over_the_boundary() {
+        let mut hooks = Hooks::new();
+        hooks.register(
+            HookStage::PreCommit,
+            host_hook_adapter(vetoing_host_hook, HookStage::PreCommit),
+        );
+        let outcome = hooks.run(HookStage::PreCommit, "bump dep", &patch(&["Cargo.toml"]));
+        assert_eq!(outcome.vetoes, ["host said no"]);
+
+        assert!(!agent_runtime_register_hook(9, vetoing_host_hook));
+    }
+}
@@ -0,0 +1,418 @@
+//! HTTP API for workspace queries.
+//!
+//! The web frontend talks to a headless instance over REST instead of a
+//! native addon: commits, file content, diffs, bookmarks, and status, all
+//! as JSON in the same `{"ok": ...}` envelope the FFI uses. The server is
+//! `tiny_http` rather than an async stack — this crate ships as a static
+//! library inside a host process, and a blocking listener on a thread
+//! needs no runtime to be embedded. Routing is a pure function over
+//! method + URL ([`HttpServer::respond`]), which is also how it's tested.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::checkpoint::JjCli;
+use crate::error::AgentError;
+
+/// One commit in `/api/commits`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitInfo {
+    pub change_id: String,
+    pub description: String,
+}
+
+/// One bookmark in `/api/bookmarks`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BookmarkInfo {
+    pub name: String,
+    pub target: String,
+}
+
+/// The read-side workspace queries the server exposes.
+pub trait WorkspaceQueries {
+    fn commits(&self, limit: usize) -> Result<Vec<CommitInfo>, AgentError>;
+    fn file(&self, revision: &str, path: &str) -> Result<String, AgentError>;
+    fn diff(&self, from: &str, to: &str) -> Result<String, AgentError>;
+    fn bookmarks(&self) -> Result<Vec<BookmarkInfo>, AgentError>;
+    fn status(&self) -> Result<String, AgentError>;
+}
+
+impl WorkspaceQueries for JjCli {
+    fn commits(&self, limit: usize) -> Result<Vec<CommitInfo>, AgentError> {
+        let out = self.jj(&[
+            "log",
+            "--no-graph",
+            "-n",
+            &limit.to_string(),
+            "-T",
+            r#"change_id.short() ++ "\t" ++ description.first_line() ++ "\n""#,
+        ])?;
+        Ok(out
+            .lines()
+            .filter_map(|line| {
+                let (change_id, description) = line.split_once('\t')?;
+                Some(CommitInfo {
+                    change_id: change_id.to_string(),
+                    description: description.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn file(&self, revision: &str, path: &str) -> Result<String, AgentError> {
+        self.jj(&["file", "show", "-r", revision, path])
+    }
+
+    fn diff(&self, from: &str, to: &str) -> Result<String, AgentError> {
+        self.jj(&["diff", "--from", from, "--to", to])
+    }
+
+    fn bookmarks(&self) -> Result<Vec<BookmarkInfo>, AgentError> {
+        let out = self.jj(&[
+            "bookmark",
+            "list",
+            "-T",
+            r#"name ++ "\t" ++ normal_target.change_id().short() ++ "\n""#,
+        ])?;
+        Ok(out
+            .lines()
+            .filter_map(|line| {
+                let (name, target) = line.split_once('\t')?;
+                Some(BookmarkInfo {
+                    name: name.to_string(),
+                    target: target.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn status(&self) -> Result<String, AgentError> {
+        self.jj(&["status"])
+    }
+}
+
+/// The server: routing plus a blocking `tiny_http` listener.
+pub struct HttpServer {
+    queries: Box<dyn WorkspaceQueries + Send + Sync>,
+    events: Option<std::sync::Arc<crate::events::EventBus>>,
+    auth: Option<crate::auth::TokenAuth>,
+    /// The workspace name tokens are checked against.
+    workspace: String,
+}
+
+/// Adapts an event subscription into the `Read` a streaming response
+/// wants: blocks for the next event, emits its SSE frame, and sends a
+/// comment as keep-alive when nothing happens for a while.
+#[cfg(feature = "http")]
+struct SseReader {
+    receiver: std::sync::mpsc::Receiver<crate::events::RepoEvent>,
+    pending: Vec<u8>,
+}
+
+#[cfg(feature = "http")]
+impl std::io::Read for SseReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::sync::mpsc::RecvTimeoutError;
+        if self.pending.is_empty() {
+            match self.receiver.recv_timeout(std::time::Duration::from_secs(15)) {
+                Ok(event) => self.pending = crate::events::sse_frame(&event).into_bytes(),
+                Err(RecvTimeoutError::Timeout) => self.pending = b": keep-alive\n\n".to_vec(),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+fn parse_query(url: &str) -> (&str, HashMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query
+        .split('&')
+        .filter_map(|pair| {
+            let (k, v) = pair.split_once('=')?;
+            Some((k.to_string(), v.replace("%2F", "/").replace("%2f", "/")))
+        })
+        .collect();
+    (path, params)
+}
+
+fn ok_body(value: serde_json::Value) -> (u16, String) {
+    (200, json!({ "ok": true, "data": value }).to_string())
+}
+
+fn err_body(status: u16, message: &str) -> (u16, String) {
+    (status, json!({ "ok": false, "error": message }).to_string())
+}
+
+impl HttpServer {
+    pub fn new(queries: impl WorkspaceQueries + Send + Sync + 'static) -> Self {
+        HttpServer {
+            queries: Box::new(queries),
+            events: None,
+            auth: None,
+            workspace: "default".to_string(),
+        }
+    }
+
+    /// Enable `/api/events`: subscribers of `bus` stream out as SSE.
+    pub fn with_events(mut self, bus: std::sync::Arc<crate::events::EventBus>) -> Self {
+        self.events = Some(bus);
+        self
+    }
+
+    /// Require a bearer token on every route. Without this the server is
+    /// open, which is the single-user local posture.
+    pub fn with_auth(mut self, auth: crate::auth::TokenAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Name this instance's workspace for per-workspace token grants.
+    pub fn with_workspace(mut self, name: impl Into<String>) -> Self {
+        self.workspace = name.into();
+        self
+    }
+
+    /// Check `authorization` against the token table, if one is
+    /// configured. Every current route is a read.
+    fn authorize(&self, authorization: Option<&str>) -> Result<(), (u16, String)> {
+        match &self.auth {
+            Some(auth) => auth.authorize(authorization, &self.workspace, crate::auth::Scope::Read),
+            None => Ok(()),
+        }
+    }
+
+    /// Route one request to a `(status, JSON body)` pair. `authorization`
+    /// is the raw `Authorization` header value, if the client sent one.
+    pub fn respond(&self, method: &str, url: &str, authorization: Option<&str>) -> (u16, String) {
+        if let Err((status, message)) = self.authorize(authorization) {
+            return err_body(status, &message);
+        }
+        if method != "GET" {
+            return err_body(405, "only GET is supported");
+        }
+        let (path, params) = parse_query(url);
+        let get = |name: &str, default: &str| {
+            params.get(name).cloned().unwrap_or_else(|| default.to_string())
+        };
+        let result = match path {
+            "/api/commits" => {
+                let limit = get("limit", "50").parse().unwrap_or(50);
+                self.queries.commits(limit).map(|c| json!(c))
+            }
+            "/api/file" => {
+                let path = get("path", "");
+                if path.is_empty() {
+                    return err_body(400, "missing `path` parameter");
+                }
+                self.queries
+                    .file(&get("rev", "@"), &path)
+                    .map(|content| json!({ "content": content }))
+            }
+            "/api/diff" => self
+                .queries
+                .diff(&get("from", "@-"), &get("to", "@"))
+                .map(|diff| json!({ "diff": diff })),
+            "/api/bookmarks" => self.queries.bookmarks().map(|b| json!(b)),
+            "/api/status" => self.queries.status().map(|s| json!({ "status": s })),
+            other => return err_body(404, &format!("no route for `{other}`")),
+        };
+        match result {
+            Ok(value) => ok_body(value),
+            Err(e) => err_body(500, &e.to_string()),
+        }
+    }
+
+    /// Listen on `addr` (e.g. `127.0.0.1:7910`) until the process exits.
+    /// The listener needs the `http` feature; routing does not.
+    #[cfg(feature = "http")]
+    pub fn serve(&self, addr: &str) -> Result<(), AgentError> {
+        let server = tiny_http::Server::http(addr).map_err(|e| AgentError::Io {
+            path: addr.to_string(),
+            message: e.to_string(),
+        })?;
+        for request in server.incoming_requests() {
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Authorization"))
+                .map(|h| h.value.as_str().to_string());
+            if request.method().as_str() == "GET"
+                && request.url().split('?').next() == Some("/api/events")
+                && let Some(bus) = &self.events
+                && self.authorize(authorization.as_deref()).is_ok()
+            {
+                // A long-lived streaming response; tiny_http chunks it
+                // because no length is declared. Served on its own thread
+                // so one open event stream doesn't block the router.
+                let reader = SseReader {
+                    receiver: bus.subscribe(),
+                    pending: Vec::new(),
+                };
+                std::thread::spawn(move || {
+                    let header = tiny_http::Header::from_bytes(
+                        &b"Content-Type"[..],
+                        &b"text/event-stream"[..],
+                    )
+                    .expect("static header is valid");
+                    let response =
+                        tiny_http::Response::new(200.into(), vec![header], reader, None, None);
+                    let _ = request.respond(response);
+                });
+                continue;
+            }
+            let (status, body) =
+                self.respond(request.method().as_str(), request.url(), authorization.as_deref());
+            let header = tiny_http::Header::from_bytes(
+                &b"Content-Type"[..],
+                &b"application/json"[..],
+            )
+            .expect("static header is valid");
+            let response = tiny_http::Response::from_string(body)
+                .with_status_code(status)
+                .with_header(header);
+            let _ = request.respond(response);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    struct FakeQueries;
+
+    impl WorkspaceQueries for FakeQueries {
+        fn commits(&self, limit: usize) -> Result<Vec<CommitInfo>, AgentError> {
+            Ok((0..limit.min(2))
+                .map(|i| CommitInfo {
+                    change_id: format!("zx{i}"),
+                    description: format!("commit {i}"),
+                })
+                .collect())
+        }
+
+        fn file(&self, revision: &str, path: &str) -> Result<String, AgentError> {
+            if path == "missing.rs" {
+                return Err(AgentError::Vcs("no such file".to_string()));
+            }
+            Ok(format!("{path}@{revision}"))
+        }
+
+        fn diff(&self, from: &str, to: &str) -> Result<String, AgentError> {
+            Ok(format!("diff {from}..{to}"))
+        }
+
+        fn bookmarks(&self) -> Result<Vec<BookmarkInfo>, AgentError> {
+            Ok(vec![BookmarkInfo {
+                name: "main".to_string(),
+                target: "zx0".to_string(),
+            }])
+        }
+
+        fn status(&self) -> Result<String, AgentError> {
+            Ok("The working copy is clean".to_string())
+        }
+    }
+
+    fn get(url: &str) -> (u16, Value) {
+        let server = HttpServer::new(FakeQueries);
+        let (status, body) = server.respond("GET", url, None);
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[test]
+    fn routes_return_structured_json() {
+        let (status, body) = get("/api/commits?limit=1");
+        assert_eq!(status, 200);
+        assert_eq!(body["data"][0]["change_id"], "zx0");
+
+        let (_, body) = get("/api/file?path=src%2Flib.rs&rev=abc");
+        assert_eq!(body["data"]["content"], "src/lib.rs@abc");
+
+        let (_, body) = get("/api/diff");
+        assert_eq!(body["data"]["diff"], "diff @-..@");
+
+        let (_, body) = get("/api/bookmarks");
+        assert_eq!(body["data"][0]["name"], "main");
+
+        let (_, body) = get("/api/status");
+        assert_eq!(body["data"]["status"], "The working copy is clean");
+    }
+
+    #[test]
+    fn bad_requests_get_proper_status_codes() {
+        assert_eq!(get("/api/file").0, 400);
+        assert_eq!(get("/api/nope").0, 404);
+        let server = HttpServer::new(FakeQueries);
+        assert_eq!(server.respond("POST", "/api/status", None).0, 405);
+
+        let (status, body) = get("/api/file?path=missing.rs");
+        assert_eq!(status, 500);
+        assert_eq!(body["ok"], false);
+        assert!(body["error"].as_str().unwrap().contains("no such file"));
+    }
+
+    #[test]
+    fn auth_gates_every_route_by_scope_and_workspace() {
+        use crate::auth::{Scope, TokenAuth};
+        let server = HttpServer::new(FakeQueries)
+            .with_workspace("smithers")
+            .with_auth(
+                TokenAuth::new()
+                    .grant("viewer-token", [Scope::Read], ["smithers"])
+                    .grant("other-token", [Scope::Read], ["other"]),
+            );
+
+        assert_eq!(server.respond("GET", "/api/status", None).0, 401);
+        assert_eq!(
+            server.respond("GET", "/api/status", Some("Bearer bogus")).0,
+            401
+        );
+        assert_eq!(
+            server.respond("GET", "/api/status", Some("Bearer other-token")).0,
+            403
+        );
+        let (status, body) =
+            server.respond("GET", "/api/status", Some("Bearer viewer-token"));
+        assert_eq!(status, 200);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["data"]["status"], "The working copy is clean");
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn the_listener_answers_over_a_real_socket() {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let server = std::sync::Arc::new(HttpServer::new(FakeQueries));
+        let background = std::sync::Arc::clone(&server);
+        std::thread::spawn(move || background.serve(&addr.to_string()));
+
+        // The listener may need a moment to bind.
+        let mut stream = None;
+        for _ in 0..50 {
+            if let Ok(s) = std::net::TcpStream::connect(addr) {
+                stream = Some(s);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let mut stream = stream.expect("server came up");
+        write!(stream, "GET /api/status HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("The working copy is clean"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Git LFS pointer detection and resolution.
+//!
+//! A repo using LFS stores a three-line pointer where the file should
+//! be; an agent that reads one and "edits" it is corrupting metadata,
+//! not changing the asset. [`LfsPointer::parse`] recognizes pointers
+//! wherever blobs are read and hands back the structured oid/size
+//! instead. When a host configures an [`LfsClient`] (behind the
+//! `network` feature, like the providers), the real content can be
+//! fetched through the standard LFS batch API.
+
+#[cfg(feature = "network")]
+use std::io::Read;
+
+#[cfg(any(test, feature = "network"))]
+use crate::error::AgentError;
+#[cfg(any(test, feature = "network"))]
+use serde_json::{Value, json};
+
+/// The spec line every LFS pointer starts with.
+const LFS_SPEC: &str = "https://git-lfs.github.com/spec/v1";
+
+/// The metadata an LFS pointer file carries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LfsPointer {
+    /// SHA-256 of the real content, lowercase hex.
+    pub oid: String,
+    /// Real content size in bytes.
+    pub size: u64,
+}
+
+impl LfsPointer {
+    /// Parse `content` as an LFS pointer. `None` for ordinary files —
+    /// the version line, `oid sha256:`, and `size` must all be present,
+    /// in the key order the spec mandates.
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+        if lines.next()? != format!("version {LFS_SPEC}") {
+            return None;
+        }
+        let mut oid = None;
+        let mut size = None;
+        for line in lines {
+            if let Some(value) = line.strip_prefix("oid sha256:") {
+                oid = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("size ") {
+                size = value.trim().parse().ok();
+            }
+        }
+        Some(LfsPointer {
+            oid: oid.filter(|o| !o.is_empty())?,
+            size: size?,
+        })
+    }
+
+    /// Whether `content` is an LFS pointer at all — the cheap check for
+    /// read paths that only need to warn, not resolve.
+    pub fn is_pointer(content: &str) -> bool {
+        Self::parse(content).is_some()
+    }
+}
+
+/// Build the LFS batch-API download request for `pointer`.
+#[cfg(any(test, feature = "network"))]
+fn build_batch_body(pointer: &LfsPointer) -> Value {
+    json!({
+        "operation": "download",
+        "transfers": ["basic"],
+        "objects": [{ "oid": pointer.oid, "size": pointer.size }],
+    })
+}
+
+/// Extract the download href for `oid` from a batch-API response.
+#[cfg(any(test, feature = "network"))]
+fn parse_batch_response(body: &Value, oid: &str) -> Result<String, AgentError> {
+    let object = body["objects"]
+        .as_array()
+        .and_then(|objects| objects.iter().find(|o| o["oid"] == oid))
+        .ok_or_else(|| AgentError::Vcs(format!("lfs: server did not answer for oid {oid}")))?;
+    if let Some(message) = object["error"]["message"].as_str() {
+        return Err(AgentError::Vcs(format!("lfs: {message}")));
+    }
+    object["actions"]["download"]["href"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| AgentError::Vcs(format!("lfs: no download action for oid {oid}")))
+}
+
+/// Fetches real content for pointers through one LFS endpoint (the
+/// `.../info/lfs` URL a git remote advertises).
+#[cfg(feature = "network")]
+pub struct LfsClient {
+    endpoint: String,
+}
+
+#[cfg(feature = "network")]
+impl LfsClient {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        LfsClient {
+            endpoint: endpoint.into(),
+        }
+    }
+
+    /// The real bytes behind `pointer`: one batch-API round trip for the
+    /// download URL, then the download itself.
+    pub fn fetch(&self, pointer: &LfsPointer) -> Result<Vec<u8>, AgentError> {
+        let vcs_err = |e: ureq::Error| AgentError::Vcs(format!("lfs: {e}"));
+        let response = ureq::post(&format!("{}/objects/batch", self.endpoint))
+            .set("Accept", "application/vnd.git-lfs+json")
+            .set("Content-Type", "application/vnd.git-lfs+json")
+            .send_json(build_batch_body(pointer))
+            .map_err(vcs_err)?;
+        let body: Value = response
+            .into_json()
+            .map_err(|e| AgentError::Vcs(format!("lfs: {e}")))?;
+        let href = parse_batch_response(&body, &pointer.oid)?;
+        let mut content = Vec::new();
+        ureq::get(&href)
+            .call()
+            .map_err(vcs_err)?
+            .into_reader()
+            .read_to_end(&mut content)
+            .map_err(|e| AgentError::Vcs(format!("lfs: {e}")))?;
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const POINTER: &str = "version https://git-lfs.github.com/spec/v1\n\
+        oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n\
+        size 12345\n";
+
+    #[test]
+    fn pointers_parse_and_ordinary_files_do_not() {
+        let pointer = LfsPointer::parse(POINTER).unwrap();
+        assert_eq!(
+            pointer.oid,
+            "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393"
+        );
+        assert_eq!(pointer.size, 12345);
+        assert!(LfsPointer::is_pointer(POINTER));
+
+        assert!(!LfsPointer::is_pointer("fn main() {}\n"));
+        // Prose mentioning the spec URL mid-file isn't a pointer.
+        assert!(!LfsPointer::is_pointer("see https://git-lfs.github.com/spec/v1\n"));
+        // A version line without oid/size is malformed, not a pointer.
+        assert!(!LfsPointer::is_pointer("version https://git-lfs.github.com/spec/v1\n"));
+    }
+
+    #[test]
+    fn the_batch_round_trip_builds_and_parses() {
+        let pointer = LfsPointer::parse(POINTER).unwrap();
+        let body = build_batch_body(&pointer);
+        assert_eq!(body["operation"], "download");
+        assert_eq!(body["objects"][0]["oid"], pointer.oid.as_str());
+        assert_eq!(body["objects"][0]["size"], 12345);
+
+        let href = parse_batch_response(
+            &json!({ "objects": [{
+                "oid": pointer.oid,
+                "actions": { "download": { "href": "https://cdn.example.com/obj" } },
+            }] }),
+            &pointer.oid,
+        )
+        .unwrap();
+        assert_eq!(href, "https://cdn.example.com/obj");
+
+        let err = parse_batch_response(
+            &json!({ "objects": [{ "oid": pointer.oid, "error": { "message": "not found" } }] }),
+            &pointer.oid,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("lfs: not found"));
+    }
+}
@@ -0,0 +1,113 @@
+//! agent-runtime — executes prompts end-to-end.
+//!
+//! [`prompt-parser`](prompt_parser) stops at the provider boundary: it
+//! parses, validates, and renders, but never sends anything. This crate is
+//! the other half — durable [`Session`]s, provider clients, tool dispatch,
+//! and the agent loop for `type: agent` prompts.
+//!
+//! Compiled as a static library and linked into libsmithers alongside
+//! prompt-parser.
+
+mod accounting;
+mod agent;
+mod analytics;
+mod auth;
+mod batch;
+mod bisect;
+mod bundle;
+mod cache;
+mod checkpoint;
+mod context;
+mod conventional;
+mod embed;
+mod error;
+mod eval;
+mod events;
+#[cfg(feature = "history")]
+mod history;
+mod hooks;
+mod http;
+mod lfs;
+mod lru;
+mod manifest;
+mod mcp;
+mod mock;
+mod orchestrate;
+mod patch;
+mod policy;
+mod provenance;
+mod provider;
+mod redact;
+mod repair;
+mod runner;
+mod sandbox;
+mod secrets;
+mod session;
+mod shell;
+mod signing;
+mod stream;
+mod telemetry;
+mod timetravel;
+mod trailer;
+mod treehash;
+mod warn;
+
+pub use accounting::{SpendReport, UsageEvent, UsageTotals};
+pub use agent::{
+    AgentRun, LimitKind, RunLimits, RunOutcome, TurnRecord, run_agent, run_agent_checkpointed,
+    run_agent_limited,
+};
+pub use analytics::{
+    ActivityReport, CommitActivity, ContributorStats, DirChurn, TimeBucket, analyze,
+};
+pub use auth::{Scope, TokenAuth};
+pub use batch::{DEFAULT_FETCH_PARALLELISM, fetch_files};
+pub use bisect::{BisectResult, Bisector};
+pub use bundle::{BundleEntry, SnapshotBundle};
+pub use cache::{
+    CachingProvider, ExecutionCache, ExecutionCacheStats, cache_key, request_fingerprint,
+};
+pub use checkpoint::{Checkpoint, Checkpointer, JjCli, VcsBackends, WorkspaceVcs};
+pub use context::{ContextPacker, PackedContext, PackedSnippet};
+pub use conventional::{CommitConvention, ConventionalCommit};
+pub use embed::{
+    ChunkRecord, EmbeddingIndex, EmbeddingProvider, IndexReport, SearchHit, chunk_lines,
+    register_semantic_search,
+};
+pub use error::AgentError;
+pub use eval::{EvalCase, EvalOutcome, EvalReport, EvalSuite};
+pub use events::{EventBus, RepoEvent, RepoEventKind, RepoWatcher, sse_frame};
+#[cfg(feature = "history")]
+pub use history::{HistoryMessage, HistoryOutcome, HistoryStore, HistoryToolCall};
+pub use hooks::{HookOutcome, HookStage, HookVerdict, Hooks, HostHook};
+pub use http::{BookmarkInfo, CommitInfo, HttpServer, WorkspaceQueries};
+#[cfg(feature = "network")]
+pub use lfs::LfsClient;
+pub use lfs::LfsPointer;
+pub use lru::{CachedWorkspace, DEFAULT_LRU_CAPACITY};
+pub use manifest::{ManifestEntry, SnapshotManifest};
+pub use mcp::{McpServer, McpWorkspace};
+pub use mock::{MockProvider, text_response, tool_call_response};
+pub use orchestrate::{DEFAULT_MAX_DEPTH, register_delegate_tool};
+pub use patch::{FileChange, FileChangeKind, SessionPatch, TreeSnapshot, session_patch};
+pub use policy::{ChangePolicy, PolicyViolation};
+pub use provenance::SessionProvenance;
+pub use provider::{
+    Provider, ProviderRequest, ProviderResponse, StopReason, ToolCallRequest, ToolSpec, Usage,
+};
+#[cfg(feature = "network")]
+pub use provider::{AnthropicProvider, OpenAiProvider, provider_for};
+pub use redact::{REDACTED, Redactor};
+pub use repair::{DEFAULT_MAX_REPAIRS, RepairAttempt, StructuredOutput, complete_structured};
+pub use runner::{ToolResult, ToolRunner};
+pub use sandbox::{PathSandbox, register_file_tools};
+pub use secrets::{SecretFinding, scan_patch};
+pub use session::{Session, SessionStatus, SessionStore, ToolCallRecord};
+pub use shell::{ShellConfig, ShellOutcome, register_shell_tool, run_shell};
+pub use signing::{CommitSignature, SignatureState};
+pub use stream::{StreamAccumulator, StreamEvent};
+pub use telemetry::{HostLogSink, LogEvent, TelemetrySubscriber, install_telemetry};
+pub use timetravel::FileAtTime;
+pub use trailer::{CommitDescription, Trailer};
+pub use treehash::{TreeHashCache, TreeHashes};
+pub use warn::Warned;
@@ -0,0 +1,211 @@
+//! Bounded LRU cache over immutable workspace reads.
+//!
+//! The jj CLI re-reads and re-parses the same objects on every call:
+//! listing changes twice shells out twice, showing the same file at the
+//! same revision twice pays the full cost twice. File contents and diffs
+//! at a *pinned* revision never change, so [`CachedWorkspace`] wraps any
+//! [`WorkspaceQueries`] and memoizes those reads in a bounded LRU shared
+//! across calls on the handle. Anything involving the working copy (`@`)
+//! or the mutable log — `commits`, `bookmarks`, `status` — always passes
+//! through.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use crate::error::AgentError;
+use crate::http::{BookmarkInfo, CommitInfo, WorkspaceQueries};
+
+/// Entries a [`CachedWorkspace`] holds per cache before evicting, by
+/// default.
+pub const DEFAULT_LRU_CAPACITY: usize = 256;
+
+/// A minimal LRU: a map of key → (value, last-use tick). Eviction scans
+/// for the stalest tick — linear, but capacities here are small and the
+/// alternative is another dependency.
+struct Lru<K, V> {
+    entries: HashMap<K, (V, u64)>,
+    capacity: usize,
+    tick: u64,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (value, last_used) = self.entries.get_mut(key)?;
+        *last_used = tick;
+        Some(value.clone())
+    }
+
+    fn put(&mut self, key: K, value: V) {
+        self.tick += 1;
+        if self.entries.len() >= self.capacity
+            && !self.entries.contains_key(&key)
+            && let Some(stalest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, tick))| *tick)
+                .map(|(k, _)| k.clone())
+        {
+            self.entries.remove(&stalest);
+        }
+        self.entries.insert(key, (value, self.tick));
+    }
+}
+
+/// A [`WorkspaceQueries`] wrapper that caches file and diff reads at
+/// pinned revisions. Reads that name the working copy bypass the cache,
+/// since `@` moves under the handle.
+pub struct CachedWorkspace<Q> {
+    inner: Q,
+    files: Mutex<Lru<(String, String), String>>,
+    diffs: Mutex<Lru<(String, String), String>>,
+}
+
+impl<Q> CachedWorkspace<Q> {
+    pub fn new(inner: Q) -> Self {
+        Self::with_capacity(inner, DEFAULT_LRU_CAPACITY)
+    }
+
+    pub fn with_capacity(inner: Q, capacity: usize) -> Self {
+        Self {
+            inner,
+            files: Mutex::new(Lru::new(capacity)),
+            diffs: Mutex::new(Lru::new(capacity)),
+        }
+    }
+}
+
+/// Revisions that move between calls must not be cached.
+fn is_pinned(revision: &str) -> bool {
+    !revision.contains('@')
+}
+
+impl<Q: WorkspaceQueries> WorkspaceQueries for CachedWorkspace<Q> {
+    fn commits(&self, limit: usize) -> Result<Vec<CommitInfo>, AgentError> {
+        self.inner.commits(limit)
+    }
+
+    fn file(&self, revision: &str, path: &str) -> Result<String, AgentError> {
+        if !is_pinned(revision) {
+            return self.inner.file(revision, path);
+        }
+        let key = (revision.to_string(), path.to_string());
+        if let Some(hit) = self.files.lock().unwrap().get(&key) {
+            return Ok(hit);
+        }
+        let content = self.inner.file(revision, path)?;
+        self.files.lock().unwrap().put(key, content.clone());
+        Ok(content)
+    }
+
+    fn diff(&self, from: &str, to: &str) -> Result<String, AgentError> {
+        if !is_pinned(from) || !is_pinned(to) {
+            return self.inner.diff(from, to);
+        }
+        let key = (from.to_string(), to.to_string());
+        if let Some(hit) = self.diffs.lock().unwrap().get(&key) {
+            return Ok(hit);
+        }
+        let diff = self.inner.diff(from, to)?;
+        self.diffs.lock().unwrap().put(key, diff.clone());
+        Ok(diff)
+    }
+
+    fn bookmarks(&self) -> Result<Vec<BookmarkInfo>, AgentError> {
+        self.inner.bookmarks()
+    }
+
+    fn status(&self) -> Result<String, AgentError> {
+        self.inner.status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting {
+        reads: AtomicUsize,
+    }
+
+    impl WorkspaceQueries for Counting {
+        fn commits(&self, _limit: usize) -> Result<Vec<CommitInfo>, AgentError> {
+            Ok(Vec::new())
+        }
+
+        fn file(&self, revision: &str, path: &str) -> Result<String, AgentError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("{path} at {revision}"))
+        }
+
+        fn diff(&self, from: &str, to: &str) -> Result<String, AgentError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("{from}..{to}"))
+        }
+
+        fn bookmarks(&self) -> Result<Vec<BookmarkInfo>, AgentError> {
+            Ok(Vec::new())
+        }
+
+        fn status(&self) -> Result<String, AgentError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok("clean".to_string())
+        }
+    }
+
+    fn counting() -> CachedWorkspace<Counting> {
+        CachedWorkspace::new(Counting {
+            reads: AtomicUsize::new(0),
+        })
+    }
+
+    #[test]
+    fn pinned_reads_hit_the_store_once() {
+        let ws = counting();
+        assert_eq!(ws.file("abc123", "src/lib.rs").unwrap(), "src/lib.rs at abc123");
+        assert_eq!(ws.file("abc123", "src/lib.rs").unwrap(), "src/lib.rs at abc123");
+        assert_eq!(ws.diff("abc123", "def456").unwrap(), "abc123..def456");
+        ws.diff("abc123", "def456").unwrap();
+        assert_eq!(ws.inner.reads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn working_copy_reads_always_pass_through() {
+        let ws = counting();
+        ws.file("@", "src/lib.rs").unwrap();
+        ws.file("@", "src/lib.rs").unwrap();
+        ws.diff("@-", "@").unwrap();
+        ws.status().unwrap();
+        ws.status().unwrap();
+        assert_eq!(ws.inner.reads.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn the_cache_is_bounded_and_evicts_the_stalest_entry() {
+        let ws = CachedWorkspace::with_capacity(
+            Counting {
+                reads: AtomicUsize::new(0),
+            },
+            2,
+        );
+        ws.file("r1", "a").unwrap();
+        ws.file("r2", "b").unwrap();
+        ws.file("r1", "a").unwrap(); // refresh a: b is now stalest
+        ws.file("r3", "c").unwrap(); // evicts b
+        ws.file("r1", "a").unwrap(); // still cached
+        ws.file("r2", "b").unwrap(); // re-read
+        assert_eq!(ws.inner.reads.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,158 @@
+//! Exportable snapshot manifests.
+//!
+//! An external system — a mirror, an auditor, a deploy pipeline —
+//! shouldn't need to speak the workspace's store format to answer "is
+//! this the tree you say it is?". A [`SnapshotManifest`] is a canonical
+//! JSON document for one revision: every path with its SHA-256 blob
+//! hash and size, plus a root hash over the whole listing. SHA-256
+//! rather than the in-process hashes elsewhere in this crate, because a
+//! manifest is only useful if the other side can recompute it.
+//!
+//! Snapshots don't record file modes (they hold UTF-8 text captured
+//! through the tree walk), so the manifest doesn't claim any.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::patch::TreeSnapshot;
+
+/// One file in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// SHA-256 of the file content, lowercase hex.
+    pub hash: String,
+    /// Content size in bytes.
+    pub size: u64,
+}
+
+/// A canonical, externally verifiable description of one tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// The revision this tree was captured at, when the caller knows it.
+    pub revision: Option<String>,
+    /// SHA-256 over the sorted `path\0hash\n` listing, lowercase hex.
+    pub root: String,
+    /// Path → entry, sorted by path (and serialized in that order).
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+impl SnapshotManifest {
+    /// Manifest for a snapshot at a known revision.
+    pub fn for_revision(revision: impl Into<String>, snapshot: &TreeSnapshot) -> Self {
+        let mut manifest = Self::from_snapshot(snapshot);
+        manifest.revision = Some(revision.into());
+        manifest
+    }
+
+    /// Manifest for a bare snapshot.
+    pub fn from_snapshot(snapshot: &TreeSnapshot) -> Self {
+        let files: BTreeMap<String, ManifestEntry> = snapshot
+            .files()
+            .iter()
+            .map(|(path, content)| {
+                (
+                    path.clone(),
+                    ManifestEntry {
+                        hash: sha256_hex(content.as_bytes()),
+                        size: content.len() as u64,
+                    },
+                )
+            })
+            .collect();
+        let mut listing = String::new();
+        for (path, entry) in &files {
+            listing.push_str(path);
+            listing.push('\0');
+            listing.push_str(&entry.hash);
+            listing.push('\n');
+        }
+        SnapshotManifest {
+            revision: None,
+            root: sha256_hex(listing.as_bytes()),
+            files,
+        }
+    }
+
+    /// The canonical wire form: JSON with sorted keys and no extra
+    /// whitespace, so two parties hashing it agree byte for byte.
+    pub fn canonical_json(&self) -> String {
+        serde_json::to_string(self).expect("manifest serializes")
+    }
+
+    /// Check a snapshot against this manifest; each mismatch is one
+    /// human-readable line. Empty means the trees are identical.
+    pub fn verify(&self, snapshot: &TreeSnapshot) -> Vec<String> {
+        let theirs = Self::from_snapshot(snapshot);
+        let mut mismatches = Vec::new();
+        for (path, entry) in &self.files {
+            match theirs.files.get(path) {
+                None => mismatches.push(format!("missing: {path}")),
+                Some(other) if other != entry => {
+                    mismatches.push(format!("content differs: {path}"));
+                }
+                Some(_) => {}
+            }
+        }
+        for path in theirs.files.keys() {
+            if !self.files.contains_key(path) {
+                mismatches.push(format!("unexpected: {path}"));
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn snapshot(files: &[(&str, &str)]) -> TreeSnapshot {
+        TreeSnapshot::from_files(
+            files.iter().map(|(p, c)| (p.to_string(), c.to_string())),
+        )
+    }
+
+    #[test]
+    fn manifests_are_canonical_and_recomputable() {
+        let tree = snapshot(&[("src/lib.rs", "fn main() {}\n"), ("README.md", "# hi\n")]);
+        let manifest = SnapshotManifest::for_revision("zx1", &tree);
+
+        assert_eq!(manifest.files.len(), 2);
+        // Well-known digest, checkable with `printf '# hi\n' | sha256sum`.
+        assert_eq!(
+            manifest.files["README.md"].hash,
+            "045d2d07c2db3b9e6cef022457ee89434045a508c2dadccf9abe182ad633c273"
+        );
+        assert_eq!(manifest.files["README.md"].size, 5);
+
+        // Same tree, same bytes — regardless of construction order.
+        let again = SnapshotManifest::for_revision(
+            "zx1",
+            &snapshot(&[("README.md", "# hi\n"), ("src/lib.rs", "fn main() {}\n")]),
+        );
+        assert_eq!(manifest.canonical_json(), again.canonical_json());
+        assert_eq!(manifest.root, again.root);
+    }
+
+    #[test]
+    fn verification_names_every_divergence() {
+        let manifest =
+            SnapshotManifest::from_snapshot(&snapshot(&[("a.txt", "one\n"), ("b.txt", "two\n")]));
+        let mismatches =
+            manifest.verify(&snapshot(&[("a.txt", "changed\n"), ("c.txt", "new\n")]));
+        assert_eq!(
+            mismatches,
+            ["content differs: a.txt", "missing: b.txt", "unexpected: c.txt"]
+        );
+        assert!(manifest.verify(&snapshot(&[("a.txt", "one\n"), ("b.txt", "two\n")])).is_empty());
+    }
+}
@@ -0,0 +1,450 @@
+//! Model Context Protocol server over the workspace.
+//!
+//! External MCP-capable clients get the same jj layer the built-in tools
+//! use: list changes, read a file at a revision, diff, snapshot, restore.
+//! The protocol is JSON-RPC 2.0; [`McpServer::handle_message`] maps one
+//! request line to one response and is transport-agnostic — the stdio loop
+//! here reads newline-delimited messages, and the HTTP layer reuses the
+//! same handler for its SSE transport. Tool failures follow the MCP
+//! convention: an `isError` result, not a protocol error.
+
+use std::io::{BufRead, Write};
+
+use serde_json::{Value, json};
+
+use crate::checkpoint::JjCli;
+use crate::error::AgentError;
+
+/// The workspace operations the server exposes as tools.
+pub trait McpWorkspace {
+    /// Human-readable change log.
+    fn list_changes(&self) -> Result<String, AgentError>;
+    /// A file's content at a revision.
+    fn read_file_at(&self, revision: &str, path: &str) -> Result<String, AgentError>;
+    /// Diff between two revisions.
+    fn diff(&self, from: &str, to: &str) -> Result<String, AgentError>;
+    /// Snapshot the working copy; returns the operation id.
+    fn snapshot(&self) -> Result<String, AgentError>;
+    /// Restore a previously returned snapshot id.
+    fn restore(&self, snapshot_id: &str) -> Result<(), AgentError>;
+}
+
+impl McpWorkspace for JjCli {
+    fn list_changes(&self) -> Result<String, AgentError> {
+        self.jj(&["log", "--no-graph"])
+    }
+
+    fn read_file_at(&self, revision: &str, path: &str) -> Result<String, AgentError> {
+        self.jj(&["file", "show", "-r", revision, path])
+    }
+
+    fn diff(&self, from: &str, to: &str) -> Result<String, AgentError> {
+        self.jj(&["diff", "--from", from, "--to", to])
+    }
+
+    fn snapshot(&self) -> Result<String, AgentError> {
+        self.jj(&["status"])?;
+        self.jj(&["operation", "log", "--no-graph", "-n", "1", "-T", "id"])
+    }
+
+    fn restore(&self, snapshot_id: &str) -> Result<(), AgentError> {
+        self.jj(&["operation", "restore", snapshot_id]).map(|_| ())
+    }
+}
+
+/// The server. One workspace, any transport. `Sync` so `read_files` can
+/// fan its fetches out across threads.
+pub struct McpServer {
+    workspace: Box<dyn McpWorkspace + Send + Sync>,
+}
+
+fn string_arg(arguments: &Value, name: &str, default: &str) -> String {
+    arguments[name].as_str().unwrap_or(default).to_string()
+}
+
+/// The advertised tool descriptors, in `tools/list` shape.
+fn tool_descriptors() -> Value {
+    let tool = |name: &str, description: &str, schema: Value| {
+        json!({ "name": name, "description": description, "inputSchema": schema })
+    };
+    let empty = json!({ "type": "object", "properties": {}, "additionalProperties": false });
+    json!([
+        tool("list_changes", "List the workspace's changes (jj log)", empty.clone()),
+        tool(
+            "read_file",
+            "Read a file's content at a revision",
+            json!({
+                "type": "object",
+                "properties": {
+                    "revision": { "type": "string" },
+                    "path": { "type": "string" },
+                },
+                "required": ["path"],
+                "additionalProperties": false,
+            })
+        ),
+        tool(
+            "read_files",
+            "Read several files in one call; contents come back in request order",
+            json!({
+                "type": "object",
+                "properties": {
+                    "files": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "revision": { "type": "string" },
+                                "path": { "type": "string" },
+                            },
+                            "required": ["path"],
+                        },
+                    },
+                },
+                "required": ["files"],
+                "additionalProperties": false,
+            })
+        ),
+        tool(
+            "diff",
+            "Diff between two revisions",
+            json!({
+                "type": "object",
+                "properties": {
+                    "from": { "type": "string" },
+                    "to": { "type": "string" },
+                },
+                "additionalProperties": false,
+            })
+        ),
+        tool(
+            "lint_commit",
+            "Lint a commit description against the conventional-commit format",
+            json!({
+                "type": "object",
+                "properties": { "description": { "type": "string" } },
+                "required": ["description"],
+                "additionalProperties": false,
+            })
+        ),
+        tool("snapshot", "Snapshot the working copy; returns the operation id", empty.clone()),
+        tool(
+            "restore",
+            "Restore the working copy to a snapshot",
+            json!({
+                "type": "object",
+                "properties": { "snapshot_id": { "type": "string" } },
+                "required": ["snapshot_id"],
+                "additionalProperties": false,
+            })
+        ),
+    ])
+}
+
+impl McpServer {
+    pub fn new(workspace: impl McpWorkspace + Send + Sync + 'static) -> Self {
+        McpServer {
+            workspace: Box::new(workspace),
+        }
+    }
+
+    /// Handle one JSON-RPC message; `None` for notifications (which get no
+    /// response).
+    pub fn handle_message(&self, message: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(message) {
+            Ok(v) => v,
+            Err(e) => {
+                return Some(
+                    error_response(Value::Null, -32700, &format!("parse error: {e}")),
+                );
+            }
+        };
+        let id = request["id"].clone();
+        if id.is_null() {
+            // Notification (e.g. notifications/initialized): nothing to say.
+            return None;
+        }
+        let response = match request["method"].as_str().unwrap_or_default() {
+            "initialize" => json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "agent-runtime", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "tools/list" => json!({ "tools": tool_descriptors() }),
+            "tools/call" => {
+                let name = request["params"]["name"].as_str().unwrap_or_default();
+                let arguments = &request["params"]["arguments"];
+                return Some(self.call_tool(id, name, arguments));
+            }
+            "ping" => json!({}),
+            other => return Some(error_response(id, -32601, &format!("unknown method `{other}`"))),
+        };
+        Some(success_response(id, response))
+    }
+
+    fn call_tool(&self, id: Value, name: &str, arguments: &Value) -> String {
+        let outcome = match name {
+            "list_changes" => self.workspace.list_changes(),
+            "read_file" => self.workspace.read_file_at(
+                &string_arg(arguments, "revision", "@"),
+                &string_arg(arguments, "path", ""),
+            ),
+            "read_files" => self.read_files(arguments),
+            "diff" => self.workspace.diff(
+                &string_arg(arguments, "from", "@-"),
+                &string_arg(arguments, "to", "@"),
+            ),
+            "lint_commit" => Ok(crate::conventional::lint_report(&string_arg(
+                arguments,
+                "description",
+                "",
+            ))
+            .to_string()),
+            "snapshot" => self.workspace.snapshot(),
+            "restore" => self
+                .workspace
+                .restore(&string_arg(arguments, "snapshot_id", ""))
+                .map(|()| "restored".to_string()),
+            other => {
+                return error_response(id, -32602, &format!("unknown tool `{other}`"));
+            }
+        };
+        let (text, is_error) = match outcome {
+            Ok(text) => (text, false),
+            Err(e) => (e.to_string(), true),
+        };
+        success_response(
+            id,
+            json!({
+                "content": [{ "type": "text", "text": text }],
+                "isError": is_error,
+            }),
+        )
+    }
+
+    /// Fetch every requested file concurrently (bounded), one JSON entry
+    /// per request in request order — a missing file errors its own
+    /// entry, not the call.
+    fn read_files(&self, arguments: &Value) -> Result<String, AgentError> {
+        let requests: Vec<(String, String)> = arguments["files"]
+            .as_array()
+            .ok_or_else(|| AgentError::Vcs("`files` must be an array".to_string()))?
+            .iter()
+            .map(|f| {
+                (
+                    f["revision"].as_str().unwrap_or("@").to_string(),
+                    f["path"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect();
+        let contents = crate::batch::fetch_with(
+            &requests,
+            crate::batch::DEFAULT_FETCH_PARALLELISM,
+            |(revision, path)| self.workspace.read_file_at(revision, path),
+        );
+        let entries: Vec<Value> = requests
+            .iter()
+            .zip(contents)
+            .map(|((revision, path), content)| match content {
+                Ok(content) => {
+                    json!({ "path": path, "revision": revision, "content": content })
+                }
+                Err(e) => json!({ "path": path, "revision": revision, "error": e.to_string() }),
+            })
+            .collect();
+        Ok(json!(entries).to_string())
+    }
+
+    /// Serve newline-delimited JSON-RPC until `input` closes.
+    pub fn serve(
+        &self,
+        input: impl BufRead,
+        mut output: impl Write,
+    ) -> Result<(), AgentError> {
+        for line in input.lines() {
+            let line = line.map_err(|e| AgentError::Io {
+                path: "<mcp stdin>".to_string(),
+                message: e.to_string(),
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line) {
+                writeln!(output, "{response}").map_err(|e| AgentError::Io {
+                    path: "<mcp stdout>".to_string(),
+                    message: e.to_string(),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// [`Self::serve`] over this process's stdio.
+    pub fn serve_stdio(&self) -> Result<(), AgentError> {
+        self.serve(std::io::stdin().lock(), std::io::stdout().lock())
+    }
+}
+
+fn success_response(id: Value, result: Value) -> String {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string()
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    struct FakeWorkspace;
+
+    impl McpWorkspace for FakeWorkspace {
+        fn list_changes(&self) -> Result<String, AgentError> {
+            Ok("zxq initial commit".to_string())
+        }
+
+        fn read_file_at(&self, revision: &str, path: &str) -> Result<String, AgentError> {
+            Ok(format!("{path}@{revision}: contents"))
+        }
+
+        fn diff(&self, from: &str, to: &str) -> Result<String, AgentError> {
+            Ok(format!("diff {from}..{to}"))
+        }
+
+        fn snapshot(&self) -> Result<String, AgentError> {
+            Ok("op1".to_string())
+        }
+
+        fn restore(&self, snapshot_id: &str) -> Result<(), AgentError> {
+            if snapshot_id == "op1" {
+                Ok(())
+            } else {
+                Err(AgentError::Vcs(format!("unknown snapshot `{snapshot_id}`")))
+            }
+        }
+    }
+
+    fn respond(request: Value) -> Value {
+        let server = McpServer::new(FakeWorkspace);
+        let response = server.handle_message(&request.to_string()).expect("a response");
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn initialize_and_tools_list_advertise_the_surface() {
+        let init = respond(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }));
+        assert_eq!(init["result"]["serverInfo"]["name"], "agent-runtime");
+
+        let list = respond(json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }));
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "list_changes",
+                "read_file",
+                "read_files",
+                "diff",
+                "lint_commit",
+                "snapshot",
+                "restore",
+            ]
+        );
+    }
+
+    #[test]
+    fn tool_calls_dispatch_with_defaults_and_errors_are_flagged() {
+        let read = respond(json!({
+            "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+            "params": { "name": "read_file", "arguments": { "path": "src/lib.rs" } },
+        }));
+        assert_eq!(read["result"]["content"][0]["text"], "src/lib.rs@@: contents");
+        assert_eq!(read["result"]["isError"], false);
+
+        let restore = respond(json!({
+            "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+            "params": { "name": "restore", "arguments": { "snapshot_id": "bogus" } },
+        }));
+        assert_eq!(restore["result"]["isError"], true);
+        assert!(
+            restore["result"]["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .contains("unknown snapshot")
+        );
+    }
+
+    #[test]
+    fn read_files_answers_in_request_order_with_per_file_errors() {
+        let batch = respond(json!({
+            "jsonrpc": "2.0", "id": 6, "method": "tools/call",
+            "params": { "name": "read_files", "arguments": { "files": [
+                { "path": "b.rs", "revision": "zx1" },
+                { "path": "a.rs" },
+            ] } },
+        }));
+        assert_eq!(batch["result"]["isError"], false);
+        let entries: Value =
+            serde_json::from_str(batch["result"]["content"][0]["text"].as_str().unwrap())
+                .unwrap();
+        assert_eq!(entries[0]["content"], "b.rs@zx1: contents");
+        assert_eq!(entries[1]["content"], "a.rs@@: contents");
+
+        let bad = respond(json!({
+            "jsonrpc": "2.0", "id": 7, "method": "tools/call",
+            "params": { "name": "read_files", "arguments": { "files": "nope" } },
+        }));
+        assert_eq!(bad["result"]["isError"], true);
+    }
+
+    #[test]
+    fn protocol_errors_and_notifications_follow_json_rpc() {
+        let server = McpServer::new(FakeWorkspace);
+        assert!(
+            server
+                .handle_message("{ not json")
+                .unwrap()
+                .contains("-32700")
+        );
+        let unknown = respond(json!({ "jsonrpc": "2.0", "id": 5, "method": "resources/list" }));
+        assert_eq!(unknown["error"]["code"], -32601);
+        assert!(
+            server
+                .handle_message(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }).to_string())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn the_stdio_loop_answers_line_by_line() {
+        let server = McpServer::new(FakeWorkspace);
+        let input = format!(
+            "{}\n{}\n",
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }),
+            json!({
+                "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                "params": { "name": "snapshot", "arguments": {} },
+            }),
+        );
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).unwrap();
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["result"]["content"][0]["text"], "op1");
+    }
+}
@@ -0,0 +1,279 @@
+//! Deterministic provider for hermetic tests.
+//!
+//! Every test that exercises the agent loop needs a provider, and the
+//! real ones need a network and a key. [`MockProvider`] answers from
+//! canned responses instead: a plain queue for sequential scripting,
+//! substring rules matched against the request's messages, and exact
+//! request-fingerprint rules (see
+//! [`request_fingerprint`](crate::cache::request_fingerprint)). Streamed
+//! calls replay the canned response as a plausible event sequence —
+//! text deltas, tool-call deltas, usage, stop — so stream consumers are
+//! testable too. Requests are recorded for assertions.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::cache::request_fingerprint;
+use crate::error::AgentError;
+use crate::provider::{
+    Provider, ProviderRequest, ProviderResponse, StopReason, ToolCallRequest, Usage,
+};
+use crate::stream::StreamEvent;
+
+enum Rule {
+    /// Matches when any message (or the system prompt) contains the text.
+    Contains(String),
+    /// Matches the exact request fingerprint.
+    Fingerprint(u64),
+}
+
+struct Scripted {
+    rule: Rule,
+    responses: VecDeque<ProviderResponse>,
+}
+
+struct State {
+    rules: Vec<Scripted>,
+    queue: VecDeque<ProviderResponse>,
+    requests: Vec<ProviderRequest>,
+}
+
+/// A scripted [`Provider`]. Construction is builder-style; the provider
+/// is then immutable and thread-safe.
+pub struct MockProvider {
+    state: Mutex<State>,
+}
+
+/// A canned text response.
+pub fn text_response(text: impl Into<String>) -> ProviderResponse {
+    ProviderResponse {
+        text: text.into(),
+        tool_calls: Vec::new(),
+        usage: Usage::default(),
+        stop_reason: StopReason::EndTurn,
+    }
+}
+
+/// A canned response requesting one tool call.
+pub fn tool_call_response(name: impl Into<String>, arguments: serde_json::Value) -> ProviderResponse {
+    let name = name.into();
+    ProviderResponse {
+        text: String::new(),
+        tool_calls: vec![ToolCallRequest {
+            id: format!("mock-{name}"),
+            name,
+            arguments,
+        }],
+        usage: Usage::default(),
+        stop_reason: StopReason::ToolUse,
+    }
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        MockProvider {
+            state: Mutex::new(State {
+                rules: Vec::new(),
+                queue: VecDeque::new(),
+                requests: Vec::new(),
+            }),
+        }
+    }
+
+    /// Queue a response served to the next otherwise-unmatched request.
+    pub fn reply(self, response: ProviderResponse) -> Self {
+        self.state.lock().unwrap().queue.push_back(response);
+        self
+    }
+
+    /// Serve `response` when a message contains `text`. Repeated calls
+    /// with the same text queue further responses on the same rule.
+    pub fn when_contains(self, text: impl Into<String>, response: ProviderResponse) -> Self {
+        let text = text.into();
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(scripted) = state.rules.iter_mut().find(
+                |s| matches!(&s.rule, Rule::Contains(existing) if *existing == text),
+            ) {
+                scripted.responses.push_back(response);
+            } else {
+                state.rules.push(Scripted {
+                    rule: Rule::Contains(text),
+                    responses: VecDeque::from([response]),
+                });
+            }
+        }
+        self
+    }
+
+    /// Serve `response` to the request with this exact fingerprint.
+    pub fn when_fingerprint(self, fingerprint: u64, response: ProviderResponse) -> Self {
+        self.state.lock().unwrap().rules.push(Scripted {
+            rule: Rule::Fingerprint(fingerprint),
+            responses: VecDeque::from([response]),
+        });
+        self
+    }
+
+    /// Every request served so far, in order.
+    pub fn requests(&self) -> Vec<ProviderRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    fn next_response(&self, request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(request.clone());
+        let fingerprint = request_fingerprint(request);
+        for scripted in &mut state.rules {
+            let matched = match &scripted.rule {
+                Rule::Contains(text) => {
+                    request.system.as_deref().is_some_and(|s| s.contains(text))
+                        || request.messages.iter().any(|m| m.content.contains(text))
+                }
+                Rule::Fingerprint(f) => *f == fingerprint,
+            };
+            if matched && let Some(response) = scripted.responses.pop_front() {
+                return Ok(response);
+            }
+        }
+        state.queue.pop_front().ok_or_else(|| AgentError::Provider {
+            provider: "mock".to_string(),
+            message: "no scripted response matches the request".to_string(),
+        })
+    }
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Provider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn complete(&self, request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+        self.next_response(request)
+    }
+
+    fn complete_stream(
+        &self,
+        request: &ProviderRequest,
+        on_event: &mut dyn FnMut(&StreamEvent),
+    ) -> Result<ProviderResponse, AgentError> {
+        let response = self.next_response(request)?;
+        // Replay the canned response as the events a real stream would
+        // produce: text split per line, then tool calls, usage, stop.
+        for line in response.text.split_inclusive('\n') {
+            on_event(&StreamEvent::TextDelta {
+                text: line.to_string(),
+            });
+        }
+        for (index, call) in response.tool_calls.iter().enumerate() {
+            on_event(&StreamEvent::ToolCallDelta {
+                index,
+                id: Some(call.id.clone()),
+                name: Some(call.name.clone()),
+                arguments: call.arguments.to_string(),
+            });
+        }
+        on_event(&StreamEvent::Usage {
+            input_tokens: response.usage.input_tokens,
+            output_tokens: response.usage.output_tokens,
+        });
+        on_event(&StreamEvent::Stop {
+            reason: response.stop_reason.clone(),
+        });
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use prompt_parser::{ClientId, Message};
+    use serde_json::json;
+
+    fn request(content: &str) -> ProviderRequest {
+        ProviderRequest {
+            client: ClientId {
+                provider: "anthropic".to_string(),
+                model: "claude".to_string(),
+                version: None,
+            },
+            system: None,
+            messages: vec![Message {
+                role: "user".into(),
+                content: content.into(),
+                attachments: Vec::new(),
+            }],
+            tools: Vec::new(),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stop: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rules_beat_the_queue_and_drain_in_order() {
+        let provider = MockProvider::new()
+            .reply(text_response("fallback"))
+            .when_contains("weather", text_response("sunny"))
+            .when_contains("weather", text_response("rainy"));
+
+        assert_eq!(provider.complete(&request("weather today?")).unwrap().text, "sunny");
+        assert_eq!(provider.complete(&request("weather tomorrow?")).unwrap().text, "rainy");
+        assert_eq!(provider.complete(&request("anything else")).unwrap().text, "fallback");
+        let err = provider.complete(&request("one more")).unwrap_err();
+        assert!(err.to_string().contains("no scripted response"));
+        assert_eq!(provider.requests().len(), 4);
+    }
+
+    #[test]
+    fn fingerprint_rules_match_one_exact_request() {
+        let pinned = request("the exact one");
+        let provider = MockProvider::new().when_fingerprint(
+            request_fingerprint(&pinned),
+            text_response("pinned"),
+        );
+        assert_eq!(provider.complete(&pinned).unwrap().text, "pinned");
+        assert!(provider.complete(&request("another")).is_err());
+    }
+
+    #[test]
+    fn the_agent_loop_runs_hermetically() {
+        let source = "---\nname: lookup\ntype: agent\nclient: anthropic/claude-sonnet-4\noutput_mode: text\ntools:\n  - echo\noutput:\n  type: string\n---\nlook things up";
+        let def = prompt_parser::parse(source).unwrap();
+        let mut tools = crate::runner::ToolRunner::new();
+        tools
+            .register("echo", Some("echo"), json!({ "type": "object" }), |args| {
+                Ok(args.clone())
+            })
+            .unwrap();
+        let provider = MockProvider::new()
+            .reply(tool_call_response("echo", json!({ "q": 1 })))
+            .reply(text_response("done"));
+        let run = crate::agent::run_agent(&def, &json!({}), &provider, &tools).unwrap();
+        assert_eq!(run.final_text, "done");
+        assert_eq!(run.turns.len(), 2);
+    }
+
+    #[test]
+    fn streaming_replays_the_canned_response_as_events() {
+        let provider = MockProvider::new().reply(tool_call_response("echo", json!({ "q": 1 })));
+        let mut events = Vec::new();
+        let response = provider
+            .complete_stream(&request("stream it"), &mut |event| {
+                events.push(serde_json::to_value(event).unwrap());
+            })
+            .unwrap();
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(events[0]["event"], "tool_call_delta");
+        assert_eq!(events[0]["name"], "echo");
+        assert_eq!(events.last().unwrap()["event"], "stop");
+    }
+}
@@ -0,0 +1,207 @@
+//! Multi-agent orchestration: prompts delegating to prompts.
+//!
+//! A complex workflow decomposes into prompt files: a coordinating agent
+//! calls a `run_prompt` tool naming another registered prompt, that
+//! prompt runs as a sub-agent with its own turn budget and tools, and its
+//! result comes back as an ordinary tool output — which the parent's
+//! session records like any other tool call, so the lineage of who ran
+//! what is in the transcript. Agent prompts run the full loop; plain
+//! prompts are a single completion. A depth guard stops two prompts that
+//! delegate to each other from recursing forever.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use serde_json::json;
+
+use prompt_parser::PromptRegistry;
+
+use crate::error::AgentError;
+use crate::provider::{Provider, ProviderRequest};
+use crate::runner::ToolRunner;
+
+/// How deep delegation chains may nest by default.
+pub const DEFAULT_MAX_DEPTH: u32 = 4;
+
+/// Install a `run_prompt` tool that executes registered prompts as
+/// sub-agents. Sub-agents dispatch through `subagent_tools` — give them
+/// their own runner (which may itself carry a `run_prompt` tool sharing
+/// this `max_depth` budget via a fresh registration).
+pub fn register_delegate_tool(
+    runner: &mut ToolRunner,
+    registry: Arc<PromptRegistry>,
+    provider: Arc<dyn Provider + Send + Sync>,
+    subagent_tools: Arc<ToolRunner>,
+    max_depth: u32,
+) -> Result<(), AgentError> {
+    let depth = Arc::new(AtomicU32::new(0));
+    runner.register(
+        "run_prompt",
+        Some("Run a registered prompt as a sub-agent and return its result"),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "inputs": { "type": "object" },
+            },
+            "required": ["name"],
+            "additionalProperties": false,
+        }),
+        move |args| {
+            let name = args["name"].as_str().unwrap_or_default();
+            let inputs = args.get("inputs").cloned().unwrap_or_else(|| json!({}));
+            let def = registry
+                .get(name)
+                .ok_or_else(|| format!("no registered prompt named `{name}`"))?
+                .clone();
+
+            if depth.fetch_add(1, Ordering::SeqCst) >= max_depth {
+                depth.fetch_sub(1, Ordering::SeqCst);
+                return Err(format!(
+                    "delegation deeper than {max_depth} levels; is a prompt delegating to itself?"
+                ));
+            }
+            let result = run_subagent(&def, &inputs, provider.as_ref(), &subagent_tools);
+            depth.fetch_sub(1, Ordering::SeqCst);
+            result.map_err(|e| e.to_string())
+        },
+    )
+}
+
+fn run_subagent(
+    def: &prompt_parser::PromptDefinition,
+    inputs: &serde_json::Value,
+    provider: &dyn Provider,
+    tools: &ToolRunner,
+) -> Result<serde_json::Value, AgentError> {
+    if def.prompt_type.as_deref() == Some("agent") {
+        let run = crate::agent::run_agent(def, inputs, provider, tools)?;
+        return Ok(json!({
+            "prompt": def.name,
+            "text": run.final_text,
+            "output": run.output,
+            "turns": run.turns.len(),
+        }));
+    }
+    let request = ProviderRequest::from_definition(def, inputs)?;
+    let response = provider.complete(&request)?;
+    Ok(json!({ "prompt": def.name, "text": response.text }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockProvider, text_response, tool_call_response};
+    use pretty_assertions::assert_eq;
+
+    fn registry() -> Arc<PromptRegistry> {
+        let mut registry = PromptRegistry::new();
+        registry.register(
+            prompt_parser::parse(
+                "---\nname: summarize\nclient: anthropic/claude-sonnet-4\ninputs:\n  text: string\n---\nSummarize: {{ text }}",
+            )
+            .unwrap(),
+        );
+        registry.register(
+            prompt_parser::parse(
+                "---\n\
+                 name: research\n\
+                 type: agent\n\
+                 client: anthropic/claude-sonnet-4\n\
+                 output_mode: text\n\
+                 tools:\n\
+                 \x20 - lookup\n\
+                 output:\n\
+                 \x20 type: string\n\
+                 ---\n\
+                 Research {{ topic }}.",
+            )
+            .unwrap(),
+        );
+        Arc::new(registry)
+    }
+
+    fn call(arguments: serde_json::Value) -> crate::provider::ToolCallRequest {
+        crate::provider::ToolCallRequest {
+            id: "d1".to_string(),
+            name: "run_prompt".to_string(),
+            arguments,
+        }
+    }
+
+    #[test]
+    fn plain_prompts_delegate_as_one_completion() {
+        let provider: Arc<dyn Provider + Send + Sync> = Arc::new(
+            MockProvider::new().when_contains("Summarize", text_response("a summary")),
+        );
+        let mut runner = ToolRunner::new();
+        register_delegate_tool(
+            &mut runner,
+            registry(),
+            provider,
+            Arc::new(ToolRunner::new()),
+            DEFAULT_MAX_DEPTH,
+        )
+        .unwrap();
+
+        let result = runner
+            .dispatch(&call(json!({ "name": "summarize", "inputs": { "text": "long text" } })))
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(result.result["text"], "a summary");
+        assert_eq!(result.result["prompt"], "summarize");
+    }
+
+    #[test]
+    fn agent_prompts_run_their_own_tool_loop() {
+        let provider: Arc<dyn Provider + Send + Sync> = Arc::new(
+            MockProvider::new()
+                .reply(tool_call_response("lookup", json!({ "q": "x" })))
+                .reply(text_response("found it")),
+        );
+        let mut subagent_tools = ToolRunner::new();
+        subagent_tools
+            .register("lookup", Some("look up"), json!({ "type": "object" }), |_| {
+                Ok(json!("a fact"))
+            })
+            .unwrap();
+        let mut runner = ToolRunner::new();
+        register_delegate_tool(
+            &mut runner,
+            registry(),
+            provider,
+            Arc::new(subagent_tools),
+            DEFAULT_MAX_DEPTH,
+        )
+        .unwrap();
+
+        let result = runner
+            .dispatch(&call(json!({ "name": "research", "inputs": { "topic": "jj" } })))
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(result.result["text"], "found it");
+        assert_eq!(result.result["turns"], 2);
+    }
+
+    #[test]
+    fn unknown_prompts_and_exhausted_depth_are_tool_errors() {
+        let provider: Arc<dyn Provider + Send + Sync> = Arc::new(MockProvider::new());
+        let mut runner = ToolRunner::new();
+        register_delegate_tool(
+            &mut runner,
+            registry(),
+            Arc::clone(&provider),
+            Arc::new(ToolRunner::new()),
+            0,
+        )
+        .unwrap();
+
+        let result = runner.dispatch(&call(json!({ "name": "nope" }))).unwrap();
+        assert!(result.is_error);
+        assert!(result.result.as_str().unwrap().contains("no registered prompt"));
+
+        let result = runner.dispatch(&call(json!({ "name": "summarize" }))).unwrap();
+        assert!(result.is_error);
+        assert!(result.result.as_str().unwrap().contains("delegation deeper"));
+    }
+}
@@ -0,0 +1,532 @@
+//! The consolidated session patch.
+//!
+//! Review happens once per session, not once per tool call: the UI shows
+//! one patch covering everything the agent changed, and the human approves
+//! or rejects it before anything is committed. A [`TreeSnapshot`] captures
+//! the workspace's text files when the session starts; diffing it against
+//! a snapshot of the current tree yields a [`SessionPatch`] — structured
+//! per-file changes plus standard unified diff text.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::AgentError;
+use crate::warn::Warned;
+
+/// What happened to one file over the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// One file's consolidated change.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileChange {
+    /// Workspace-relative, `/`-separated.
+    pub path: String,
+    pub kind: FileChangeKind,
+    pub additions: usize,
+    pub deletions: usize,
+    /// This file's unified diff hunks (no `---`/`+++` header).
+    pub diff: String,
+}
+
+/// Everything a session changed, from baseline snapshot to current tree.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionPatch {
+    pub changes: Vec<FileChange>,
+}
+
+impl SessionPatch {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The whole patch as one `git apply`-shaped unified diff.
+    pub fn unified(&self) -> String {
+        let mut out = String::new();
+        for change in &self.changes {
+            let (old, new) = match change.kind {
+                FileChangeKind::Added => ("/dev/null".to_string(), format!("b/{}", change.path)),
+                FileChangeKind::Deleted => (format!("a/{}", change.path), "/dev/null".to_string()),
+                FileChangeKind::Modified => {
+                    (format!("a/{}", change.path), format!("b/{}", change.path))
+                }
+            };
+            out.push_str(&format!("--- {old}\n+++ {new}\n"));
+            out.push_str(&change.diff);
+        }
+        out
+    }
+}
+
+/// The text files under a workspace at one point in time.
+///
+/// VCS metadata (`.jj`, `.git`) is skipped, as are files that aren't
+/// UTF-8 — the review UI has nothing useful to show for a binary anyway.
+/// A git submodule checkout is not walked into; it appears as a single
+/// `Subproject commit <id>` entry (git's own diff convention), so
+/// submodule bumps show up in patches instead of vanishing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeSnapshot {
+    files: BTreeMap<String, String>,
+}
+
+impl TreeSnapshot {
+    /// Snapshot `dir` recursively.
+    pub fn capture(dir: impl AsRef<Path>) -> Result<Self, AgentError> {
+        Self::capture_under(dir, "")
+    }
+
+    /// Snapshot only the subtree under `prefix` (a `/`-separated path
+    /// relative to `dir`; empty means everything). Directories outside
+    /// the prefix are never descended into, so snapshotting one corner
+    /// of a monorepo doesn't walk the whole tree.
+    pub fn capture_under(dir: impl AsRef<Path>, prefix: &str) -> Result<Self, AgentError> {
+        Ok(Self::capture_warned(dir, prefix)?.into_value())
+    }
+
+    /// Like [`capture_under`](Self::capture_under), but reports what the
+    /// walk skipped — today, files that aren't valid UTF-8 — as
+    /// non-fatal warnings instead of dropping them silently.
+    pub fn capture_warned(
+        dir: impl AsRef<Path>,
+        prefix: &str,
+    ) -> Result<Warned<Self>, AgentError> {
+        let dir = dir.as_ref();
+        let prefix = prefix.trim_matches('/');
+        let mut files = BTreeMap::new();
+        let mut warnings = Vec::new();
+        collect_files(dir, dir, prefix, &mut files, &mut warnings)?;
+        Ok(Warned::new(TreeSnapshot { files }, warnings))
+    }
+
+    /// A snapshot built from explicit contents, for tests and hosts that
+    /// already have the tree in memory.
+    pub fn from_files(files: impl IntoIterator<Item = (String, String)>) -> Self {
+        TreeSnapshot {
+            files: files.into_iter().collect(),
+        }
+    }
+
+    pub(crate) fn files(&self) -> &BTreeMap<String, String> {
+        &self.files
+    }
+
+    /// The submodules in this snapshot: path → pinned commit id.
+    pub fn submodules(&self) -> BTreeMap<String, String> {
+        self.files
+            .iter()
+            .filter_map(|(path, content)| {
+                let id = content.strip_prefix("Subproject commit ")?.trim_end();
+                (!id.is_empty() && !id.contains('\n')).then(|| (path.clone(), id.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Whether a walk rooted at `candidate` can contain paths under
+/// `prefix`: either the candidate sits inside the prefix, or the prefix
+/// sits further down inside the candidate.
+fn overlaps_prefix(candidate: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || candidate == prefix
+        || candidate.starts_with(&format!("{prefix}/"))
+        || prefix.starts_with(&format!("{candidate}/"))
+}
+
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    prefix: &str,
+    files: &mut BTreeMap<String, String>,
+    warnings: &mut Vec<String>,
+) -> Result<(), AgentError> {
+    let io_err = |e: std::io::Error| AgentError::Io {
+        path: dir.display().to_string(),
+        message: e.to_string(),
+    };
+    for entry in std::fs::read_dir(dir).map_err(io_err)? {
+        let path = entry.map_err(io_err)?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let relative = path
+            .strip_prefix(root)
+            .expect("walked paths sit under root")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.is_dir() {
+            if name == ".jj" || name == ".git" || !overlaps_prefix(&relative, prefix) {
+                continue;
+            }
+            if path.join(".git").exists() {
+                // A git submodule checkout: record the pin, don't walk in.
+                match submodule_pin(&path) {
+                    Some(id) => {
+                        files.insert(relative, format!("Subproject commit {id}\n"));
+                    }
+                    None => warnings.push(format!(
+                        "skipped submodule `{relative}`: could not resolve its pinned commit"
+                    )),
+                }
+                continue;
+            }
+            collect_files(root, &path, prefix, files, warnings)?;
+        } else if overlaps_prefix(&relative, prefix) {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    files.insert(relative, content);
+                }
+                Err(_) => warnings.push(format!("skipped `{relative}`: not valid UTF-8")),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The commit a submodule checkout at `dir` is pinned to, read straight
+/// from its git metadata: the `.git` gitfile (or directory) leads to
+/// `HEAD`, which is either a commit id or a ref to chase — including
+/// into `packed-refs`. `None` when any link in that chain is missing.
+fn submodule_pin(dir: &Path) -> Option<String> {
+    let dotgit = dir.join(".git");
+    let gitdir = if dotgit.is_dir() {
+        dotgit
+    } else {
+        let gitfile = std::fs::read_to_string(&dotgit).ok()?;
+        let target = gitfile.strip_prefix("gitdir:")?.trim();
+        let target = Path::new(target);
+        if target.is_absolute() {
+            target.to_path_buf()
+        } else {
+            dir.join(target)
+        }
+    };
+    let head = std::fs::read_to_string(gitdir.join("HEAD")).ok()?;
+    let head = head.trim();
+    let Some(refname) = head.strip_prefix("ref:") else {
+        return Some(head.to_string());
+    };
+    let refname = refname.trim();
+    if let Ok(id) = std::fs::read_to_string(gitdir.join(refname)) {
+        return Some(id.trim().to_string());
+    }
+    let packed = std::fs::read_to_string(gitdir.join("packed-refs")).ok()?;
+    packed
+        .lines()
+        .filter(|line| !line.starts_with(['#', '^']))
+        .find_map(|line| {
+            let (id, name) = line.split_once(' ')?;
+            (name == refname).then(|| id.to_string())
+        })
+}
+
+/// Diff two snapshots into the session's consolidated patch.
+pub fn session_patch(baseline: &TreeSnapshot, current: &TreeSnapshot) -> SessionPatch {
+    let mut paths: Vec<&String> = baseline.files.keys().chain(current.files.keys()).collect();
+    paths.sort_unstable();
+    paths.dedup();
+
+    let mut changes = Vec::new();
+    for path in paths {
+        let (old, new) = (baseline.files.get(path), current.files.get(path));
+        let kind = match (old, new) {
+            (None, Some(_)) => FileChangeKind::Added,
+            (Some(_), None) => FileChangeKind::Deleted,
+            (Some(old), Some(new)) if old != new => FileChangeKind::Modified,
+            _ => continue,
+        };
+        let (diff, additions, deletions) = unified_hunks(
+            old.map(String::as_str).unwrap_or_default(),
+            new.map(String::as_str).unwrap_or_default(),
+        );
+        changes.push(FileChange {
+            path: path.clone(),
+            kind,
+            additions,
+            deletions,
+            diff,
+        });
+    }
+    SessionPatch { changes }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op<'a> {
+    Equal(&'a str),
+    Del(&'a str),
+    Ins(&'a str),
+}
+
+/// LCS edit script, same approach as the parser's definition diff.
+fn edit_script<'a>(old: &'a str, new: &'a str) -> Vec<Op<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let (n, m) = (old_lines.len(), new_lines.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_lines[i] == new_lines[j] {
+            ops.push(Op::Equal(old_lines[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(Op::Del(old_lines[i]));
+            i += 1;
+        } else {
+            ops.push(Op::Ins(new_lines[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old_lines[i..].iter().map(|l| Op::Del(l)));
+    ops.extend(new_lines[j..].iter().map(|l| Op::Ins(l)));
+    ops
+}
+
+const CONTEXT: usize = 3;
+
+/// Render hunks with standard `@@` headers and 3 lines of context.
+/// Returns the text plus added/removed line counts.
+fn unified_hunks(old: &str, new: &str) -> (String, usize, usize) {
+    let ops = edit_script(old, new);
+    let mut additions = 0;
+    let mut deletions = 0;
+
+    // Mark which ops belong to a hunk: every non-equal op plus CONTEXT
+    // equal lines on either side.
+    let mut keep = vec![false; ops.len()];
+    for (i, op) in ops.iter().enumerate() {
+        if !matches!(op, Op::Equal(_)) {
+            let from = i.saturating_sub(CONTEXT);
+            let to = (i + CONTEXT + 1).min(ops.len());
+            keep[from..to].iter_mut().for_each(|k| *k = true);
+        }
+    }
+
+    let mut out = String::new();
+    let (mut old_line, mut new_line) = (1usize, 1usize);
+    let mut i = 0;
+    while i < ops.len() {
+        if !keep[i] {
+            match ops[i] {
+                Op::Equal(_) => {
+                    old_line += 1;
+                    new_line += 1;
+                }
+                _ => unreachable!("non-equal ops are always kept"),
+            }
+            i += 1;
+            continue;
+        }
+        // One hunk: the contiguous kept run starting here.
+        let start = i;
+        while i < ops.len() && keep[i] {
+            i += 1;
+        }
+        let hunk = &ops[start..i];
+        let old_count = hunk.iter().filter(|o| !matches!(o, Op::Ins(_))).count();
+        let new_count = hunk.iter().filter(|o| !matches!(o, Op::Del(_))).count();
+        out.push_str(&format!(
+            "@@ -{},{old_count} +{},{new_count} @@\n",
+            if old_count == 0 { old_line - 1 } else { old_line },
+            if new_count == 0 { new_line - 1 } else { new_line },
+        ));
+        for op in hunk {
+            match op {
+                Op::Equal(l) => {
+                    out.push_str(&format!(" {l}\n"));
+                    old_line += 1;
+                    new_line += 1;
+                }
+                Op::Del(l) => {
+                    out.push_str(&format!("-{l}\n"));
+                    old_line += 1;
+                    deletions += 1;
+                }
+                Op::Ins(l) => {
+                    out.push_str(&format!("+{l}\n"));
+                    new_line += 1;
+                    additions += 1;
+                }
+            }
+        }
+    }
+    (out, additions, deletions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn snapshot(files: &[(&str, &str)]) -> TreeSnapshot {
+        TreeSnapshot::from_files(
+            files
+                .iter()
+                .map(|(p, c)| (p.to_string(), c.to_string())),
+        )
+    }
+
+    #[test]
+    fn adds_modifies_and_deletes_are_classified() {
+        let before = snapshot(&[("kept.txt", "same\n"), ("edited.txt", "old\n"), ("gone.txt", "x\n")]);
+        let after = snapshot(&[("kept.txt", "same\n"), ("edited.txt", "new\n"), ("fresh.txt", "y\n")]);
+        let patch = session_patch(&before, &after);
+
+        let kinds: Vec<(&str, FileChangeKind)> = patch
+            .changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("edited.txt", FileChangeKind::Modified),
+                ("fresh.txt", FileChangeKind::Added),
+                ("gone.txt", FileChangeKind::Deleted),
+            ]
+        );
+        assert_eq!(patch.changes[0].additions, 1);
+        assert_eq!(patch.changes[0].deletions, 1);
+        assert!(patch.unified().contains("--- /dev/null\n+++ b/fresh.txt"));
+        assert!(patch.unified().contains("--- a/gone.txt\n+++ /dev/null"));
+    }
+
+    #[test]
+    fn hunks_carry_context_and_correct_headers() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\nj\n";
+        let (diff, additions, deletions) = unified_hunks(old, new);
+        assert_eq!(additions, 1);
+        assert_eq!(deletions, 1);
+        assert_eq!(
+            diff,
+            "@@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n"
+        );
+    }
+
+    #[test]
+    fn identical_trees_produce_an_empty_patch() {
+        let tree = snapshot(&[("a.txt", "hello\n")]);
+        assert!(session_patch(&tree, &tree.clone()).is_empty());
+        assert_eq!(session_patch(&tree, &tree).unified(), "");
+    }
+
+    #[test]
+    fn capture_walks_the_workspace_and_skips_vcs_metadata() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-patch-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join(".jj")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(dir.join(".jj/store"), "ignore me\n").unwrap();
+
+        let before = TreeSnapshot::capture(&dir).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn main() { run(); }\n").unwrap();
+        let after = TreeSnapshot::capture(&dir).unwrap();
+
+        let patch = session_patch(&before, &after);
+        assert_eq!(patch.changes.len(), 1);
+        assert_eq!(patch.changes[0].path, "src/lib.rs");
+        assert!(patch.unified().contains("+fn main() { run(); }"));
+    }
+
+    #[test]
+    fn capture_under_only_walks_the_requested_subtree() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-patch-prefix-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("crates/core/src")).unwrap();
+        std::fs::create_dir_all(dir.join("web")).unwrap();
+        std::fs::write(dir.join("crates/core/src/lib.rs"), "core\n").unwrap();
+        std::fs::write(dir.join("web/app.ts"), "web\n").unwrap();
+        std::fs::write(dir.join("README.md"), "root\n").unwrap();
+
+        let snapshot = TreeSnapshot::capture_under(&dir, "crates/core").unwrap();
+        assert_eq!(
+            snapshot.files.keys().collect::<Vec<_>>(),
+            vec!["crates/core/src/lib.rs"]
+        );
+
+        // An empty prefix is a full capture.
+        let full = TreeSnapshot::capture_under(&dir, "").unwrap();
+        assert_eq!(full.files.len(), 3);
+    }
+
+    #[test]
+    fn submodules_appear_as_pinned_entries_and_bumps_diff() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-patch-submodule-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        // A submodule checkout the way git lays it out: a `.git` gitfile
+        // pointing at the superproject's modules dir, HEAD behind a ref.
+        let gitdir = dir.join(".git/modules/vendor/dep");
+        std::fs::create_dir_all(gitdir.join("refs/heads")).unwrap();
+        std::fs::create_dir_all(dir.join("vendor/dep")).unwrap();
+        std::fs::write(
+            dir.join("vendor/dep/.git"),
+            "gitdir: ../../.git/modules/vendor/dep\n",
+        )
+        .unwrap();
+        std::fs::write(gitdir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        std::fs::write(gitdir.join("refs/heads/main"), "aaaa1111\n").unwrap();
+        std::fs::write(dir.join("vendor/dep/inner.txt"), "not walked\n").unwrap();
+        std::fs::write(dir.join("app.rs"), "fn app() {}\n").unwrap();
+
+        let before = TreeSnapshot::capture(&dir).unwrap();
+        assert_eq!(
+            before.files.get("vendor/dep").map(String::as_str),
+            Some("Subproject commit aaaa1111\n")
+        );
+        assert!(!before.files.contains_key("vendor/dep/inner.txt"));
+        assert_eq!(before.submodules()["vendor/dep"], "aaaa1111");
+
+        // A bump shows up as an ordinary modification in the patch.
+        std::fs::write(gitdir.join("refs/heads/main"), "bbbb2222\n").unwrap();
+        let after = TreeSnapshot::capture(&dir).unwrap();
+        let patch = session_patch(&before, &after);
+        assert_eq!(patch.changes.len(), 1);
+        assert_eq!(patch.changes[0].path, "vendor/dep");
+        assert_eq!(patch.changes[0].kind, FileChangeKind::Modified);
+        assert!(patch.unified().contains("+Subproject commit bbbb2222"));
+    }
+
+    #[test]
+    fn skipped_binary_files_come_back_as_warnings_not_silence() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-patch-warn-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.md"), "text\n").unwrap();
+        std::fs::write(dir.join("logo.png"), [0xff, 0xfe, 0x00, 0x01]).unwrap();
+
+        let warned = TreeSnapshot::capture_warned(&dir, "").unwrap();
+        assert_eq!(warned.value.files.len(), 1);
+        assert_eq!(warned.warnings, vec!["skipped `logo.png`: not valid UTF-8"]);
+    }
+}
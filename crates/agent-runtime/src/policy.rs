@@ -0,0 +1,244 @@
+//! Policy checks on the pending change set.
+//!
+//! Before a session's patch becomes a commit, the host gets to insist on
+//! ground rules: nothing oversized, nothing under forbidden paths, no
+//! hand edits to generated files, and a description that matches the
+//! repo's convention. [`ChangePolicy::evaluate`] runs every configured
+//! rule over the [`SessionPatch`] and returns structured
+//! [`PolicyViolation`]s — the agent loop feeds them back to the model as
+//! an error-flagged tool result, the same way a failed tool call reads,
+//! so the model can fix the patch instead of a human rejecting it later.
+
+use serde::Serialize;
+
+use crate::conventional::CommitConvention;
+use crate::patch::{FileChangeKind, SessionPatch, TreeSnapshot};
+
+/// One broken rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyViolation {
+    /// Which rule fired: `max_file_size`, `forbidden_path`,
+    /// `generated_file`, `secret`, or `description`.
+    pub rule: String,
+    /// The offending path, for per-file rules.
+    pub path: Option<String>,
+    pub message: String,
+}
+
+/// The configured rules. An empty policy allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangePolicy {
+    max_file_bytes: Option<usize>,
+    forbidden: Vec<String>,
+    generated: Vec<String>,
+    convention: Option<CommitConvention>,
+    scan_secrets: bool,
+}
+
+impl ChangePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject changed files whose new content exceeds `bytes`.
+    pub fn with_max_file_bytes(mut self, bytes: usize) -> Self {
+        self.max_file_bytes = Some(bytes);
+        self
+    }
+
+    /// Reject any change under a path matching `pattern` (`*` matches
+    /// any run of characters, including `/`).
+    pub fn with_forbidden_path(mut self, pattern: impl Into<String>) -> Self {
+        self.forbidden.push(pattern.into());
+        self
+    }
+
+    /// Mark paths matching `pattern` as generated: the agent must not
+    /// edit them by hand (regenerate them instead).
+    pub fn with_generated_path(mut self, pattern: impl Into<String>) -> Self {
+        self.generated.push(pattern.into());
+        self
+    }
+
+    /// Require the description to pass `convention`.
+    pub fn with_convention(mut self, convention: CommitConvention) -> Self {
+        self.convention = Some(convention);
+        self
+    }
+
+    /// Run the secret scanner over the patch's added lines; every
+    /// finding is a violation, so credentials never reach history.
+    pub fn with_secret_scan(mut self) -> Self {
+        self.scan_secrets = true;
+        self
+    }
+
+    /// Run every rule over the pending change set. `current` is the tree
+    /// the patch produces (for size checks); `description` is the commit
+    /// message being written. Empty means the commit may proceed.
+    pub fn evaluate(
+        &self,
+        description: &str,
+        patch: &SessionPatch,
+        current: &TreeSnapshot,
+    ) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        for change in &patch.changes {
+            for pattern in &self.forbidden {
+                if matches_pattern(&change.path, pattern) {
+                    violations.push(PolicyViolation {
+                        rule: "forbidden_path".into(),
+                        path: Some(change.path.clone()),
+                        message: format!("`{}` matches forbidden pattern `{pattern}`", change.path),
+                    });
+                }
+            }
+            for pattern in &self.generated {
+                if matches_pattern(&change.path, pattern) {
+                    violations.push(PolicyViolation {
+                        rule: "generated_file".into(),
+                        path: Some(change.path.clone()),
+                        message: format!(
+                            "`{}` is generated (pattern `{pattern}`); regenerate it instead of editing",
+                            change.path
+                        ),
+                    });
+                }
+            }
+            if let Some(limit) = self.max_file_bytes
+                && change.kind != FileChangeKind::Deleted
+                && let Some(content) = current.files().get(&change.path)
+                && content.len() > limit
+            {
+                violations.push(PolicyViolation {
+                    rule: "max_file_size".into(),
+                    path: Some(change.path.clone()),
+                    message: format!(
+                        "`{}` is {} bytes (limit {limit})",
+                        change.path,
+                        content.len()
+                    ),
+                });
+            }
+        }
+        if self.scan_secrets {
+            for finding in crate::secrets::scan_patch(patch) {
+                violations.push(PolicyViolation {
+                    rule: "secret".into(),
+                    path: Some(finding.path.clone()),
+                    message: format!(
+                        "`{}` line {} looks like a credential ({})",
+                        finding.path, finding.line, finding.rule
+                    ),
+                });
+            }
+        }
+        if let Some(convention) = &self.convention {
+            for message in convention.validate(description) {
+                violations.push(PolicyViolation {
+                    rule: "description".into(),
+                    path: None,
+                    message,
+                });
+            }
+        }
+        violations
+    }
+}
+
+/// Whole-path wildcard match: `*` matches any run of characters,
+/// including `/`, so `vendor/*` covers the whole subtree and `*.lock`
+/// covers lockfiles anywhere.
+fn matches_pattern(path: &str, pattern: &str) -> bool {
+    match pattern.split_once('*') {
+        None => path == pattern,
+        Some((head, tail)) => {
+            path.strip_prefix(head).is_some_and(|rest| {
+                (0..=rest.len()).any(|skip| matches_pattern(&rest[skip..], tail))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch::session_patch;
+    use pretty_assertions::assert_eq;
+
+    fn tree(files: &[(&str, &str)]) -> TreeSnapshot {
+        TreeSnapshot::from_files(files.iter().map(|(p, c)| (p.to_string(), c.to_string())))
+    }
+
+    #[test]
+    fn each_rule_reports_its_own_structured_violation() {
+        let baseline = tree(&[("src/gen/schema.rs", "old\n")]);
+        let current = tree(&[
+            ("src/gen/schema.rs", "edited by hand\n"),
+            ("secrets/key.pem", "----\n"),
+            ("src/big.rs", &"x".repeat(100)),
+        ]);
+        let patch = session_patch(&baseline, &current);
+
+        let policy = ChangePolicy::new()
+            .with_max_file_bytes(50)
+            .with_forbidden_path("secrets/*")
+            .with_generated_path("src/gen/*")
+            .with_convention(CommitConvention::default());
+        let violations = policy.evaluate("did stuff", &patch, &current);
+
+        let rules: Vec<(&str, Option<&str>)> = violations
+            .iter()
+            .map(|v| (v.rule.as_str(), v.path.as_deref()))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("forbidden_path", Some("secrets/key.pem")),
+                ("max_file_size", Some("src/big.rs")),
+                ("generated_file", Some("src/gen/schema.rs")),
+                ("description", None),
+            ]
+        );
+        assert!(violations[3].message.contains("conventional commit header"));
+    }
+
+    #[test]
+    fn a_clean_patch_under_an_empty_policy_passes() {
+        let current = tree(&[("src/lib.rs", "fn main() {}\n")]);
+        let patch = session_patch(&TreeSnapshot::default(), &current);
+        assert!(ChangePolicy::new().evaluate("anything", &patch, &current).is_empty());
+
+        let policy = ChangePolicy::new()
+            .with_max_file_bytes(1024)
+            .with_forbidden_path("secrets/*")
+            .with_convention(CommitConvention::default());
+        assert!(policy.evaluate("feat: add main", &patch, &current).is_empty());
+    }
+
+    #[test]
+    fn secret_findings_surface_as_policy_violations() {
+        let current = tree(&[(".envrc", "export TOKEN=ghp_0123456789abcdef\n")]);
+        let patch = session_patch(&TreeSnapshot::default(), &current);
+        let violations = ChangePolicy::new()
+            .with_secret_scan()
+            .evaluate("chore: env", &patch, &current);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "secret");
+        assert_eq!(
+            violations[0].message,
+            "`.envrc` line 1 looks like a credential (token)"
+        );
+        // The finding names the location, never the matched text.
+        assert!(!violations[0].message.contains("ghp_"));
+    }
+
+    #[test]
+    fn patterns_match_whole_paths_with_star_crossing_slashes() {
+        assert!(matches_pattern("vendor/dep/lib.rs", "vendor/*"));
+        assert!(matches_pattern("web/pnpm-lock.yaml", "*.yaml"));
+        assert!(matches_pattern("Cargo.lock", "Cargo.lock"));
+        assert!(!matches_pattern("src/lib.rs", "vendor/*"));
+        assert!(!matches_pattern("src/lib.rs.bak", "*.rs"));
+    }
+}
@@ -0,0 +1,138 @@
+//! Agent provenance on commits.
+//!
+//! When an agent writes a commit, the repo itself should say so — which
+//! session produced it and which prompt was running — so provenance
+//! survives pushes, clones, and the session store being long gone. jj
+//! commits have no custom header fields, so the metadata rides in the
+//! description as trailers (`Agent-Session-Id:`, `Prompt-Name:`), the
+//! same place `Signed-off-by:` lives in git. Stamping, reading, and the
+//! "everything this session made" query all live here.
+
+use crate::checkpoint::JjCli;
+use crate::error::AgentError;
+
+/// The trailer that links a commit back to its session.
+const SESSION_TRAILER: &str = "Agent-Session-Id";
+/// The trailer naming the prompt that was running.
+const PROMPT_TRAILER: &str = "Prompt-Name";
+
+/// Who made a commit, as recorded in its description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionProvenance {
+    /// The session that produced the commit.
+    pub session_id: String,
+    /// The prompt the session was executing, when known.
+    pub prompt_name: Option<String>,
+}
+
+impl SessionProvenance {
+    pub fn new(session_id: impl Into<String>) -> Self {
+        SessionProvenance {
+            session_id: session_id.into(),
+            prompt_name: None,
+        }
+    }
+
+    pub fn with_prompt(mut self, prompt_name: impl Into<String>) -> Self {
+        self.prompt_name = Some(prompt_name.into());
+        self
+    }
+
+    /// `description` with this provenance appended as trailers, blank
+    /// line before them when the description has a body. Stamping twice
+    /// is a no-op for the same session.
+    pub fn stamp(&self, description: &str) -> String {
+        if Self::read(description).as_ref() == Some(self) {
+            return description.to_string();
+        }
+        let mut stamped = description.trim_end().to_string();
+        if !stamped.is_empty() {
+            stamped.push_str("\n\n");
+        }
+        stamped.push_str(&format!("{SESSION_TRAILER}: {}\n", self.session_id));
+        if let Some(prompt) = &self.prompt_name {
+            stamped.push_str(&format!("{PROMPT_TRAILER}: {prompt}\n"));
+        }
+        stamped
+    }
+
+    /// The provenance recorded in `description`, if any. Trailers may
+    /// appear anywhere in the trailing lines; the last occurrence wins,
+    /// matching how git tooling reads duplicated trailers.
+    pub fn read(description: &str) -> Option<Self> {
+        let mut session_id = None;
+        let mut prompt_name = None;
+        for line in description.lines() {
+            if let Some(value) = line.strip_prefix(&format!("{SESSION_TRAILER}: ")) {
+                session_id = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix(&format!("{PROMPT_TRAILER}: ")) {
+                prompt_name = Some(value.trim().to_string());
+            }
+        }
+        session_id.map(|session_id| SessionProvenance {
+            session_id,
+            prompt_name,
+        })
+    }
+}
+
+impl JjCli {
+    /// Describe `revision` with `description` plus provenance trailers.
+    pub fn describe_with_provenance(
+        &self,
+        revision: &str,
+        description: &str,
+        provenance: &SessionProvenance,
+    ) -> Result<(), AgentError> {
+        let stamped = provenance.stamp(description);
+        self.jj(&["describe", "-r", revision, "-m", &stamped])
+            .map(|_| ())
+    }
+
+    /// Short commit ids of every commit stamped with `session_id`,
+    /// oldest first.
+    pub fn commits_for_session(&self, session_id: &str) -> Result<Vec<String>, AgentError> {
+        let revset = format!("description(\"{SESSION_TRAILER}: {session_id}\")");
+        let out = self.jj(&[
+            "log",
+            "-r",
+            &revset,
+            "--reversed",
+            "--no-graph",
+            "-T",
+            r#"commit_id.short() ++ "\n""#,
+        ])?;
+        Ok(out.lines().map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn stamping_appends_trailers_once() {
+        let provenance = SessionProvenance::new("sess-42").with_prompt("refactor");
+        let stamped = provenance.stamp("Fix the flaky watcher test");
+        assert_eq!(
+            stamped,
+            "Fix the flaky watcher test\n\nAgent-Session-Id: sess-42\nPrompt-Name: refactor\n"
+        );
+        // Re-stamping the same session doesn't duplicate the trailers.
+        assert_eq!(provenance.stamp(&stamped), stamped);
+        // An empty description gets trailers with no leading blank line.
+        assert_eq!(
+            SessionProvenance::new("sess-7").stamp(""),
+            "Agent-Session-Id: sess-7\n"
+        );
+    }
+
+    #[test]
+    fn reading_recovers_what_stamping_wrote() {
+        let provenance = SessionProvenance::new("sess-42").with_prompt("refactor");
+        let stamped = provenance.stamp("Fix things\n\nLonger body here.\n");
+        assert_eq!(SessionProvenance::read(&stamped), Some(provenance));
+        assert_eq!(SessionProvenance::read("no trailers here"), None);
+    }
+}
@@ -0,0 +1,588 @@
+//! Provider clients.
+//!
+//! A prompt declares *what* to send (`client: anthropic/claude-sonnet-4`,
+//! rendered messages, sampling parameters); this module owns *how*: the
+//! [`Provider`] trait turns a [`ProviderRequest`] into a typed
+//! [`ProviderResponse`], and [`provider_for`] picks the Anthropic or OpenAI
+//! client from the prompt's resolved [`ClientId`]. Response parsing is kept
+//! separate from transport so the wire formats are tested without a
+//! network.
+
+use serde_json::{Value, json};
+#[cfg(any(test, feature = "network"))]
+use serde_json::Map;
+
+use prompt_parser::{ClientId, Message, PromptDefinition, PromptError, resolve_client};
+
+use crate::error::AgentError;
+
+/// A tool offered to the model, with its JSON Schema parameters.
+///
+/// Prompt frontmatter only names tools; the parameter schemas live with the
+/// registered handlers (see `ToolRunner`), so callers fill them in here. An
+/// empty object schema means "any arguments".
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: Option<String>,
+    pub parameters: Value,
+}
+
+/// Everything one completion call needs, provider-agnostic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderRequest {
+    pub client: ClientId,
+    /// The rendered `system` template, when the prompt declares one.
+    pub system: Option<String>,
+    /// Conversation turns, system message excluded.
+    pub messages: Vec<Message>,
+    pub tools: Vec<ToolSpec>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub stop: Vec<String>,
+}
+
+impl ProviderRequest {
+    /// Build a request from a prompt definition and its inputs: resolve the
+    /// `client`, render the messages, and carry the sampling parameters
+    /// over. Fails when the prompt declares no `client`.
+    pub fn from_definition(def: &PromptDefinition, data: &Value) -> Result<Self, AgentError> {
+        let client = def.client.as_deref().ok_or_else(|| {
+            AgentError::Prompt(PromptError::Client {
+                client: String::new(),
+                message: format!("prompt `{}` declares no client", def.name),
+            })
+        })?;
+        let client = resolve_client(client)?;
+        let mut messages = def.render_messages(data)?;
+        let system = (messages.first().is_some_and(|m| m.role == "system"))
+            .then(|| messages.remove(0).content);
+        let tools = def
+            .tools
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|t| ToolSpec {
+                name: t.name.clone(),
+                description: t.description.clone(),
+                parameters: json!({ "type": "object" }),
+            })
+            .collect();
+        Ok(ProviderRequest {
+            client,
+            system,
+            messages,
+            tools,
+            temperature: def.temperature,
+            top_p: def.top_p,
+            max_tokens: def.max_tokens,
+            stop: def.stop.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// One tool invocation the model asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCallRequest {
+    /// Provider-assigned call id, echoed back in the tool result.
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// Token counts reported by the provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// Why generation stopped, normalized across providers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    EndTurn,
+    MaxTokens,
+    StopSequence,
+    /// The model wants tool results before continuing.
+    ToolUse,
+    /// A reason this crate doesn't know, preserved verbatim.
+    Other(String),
+}
+
+/// A parsed completion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderResponse {
+    /// Concatenated text content.
+    pub text: String,
+    pub tool_calls: Vec<ToolCallRequest>,
+    pub usage: Usage,
+    pub stop_reason: StopReason,
+}
+
+/// One model provider's HTTP client.
+pub trait Provider {
+    /// The provider name as it appears in `client` strings.
+    fn name(&self) -> &str;
+
+    /// Execute one completion call.
+    fn complete(&self, request: &ProviderRequest) -> Result<ProviderResponse, AgentError>;
+
+    /// Execute one completion call streamed: `on_event` sees each
+    /// [`StreamEvent`](crate::stream::StreamEvent) as it arrives, and the
+    /// assembled response comes back at the end, identical in shape to
+    /// [`Self::complete`].
+    fn complete_stream(
+        &self,
+        request: &ProviderRequest,
+        on_event: &mut dyn FnMut(&crate::stream::StreamEvent),
+    ) -> Result<ProviderResponse, AgentError>;
+}
+
+/// The client for `request.client.provider`, keyed from the environment
+/// (`ANTHROPIC_API_KEY` / `OPENAI_API_KEY`).
+#[cfg(feature = "network")]
+pub fn provider_for(client: &ClientId) -> Result<Box<dyn Provider>, AgentError> {
+    match client.provider.as_str() {
+        "anthropic" => Ok(Box::new(AnthropicProvider::from_env()?)),
+        "openai" => Ok(Box::new(OpenAiProvider::from_env()?)),
+        other => Err(AgentError::UnsupportedProvider(other.to_string())),
+    }
+}
+
+#[cfg(feature = "network")]
+fn env_key(provider: &str, var: &str) -> Result<String, AgentError> {
+    std::env::var(var).map_err(|_| AgentError::Provider {
+        provider: provider.to_string(),
+        message: format!("{var} is not set"),
+    })
+}
+
+#[cfg(any(test, feature = "network"))]
+fn provider_err(provider: &str, message: impl Into<String>) -> AgentError {
+    AgentError::Provider {
+        provider: provider.to_string(),
+        message: message.into(),
+    }
+}
+
+/// The model id a provider sees: `model` or `model@version` joined with a
+/// dash for Anthropic-style dated versions, verbatim otherwise.
+#[cfg(any(test, feature = "network"))]
+fn model_id(client: &ClientId) -> String {
+    match &client.version {
+        Some(version) => format!("{}-{version}", client.model),
+        None => client.model.clone(),
+    }
+}
+
+// --- Anthropic -------------------------------------------------------------
+
+#[cfg(feature = "network")]
+pub struct AnthropicProvider {
+    api_key: String,
+    base_url: String,
+}
+
+#[cfg(feature = "network")]
+impl AnthropicProvider {
+    pub fn new(api_key: impl Into<String>, base_url: impl Into<String>) -> Self {
+        AnthropicProvider {
+            api_key: api_key.into(),
+            base_url: base_url.into(),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, AgentError> {
+        Ok(Self::new(
+            env_key("anthropic", "ANTHROPIC_API_KEY")?,
+            "https://api.anthropic.com",
+        ))
+    }
+}
+
+#[cfg(any(test, feature = "network"))]
+pub(crate) fn build_anthropic_body(request: &ProviderRequest) -> Value {
+    let mut body = Map::new();
+    body.insert("model".into(), json!(model_id(&request.client)));
+    // The API requires max_tokens; fall back to a generous default.
+    body.insert(
+        "max_tokens".into(),
+        json!(request.max_tokens.unwrap_or(4096)),
+    );
+    if let Some(system) = &request.system {
+        body.insert("system".into(), json!(system));
+    }
+    body.insert(
+        "messages".into(),
+        json!(
+            request
+                .messages
+                .iter()
+                .map(|m| json!({ "role": m.role, "content": m.content }))
+                .collect::<Vec<_>>()
+        ),
+    );
+    if !request.tools.is_empty() {
+        body.insert(
+            "tools".into(),
+            json!(
+                request
+                    .tools
+                    .iter()
+                    .map(|t| {
+                        json!({
+                            "name": t.name,
+                            "description": t.description.as_deref().unwrap_or_default(),
+                            "input_schema": t.parameters,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            ),
+        );
+    }
+    if let Some(temperature) = request.temperature {
+        body.insert("temperature".into(), json!(temperature));
+    }
+    if let Some(top_p) = request.top_p {
+        body.insert("top_p".into(), json!(top_p));
+    }
+    if !request.stop.is_empty() {
+        body.insert("stop_sequences".into(), json!(request.stop));
+    }
+    Value::Object(body)
+}
+
+#[cfg(any(test, feature = "network"))]
+pub(crate) fn parse_anthropic_response(body: &Value) -> Result<ProviderResponse, AgentError> {
+    let content = body["content"]
+        .as_array()
+        .ok_or_else(|| provider_err("anthropic", "response has no `content` array"))?;
+    let mut text = String::new();
+    let mut tool_calls = Vec::new();
+    for block in content {
+        match block["type"].as_str() {
+            Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+            Some("tool_use") => tool_calls.push(ToolCallRequest {
+                id: block["id"].as_str().unwrap_or_default().to_string(),
+                name: block["name"].as_str().unwrap_or_default().to_string(),
+                arguments: block["input"].clone(),
+            }),
+            _ => {}
+        }
+    }
+    let stop_reason = match body["stop_reason"].as_str() {
+        Some("end_turn") | None => StopReason::EndTurn,
+        Some("max_tokens") => StopReason::MaxTokens,
+        Some("stop_sequence") => StopReason::StopSequence,
+        Some("tool_use") => StopReason::ToolUse,
+        Some(other) => StopReason::Other(other.to_string()),
+    };
+    Ok(ProviderResponse {
+        text,
+        tool_calls,
+        usage: Usage {
+            input_tokens: body["usage"]["input_tokens"].as_u64().unwrap_or_default(),
+            output_tokens: body["usage"]["output_tokens"].as_u64().unwrap_or_default(),
+        },
+        stop_reason,
+    })
+}
+
+#[cfg(feature = "network")]
+impl Provider for AnthropicProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn complete(&self, request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+        tracing::info!(model = %request.client.model, "anthropic completion call");
+        let response = ureq::post(&format!("{}/v1/messages", self.base_url))
+            .set("x-api-key", &self.api_key)
+            .set("anthropic-version", "2023-06-01")
+            .send_json(build_anthropic_body(request))
+            .map_err(|e| provider_err("anthropic", e.to_string()))?;
+        let body: Value = response
+            .into_json()
+            .map_err(|e| provider_err("anthropic", e.to_string()))?;
+        parse_anthropic_response(&body)
+    }
+
+    fn complete_stream(
+        &self,
+        request: &ProviderRequest,
+        on_event: &mut dyn FnMut(&crate::stream::StreamEvent),
+    ) -> Result<ProviderResponse, AgentError> {
+        let mut body = build_anthropic_body(request);
+        body["stream"] = json!(true);
+        let response = ureq::post(&format!("{}/v1/messages", self.base_url))
+            .set("x-api-key", &self.api_key)
+            .set("anthropic-version", "2023-06-01")
+            .send_json(body)
+            .map_err(|e| provider_err("anthropic", e.to_string()))?;
+        crate::stream::parse_anthropic_stream(
+            std::io::BufReader::new(response.into_reader()),
+            on_event,
+        )
+    }
+}
+
+// --- OpenAI ----------------------------------------------------------------
+
+#[cfg(feature = "network")]
+pub struct OpenAiProvider {
+    api_key: String,
+    base_url: String,
+}
+
+#[cfg(feature = "network")]
+impl OpenAiProvider {
+    pub fn new(api_key: impl Into<String>, base_url: impl Into<String>) -> Self {
+        OpenAiProvider {
+            api_key: api_key.into(),
+            base_url: base_url.into(),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, AgentError> {
+        Ok(Self::new(
+            env_key("openai", "OPENAI_API_KEY")?,
+            "https://api.openai.com",
+        ))
+    }
+}
+
+#[cfg(any(test, feature = "network"))]
+pub(crate) fn build_openai_body(request: &ProviderRequest) -> Value {
+    let mut messages = Vec::new();
+    if let Some(system) = &request.system {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    for m in &request.messages {
+        messages.push(json!({ "role": m.role, "content": m.content }));
+    }
+    let mut body = Map::new();
+    body.insert("model".into(), json!(model_id(&request.client)));
+    body.insert("messages".into(), json!(messages));
+    if !request.tools.is_empty() {
+        body.insert(
+            "tools".into(),
+            json!(
+                request
+                    .tools
+                    .iter()
+                    .map(|t| {
+                        json!({
+                            "type": "function",
+                            "function": {
+                                "name": t.name,
+                                "description": t.description.as_deref().unwrap_or_default(),
+                                "parameters": t.parameters,
+                            },
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            ),
+        );
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        body.insert("max_completion_tokens".into(), json!(max_tokens));
+    }
+    if let Some(temperature) = request.temperature {
+        body.insert("temperature".into(), json!(temperature));
+    }
+    if let Some(top_p) = request.top_p {
+        body.insert("top_p".into(), json!(top_p));
+    }
+    if !request.stop.is_empty() {
+        body.insert("stop".into(), json!(request.stop));
+    }
+    Value::Object(body)
+}
+
+#[cfg(any(test, feature = "network"))]
+pub(crate) fn parse_openai_response(body: &Value) -> Result<ProviderResponse, AgentError> {
+    let choice = body["choices"]
+        .get(0)
+        .ok_or_else(|| provider_err("openai", "response has no `choices`"))?;
+    let message = &choice["message"];
+    let mut tool_calls = Vec::new();
+    for call in message["tool_calls"].as_array().unwrap_or(&Vec::new()) {
+        // OpenAI ships arguments as a JSON string; a model can emit invalid
+        // JSON there, which we surface rather than guess around.
+        let raw = call["function"]["arguments"].as_str().unwrap_or("{}");
+        let arguments = serde_json::from_str(raw)
+            .map_err(|e| provider_err("openai", format!("bad tool arguments: {e}")))?;
+        tool_calls.push(ToolCallRequest {
+            id: call["id"].as_str().unwrap_or_default().to_string(),
+            name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+            arguments,
+        });
+    }
+    let stop_reason = match choice["finish_reason"].as_str() {
+        Some("stop") | None => StopReason::EndTurn,
+        Some("length") => StopReason::MaxTokens,
+        Some("tool_calls") => StopReason::ToolUse,
+        Some(other) => StopReason::Other(other.to_string()),
+    };
+    Ok(ProviderResponse {
+        text: message["content"].as_str().unwrap_or_default().to_string(),
+        tool_calls,
+        usage: Usage {
+            input_tokens: body["usage"]["prompt_tokens"].as_u64().unwrap_or_default(),
+            output_tokens: body["usage"]["completion_tokens"]
+                .as_u64()
+                .unwrap_or_default(),
+        },
+        stop_reason,
+    })
+}
+
+#[cfg(feature = "network")]
+impl Provider for OpenAiProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn complete(&self, request: &ProviderRequest) -> Result<ProviderResponse, AgentError> {
+        tracing::info!(model = %request.client.model, "openai completion call");
+        let response = ureq::post(&format!("{}/v1/chat/completions", self.base_url))
+            .set("authorization", &format!("Bearer {}", self.api_key))
+            .send_json(build_openai_body(request))
+            .map_err(|e| provider_err("openai", e.to_string()))?;
+        let body: Value = response
+            .into_json()
+            .map_err(|e| provider_err("openai", e.to_string()))?;
+        parse_openai_response(&body)
+    }
+
+    fn complete_stream(
+        &self,
+        request: &ProviderRequest,
+        on_event: &mut dyn FnMut(&crate::stream::StreamEvent),
+    ) -> Result<ProviderResponse, AgentError> {
+        let mut body = build_openai_body(request);
+        body["stream"] = json!(true);
+        // Without this the final frame omits token counts.
+        body["stream_options"] = json!({ "include_usage": true });
+        let response = ureq::post(&format!("{}/v1/chat/completions", self.base_url))
+            .set("authorization", &format!("Bearer {}", self.api_key))
+            .send_json(body)
+            .map_err(|e| provider_err("openai", e.to_string()))?;
+        crate::stream::parse_openai_stream(
+            std::io::BufReader::new(response.into_reader()),
+            on_event,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn request() -> ProviderRequest {
+        let def = PromptDefinition::parse(
+            "---\n\
+             name: triage\n\
+             client: anthropic/claude-sonnet-4\n\
+             temperature: 0.2\n\
+             max_tokens: 512\n\
+             stop:\n\
+             \x20 - DONE\n\
+             system: Be terse.\n\
+             tools:\n\
+             \x20 - name: read_file\n\
+             \x20   description: Read a file\n\
+             ---\n\
+             Triage issue {{ issue }}.",
+        )
+        .unwrap();
+        ProviderRequest::from_definition(&def, &json!({ "issue": 42 })).unwrap()
+    }
+
+    #[test]
+    fn requests_carry_client_messages_and_sampling_parameters() {
+        let request = request();
+        assert_eq!(request.client.to_string(), "anthropic/claude-sonnet-4");
+        assert_eq!(request.system.as_deref(), Some("Be terse."));
+        assert_eq!(request.messages.len(), 1);
+        assert_eq!(request.messages[0].content, "Triage issue 42.");
+        assert_eq!(request.temperature, Some(0.2));
+        assert_eq!(request.stop, vec!["DONE"]);
+        assert_eq!(request.tools[0].name, "read_file");
+    }
+
+    #[test]
+    fn prompts_without_a_client_are_rejected_up_front() {
+        let def = PromptDefinition::parse("---\nname: x\n---\nhi").unwrap();
+        let err = ProviderRequest::from_definition(&def, &json!({})).unwrap_err();
+        assert!(err.to_string().contains("declares no client"));
+    }
+
+    #[test]
+    fn anthropic_wire_format_round_trips() {
+        let body = build_anthropic_body(&request());
+        assert_eq!(body["model"], "claude-sonnet-4");
+        assert_eq!(body["system"], "Be terse.");
+        assert_eq!(body["max_tokens"], 512);
+        assert_eq!(body["stop_sequences"], json!(["DONE"]));
+        assert_eq!(body["tools"][0]["name"], "read_file");
+        assert_eq!(body["tools"][0]["input_schema"]["type"], "object");
+
+        let parsed = parse_anthropic_response(&json!({
+            "content": [
+                { "type": "text", "text": "Looking. " },
+                { "type": "tool_use", "id": "tu_1", "name": "read_file",
+                  "input": { "path": "src/lib.rs" } },
+            ],
+            "stop_reason": "tool_use",
+            "usage": { "input_tokens": 10, "output_tokens": 4 },
+        }))
+        .unwrap();
+        assert_eq!(parsed.text, "Looking. ");
+        assert_eq!(parsed.tool_calls[0].name, "read_file");
+        assert_eq!(parsed.tool_calls[0].arguments, json!({ "path": "src/lib.rs" }));
+        assert_eq!(parsed.stop_reason, StopReason::ToolUse);
+        assert_eq!(parsed.usage.input_tokens, 10);
+    }
+
+    #[test]
+    fn openai_wire_format_round_trips() {
+        let body = build_openai_body(&request());
+        // System travels as the first message, not a top-level field.
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "Triage issue 42.");
+        assert_eq!(body["tools"][0]["function"]["name"], "read_file");
+        assert_eq!(body["max_completion_tokens"], 512);
+
+        let parsed = parse_openai_response(&json!({
+            "choices": [{
+                "message": {
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "function": {
+                            "name": "read_file",
+                            "arguments": "{\"path\":\"src/lib.rs\"}",
+                        },
+                    }],
+                },
+                "finish_reason": "tool_calls",
+            }],
+            "usage": { "prompt_tokens": 9, "completion_tokens": 3 },
+        }))
+        .unwrap();
+        assert_eq!(parsed.tool_calls[0].arguments, json!({ "path": "src/lib.rs" }));
+        assert_eq!(parsed.stop_reason, StopReason::ToolUse);
+        assert_eq!(parsed.usage.output_tokens, 3);
+    }
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn unknown_providers_fail_selection() {
+        let client = resolve_client("local/llama-3").unwrap();
+        let err = provider_for(&client).err().expect("no local client");
+        assert!(matches!(err, AgentError::UnsupportedProvider(p) if p == "local"));
+    }
+}
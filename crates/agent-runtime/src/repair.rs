@@ -0,0 +1,170 @@
+//! Schema-enforcement retry loop for structured outputs.
+//!
+//! Models drift: a prompt with an `output` schema usually gets valid JSON
+//! back, but not always, and one bad response shouldn't fail the caller
+//! when the model can fix it. [`complete_structured`] validates each
+//! response and, on failure, re-prompts with the response and its
+//! validation errors appended — the model sees exactly what it wrote and
+//! why it was rejected — up to a configurable number of repairs. The
+//! result carries the validated output *and* the repair history, so
+//! callers can track how often prompts need repairing.
+
+use serde_json::Value;
+
+use prompt_parser::{Message, PromptDefinition};
+
+use crate::error::AgentError;
+use crate::provider::{Provider, ProviderRequest, Usage};
+
+/// Repair attempts tried after the initial response, by default.
+pub const DEFAULT_MAX_REPAIRS: u32 = 2;
+
+/// One rejected response and why it was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairAttempt {
+    /// 1-based attempt ordinal.
+    pub attempt: u32,
+    pub text: String,
+    pub error: String,
+}
+
+/// A validated structured completion with its repair history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredOutput {
+    /// The final output, valid against the prompt's `output` schema.
+    pub output: Value,
+    /// The raw text of the accepted response.
+    pub text: String,
+    /// Rejected responses, oldest first; empty when the first response
+    /// validated.
+    pub repairs: Vec<RepairAttempt>,
+    /// Usage summed over every attempt, repairs included.
+    pub usage: Usage,
+}
+
+/// Execute `def` and validate the response against its `output` schema,
+/// re-prompting with the validation errors up to `max_repairs` times.
+/// When the budget runs out the last error comes back as
+/// [`AgentError::InvalidOutput`].
+pub fn complete_structured(
+    def: &PromptDefinition,
+    data: &Value,
+    provider: &dyn Provider,
+    max_repairs: u32,
+) -> Result<StructuredOutput, AgentError> {
+    let mut request = ProviderRequest::from_definition(def, data)?;
+    let mut repairs = Vec::new();
+    let mut usage = Usage::default();
+
+    for attempt in 1..=max_repairs + 1 {
+        let response = provider.complete(&request)?;
+        usage.input_tokens += response.usage.input_tokens;
+        usage.output_tokens += response.usage.output_tokens;
+
+        match validate(def, &response.text) {
+            Ok(output) => {
+                return Ok(StructuredOutput {
+                    output,
+                    text: response.text,
+                    repairs,
+                    usage,
+                });
+            }
+            Err(error) => {
+                request.messages.push(Message {
+                    role: "assistant".into(),
+                    content: response.text.clone(),
+                    attachments: Vec::new(),
+                });
+                request.messages.push(Message {
+                    role: "user".into(),
+                    content: format!(
+                        "Your response failed validation: {error}\n\
+                         Reply again with only JSON that satisfies the output schema."
+                    ),
+                    attachments: Vec::new(),
+                });
+                repairs.push(RepairAttempt {
+                    attempt,
+                    text: response.text,
+                    error,
+                });
+            }
+        }
+    }
+
+    let last = repairs.last().expect("at least one failed attempt");
+    Err(AgentError::InvalidOutput {
+        attempts: max_repairs + 1,
+        message: last.error.clone(),
+    })
+}
+
+fn validate(def: &PromptDefinition, text: &str) -> Result<Value, String> {
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| format!("response is not valid JSON: {e}"))?;
+    def.validate_output(&value).map_err(|e| e.to_string())?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockProvider, text_response};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn def() -> PromptDefinition {
+        prompt_parser::parse(
+            "---\n\
+             name: classify\n\
+             client: anthropic/claude-sonnet-4\n\
+             output:\n\
+             \x20 type: object\n\
+             \x20 properties:\n\
+             \x20   label: { type: string }\n\
+             \x20 required: [label]\n\
+             ---\n\
+             classify this",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn a_valid_first_response_needs_no_repairs() {
+        let provider = MockProvider::new().reply(text_response(r#"{"label":"bug"}"#));
+        let result = complete_structured(&def(), &json!({}), &provider, DEFAULT_MAX_REPAIRS).unwrap();
+        assert_eq!(result.output, json!({ "label": "bug" }));
+        assert!(result.repairs.is_empty());
+    }
+
+    #[test]
+    fn invalid_responses_are_replayed_with_their_errors() {
+        let provider = MockProvider::new()
+            .reply(text_response("not json at all"))
+            .reply(text_response(r#"{"wrong":1}"#))
+            .reply(text_response(r#"{"label":"bug"}"#));
+        let result = complete_structured(&def(), &json!({}), &provider, 2).unwrap();
+        assert_eq!(result.output, json!({ "label": "bug" }));
+        assert_eq!(result.repairs.len(), 2);
+        assert!(result.repairs[0].error.contains("not valid JSON"));
+        assert!(result.repairs[1].error.contains("label"));
+
+        // The final request carried both rejected responses and their errors.
+        let requests = provider.requests();
+        let last = &requests[2].messages;
+        assert_eq!(last.len(), 5);
+        assert_eq!(last[1].content, "not json at all");
+        assert!(last[2].content.contains("failed validation"));
+        assert_eq!(last[3].content, r#"{"wrong":1}"#);
+    }
+
+    #[test]
+    fn the_repair_budget_is_finite() {
+        let provider = MockProvider::new()
+            .reply(text_response("junk"))
+            .reply(text_response("more junk"));
+        let err = complete_structured(&def(), &json!({}), &provider, 1).unwrap_err();
+        assert!(matches!(err, AgentError::InvalidOutput { attempts: 2, .. }));
+    }
+}
@@ -0,0 +1,231 @@
+//! Tool dispatch.
+//!
+//! Prompts *declare* tools by name; a [`ToolRunner`] is where those names
+//! meet actual Rust code. Each registered tool carries a JSON Schema for
+//! its arguments, enforced before the handler runs, so a handler never
+//! sees arguments it didn't ask for. Handler failures and schema
+//! violations don't abort the run — they become error-flagged tool
+//! results fed back to the model, which is how a model corrects itself.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+
+use prompt_parser::Message;
+
+use crate::error::AgentError;
+use crate::provider::{ToolCallRequest, ToolSpec};
+
+type Handler = Box<dyn Fn(&Value) -> Result<Value, String> + Send + Sync>;
+
+struct RegisteredTool {
+    description: Option<String>,
+    parameters: Value,
+    validator: jsonschema::Validator,
+    handler: Handler,
+}
+
+/// The outcome of one dispatched tool call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolResult {
+    /// The provider's call id, echoed back.
+    pub id: String,
+    pub name: String,
+    pub result: Value,
+    /// Set for schema violations and handler failures; the result then
+    /// holds the error text for the model to read.
+    pub is_error: bool,
+}
+
+impl ToolResult {
+    /// The tool-result message appended to the conversation.
+    pub fn into_message(self) -> Message {
+        Message {
+            role: "tool".to_string(),
+            content: serialize_result(&self.result),
+            attachments: Vec::new(),
+        }
+    }
+}
+
+fn serialize_result(result: &Value) -> String {
+    match result {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Maps tool names to registered Rust handlers.
+#[derive(Default)]
+pub struct ToolRunner {
+    tools: BTreeMap<String, RegisteredTool>,
+}
+
+impl ToolRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool. `parameters` is the JSON Schema its arguments must
+    /// satisfy; it must itself compile.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        description: Option<&str>,
+        parameters: Value,
+        handler: impl Fn(&Value) -> Result<Value, String> + Send + Sync + 'static,
+    ) -> Result<(), AgentError> {
+        let name = name.into();
+        let validator =
+            jsonschema::validator_for(&parameters).map_err(|e| AgentError::Provider {
+                provider: "tools".to_string(),
+                message: format!("tool `{name}` parameter schema does not compile: {e}"),
+            })?;
+        self.tools.insert(
+            name,
+            RegisteredTool {
+                description: description.map(String::from),
+                parameters,
+                validator,
+                handler: Box::new(handler),
+            },
+        );
+        Ok(())
+    }
+
+    /// The registered tool names, for checking a prompt's `tools:` block.
+    pub fn registry(&self) -> prompt_parser::ToolRegistry {
+        self.tools.keys().collect()
+    }
+
+    /// Provider-facing specs (name, description, parameter schema) for the
+    /// named tools. Unknown names fail so a prompt can't offer the model a
+    /// tool nothing can execute.
+    pub fn specs(&self, names: &[String]) -> Result<Vec<ToolSpec>, AgentError> {
+        names
+            .iter()
+            .map(|name| {
+                let tool = self.get(name)?;
+                Ok(ToolSpec {
+                    name: name.clone(),
+                    description: tool.description.clone(),
+                    parameters: tool.parameters.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn get(&self, name: &str) -> Result<&RegisteredTool, AgentError> {
+        self.tools.get(name).ok_or_else(|| {
+            AgentError::Prompt(prompt_parser::PromptError::UnknownTool {
+                tool: name.to_string(),
+                available: self.tools.keys().cloned().collect(),
+            })
+        })
+    }
+
+    /// Validate the call's arguments against the tool's schema, run the
+    /// handler, and package the outcome. Only an *unknown* tool is an
+    /// `Err`; bad arguments and handler failures come back as
+    /// error-flagged results for the model.
+    pub fn dispatch(&self, call: &ToolCallRequest) -> Result<ToolResult, AgentError> {
+        tracing::debug!(tool = %call.name, "dispatching tool call");
+        let tool = self.get(&call.name)?;
+        if let Err(violation) = tool.validator.validate(&call.arguments) {
+            return Ok(ToolResult {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                result: json!(format!(
+                    "invalid arguments at `{}`: {violation}",
+                    violation.instance_path
+                )),
+                is_error: true,
+            });
+        }
+        let (result, is_error) = match (tool.handler)(&call.arguments) {
+            Ok(value) => (value, false),
+            Err(message) => (json!(message), true),
+        };
+        Ok(ToolResult {
+            id: call.id.clone(),
+            name: call.name.clone(),
+            result,
+            is_error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn runner() -> ToolRunner {
+        let mut runner = ToolRunner::new();
+        runner
+            .register(
+                "add",
+                Some("Add two numbers"),
+                json!({
+                    "type": "object",
+                    "properties": { "a": { "type": "number" }, "b": { "type": "number" } },
+                    "required": ["a", "b"],
+                    "additionalProperties": false,
+                }),
+                |args| Ok(json!(args["a"].as_f64().unwrap() + args["b"].as_f64().unwrap())),
+            )
+            .unwrap();
+        runner
+            .register("fail", None, json!({ "type": "object" }), |_| {
+                Err("disk on fire".to_string())
+            })
+            .unwrap();
+        runner
+    }
+
+    fn call(name: &str, arguments: Value) -> ToolCallRequest {
+        ToolCallRequest {
+            id: "call_1".into(),
+            name: name.into(),
+            arguments,
+        }
+    }
+
+    #[test]
+    fn valid_calls_run_the_handler() {
+        let result = runner().dispatch(&call("add", json!({ "a": 2, "b": 3 }))).unwrap();
+        assert_eq!(result.result, json!(5.0));
+        assert!(!result.is_error);
+        assert_eq!(result.into_message().role, "tool");
+    }
+
+    #[test]
+    fn schema_violations_become_error_results_not_failures() {
+        let result = runner()
+            .dispatch(&call("add", json!({ "a": "two", "b": 3 })))
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.result.as_str().unwrap().contains("invalid arguments at `/a`"));
+    }
+
+    #[test]
+    fn handler_errors_become_error_results() {
+        let result = runner().dispatch(&call("fail", json!({}))).unwrap();
+        assert!(result.is_error);
+        assert_eq!(result.result, json!("disk on fire"));
+    }
+
+    #[test]
+    fn unknown_tools_are_a_hard_error() {
+        let err = runner().dispatch(&call("rm_rf", json!({}))).unwrap_err();
+        assert!(err.to_string().contains("unknown tool `rm_rf`"));
+    }
+
+    #[test]
+    fn specs_carry_real_parameter_schemas_to_the_provider() {
+        let specs = runner().specs(&["add".to_string()]).unwrap();
+        assert_eq!(specs[0].parameters["required"], json!(["a", "b"]));
+        assert!(runner().specs(&["nope".to_string()]).is_err());
+        assert!(runner().registry().contains("add"));
+    }
+}
@@ -0,0 +1,293 @@
+//! Path-sandboxed file tools.
+//!
+//! A prompt is untrusted input, and a prompt picks tool arguments — so the
+//! *tools* enforce the boundary, in Rust, before any filesystem call. A
+//! [`PathSandbox`] roots every path at the workspace, rejects escapes
+//! (`..`, absolute paths, symlinks pointing outside), and refuses
+//! denylisted names like `.env` and `.ssh` wherever they appear.
+//! [`register_file_tools`] installs `read_file`, `write_file`, and
+//! `list_dir` on a [`ToolRunner`] behind one shared sandbox.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+
+use crate::error::AgentError;
+use crate::runner::ToolRunner;
+
+/// Names no tool may touch, wherever they sit in the tree.
+const DEFAULT_DENYLIST: &[&str] = &[".env", ".env.*", ".ssh", ".aws", ".gnupg", "*.pem", "id_rsa*"];
+
+/// The allowed filesystem surface: one root, minus denylisted names.
+#[derive(Debug, Clone)]
+pub struct PathSandbox {
+    root: PathBuf,
+    denied: Vec<String>,
+}
+
+impl PathSandbox {
+    /// Sandbox rooted at `root` (which must exist) with the default
+    /// denylist.
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, AgentError> {
+        let root = root.as_ref();
+        let root = root.canonicalize().map_err(|e| AgentError::Io {
+            path: root.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(PathSandbox {
+            root,
+            denied: DEFAULT_DENYLIST.iter().map(|p| p.to_string()).collect(),
+        })
+    }
+
+    /// Add denylist patterns (`*` wildcards, matched against every path
+    /// component).
+    pub fn deny(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.denied.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve a tool-supplied path to an absolute path inside the root.
+    ///
+    /// Errors are plain strings because they flow back to the model as
+    /// error-flagged tool results.
+    pub fn resolve(&self, requested: &str) -> Result<PathBuf, String> {
+        let requested_path = Path::new(requested);
+        if requested_path.is_absolute() {
+            return Err(format!("absolute paths are not allowed: `{requested}`"));
+        }
+        // Normalize lexically; `..` may not climb past the root.
+        let mut components: Vec<String> = Vec::new();
+        for component in requested_path.components() {
+            match component {
+                Component::Normal(name) => {
+                    let name = name.to_string_lossy().to_string();
+                    if self.denied.iter().any(|p| glob_match(p, &name)) {
+                        return Err(format!("`{name}` is denied by the sandbox"));
+                    }
+                    components.push(name);
+                }
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if components.pop().is_none() {
+                        return Err(format!("`{requested}` escapes the workspace"));
+                    }
+                }
+                Component::RootDir | Component::Prefix(_) => {
+                    return Err(format!("absolute paths are not allowed: `{requested}`"));
+                }
+            }
+        }
+        let resolved = components.iter().fold(self.root.clone(), |p, c| p.join(c));
+
+        // A symlink inside the root can still point outside it; check the
+        // deepest existing ancestor's real location. `symlink_metadata`
+        // rather than `exists()`, because `exists()` follows links — a
+        // *dangling* symlink would vanish from the walk and a later write
+        // would land wherever it points.
+        let mut existing = resolved.as_path();
+        while existing.symlink_metadata().is_err() {
+            existing = existing.parent().unwrap_or(&self.root);
+        }
+        let real = existing
+            .canonicalize()
+            .map_err(|e| format!("cannot resolve `{requested}`: {e}"))?;
+        if !real.starts_with(&self.root) {
+            return Err(format!("`{requested}` resolves outside the workspace"));
+        }
+        Ok(resolved)
+    }
+
+    /// [`Self::resolve`] for host-side callers: the same checks, but a
+    /// refusal comes back as [`AgentError::PolicyViolation`] instead of a
+    /// tool-result string.
+    pub fn resolve_checked(&self, requested: &str) -> Result<PathBuf, AgentError> {
+        self.resolve(requested).map_err(AgentError::PolicyViolation)
+    }
+}
+
+/// `*`-only glob match over one path component.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    fn matches(p: &[u8], n: &[u8]) -> bool {
+        match (p.first(), n.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => matches(&p[1..], n) || (!n.is_empty() && matches(p, &n[1..])),
+            (Some(pc), Some(nc)) if pc == nc => matches(&p[1..], &n[1..]),
+            _ => false,
+        }
+    }
+    matches(pattern.as_bytes(), name.as_bytes())
+}
+
+/// Register `read_file`, `write_file`, and `list_dir` on `runner`, all
+/// constrained to `sandbox`.
+pub fn register_file_tools(runner: &mut ToolRunner, sandbox: PathSandbox) -> Result<(), AgentError> {
+    let sandbox = Arc::new(sandbox);
+
+    let reader = Arc::clone(&sandbox);
+    runner.register(
+        "read_file",
+        Some("Read a workspace file as UTF-8 text"),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string" } },
+            "required": ["path"],
+            "additionalProperties": false,
+        }),
+        move |args| {
+            let path = reader.resolve(args["path"].as_str().unwrap_or_default())?;
+            std::fs::read_to_string(&path).map(Value::String).map_err(|e| e.to_string())
+        },
+    )?;
+
+    let writer = Arc::clone(&sandbox);
+    runner.register(
+        "write_file",
+        Some("Write a workspace file, creating parent directories"),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "content": { "type": "string" },
+            },
+            "required": ["path", "content"],
+            "additionalProperties": false,
+        }),
+        move |args| {
+            let path = writer.resolve(args["path"].as_str().unwrap_or_default())?;
+            let content = args["content"].as_str().unwrap_or_default();
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&path, content).map_err(|e| e.to_string())?;
+            Ok(json!(format!("wrote {} bytes", content.len())))
+        },
+    )?;
+
+    runner.register(
+        "list_dir",
+        Some("List a workspace directory; directories get a trailing slash"),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string" } },
+            "additionalProperties": false,
+        }),
+        move |args| {
+            let path = sandbox.resolve(args["path"].as_str().unwrap_or("."))?;
+            let mut names = Vec::new();
+            for entry in std::fs::read_dir(&path).map_err(|e| e.to_string())? {
+                let entry = entry.map_err(|e| e.to_string())?;
+                let mut name = entry.file_name().to_string_lossy().to_string();
+                if entry.path().is_dir() {
+                    name.push('/');
+                }
+                names.push(name);
+            }
+            names.sort_unstable();
+            Ok(json!(names))
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ToolCallRequest;
+    use pretty_assertions::assert_eq;
+
+    fn workspace(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-sandbox-{label}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn call(name: &str, arguments: Value) -> ToolCallRequest {
+        ToolCallRequest {
+            id: "call_1".into(),
+            name: name.into(),
+            arguments,
+        }
+    }
+
+    #[test]
+    fn escapes_and_absolute_paths_are_rejected() {
+        let sandbox = PathSandbox::new(workspace("escapes")).unwrap();
+        assert!(sandbox.resolve("../outside.txt").unwrap_err().contains("escapes"));
+        assert!(sandbox.resolve("a/../../outside.txt").unwrap_err().contains("escapes"));
+        assert!(sandbox.resolve("/etc/passwd").unwrap_err().contains("absolute"));
+        // `..` that stays inside is fine.
+        assert!(sandbox.resolve("a/../b.txt").is_ok());
+    }
+
+    #[test]
+    fn denylisted_names_are_refused_at_any_depth() {
+        let sandbox = PathSandbox::new(workspace("deny")).unwrap();
+        for path in [".env", "config/.env.local", "home/.ssh/id_rsa", "certs/server.pem"] {
+            assert!(sandbox.resolve(path).unwrap_err().contains("denied"), "{path}");
+        }
+        let stricter = PathSandbox::new(workspace("deny2")).unwrap().deny(["secrets*"]);
+        assert!(stricter.resolve("secrets.yaml").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_pointing_outside_the_root_are_refused() {
+        let dir = workspace("symlink");
+        std::os::unix::fs::symlink("/etc", dir.join("sneaky")).unwrap();
+        let sandbox = PathSandbox::new(&dir).unwrap();
+        assert!(
+            sandbox.resolve("sneaky/passwd").unwrap_err().contains("outside the workspace")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_symlinks_cannot_smuggle_writes_outside() {
+        let dir = workspace("dangling");
+        // Points outside the root at a file that does not exist yet; a
+        // write through it would create the outside file.
+        std::os::unix::fs::symlink("/tmp/definitely-not-here.txt", dir.join("trap")).unwrap();
+        let sandbox = PathSandbox::new(&dir).unwrap();
+        assert!(sandbox.resolve("trap").unwrap_err().contains("cannot resolve"));
+
+        let err = sandbox.resolve_checked("trap").unwrap_err();
+        assert!(matches!(err, AgentError::PolicyViolation(_)));
+        assert!(err.to_string().starts_with("sandbox policy violation"));
+    }
+
+    #[test]
+    fn the_file_tools_read_write_and_list_through_the_sandbox() {
+        let dir = workspace("tools");
+        let mut runner = ToolRunner::new();
+        register_file_tools(&mut runner, PathSandbox::new(&dir).unwrap()).unwrap();
+
+        let wrote = runner
+            .dispatch(&call("write_file", json!({ "path": "src/main.rs", "content": "fn main() {}\n" })))
+            .unwrap();
+        assert!(!wrote.is_error);
+
+        let read = runner
+            .dispatch(&call("read_file", json!({ "path": "src/main.rs" })))
+            .unwrap();
+        assert_eq!(read.result, json!("fn main() {}\n"));
+
+        let listed = runner.dispatch(&call("list_dir", json!({}))).unwrap();
+        assert_eq!(listed.result, json!(["src/"]));
+
+        // Sandbox violations surface as error results the model can read.
+        let denied = runner
+            .dispatch(&call("read_file", json!({ "path": "../../etc/passwd" })))
+            .unwrap();
+        assert!(denied.is_error);
+    }
+}
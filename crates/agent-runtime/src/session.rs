@@ -0,0 +1,319 @@
+//! Durable agent sessions.
+//!
+//! An agent run is minutes of provider calls, tool dispatches, and commits —
+//! far too much state to lose to a crash or a laptop lid. A [`Session`]
+//! records everything the run has done (the prompt used, its inputs, the
+//! message history, tool calls, produced commits), and a [`SessionStore`]
+//! persists it as one JSON document per session so
+//! [`SessionStore::resume`] can pick an interrupted run back up where it
+//! left off.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use prompt_parser::Message;
+
+use crate::error::AgentError;
+
+/// Where a session currently stands.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    #[default]
+    Running,
+    Completed,
+    Failed,
+    /// The process stopped mid-run; a resume continues from the recorded
+    /// history.
+    Interrupted,
+}
+
+/// One tool invocation made during a session.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCallRecord {
+    /// Which turn of the agent loop issued the call, starting at 1.
+    pub turn: u32,
+    pub tool: String,
+    pub arguments: Value,
+    /// The serialized tool result fed back to the model.
+    pub result: Value,
+}
+
+/// One agent run's durable state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Session {
+    pub id: String,
+    /// The registered prompt this session executes.
+    pub prompt_name: String,
+    /// The inputs the run started with.
+    pub inputs: Value,
+    /// Full message history, in provider order.
+    #[serde(default)]
+    pub messages: Vec<Message>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// Commit/change ids the session produced in the workspace.
+    #[serde(default)]
+    pub commits: Vec<String>,
+    /// One entry per provider call; see [`crate::accounting`].
+    #[serde(default)]
+    pub usage: Vec<crate::accounting::UsageEvent>,
+    /// Working-copy snapshots taken before write-class tool calls; see
+    /// [`crate::checkpoint`].
+    #[serde(default)]
+    pub checkpoints: Vec<crate::checkpoint::Checkpoint>,
+    #[serde(default)]
+    pub status: SessionStatus,
+    /// Unix seconds.
+    pub created_at: i64,
+    /// Unix seconds; bumped on every save.
+    pub updated_at: i64,
+}
+
+impl Session {
+    /// Append a message to the history.
+    pub fn push_message(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    /// Record a completed tool call.
+    pub fn record_tool_call(&mut self, record: ToolCallRecord) {
+        self.tool_calls.push(record);
+    }
+
+    /// Record a commit the session produced.
+    pub fn record_commit(&mut self, commit_id: impl Into<String>) {
+        self.commits.push(commit_id.into());
+    }
+
+    /// Record one provider call's usage and cost.
+    pub fn record_usage(&mut self, event: crate::accounting::UsageEvent) {
+        self.usage.push(event);
+    }
+}
+
+/// One-JSON-document-per-session storage under a directory.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+    redactor: Option<crate::redact::Redactor>,
+}
+
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn now_unix() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// Collision-free without a uuid dependency: wall clock + pid + a process
+/// counter.
+fn generate_id() -> String {
+    format!(
+        "{:x}-{:x}-{:x}",
+        now_unix(),
+        std::process::id(),
+        SESSION_COUNTER.fetch_add(1, Ordering::Relaxed),
+    )
+}
+
+impl SessionStore {
+    /// Open (creating if needed) a store rooted at `dir`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, AgentError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| AgentError::Io {
+            path: dir.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(SessionStore { dir, redactor: None })
+    }
+
+    /// Scrub secrets from every session this store writes; see
+    /// [`crate::redact::Redactor`].
+    pub fn with_redactor(mut self, redactor: crate::redact::Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    fn path_for(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    /// Start a new session and persist its initial state.
+    pub fn create(
+        &self,
+        prompt_name: impl Into<String>,
+        inputs: Value,
+    ) -> Result<Session, AgentError> {
+        let now = now_unix();
+        let session = Session {
+            id: generate_id(),
+            prompt_name: prompt_name.into(),
+            inputs,
+            messages: Vec::new(),
+            tool_calls: Vec::new(),
+            commits: Vec::new(),
+            usage: Vec::new(),
+            checkpoints: Vec::new(),
+            status: SessionStatus::Running,
+            created_at: now,
+            updated_at: now,
+        };
+        self.save(&session)?;
+        Ok(session)
+    }
+
+    /// Persist the session, bumping `updated_at`. Writes are
+    /// atomic-via-rename so a crash never leaves a half-written document.
+    pub fn save(&self, session: &Session) -> Result<(), AgentError> {
+        let mut session = session.clone();
+        session.updated_at = now_unix();
+        let path = self.path_for(&session.id);
+        let tmp = path.with_extension("json.tmp");
+        let mut json = serde_json::to_string_pretty(&session).expect("sessions serialize");
+        // Scrub the serialized text, not the struct — it covers every
+        // field (messages, tool results, diffs) in one pass.
+        if let Some(redactor) = &self.redactor {
+            json = redactor.redact(&json);
+        }
+        std::fs::write(&tmp, json)
+            .and_then(|()| std::fs::rename(&tmp, &path))
+            .map_err(|e| AgentError::Io {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Load a stored session.
+    pub fn load(&self, id: &str) -> Result<Session, AgentError> {
+        let path = self.path_for(id);
+        let json = std::fs::read_to_string(&path)
+            .map_err(|_| AgentError::UnknownSession(id.to_string()))?;
+        serde_json::from_str(&json).map_err(|e| AgentError::CorruptSession {
+            id: id.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// [`Self::load`], then mark the session running again so an
+    /// interrupted run continues where it left off.
+    pub fn resume(&self, id: &str) -> Result<Session, AgentError> {
+        let mut session = self.load(id)?;
+        session.status = SessionStatus::Running;
+        self.save(&session)?;
+        Ok(session)
+    }
+
+    /// Stored session ids, newest first.
+    pub fn list(&self) -> Result<Vec<String>, AgentError> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| AgentError::Io {
+            path: self.dir.display().to_string(),
+            message: e.to_string(),
+        })?;
+        let mut ids: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn store(label: &str) -> SessionStore {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-sessions-{label}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        SessionStore::open(dir).unwrap()
+    }
+
+    #[test]
+    fn a_redacting_store_scrubs_what_it_persists() {
+        let store = store("redacted").with_redactor(
+            crate::redact::Redactor::new().with_literal("hunter2-hunter2"),
+        );
+        let mut session = store.create("deploy", json!({})).unwrap();
+        session.push_message(prompt_parser::Message {
+            role: "user".into(),
+            content: "the password is hunter2-hunter2".into(),
+            attachments: Vec::new(),
+        });
+        store.save(&session).unwrap();
+        let loaded = store.load(&session.id).unwrap();
+        assert_eq!(loaded.messages[0].content, "the password is [REDACTED]");
+    }
+
+    #[test]
+    fn sessions_round_trip_through_the_store() {
+        let store = store("roundtrip");
+        let mut session = store
+            .create("triage", json!({ "issue": 42 }))
+            .unwrap();
+        session.push_message(prompt_parser::Message {
+            role: "user".into(),
+            content: "triage issue 42".into(),
+            attachments: Vec::new(),
+        });
+        session.record_tool_call(ToolCallRecord {
+            turn: 1,
+            tool: "read_file".into(),
+            arguments: json!({ "path": "src/lib.rs" }),
+            result: json!("contents"),
+        });
+        session.record_commit("zxqlmnop");
+        session.status = SessionStatus::Interrupted;
+        store.save(&session).unwrap();
+
+        let resumed = store.resume(&session.id).unwrap();
+        assert_eq!(resumed.status, SessionStatus::Running);
+        assert_eq!(resumed.prompt_name, "triage");
+        assert_eq!(resumed.messages.len(), 1);
+        assert_eq!(resumed.tool_calls[0].tool, "read_file");
+        assert_eq!(resumed.commits, vec!["zxqlmnop"]);
+        assert!(resumed.updated_at >= resumed.created_at);
+    }
+
+    #[test]
+    fn unknown_and_corrupt_sessions_are_distinct_errors() {
+        let store = store("errors");
+        assert!(matches!(
+            store.load("nope").unwrap_err(),
+            AgentError::UnknownSession(_)
+        ));
+
+        let session = store.create("x", json!({})).unwrap();
+        std::fs::write(store.path_for(&session.id), "{ not json").unwrap();
+        assert!(matches!(
+            store.load(&session.id).unwrap_err(),
+            AgentError::CorruptSession { .. }
+        ));
+    }
+
+    #[test]
+    fn list_returns_ids_newest_first() {
+        let store = store("list");
+        let a = store.create("a", json!({})).unwrap();
+        let b = store.create("b", json!({})).unwrap();
+        let ids = store.list().unwrap();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&a.id) && ids.contains(&b.id));
+        assert!(ids[0] >= ids[1]);
+    }
+}
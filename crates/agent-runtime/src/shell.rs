@@ -0,0 +1,236 @@
+//! The built-in shell tool.
+//!
+//! Letting a model run commands is useful exactly until one hangs, floods
+//! memory with output, or reads the host's environment. [`run_shell`]
+//! executes `sh -c` with a wall-clock timeout, capped stdout/stderr,
+//! the working directory pinned to the workspace, and a scrubbed
+//! environment (only an explicit allowlist survives). The result is
+//! structured — exit code, truncated output, duration — so the agent loop
+//! and the UI both get facts, not a blob.
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::AgentError;
+use crate::runner::ToolRunner;
+
+/// Environment variables worth keeping; everything else is scrubbed.
+const DEFAULT_ENV_ALLOWLIST: &[&str] = &["PATH", "HOME", "LANG", "LC_ALL", "TERM", "TMPDIR"];
+
+/// Limits and placement for shell executions.
+#[derive(Debug, Clone)]
+pub struct ShellConfig {
+    /// Commands run here and nowhere else.
+    pub workspace: PathBuf,
+    pub timeout: Duration,
+    /// Cap per stream; output beyond it is dropped and flagged.
+    pub max_output_bytes: usize,
+    /// Variables passed through from the host environment.
+    pub env_allowlist: Vec<String>,
+}
+
+impl ShellConfig {
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        ShellConfig {
+            workspace: workspace.into(),
+            timeout: Duration::from_secs(30),
+            max_output_bytes: 64 * 1024,
+            env_allowlist: DEFAULT_ENV_ALLOWLIST.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+}
+
+/// One command's structured outcome.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShellOutcome {
+    /// `None` when the command was killed (timeout or signal).
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// True when either stream hit `max_output_bytes`.
+    pub truncated: bool,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+/// Drain a stream fully (so the child never blocks on a full pipe) but
+/// keep at most `cap` bytes.
+fn drain_capped(mut stream: impl Read, cap: usize) -> (Vec<u8>, bool) {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0u8; 8192];
+    while let Ok(n) = stream.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        if kept.len() < cap {
+            let take = n.min(cap - kept.len());
+            kept.extend_from_slice(&buf[..take]);
+            if take < n {
+                truncated = true;
+            }
+        } else {
+            truncated = true;
+        }
+    }
+    (kept, truncated)
+}
+
+/// Run one command under the configured limits.
+pub fn run_shell(config: &ShellConfig, command: &str) -> Result<ShellOutcome, AgentError> {
+    let started = Instant::now();
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(&config.workspace)
+        .env_clear()
+        .envs(
+            config
+                .env_allowlist
+                .iter()
+                .filter_map(|k| std::env::var(k).ok().map(|v| (k.clone(), v))),
+        )
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AgentError::Io {
+            path: config.workspace.display().to_string(),
+            message: format!("failed to spawn `sh`: {e}"),
+        })?;
+
+    let cap = config.max_output_bytes;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let out_thread = std::thread::spawn(move || drain_capped(stdout, cap));
+    let err_thread = std::thread::spawn(move || drain_capped(stderr, cap));
+
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait().ok().flatten() {
+            break Some(status);
+        }
+        if started.elapsed() >= config.timeout {
+            timed_out = true;
+            let _ = child.kill();
+            break child.wait().ok();
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    // Measure before draining: a grandchild holding the pipes open (e.g.
+    // the `sleep` under a killed `sh`) shouldn't count toward duration.
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let (stdout, out_truncated) = out_thread.join().expect("stdout drain doesn't panic");
+    let (stderr, err_truncated) = err_thread.join().expect("stderr drain doesn't panic");
+
+    Ok(ShellOutcome {
+        exit_code: status.and_then(|s| s.code()),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        truncated: out_truncated || err_truncated,
+        timed_out,
+        duration_ms,
+    })
+}
+
+/// Register the `shell` tool on `runner`.
+pub fn register_shell_tool(runner: &mut ToolRunner, config: ShellConfig) -> Result<(), AgentError> {
+    runner.register(
+        "shell",
+        Some("Run a shell command in the workspace; output is size-capped and time-limited"),
+        json!({
+            "type": "object",
+            "properties": { "command": { "type": "string" } },
+            "required": ["command"],
+            "additionalProperties": false,
+        }),
+        move |args| {
+            let command = args["command"].as_str().unwrap_or_default();
+            let outcome = run_shell(&config, command).map_err(|e| e.to_string())?;
+            Ok(serde_json::to_value(&outcome).expect("outcomes serialize"))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ToolCallRequest;
+    use pretty_assertions::assert_eq;
+
+    fn config(label: &str) -> ShellConfig {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-shell-{label}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        ShellConfig::new(dir)
+    }
+
+    #[test]
+    fn commands_run_in_the_workspace_with_structured_results() {
+        let config = config("basic");
+        let outcome = run_shell(&config, "pwd && echo oops >&2 && exit 3").unwrap();
+        assert_eq!(outcome.exit_code, Some(3));
+        assert_eq!(
+            PathBuf::from(outcome.stdout.trim()).canonicalize().unwrap(),
+            config.workspace.canonicalize().unwrap()
+        );
+        assert_eq!(outcome.stderr, "oops\n");
+        assert!(!outcome.timed_out);
+        assert!(!outcome.truncated);
+    }
+
+    #[test]
+    fn timeouts_kill_the_command_and_say_so() {
+        let mut config = config("timeout");
+        config.timeout = Duration::from_millis(100);
+        let outcome = run_shell(&config, "sleep 5").unwrap();
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.exit_code, None);
+        assert!(outcome.duration_ms < 3_000);
+    }
+
+    #[test]
+    fn oversized_output_is_capped_and_flagged() {
+        let mut config = config("cap");
+        config.max_output_bytes = 100;
+        let outcome = run_shell(&config, "yes x | head -c 10000").unwrap();
+        assert!(outcome.truncated);
+        assert_eq!(outcome.stdout.len(), 100);
+        assert_eq!(outcome.exit_code, Some(0));
+    }
+
+    #[test]
+    fn the_environment_is_scrubbed_to_the_allowlist() {
+        // SAFETY: test-only; no other thread reads this variable.
+        unsafe { std::env::set_var("AGENT_RUNTIME_SHELL_SECRET", "hunter2") };
+        let outcome = run_shell(&config("env"), "echo \"[$AGENT_RUNTIME_SHELL_SECRET]\" && echo \"$PATH\"").unwrap();
+        let mut lines = outcome.stdout.lines();
+        assert_eq!(lines.next(), Some("[]"));
+        assert!(!lines.next().unwrap_or_default().is_empty(), "PATH survives");
+    }
+
+    #[test]
+    fn the_registered_tool_round_trips_through_dispatch() {
+        let mut runner = ToolRunner::new();
+        register_shell_tool(&mut runner, config("tool")).unwrap();
+        let result = runner
+            .dispatch(&ToolCallRequest {
+                id: "call_1".into(),
+                name: "shell".into(),
+                arguments: serde_json::json!({ "command": "echo hi" }),
+            })
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(result.result["stdout"], "hi\n");
+        assert_eq!(result.result["exit_code"], 0);
+    }
+}
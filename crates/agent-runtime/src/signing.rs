@@ -0,0 +1,131 @@
+//! Commit signature verification.
+//!
+//! Review tooling wants a trust indicator next to agent-created commits:
+//! signed by whom, and does the signature still check out. jj verifies
+//! signatures itself (GPG or SSH, per the user's config); this module
+//! asks it through the template language — `signature.status()` and
+//! `signature.key()` — and turns the answer into structured
+//! [`CommitSignature`] rows, one per commit in the revset.
+
+use crate::checkpoint::JjCli;
+use crate::error::AgentError;
+
+/// What jj said about one commit's signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureState {
+    /// No signature at all.
+    Unsigned,
+    /// Signed and the signature verifies against a known key.
+    Good,
+    /// Signed, but verification failed outright.
+    Bad,
+    /// Signed, but the key is unknown to this machine, so the signature
+    /// can't be judged either way.
+    Unknown,
+    /// Signature data present but malformed.
+    Invalid,
+}
+
+/// Verification result for one commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSignature {
+    /// Short change id, as shown in `jj log`.
+    pub commit_id: String,
+    pub state: SignatureState,
+    /// The signing key id, when the commit is signed at all.
+    pub key: Option<String>,
+}
+
+impl CommitSignature {
+    /// Whether review UIs should show this commit as trusted.
+    pub fn is_trusted(&self) -> bool {
+        self.state == SignatureState::Good
+    }
+}
+
+/// The `-T` template behind [`JjCli::verify_signatures`]: one
+/// tab-separated line per commit, `unsigned` standing in for the status
+/// and key when there is no signature.
+const SIGNATURE_TEMPLATE: &str = concat!(
+    r#"commit_id.short() ++ "\t" ++ "#,
+    r#"if(signature, signature.status() ++ "\t" ++ signature.key(), "unsigned") ++ "\n""#,
+);
+
+impl JjCli {
+    /// Per-commit signature status for every commit in `revset`.
+    pub fn verify_signatures(&self, revset: &str) -> Result<Vec<CommitSignature>, AgentError> {
+        let out = self.jj(&["log", "-r", revset, "--no-graph", "-T", SIGNATURE_TEMPLATE])?;
+        parse_signature_lines(&out)
+    }
+}
+
+/// Parse the template output: `<id>\t<status>[\t<key>]` per line.
+pub(crate) fn parse_signature_lines(out: &str) -> Result<Vec<CommitSignature>, AgentError> {
+    out.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.split('\t');
+            let commit_id = fields
+                .next()
+                .filter(|id| !id.is_empty())
+                .ok_or_else(|| AgentError::Vcs(format!("unparseable signature line: `{line}`")))?
+                .to_string();
+            let status = fields.next().unwrap_or("unsigned");
+            let state = match status {
+                "unsigned" => SignatureState::Unsigned,
+                "good" => SignatureState::Good,
+                "bad" => SignatureState::Bad,
+                "unknown" => SignatureState::Unknown,
+                "invalid" => SignatureState::Invalid,
+                other => {
+                    return Err(AgentError::Vcs(format!(
+                        "jj reported an unrecognized signature status `{other}`"
+                    )));
+                }
+            };
+            let key = fields.next().filter(|k| !k.is_empty()).map(String::from);
+            Ok(CommitSignature { commit_id, state, key })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn template_output_becomes_per_commit_statuses() {
+        let out = "abc123\tgood\tkey-1\nfff999\tunsigned\n0d0d0d\tunknown\tkey-2\n";
+        let parsed = parse_signature_lines(out).unwrap();
+        assert_eq!(
+            parsed,
+            [
+                CommitSignature {
+                    commit_id: "abc123".into(),
+                    state: SignatureState::Good,
+                    key: Some("key-1".into()),
+                },
+                CommitSignature {
+                    commit_id: "fff999".into(),
+                    state: SignatureState::Unsigned,
+                    key: None,
+                },
+                CommitSignature {
+                    commit_id: "0d0d0d".into(),
+                    state: SignatureState::Unknown,
+                    key: Some("key-2".into()),
+                },
+            ]
+        );
+        assert!(parsed[0].is_trusted());
+        assert!(!parsed[2].is_trusted());
+    }
+
+    #[test]
+    fn surprising_statuses_fail_loudly_instead_of_guessing() {
+        let err = parse_signature_lines("abc\tsorta-fine\n").unwrap_err();
+        assert!(err.to_string().contains("unrecognized signature status `sorta-fine`"));
+        assert!(parse_signature_lines("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,424 @@
+//! Streamed completions.
+//!
+//! Both providers stream over server-sent events but disagree on the frame
+//! contents, so this module normalizes them into one [`StreamEvent`] enum —
+//! text deltas, tool-call deltas, usage, stop reason — delivered through a
+//! callback as bytes arrive. Events serialize as tagged JSON, so FFI and
+//! web hosts forward them verbatim. A [`StreamAccumulator`] folds the
+//! events back into the same [`ProviderResponse`] a blocking call returns,
+//! so the agent loop doesn't care which path produced it.
+
+#[cfg(any(test, feature = "network"))]
+use std::io::BufRead;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::AgentError;
+use crate::provider::{ProviderResponse, StopReason, ToolCallRequest, Usage};
+
+/// One increment of a streamed completion, in arrival order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// A run of response text.
+    TextDelta { text: String },
+    /// Part of a tool call. The first delta for an `index` carries the id
+    /// and name; later ones append to the JSON arguments string.
+    ToolCallDelta {
+        index: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        arguments: String,
+    },
+    /// Token counts; may arrive split across frames and accumulates by max.
+    Usage { input_tokens: u64, output_tokens: u64 },
+    /// Why generation stopped; the stream ends shortly after.
+    Stop { reason: StopReason },
+}
+
+// StopReason serializes for event consumers even though the blocking path
+// never needs it.
+impl Serialize for StopReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            StopReason::EndTurn => serializer.serialize_str("end_turn"),
+            StopReason::MaxTokens => serializer.serialize_str("max_tokens"),
+            StopReason::StopSequence => serializer.serialize_str("stop_sequence"),
+            StopReason::ToolUse => serializer.serialize_str("tool_use"),
+            StopReason::Other(other) => serializer.serialize_str(other),
+        }
+    }
+}
+
+/// Folds [`StreamEvent`]s into the [`ProviderResponse`] the blocking API
+/// returns.
+#[derive(Debug, Default)]
+pub struct StreamAccumulator {
+    text: String,
+    /// Sparse by provider index; each entry is (id, name, arguments JSON).
+    tool_calls: Vec<(String, String, String)>,
+    usage: Usage,
+    stop_reason: Option<StopReason>,
+}
+
+impl StreamAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply one event.
+    pub fn push(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::TextDelta { text } => self.text.push_str(text),
+            StreamEvent::ToolCallDelta {
+                index,
+                id,
+                name,
+                arguments,
+            } => {
+                if self.tool_calls.len() <= *index {
+                    self.tool_calls
+                        .resize_with(index + 1, || (String::new(), String::new(), String::new()));
+                }
+                let entry = &mut self.tool_calls[*index];
+                if let Some(id) = id {
+                    entry.0 = id.clone();
+                }
+                if let Some(name) = name {
+                    entry.1 = name.clone();
+                }
+                entry.2.push_str(arguments);
+            }
+            StreamEvent::Usage {
+                input_tokens,
+                output_tokens,
+            } => {
+                self.usage.input_tokens = self.usage.input_tokens.max(*input_tokens);
+                self.usage.output_tokens = self.usage.output_tokens.max(*output_tokens);
+            }
+            StreamEvent::Stop { reason } => self.stop_reason = Some(reason.clone()),
+        }
+    }
+
+    /// The assembled response. Fails when a tool call's accumulated
+    /// arguments never became valid JSON.
+    pub fn finish(self, provider: &str) -> Result<ProviderResponse, AgentError> {
+        let mut tool_calls = Vec::with_capacity(self.tool_calls.len());
+        // Anthropic indexes tool blocks among *all* content blocks, so the
+        // sparse slots under text blocks stay empty; skip them.
+        for (id, name, arguments) in self.tool_calls {
+            if name.is_empty() {
+                continue;
+            }
+            let arguments = if arguments.is_empty() {
+                Value::Object(serde_json::Map::new())
+            } else {
+                serde_json::from_str(&arguments).map_err(|e| AgentError::Provider {
+                    provider: provider.to_string(),
+                    message: format!("bad streamed tool arguments for `{name}`: {e}"),
+                })?
+            };
+            tool_calls.push(ToolCallRequest {
+                id,
+                name,
+                arguments,
+            });
+        }
+        Ok(ProviderResponse {
+            text: self.text,
+            tool_calls,
+            usage: self.usage,
+            stop_reason: self.stop_reason.unwrap_or(StopReason::EndTurn),
+        })
+    }
+}
+
+/// Read SSE frames from `reader`, handing each `data:` payload to `frame`.
+/// Stops at EOF or when `frame` returns `false`.
+#[cfg(any(test, feature = "network"))]
+fn each_sse_data<R: BufRead>(
+    reader: R,
+    provider: &str,
+    mut frame: impl FnMut(&str) -> bool,
+) -> Result<(), AgentError> {
+    let mut data = String::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| AgentError::Provider {
+            provider: provider.to_string(),
+            message: e.to_string(),
+        })?;
+        if let Some(payload) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(payload.trim_start());
+        } else if line.is_empty() && !data.is_empty() {
+            let done = !frame(&data);
+            data.clear();
+            if done {
+                return Ok(());
+            }
+        }
+        // Comment and `event:` lines carry nothing we need.
+    }
+    Ok(())
+}
+
+/// Parse an Anthropic `stream: true` response, emitting events as frames
+/// arrive.
+#[cfg(any(test, feature = "network"))]
+pub(crate) fn parse_anthropic_stream<R: BufRead>(
+    reader: R,
+    mut on_event: impl FnMut(&StreamEvent),
+) -> Result<ProviderResponse, AgentError> {
+    let mut acc = StreamAccumulator::new();
+    let mut emit = |event: StreamEvent, acc: &mut StreamAccumulator| {
+        acc.push(&event);
+        on_event(&event);
+    };
+    each_sse_data(reader, "anthropic", |data| {
+        let Ok(frame) = serde_json::from_str::<Value>(data) else {
+            return true;
+        };
+        let index = frame["index"].as_u64().unwrap_or_default() as usize;
+        match frame["type"].as_str() {
+            Some("message_start") => {
+                let usage = &frame["message"]["usage"];
+                emit(
+                    StreamEvent::Usage {
+                        input_tokens: usage["input_tokens"].as_u64().unwrap_or_default(),
+                        output_tokens: usage["output_tokens"].as_u64().unwrap_or_default(),
+                    },
+                    &mut acc,
+                );
+            }
+            Some("content_block_start") => {
+                let block = &frame["content_block"];
+                if block["type"].as_str() == Some("tool_use") {
+                    emit(
+                        StreamEvent::ToolCallDelta {
+                            index,
+                            id: block["id"].as_str().map(String::from),
+                            name: block["name"].as_str().map(String::from),
+                            arguments: String::new(),
+                        },
+                        &mut acc,
+                    );
+                }
+            }
+            Some("content_block_delta") => match frame["delta"]["type"].as_str() {
+                Some("text_delta") => emit(
+                    StreamEvent::TextDelta {
+                        text: frame["delta"]["text"].as_str().unwrap_or_default().into(),
+                    },
+                    &mut acc,
+                ),
+                Some("input_json_delta") => emit(
+                    StreamEvent::ToolCallDelta {
+                        index,
+                        id: None,
+                        name: None,
+                        arguments: frame["delta"]["partial_json"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .into(),
+                    },
+                    &mut acc,
+                ),
+                _ => {}
+            },
+            Some("message_delta") => {
+                if let Some(output) = frame["usage"]["output_tokens"].as_u64() {
+                    emit(
+                        StreamEvent::Usage {
+                            input_tokens: 0,
+                            output_tokens: output,
+                        },
+                        &mut acc,
+                    );
+                }
+                if let Some(reason) = frame["delta"]["stop_reason"].as_str() {
+                    let reason = match reason {
+                        "end_turn" => StopReason::EndTurn,
+                        "max_tokens" => StopReason::MaxTokens,
+                        "stop_sequence" => StopReason::StopSequence,
+                        "tool_use" => StopReason::ToolUse,
+                        other => StopReason::Other(other.to_string()),
+                    };
+                    emit(StreamEvent::Stop { reason }, &mut acc);
+                }
+            }
+            Some("message_stop") => return false,
+            _ => {}
+        }
+        true
+    })?;
+    acc.finish("anthropic")
+}
+
+/// Parse an OpenAI `stream: true` response, emitting events as frames
+/// arrive.
+#[cfg(any(test, feature = "network"))]
+pub(crate) fn parse_openai_stream<R: BufRead>(
+    reader: R,
+    mut on_event: impl FnMut(&StreamEvent),
+) -> Result<ProviderResponse, AgentError> {
+    let mut acc = StreamAccumulator::new();
+    let mut emit = |event: StreamEvent, acc: &mut StreamAccumulator| {
+        acc.push(&event);
+        on_event(&event);
+    };
+    each_sse_data(reader, "openai", |data| {
+        if data == "[DONE]" {
+            return false;
+        }
+        let Ok(frame) = serde_json::from_str::<Value>(data) else {
+            return true;
+        };
+        if let Some(usage) = frame["usage"].as_object() {
+            emit(
+                StreamEvent::Usage {
+                    input_tokens: usage["prompt_tokens"].as_u64().unwrap_or_default(),
+                    output_tokens: usage["completion_tokens"].as_u64().unwrap_or_default(),
+                },
+                &mut acc,
+            );
+        }
+        let Some(choice) = frame["choices"].get(0) else {
+            return true;
+        };
+        if let Some(text) = choice["delta"]["content"].as_str() {
+            emit(StreamEvent::TextDelta { text: text.into() }, &mut acc);
+        }
+        for call in choice["delta"]["tool_calls"].as_array().unwrap_or(&Vec::new()) {
+            emit(
+                StreamEvent::ToolCallDelta {
+                    index: call["index"].as_u64().unwrap_or_default() as usize,
+                    id: call["id"].as_str().map(String::from),
+                    name: call["function"]["name"].as_str().map(String::from),
+                    arguments: call["function"]["arguments"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .into(),
+                },
+                &mut acc,
+            );
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            let reason = match reason {
+                "stop" => StopReason::EndTurn,
+                "length" => StopReason::MaxTokens,
+                "tool_calls" => StopReason::ToolUse,
+                other => StopReason::Other(other.to_string()),
+            };
+            emit(StreamEvent::Stop { reason }, &mut acc);
+        }
+        true
+    })?;
+    acc.finish("openai")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn anthropic_streams_parse_into_events_and_a_response() {
+        let sse = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"tu_1\",\"name\":\"read_file\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"path\\\":\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"x\\\"}\"}}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":7}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        let mut events = Vec::new();
+        let response =
+            parse_anthropic_stream(sse.as_bytes(), |e| events.push(e.clone())).unwrap();
+        assert_eq!(response.text, "Hello");
+        // The tool block sat at content index 1 (after the text block);
+        // the empty slot under the text block must not become a call.
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].name, "read_file");
+        assert_eq!(response.tool_calls[0].arguments, json!({ "path": "x" }));
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.input_tokens, 12);
+        assert_eq!(response.usage.output_tokens, 7);
+        assert!(matches!(events[1], StreamEvent::TextDelta { .. }));
+    }
+
+    #[test]
+    fn openai_streams_parse_into_events_and_a_response() {
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hi \"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"there\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"read_file\",\"arguments\":\"{\\\"pa\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"th\\\":\\\"x\\\"}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":20,\"completion_tokens\":9}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut deltas = String::new();
+        let response = parse_openai_stream(sse.as_bytes(), |e| {
+            if let StreamEvent::TextDelta { text } = e {
+                deltas.push_str(text);
+            }
+        })
+        .unwrap();
+        assert_eq!(deltas, "Hi there");
+        assert_eq!(response.text, "Hi there");
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].id, "call_1");
+        assert_eq!(response.tool_calls[0].arguments, json!({ "path": "x" }));
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.input_tokens, 20);
+    }
+
+    #[test]
+    fn events_serialize_as_tagged_json_for_host_consumers() {
+        let event = StreamEvent::ToolCallDelta {
+            index: 0,
+            id: Some("call_1".into()),
+            name: Some("read_file".into()),
+            arguments: "{\"pa".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({
+                "event": "tool_call_delta",
+                "index": 0,
+                "id": "call_1",
+                "name": "read_file",
+                "arguments": "{\"pa",
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(StreamEvent::Stop {
+                reason: StopReason::EndTurn
+            })
+            .unwrap(),
+            json!({ "event": "stop", "reason": "end_turn" })
+        );
+    }
+
+    #[test]
+    fn bad_streamed_tool_arguments_surface_as_provider_errors() {
+        let mut acc = StreamAccumulator::new();
+        acc.push(&StreamEvent::ToolCallDelta {
+            index: 0,
+            id: Some("call_1".into()),
+            name: Some("read_file".into()),
+            arguments: "{ not json".into(),
+        });
+        let err = acc.finish("openai").unwrap_err();
+        assert!(err.to_string().contains("bad streamed tool arguments"));
+    }
+}
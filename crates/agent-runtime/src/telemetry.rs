@@ -0,0 +1,232 @@
+//! Structured telemetry for the host process.
+//!
+//! The crates are linked into a Zig/Node host as static libraries, so
+//! their `tracing` events would otherwise vanish — there is no terminal
+//! to print to and the host owns the log pipeline. This module forwards
+//! every event (from this crate *and* prompt-parser, since the `tracing`
+//! dispatcher is process-global) to a sink as a [`LogEvent`]: level,
+//! target, message, and the structured fields. The FFI hook
+//! [`agent_runtime_set_log_sink`] serializes each event to JSON and hands
+//! the host a C string for the duration of the callback.
+//!
+//! The subscriber is hand-rolled rather than built on `tracing-subscriber`
+//! — span bookkeeping and layering buy nothing here, and one fewer
+//! dependency matters in an embedded static library.
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// One structured log event, as the host sees it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEvent {
+    /// `TRACE` through `ERROR`.
+    pub level: String,
+    /// The emitting module path, e.g. `agent_runtime::runner`.
+    pub target: String,
+    pub message: String,
+    /// Every non-message field, keyed by name.
+    pub fields: Map<String, Value>,
+}
+
+/// Collects an event's fields into [`LogEvent`] shape.
+#[derive(Default)]
+struct FieldCollector {
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl Visit for FieldCollector {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields.insert(field.name().to_string(), json!(format!("{value:?}")));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.insert(field.name().to_string(), json!(value));
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+}
+
+/// A [`Subscriber`] that forwards every event at or above `min_level` to
+/// one sink. Spans are accepted but not tracked — the host log pipeline
+/// works in events.
+pub struct TelemetrySubscriber {
+    min_level: Level,
+    sink: Box<dyn Fn(&LogEvent) + Send + Sync>,
+    next_span: AtomicU64,
+}
+
+impl TelemetrySubscriber {
+    pub fn new(min_level: Level, sink: impl Fn(&LogEvent) + Send + Sync + 'static) -> Self {
+        TelemetrySubscriber {
+            min_level,
+            sink: Box::new(sink),
+            next_span: AtomicU64::new(1),
+        }
+    }
+}
+
+impl Subscriber for TelemetrySubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.min_level
+    }
+
+    fn new_span(&self, _attrs: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut collector = FieldCollector::default();
+        event.record(&mut collector);
+        (self.sink)(&LogEvent {
+            level: event.metadata().level().to_string(),
+            target: event.metadata().target().to_string(),
+            message: collector.message,
+            fields: collector.fields,
+        });
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Install `sink` as the process-wide telemetry destination. Returns
+/// false when a global dispatcher was already installed — `tracing`
+/// allows exactly one per process.
+pub fn install_telemetry(
+    min_level: Level,
+    sink: impl Fn(&LogEvent) + Send + Sync + 'static,
+) -> bool {
+    tracing::subscriber::set_global_default(TelemetrySubscriber::new(min_level, sink)).is_ok()
+}
+
+/// The host-side callback: receives each event as a JSON C string valid
+/// only for the duration of the call.
+pub type HostLogSink = extern "C" fn(event_json: *const c_char);
+
+/// Route all telemetry to `sink`. Levels map to `tracing` levels, 0
+/// (error) through 4 (trace); out-of-range values clamp to trace.
+/// Returns false if a sink (or any other subscriber) was already
+/// installed.
+#[unsafe(no_mangle)]
+pub extern "C" fn agent_runtime_set_log_sink(sink: HostLogSink, max_level: u8) -> bool {
+    let level = match max_level {
+        0 => Level::ERROR,
+        1 => Level::WARN,
+        2 => Level::INFO,
+        3 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let redactor = crate::redact::Redactor::new()
+        .with_env_keys(&["ANTHROPIC_API_KEY", "OPENAI_API_KEY"]);
+    install_telemetry(level, move |event| {
+        let json = serde_json::to_string(event).expect("log events serialize");
+        // Serialized JSON has no raw NUL, but a logging callback must
+        // never be able to abort the host — escape instead of panicking
+        // if that invariant ever breaks.
+        let c = match CString::new(redactor.redact(&json)) {
+            Ok(c) => c,
+            Err(err) => {
+                let escaped =
+                    String::from_utf8_lossy(&err.into_vec()).replace('\0', "\\u0000");
+                CString::new(escaped).expect("interior NULs were just escaped")
+            }
+        };
+        sink(c.as_ptr());
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+
+    fn capture() -> (Arc<Mutex<Vec<LogEvent>>>, TelemetrySubscriber) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let subscriber = TelemetrySubscriber::new(Level::DEBUG, move |event| {
+            sink.lock().unwrap().push(event.clone());
+        });
+        (seen, subscriber)
+    }
+
+    #[test]
+    fn events_carry_level_target_message_and_fields() {
+        let (seen, subscriber) = capture();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(tool = "read_file", turn = 3u64, "dispatching");
+        });
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].level, "INFO");
+        assert_eq!(seen[0].target, "agent_runtime::telemetry::tests");
+        assert_eq!(seen[0].message, "dispatching");
+        assert_eq!(seen[0].fields["tool"], "read_file");
+        assert_eq!(seen[0].fields["turn"], 3);
+    }
+
+    #[test]
+    fn events_below_the_minimum_level_are_dropped() {
+        let (seen, subscriber) = capture();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::trace!("too quiet");
+            tracing::warn!("loud enough");
+        });
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].message, "loud enough");
+    }
+
+    #[test]
+    fn instrumented_code_reaches_the_sink() {
+        let (seen, subscriber) = capture();
+        tracing::subscriber::with_default(subscriber, || {
+            let runner = crate::runner::ToolRunner::new();
+            let _ = runner.dispatch(&crate::provider::ToolCallRequest {
+                id: "t1".to_string(),
+                name: "echo".to_string(),
+                arguments: serde_json::json!({}),
+            });
+        });
+        let seen = seen.lock().unwrap();
+        assert!(
+            seen.iter().any(|e| e.fields.get("tool") == Some(&json!("echo"))),
+            "dispatch did not emit its event: {seen:?}"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Time-travel file access.
+//!
+//! "Show me this file as of last Tuesday": resolve the latest commit at
+//! or before a timestamp and read the file there. The resolution is one
+//! `jj log` template call listing commit ids with their committer
+//! timestamps — picking the winner locally keeps the logic testable and
+//! avoids leaning on revset date syntax — and the read is the same
+//! `jj file show` every other path uses. The answer carries the
+//! resolving commit, so the caller can say *which* Tuesday-ish state it
+//! is showing.
+
+use crate::checkpoint::JjCli;
+use crate::error::AgentError;
+
+/// A file's content at a resolved point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileAtTime {
+    /// The commit that answered: the latest one at or before the asked
+    /// timestamp.
+    pub commit_id: String,
+    /// That commit's committer timestamp, unix seconds.
+    pub timestamp: i64,
+    pub content: String,
+}
+
+/// The `-T` template behind the resolution: commit id and unix
+/// timestamp, tab-separated.
+const COMMIT_TIME_TEMPLATE: &str =
+    r#"commit_id.short() ++ "\t" ++ committer.timestamp().utc().format("%s") ++ "\n""#;
+
+impl JjCli {
+    /// `path`'s content as of `timestamp` (unix seconds), searching
+    /// `revset` — a bookmark's ancestry like `::main`, or anything else
+    /// revset-shaped. Fails when no commit in the revset is old enough.
+    pub fn file_at_time(
+        &self,
+        path: &str,
+        timestamp: i64,
+        revset: &str,
+    ) -> Result<FileAtTime, AgentError> {
+        let out = self.jj(&["log", "-r", revset, "--no-graph", "-T", COMMIT_TIME_TEMPLATE])?;
+        let (commit_id, committed_at) = pick_commit(&parse_commit_times(&out)?, timestamp)
+            .ok_or_else(|| {
+                AgentError::Vcs(format!(
+                    "no commit in `{revset}` at or before timestamp {timestamp}"
+                ))
+            })?;
+        let content = self.jj(&["file", "show", "-r", &commit_id, path])?;
+        Ok(FileAtTime {
+            commit_id,
+            timestamp: committed_at,
+            content,
+        })
+    }
+}
+
+/// Parse `<id>\t<unix-ts>` template lines.
+pub(crate) fn parse_commit_times(out: &str) -> Result<Vec<(String, i64)>, AgentError> {
+    out.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (id, ts) = line
+                .split_once('\t')
+                .and_then(|(id, ts)| Some((id, ts.parse().ok()?)))
+                .ok_or_else(|| AgentError::Vcs(format!("unparseable commit time: `{line}`")))?;
+            Ok((id.to_string(), ts))
+        })
+        .collect()
+}
+
+/// The latest commit at or before `timestamp`; commit order in the
+/// input doesn't matter. Ties go to the earlier-listed commit, which in
+/// jj log order is the descendant.
+pub(crate) fn pick_commit(commits: &[(String, i64)], timestamp: i64) -> Option<(String, i64)> {
+    commits
+        .iter()
+        .filter(|(_, ts)| *ts <= timestamp)
+        .max_by_key(|(_, ts)| *ts)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn commits() -> Vec<(String, i64)> {
+        vec![
+            ("ccc".to_string(), 3_000),
+            ("bbb".to_string(), 2_000),
+            ("aaa".to_string(), 1_000),
+        ]
+    }
+
+    #[test]
+    fn the_latest_commit_at_or_before_the_timestamp_wins() {
+        assert_eq!(pick_commit(&commits(), 2_500), Some(("bbb".into(), 2_000)));
+        // An exact hit counts as "at".
+        assert_eq!(pick_commit(&commits(), 2_000), Some(("bbb".into(), 2_000)));
+        // Asking after everything returns the newest.
+        assert_eq!(pick_commit(&commits(), 9_000), Some(("ccc".into(), 3_000)));
+        // Asking before history began finds nothing.
+        assert_eq!(pick_commit(&commits(), 500), None);
+    }
+
+    #[test]
+    fn template_lines_parse_and_garbage_is_named() {
+        let parsed = parse_commit_times("ccc\t3000\naaa\t1000\n").unwrap();
+        assert_eq!(parsed, [("ccc".to_string(), 3_000), ("aaa".to_string(), 1_000)]);
+
+        let err = parse_commit_times("ccc\tlast tuesday\n").unwrap_err();
+        assert!(err.to_string().contains("unparseable commit time"));
+    }
+}
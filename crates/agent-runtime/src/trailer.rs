@@ -0,0 +1,205 @@
+//! RFC-style trailers in commit descriptions.
+//!
+//! `Co-authored-by:`, `Signed-off-by:`, `Change-Id:` — the structured
+//! facts tooling hangs off commits all live in the final paragraph of
+//! the description, one `Key: value` per line. This module parses that
+//! block into fields (following git's interpret-trailers rules: the last
+//! paragraph counts only when every line is a trailer, continuation
+//! lines fold into the previous one) and appends new trailers without
+//! mangling the body or duplicating what's already there.
+
+use std::fmt;
+
+/// One `Key: value` line from the trailer block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trailer {
+    /// Normalized to `Train-Case` (`co-AUTHORED-by` → `Co-Authored-By`).
+    pub key: String,
+    pub value: String,
+}
+
+impl Trailer {
+    pub fn new(key: &str, value: impl Into<String>) -> Self {
+        Trailer {
+            key: normalize_key(key),
+            value: value.into(),
+        }
+    }
+}
+
+/// A description split into its body and trailer block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitDescription {
+    /// Everything before the trailer block, trailing whitespace trimmed.
+    pub body: String,
+    /// The trailer block in order of appearance; empty when the last
+    /// paragraph isn't all trailers.
+    pub trailers: Vec<Trailer>,
+}
+
+impl CommitDescription {
+    /// Split `description` into body and trailers.
+    pub fn parse(description: &str) -> Self {
+        let trimmed = description.trim_end();
+        let block_start = match trailer_block_start(trimmed) {
+            Some(start) => start,
+            None => {
+                return CommitDescription {
+                    body: trimmed.to_string(),
+                    trailers: Vec::new(),
+                };
+            }
+        };
+        let (body, block) = trimmed.split_at(block_start);
+        let mut trailers: Vec<Trailer> = Vec::new();
+        for line in block.lines() {
+            if line.starts_with([' ', '\t']) {
+                // Continuation line: folds into the previous trailer.
+                if let Some(last) = trailers.last_mut() {
+                    last.value.push(' ');
+                    last.value.push_str(line.trim());
+                }
+            } else if let Some((key, value)) = line.split_once(':') {
+                trailers.push(Trailer::new(key, value.trim()));
+            }
+        }
+        CommitDescription {
+            body: body.trim_end().to_string(),
+            trailers,
+        }
+    }
+
+    /// Every value recorded under `key` (comparison is normalized, so
+    /// `change-id` finds `Change-Id:` lines).
+    pub fn values(&self, key: &str) -> Vec<&str> {
+        let key = normalize_key(key);
+        self.trailers
+            .iter()
+            .filter(|t| t.key == key)
+            .map(|t| t.value.as_str())
+            .collect()
+    }
+
+    /// Append `key: value`, unless an identical trailer already exists.
+    /// The key is normalized on the way in.
+    pub fn with_trailer(mut self, key: &str, value: impl Into<String>) -> Self {
+        let trailer = Trailer::new(key, value);
+        if !self.trailers.contains(&trailer) {
+            self.trailers.push(trailer);
+        }
+        self
+    }
+}
+
+impl fmt::Display for CommitDescription {
+    /// Render back to a description: body, blank line, trailer block.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.body)?;
+        if !self.trailers.is_empty() {
+            if !self.body.is_empty() {
+                write!(f, "\n\n")?;
+            }
+            for trailer in &self.trailers {
+                writeln!(f, "{}: {}", trailer.key, trailer.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Byte offset where the trailing trailer block starts, when the last
+/// paragraph is entirely trailers (or their continuations).
+fn trailer_block_start(description: &str) -> Option<usize> {
+    let start = match description.rfind("\n\n") {
+        Some(pos) => pos + 2,
+        // A lone paragraph can't be a trailer block: a description that
+        // is only trailers has no subject, which means someone pasted a
+        // block where the message should be. Leave it as body.
+        None => return None,
+    };
+    let block = &description[start..];
+    let all_trailers = block.lines().all(|line| {
+        line.starts_with([' ', '\t'])
+            || line
+                .split_once(':')
+                .is_some_and(|(key, _)| !key.is_empty() && key.chars().all(is_key_char))
+    });
+    (all_trailers && !block.is_empty()).then_some(start)
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+/// `co-AUTHORED-by` → `Co-Authored-By`.
+fn normalize_key(key: &str) -> String {
+    key.trim()
+        .split('-')
+        .map(|segment| {
+            let mut chars = segment.chars();
+            match chars.next() {
+                Some(first) => {
+                    first.to_ascii_uppercase().to_string() + &chars.as_str().to_ascii_lowercase()
+                }
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn the_final_paragraph_parses_into_structured_trailers() {
+        let parsed = CommitDescription::parse(
+            "Fix the watcher race\n\nLonger explanation\nwith a colon: in prose.\n\n\
+             Signed-off-by: Dev One <dev@example.com>\n\
+             co-authored-by: Dev Two <two@example.com>\n\
+             Change-Id: I1234\n",
+        );
+        assert_eq!(parsed.body, "Fix the watcher race\n\nLonger explanation\nwith a colon: in prose.");
+        assert_eq!(
+            parsed.values("signed-off-by"),
+            ["Dev One <dev@example.com>"]
+        );
+        assert_eq!(parsed.trailers[1].key, "Co-Authored-By");
+        assert_eq!(parsed.values("Change-Id"), ["I1234"]);
+    }
+
+    #[test]
+    fn prose_paragraphs_and_bare_subjects_are_not_trailers() {
+        let parsed = CommitDescription::parse("Fix it\n\nNote: this is prose, not a trailer?\nSecond line.");
+        assert!(parsed.trailers.is_empty());
+        // A description that is nothing but `Key: value` still needs a
+        // subject; treat the lone paragraph as body.
+        assert!(CommitDescription::parse("Change-Id: I99").trailers.is_empty());
+    }
+
+    #[test]
+    fn appending_normalizes_dedupes_and_round_trips() {
+        let rendered = CommitDescription::parse("Add feature")
+            .with_trailer("agent-session-id", "sess-1")
+            .with_trailer("Agent-Session-Id", "sess-1")
+            .with_trailer("Change-Id", "I42")
+            .to_string();
+        assert_eq!(rendered, "Add feature\n\nAgent-Session-Id: sess-1\nChange-Id: I42\n");
+        let reparsed = CommitDescription::parse(&rendered);
+        assert_eq!(reparsed.values("agent-session-id"), ["sess-1"]);
+        assert_eq!(reparsed.to_string(), rendered);
+    }
+
+    #[test]
+    fn continuation_lines_fold_into_the_previous_trailer() {
+        let parsed = CommitDescription::parse(
+            "Subject\n\nCo-Authored-By: Someone With\n  A Very Long Name <long@example.com>\n",
+        );
+        assert_eq!(
+            parsed.values("co-authored-by"),
+            ["Someone With A Very Long Name <long@example.com>"]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Persistent tree-hash cache for instant equality checks.
+//!
+//! "Did anything change under `src/` between these two revisions?" is a
+//! question the runtime asks constantly — context packing, policy
+//! checks, deciding whether to re-index. Answering it by walking and
+//! diffing trees costs a snapshot per revision per question. A
+//! [`TreeHashCache`] records, once per revision, the root hash and a
+//! hash per directory; after that, equality anywhere in the tree is a
+//! map lookup. The cache persists as JSON next to the other on-disk
+//! state and grows incrementally as new revisions are recorded.
+
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AgentError;
+use crate::patch::TreeSnapshot;
+
+/// The hashes recorded for one revision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeHashes {
+    /// Hash over every file in the tree.
+    pub root: u64,
+    /// Directory path (`""` is the root) → hash over the files under it,
+    /// recursively.
+    pub dirs: BTreeMap<String, u64>,
+}
+
+/// Revision → tree hashes, persisted as JSON.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TreeHashCache {
+    entries: BTreeMap<String, TreeHashes>,
+}
+
+/// Every directory prefix of `path`, excluding the file itself:
+/// `a/b/c.rs` yields `""`, `"a"`, `"a/b"`.
+fn dir_prefixes(path: &str) -> Vec<String> {
+    let mut prefixes = vec![String::new()];
+    let mut current = String::new();
+    for component in path.split('/').rev().skip(1).collect::<Vec<_>>().into_iter().rev() {
+        if !current.is_empty() {
+            current.push('/');
+        }
+        current.push_str(component);
+        prefixes.push(current.clone());
+    }
+    prefixes
+}
+
+fn hashes_for(snapshot: &TreeSnapshot) -> TreeHashes {
+    let mut dirs: BTreeMap<String, DefaultHasher> = BTreeMap::new();
+    // Files iterate in path order, so every directory hash is built from
+    // a deterministic sequence.
+    for (path, content) in snapshot.files() {
+        for dir in dir_prefixes(path) {
+            let hasher = dirs.entry(dir).or_default();
+            path.hash(hasher);
+            content.hash(hasher);
+        }
+    }
+    let dirs: BTreeMap<String, u64> =
+        dirs.into_iter().map(|(dir, h)| (dir, h.finish())).collect();
+    let root = dirs.get("").copied().unwrap_or_default();
+    TreeHashes { root, dirs }
+}
+
+impl TreeHashCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `snapshot`'s hashes under `revision`. Recording the same
+    /// revision twice is a no-op; returns whether this one was new.
+    pub fn record(&mut self, revision: impl Into<String>, snapshot: &TreeSnapshot) -> bool {
+        let revision = revision.into();
+        if self.entries.contains_key(&revision) {
+            return false;
+        }
+        self.entries.insert(revision, hashes_for(snapshot));
+        true
+    }
+
+    pub fn contains(&self, revision: &str) -> bool {
+        self.entries.contains_key(revision)
+    }
+
+    /// The root tree hash, when `revision` has been recorded.
+    pub fn root_hash(&self, revision: &str) -> Option<u64> {
+        self.entries.get(revision).map(|h| h.root)
+    }
+
+    /// Whether anything changed under `prefix` (`""` is the whole tree)
+    /// between two recorded revisions. `None` when either revision is
+    /// not in the cache — the caller falls back to a real diff.
+    pub fn changed_under(&self, from: &str, to: &str, prefix: &str) -> Option<bool> {
+        let prefix = prefix.trim_matches('/');
+        let from = self.entries.get(from)?;
+        let to = self.entries.get(to)?;
+        // A directory absent on one side counts as empty there.
+        Some(from.dirs.get(prefix) != to.dirs.get(prefix))
+    }
+
+    /// Write atomically to `path` as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AgentError> {
+        let path = path.as_ref();
+        let io_err = |e: std::io::Error| AgentError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        };
+        let json = serde_json::to_string_pretty(self).expect("cache serializes");
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(io_err)?;
+        std::fs::rename(&tmp, path).map_err(io_err)
+    }
+
+    /// Load a previously saved cache.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AgentError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| AgentError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        serde_json::from_str(&json).map_err(|e| AgentError::Io {
+            path: path.display().to_string(),
+            message: format!("corrupt tree-hash cache: {e}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn snapshot(files: &[(&str, &str)]) -> TreeSnapshot {
+        TreeSnapshot::from_files(
+            files.iter().map(|(p, c)| (p.to_string(), c.to_string())),
+        )
+    }
+
+    #[test]
+    fn equality_questions_answer_from_hashes_alone() {
+        let mut cache = TreeHashCache::new();
+        let base = snapshot(&[
+            ("src/lib.rs", "fn a() {}\n"),
+            ("src/deep/util.rs", "fn u() {}\n"),
+            ("docs/guide.md", "# guide\n"),
+        ]);
+        let edited = snapshot(&[
+            ("src/lib.rs", "fn a() { b(); }\n"),
+            ("src/deep/util.rs", "fn u() {}\n"),
+            ("docs/guide.md", "# guide\n"),
+        ]);
+        assert!(cache.record("r1", &base));
+        assert!(cache.record("r2", &edited));
+        assert!(!cache.record("r2", &edited));
+
+        assert_ne!(cache.root_hash("r1"), cache.root_hash("r2"));
+        assert_eq!(cache.changed_under("r1", "r2", "src"), Some(true));
+        assert_eq!(cache.changed_under("r1", "r2", "src/deep"), Some(false));
+        assert_eq!(cache.changed_under("r1", "r2", "docs"), Some(false));
+        assert_eq!(cache.changed_under("r1", "r2", ""), Some(true));
+        // Unknown revisions punt to a real diff.
+        assert_eq!(cache.changed_under("r1", "r9", "src"), None);
+    }
+
+    #[test]
+    fn added_and_removed_directories_count_as_changes() {
+        let mut cache = TreeHashCache::new();
+        cache.record("r1", &snapshot(&[("src/lib.rs", "x\n")]));
+        cache.record("r2", &snapshot(&[("src/lib.rs", "x\n"), ("web/app.ts", "y\n")]));
+        assert_eq!(cache.changed_under("r1", "r2", "web"), Some(true));
+        assert_eq!(cache.changed_under("r1", "r2", "src"), Some(false));
+    }
+
+    #[test]
+    fn the_cache_survives_a_round_trip_to_disk() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-runtime-treehash-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tree-hashes.json");
+
+        let mut cache = TreeHashCache::new();
+        cache.record("r1", &snapshot(&[("a.txt", "hello\n")]));
+        cache.save(&path).unwrap();
+
+        let loaded = TreeHashCache::load(&path).unwrap();
+        assert!(loaded.contains("r1"));
+        assert_eq!(loaded.root_hash("r1"), cache.root_hash("r1"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Non-fatal warnings alongside successful results.
+//!
+//! Some operations succeed while leaving something worth telling the
+//! user: a snapshot that skipped binary files, an export that dropped an
+//! invalid name. Failing the call would be wrong; swallowing the detail
+//! is worse. [`Warned`] carries the value *and* the warnings, so the
+//! host decides how loudly to surface them.
+
+/// A successful result with zero or more non-fatal warnings attached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warned<T> {
+    pub value: T,
+    /// Human-readable, in the order they arose; empty when nothing was
+    /// worth mentioning.
+    pub warnings: Vec<String>,
+}
+
+impl<T> Warned<T> {
+    /// A clean result with no warnings.
+    pub fn clean(value: T) -> Self {
+        Self {
+            value,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn new(value: T, warnings: Vec<String>) -> Self {
+        Self { value, warnings }
+    }
+
+    /// Transform the value, keeping the warnings.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Warned<U> {
+        Warned {
+            value: f(self.value),
+            warnings: self.warnings,
+        }
+    }
+
+    /// The value alone, for callers that have already shown (or chosen
+    /// to drop) the warnings.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn warnings_survive_mapping() {
+        let warned = Warned::new(2, vec!["skipped 3 binary files".to_string()]);
+        let mapped = warned.map(|n| n * 10);
+        assert_eq!(mapped.value, 20);
+        assert_eq!(mapped.warnings.len(), 1);
+        assert_eq!(Warned::clean("ok").into_value(), "ok");
+    }
+}
@@ -0,0 +1,196 @@
+//! Prompt size budgets.
+//!
+//! Providers truncate oversized context silently, which is the worst
+//! possible failure mode: the prompt still "works" while missing its tail.
+//! A prompt declares how big it is allowed to get:
+//!
+//! ```yaml
+//! budget:
+//!   max_chars: 12000
+//!   max_tokens: 3000
+//! ```
+//!
+//! [`PromptDefinition::check_budget`] renders and reports usage against both
+//! caps — a structured result the host can log as a warning — and
+//! [`PromptDefinition::enforce_budget`] turns any overrun into an error.
+//! Token counts come from the same [`crate::TokenCounter`] machinery as
+//! [`PromptDefinition::count_tokens`].
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::tokens::{BpeTokenCounter, TokenCounter};
+
+/// The `budget:` frontmatter block. Either cap (or both) may be declared.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Budget {
+    /// Cap on total rendered characters across all messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_chars: Option<usize>,
+    /// Cap on total prompt tokens across all messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+}
+
+/// Which cap a [`BudgetOverrun`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    Chars,
+    Tokens,
+}
+
+impl std::fmt::Display for BudgetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BudgetKind::Chars => "chars",
+            BudgetKind::Tokens => "tokens",
+        })
+    }
+}
+
+/// One exceeded cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BudgetOverrun {
+    pub kind: BudgetKind,
+    pub used: usize,
+    pub limit: usize,
+}
+
+/// The result of [`PromptDefinition::check_budget`]: measured usage plus any
+/// exceeded caps. An empty `over` means the render fits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BudgetReport {
+    pub chars: usize,
+    /// Only measured when the budget declares `max_tokens`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<usize>,
+    pub over: Vec<BudgetOverrun>,
+}
+
+impl BudgetReport {
+    pub fn within_budget(&self) -> bool {
+        self.over.is_empty()
+    }
+}
+
+/// Parse/build-time check: declared caps must be at least 1.
+pub(crate) fn validate_budget(def: &PromptDefinition) -> Result<(), PromptError> {
+    let Some(budget) = &def.budget else {
+        return Ok(());
+    };
+    for (field, value) in [
+        ("max_chars", budget.max_chars),
+        ("max_tokens", budget.max_tokens),
+    ] {
+        if value == Some(0) {
+            return Err(PromptError::Frontmatter(format!(
+                "`budget.{field}` must be at least 1"
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl PromptDefinition {
+    /// Render with `data` and measure against the declared `budget`, using
+    /// the default per-client token counter. Prompts without a budget report
+    /// usage with no overruns.
+    pub fn check_budget(&self, data: &Value) -> Result<BudgetReport, PromptError> {
+        self.check_budget_with(data, &BpeTokenCounter::for_client(self.client.as_deref()))
+    }
+
+    /// [`Self::check_budget`] with a caller-supplied counter.
+    pub fn check_budget_with(
+        &self,
+        data: &Value,
+        counter: &dyn TokenCounter,
+    ) -> Result<BudgetReport, PromptError> {
+        let budget = self.budget.unwrap_or_default();
+        let chars = self
+            .render_messages(data)?
+            .iter()
+            .map(|m| m.content.chars().count())
+            .sum();
+        let tokens = budget
+            .max_tokens
+            .map(|_| self.count_tokens_with(data, counter))
+            .transpose()?;
+
+        let mut over = Vec::new();
+        if let Some(limit) = budget.max_chars
+            && chars > limit
+        {
+            over.push(BudgetOverrun {
+                kind: BudgetKind::Chars,
+                used: chars,
+                limit,
+            });
+        }
+        if let (Some(limit), Some(used)) = (budget.max_tokens, tokens)
+            && used > limit
+        {
+            over.push(BudgetOverrun {
+                kind: BudgetKind::Tokens,
+                used,
+                limit,
+            });
+        }
+        Ok(BudgetReport { chars, tokens, over })
+    }
+
+    /// [`Self::check_budget`], with any overrun promoted to an error — for
+    /// hosts that refuse to send oversized prompts rather than warn.
+    pub fn enforce_budget(&self, data: &Value) -> Result<BudgetReport, PromptError> {
+        let report = self.check_budget(data)?;
+        if let Some(overrun) = report.over.first() {
+            return Err(PromptError::BudgetExceeded {
+                kind: overrun.kind,
+                used: overrun.used,
+                limit: overrun.limit,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use serde_json::json;
+
+    #[test]
+    fn overruns_are_reported_and_enforceable() {
+        let def = parse("---\nname: x\nbudget:\n  max_chars: 10\n---\n{{ text }}").unwrap();
+        let report = def.check_budget(&json!({ "text": "well over ten chars" })).unwrap();
+        assert!(!report.within_budget());
+        assert_eq!(report.over[0].kind, BudgetKind::Chars);
+        assert_eq!(report.over[0].limit, 10);
+        assert_eq!(report.tokens, None);
+
+        let err = def
+            .enforce_budget(&json!({ "text": "well over ten chars" }))
+            .unwrap_err();
+        assert!(matches!(err, PromptError::BudgetExceeded { .. }), "{err}");
+
+        let report = def.check_budget(&json!({ "text": "ok" })).unwrap();
+        assert!(report.within_budget());
+    }
+
+    #[test]
+    fn token_caps_count_with_the_client_vocabulary() {
+        let def = parse("---\nname: x\nbudget:\n  max_tokens: 2\n---\none two three four five").unwrap();
+        let report = def.check_budget(&json!({})).unwrap();
+        assert!(report.tokens.unwrap() > 2);
+        assert_eq!(report.over[0].kind, BudgetKind::Tokens);
+    }
+
+    #[test]
+    fn zero_caps_fail_at_parse() {
+        let err = parse("---\nname: x\nbudget:\n  max_chars: 0\n---\nbody").unwrap_err();
+        assert!(matches!(err, PromptError::Frontmatter(_)), "{err}");
+    }
+}
@@ -0,0 +1,206 @@
+//! Programmatic construction of prompt definitions.
+//!
+//! Test harnesses and generators shouldn't format YAML strings by hand.
+//! The builder assembles a [`PromptDefinition`] field by field and runs the
+//! same validation as file parsing on `build()`, so a definition is equally
+//! trustworthy whichever way it was made.
+
+use serde_json::{Value, json};
+
+use crate::definition::{Example, PromptDefinition};
+use crate::error::PromptError;
+use crate::parser;
+
+/// Builder for [`PromptDefinition`]; see [`PromptDefinition::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct PromptDefinitionBuilder {
+    def: PromptDefinition,
+}
+
+impl PromptDefinition {
+    /// Start building a definition programmatically.
+    pub fn builder(name: impl Into<String>) -> PromptDefinitionBuilder {
+        PromptDefinitionBuilder {
+            def: PromptDefinition {
+                name: name.into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl PromptDefinitionBuilder {
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.def.version = Some(version.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.def.description = Some(description.into());
+        self
+    }
+
+    pub fn prompt_type(mut self, prompt_type: impl Into<String>) -> Self {
+        self.def.prompt_type = Some(prompt_type.into());
+        self
+    }
+
+    /// Target client; aliases resolve on `build()` exactly as in parsing.
+    pub fn client(mut self, client: impl Into<String>) -> Self {
+        self.def.client = Some(client.into());
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.def.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f64) -> Self {
+        self.def.top_p = Some(top_p);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.def.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn stop(mut self, stop: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.def.stop = Some(stop.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.def.system = Some(system.into());
+        self
+    }
+
+    pub fn example(mut self, user: Value, assistant: Value) -> Self {
+        self.def
+            .examples
+            .get_or_insert_with(Vec::new)
+            .push(Example { user, assistant });
+        self
+    }
+
+    /// Declare one typed input, accumulating an object `inputs` schema.
+    /// Mixing this with [`Self::inputs_schema`] replaces prior declarations.
+    pub fn input(mut self, name: impl Into<String>, schema: Value, required: bool) -> Self {
+        let name = name.into();
+        let inputs = self
+            .def
+            .inputs
+            .get_or_insert_with(|| json!({ "type": "object", "properties": {} }));
+        if let Some(props) = inputs
+            .get_mut("properties")
+            .and_then(Value::as_object_mut)
+        {
+            props.insert(name.clone(), schema);
+        }
+        if required
+            && let Some(obj) = inputs.as_object_mut()
+        {
+            let list = obj
+                .entry("required")
+                .or_insert_with(|| Value::Array(Vec::new()));
+            if let Some(arr) = list.as_array_mut()
+                && !arr.iter().any(|v| v == &Value::String(name.clone()))
+            {
+                arr.push(Value::String(name));
+            }
+        }
+        self
+    }
+
+    /// Set the whole `inputs` schema at once.
+    pub fn inputs_schema(mut self, schema: Value) -> Self {
+        self.def.inputs = Some(schema);
+        self
+    }
+
+    /// Set the `output` schema.
+    pub fn output_schema(mut self, schema: Value) -> Self {
+        self.def.output = Some(schema);
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.def.body = body.into();
+        self
+    }
+
+    /// Validate and produce the definition — the same checks as parsing a
+    /// file: non-empty name, model parameter ranges, client resolution,
+    /// schema compilation, template well-formedness.
+    pub fn build(mut self) -> Result<PromptDefinition, PromptError> {
+        parser::finish_definition(&mut self.def)?;
+        Ok(self.def)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn builds_a_renderable_definition() {
+        let def = PromptDefinition::builder("greet")
+            .description("Greet someone")
+            .client("claude-sonnet")
+            .max_tokens(256)
+            .input("who", json!({ "type": "string" }), true)
+            .input("tone", json!({ "type": "string" }), false)
+            .output_schema(json!({
+                "type": "object",
+                "properties": { "greeting": { "type": "string" } }
+            }))
+            .body("Hello {{ who }}!")
+            .build()
+            .unwrap();
+
+        // Same normalization as parsing: the alias resolved.
+        assert_eq!(def.client.as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(def.render(&json!({ "who": "world" })).unwrap(), "Hello world!");
+        assert!(matches!(
+            def.render(&json!({})).unwrap_err(),
+            PromptError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn build_runs_parse_validation() {
+        assert!(matches!(
+            PromptDefinition::builder("").body("x").build().unwrap_err(),
+            PromptError::Frontmatter(_)
+        ));
+        assert!(matches!(
+            PromptDefinition::builder("t")
+                .temperature(9.0)
+                .body("x")
+                .build()
+                .unwrap_err(),
+            PromptError::Frontmatter(_)
+        ));
+        assert!(matches!(
+            PromptDefinition::builder("t")
+                .body("{{#if a}}unclosed")
+                .build()
+                .unwrap_err(),
+            PromptError::Template(_)
+        ));
+    }
+
+    #[test]
+    fn built_definitions_round_trip_through_markdown() {
+        let def = PromptDefinition::builder("t")
+            .version("0.1.0")
+            .input("x", json!({ "type": "integer" }), true)
+            .body("{{ x }}")
+            .build()
+            .unwrap();
+        let reparsed = crate::parse(&def.to_markdown().unwrap()).unwrap();
+        assert_eq!(def, reparsed);
+    }
+}
@@ -0,0 +1,152 @@
+//! Content-hash-keyed caches for compiled templates and schemas.
+//!
+//! FFI calls are stateless, so without caching every render re-parses the
+//! body template and recompiles the JSON Schema validators. Both artifacts
+//! are pure functions of their source text (plus validation options), so they
+//! are cached process-wide, keyed by content hash. Only successful
+//! compilations are cached; errors are recomputed so messages stay fresh.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use jsonschema::Validator;
+use serde_json::Value;
+
+use crate::error::PromptError;
+use crate::schema::{self, ValidationOptions};
+use crate::template::{self, Node};
+
+static TEMPLATES: LazyLock<Mutex<HashMap<u64, Arc<Vec<Node>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static SCHEMAS: LazyLock<Mutex<HashMap<u64, Arc<Validator>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static TEMPLATE_HITS: AtomicU64 = AtomicU64::new(0);
+static TEMPLATE_MISSES: AtomicU64 = AtomicU64::new(0);
+static SCHEMA_HITS: AtomicU64 = AtomicU64::new(0);
+static SCHEMA_MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// A point-in-time snapshot of cache effectiveness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub template_entries: usize,
+    pub template_hits: u64,
+    pub template_misses: u64,
+    pub schema_entries: usize,
+    pub schema_hits: u64,
+    pub schema_misses: u64,
+}
+
+/// Current cache statistics.
+pub fn stats() -> CacheStats {
+    CacheStats {
+        template_entries: TEMPLATES.lock().unwrap().len(),
+        template_hits: TEMPLATE_HITS.load(Ordering::Relaxed),
+        template_misses: TEMPLATE_MISSES.load(Ordering::Relaxed),
+        schema_entries: SCHEMAS.lock().unwrap().len(),
+        schema_hits: SCHEMA_HITS.load(Ordering::Relaxed),
+        schema_misses: SCHEMA_MISSES.load(Ordering::Relaxed),
+    }
+}
+
+/// Drop every cached template and validator. Counters are kept.
+pub fn invalidate() {
+    TEMPLATES.lock().unwrap().clear();
+    SCHEMAS.lock().unwrap().clear();
+}
+
+fn content_hash(parts: &[&[u8]]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for part in parts {
+        part.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Parsed nodes for `body`, from cache when available.
+pub(crate) fn template_nodes(body: &str) -> Result<Arc<Vec<Node>>, PromptError> {
+    let key = content_hash(&[body.as_bytes()]);
+    if let Some(nodes) = TEMPLATES.lock().unwrap().get(&key) {
+        TEMPLATE_HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(Arc::clone(nodes));
+    }
+    TEMPLATE_MISSES.fetch_add(1, Ordering::Relaxed);
+    let nodes = Arc::new(template::parse_template(body)?);
+    TEMPLATES
+        .lock()
+        .unwrap()
+        .insert(key, Arc::clone(&nodes));
+    Ok(nodes)
+}
+
+/// Compiled validator for `schema` under `options`, from cache when available.
+pub(crate) fn validator(
+    field: &str,
+    schema: &Value,
+    options: &ValidationOptions,
+) -> Result<Arc<Validator>, PromptError> {
+    let serialized = schema.to_string();
+    let key = content_hash(&[
+        serialized.as_bytes(),
+        &[options.draft as u8, options.assert_formats as u8],
+    ]);
+    if let Some(validator) = SCHEMAS.lock().unwrap().get(&key) {
+        SCHEMA_HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(Arc::clone(validator));
+    }
+    SCHEMA_MISSES.fetch_add(1, Ordering::Relaxed);
+    let validator = Arc::new(schema::compile(field, schema, options)?);
+    SCHEMAS
+        .lock()
+        .unwrap()
+        .insert(key, Arc::clone(&validator));
+    Ok(validator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // One sequential test: the caches and counters are process-global, so
+    // separate #[test] fns would race each other under the parallel runner.
+    #[test]
+    fn caching_hits_keys_on_options_and_invalidates() {
+        // Repeated lookups hit. Content is unique to this test.
+        let body = "cache-test {{ cache_test_var_1642 }}";
+        let before = stats();
+        template_nodes(body).unwrap();
+        template_nodes(body).unwrap();
+        let after = stats();
+        assert_eq!(after.template_misses, before.template_misses + 1);
+        assert!(after.template_hits > before.template_hits);
+
+        let schema = json!({ "type": "string", "minLength": 164263 });
+        let opts = ValidationOptions::default();
+        let before = stats();
+        validator("schema", &schema, &opts).unwrap();
+        validator("schema", &schema, &opts).unwrap();
+        let after = stats();
+        assert_eq!(after.schema_misses, before.schema_misses + 1);
+
+        // Validation options are part of the key: the same schema compiled
+        // with and without format assertions must not be conflated.
+        let schema = json!({ "type": "string", "format": "email", "maxLength": 164264 });
+        let asserting = ValidationOptions {
+            assert_formats: true,
+            ..Default::default()
+        };
+        let lenient = validator("schema", &schema, &opts).unwrap();
+        let strict = validator("schema", &schema, &asserting).unwrap();
+        assert!(lenient.is_valid(&json!("nope")));
+        assert!(!strict.is_valid(&json!("nope")));
+
+        // Invalidation drops every entry.
+        assert!(stats().template_entries > 0);
+        invalidate();
+        assert_eq!(stats().template_entries, 0);
+        assert_eq!(stats().schema_entries, 0);
+    }
+}
@@ -0,0 +1,158 @@
+//! Client string parsing, validation, and the model registry.
+//!
+//! A `client` is `provider/model[@version]`. Bare aliases (`claude-sonnet`)
+//! resolve through the registry to a full id at parse time, and unknown
+//! providers fail the parse instead of the eventual provider request. Hosts
+//! extend the registry at runtime for local or self-hosted providers.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{LazyLock, RwLock};
+
+use crate::error::PromptError;
+
+/// A parsed `provider/model[@version]` client identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientId {
+    pub provider: String,
+    pub model: String,
+    pub version: Option<String>,
+}
+
+impl fmt::Display for ClientId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.provider, self.model)?;
+        if let Some(version) = &self.version {
+            write!(f, "@{version}")?;
+        }
+        Ok(())
+    }
+}
+
+fn default_providers() -> HashSet<String> {
+    ["anthropic", "openai", "google", "mistral", "meta", "local"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_aliases() -> HashMap<String, String> {
+    [
+        ("claude-opus", "anthropic/claude-opus-4"),
+        ("claude-sonnet", "anthropic/claude-sonnet-4"),
+        ("claude-haiku", "anthropic/claude-haiku-3.5"),
+        ("gpt-4o", "openai/gpt-4o"),
+        ("gpt-4o-mini", "openai/gpt-4o-mini"),
+        ("o1", "openai/o1"),
+    ]
+    .into_iter()
+    .map(|(a, t)| (a.to_string(), t.to_string()))
+    .collect()
+}
+
+static PROVIDERS: LazyLock<RwLock<HashSet<String>>> =
+    LazyLock::new(|| RwLock::new(default_providers()));
+static ALIASES: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(default_aliases()));
+
+/// Register an additional provider name accepted in `client` strings.
+pub fn register_provider(name: &str) {
+    PROVIDERS.write().unwrap().insert(name.to_string());
+}
+
+/// Register (or replace) an alias resolving to a full `provider/model` id.
+pub fn register_alias(alias: &str, target: &str) {
+    ALIASES
+        .write()
+        .unwrap()
+        .insert(alias.to_string(), target.to_string());
+}
+
+fn client_err(client: &str, message: impl Into<String>) -> PromptError {
+    PromptError::Client {
+        client: client.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Resolve a `client` string to a validated [`ClientId`].
+///
+/// Bare names are looked up as aliases first; `provider/model[@version]`
+/// strings are parsed directly. The provider must be registered.
+pub fn resolve_client(client: &str) -> Result<ClientId, PromptError> {
+    let spec = if client.contains('/') {
+        client.to_string()
+    } else {
+        ALIASES
+            .read()
+            .unwrap()
+            .get(client)
+            .cloned()
+            .ok_or_else(|| client_err(client, "unknown alias; expected `provider/model`"))?
+    };
+
+    let (provider, rest) = spec
+        .split_once('/')
+        .ok_or_else(|| client_err(client, "expected `provider/model[@version]`"))?;
+    let (model, version) = match rest.split_once('@') {
+        Some((model, version)) => (model, Some(version)),
+        None => (rest, None),
+    };
+
+    if provider.is_empty() || model.is_empty() {
+        return Err(client_err(client, "provider and model must be non-empty"));
+    }
+    if let Some(v) = version
+        && v.is_empty()
+    {
+        return Err(client_err(client, "`@` must be followed by a version"));
+    }
+    if !PROVIDERS.read().unwrap().contains(provider) {
+        return Err(client_err(
+            client,
+            format!("unknown provider `{provider}`"),
+        ));
+    }
+
+    Ok(ClientId {
+        provider: provider.to_string(),
+        model: model.to_string(),
+        version: version.map(String::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_full_ids_with_version() {
+        let id = resolve_client("anthropic/claude-sonnet-4@20250514").unwrap();
+        assert_eq!(id.provider, "anthropic");
+        assert_eq!(id.model, "claude-sonnet-4");
+        assert_eq!(id.version.as_deref(), Some("20250514"));
+        assert_eq!(id.to_string(), "anthropic/claude-sonnet-4@20250514");
+    }
+
+    #[test]
+    fn resolves_aliases() {
+        let id = resolve_client("claude-sonnet").unwrap();
+        assert_eq!(id.to_string(), "anthropic/claude-sonnet-4");
+    }
+
+    #[test]
+    fn rejects_unknown_providers_and_malformed_ids() {
+        for bad in ["acme/model-x", "not-an-alias", "anthropic/", "/model", "anthropic/m@"] {
+            let err = resolve_client(bad).unwrap_err();
+            assert!(matches!(err, PromptError::Client { .. }), "{bad}");
+        }
+    }
+
+    #[test]
+    fn runtime_registration_extends_the_registry() {
+        register_provider("ollama");
+        register_alias("local-llama", "ollama/llama-3.3-70b");
+        let id = resolve_client("local-llama").unwrap();
+        assert_eq!(id.to_string(), "ollama/llama-3.3-70b");
+    }
+}
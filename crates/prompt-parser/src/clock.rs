@@ -0,0 +1,111 @@
+//! Deterministic clock for templates.
+//!
+//! `{{ now() }}` renders an RFC 3339 UTC timestamp, `{{ today() }}` the UTC
+//! date. A wall clock makes renders unreproducible — golden tests flake and
+//! cached renders never hash the same twice — so hosts can pin the clock
+//! process-wide with [`set_clock`] and restore it with [`clear_clock`],
+//! following the [`crate::pricing`] override pattern.
+
+use std::sync::{LazyLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static PINNED: LazyLock<RwLock<Option<i64>>> = LazyLock::new(|| RwLock::new(None));
+
+/// Pin the clock: until [`clear_clock`], every `now()`/`today()` renders
+/// from this Unix timestamp (seconds, UTC) instead of the system clock.
+pub fn set_clock(unix_seconds: i64) {
+    *PINNED.write().unwrap() = Some(unix_seconds);
+}
+
+/// Unpin the clock; renders read the system clock again.
+pub fn clear_clock() {
+    *PINNED.write().unwrap() = None;
+}
+
+/// The template clock functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClockFn {
+    /// `now()` — `2025-01-01T12:34:56Z`.
+    Now,
+    /// `today()` — `2025-01-01`.
+    Today,
+}
+
+pub(crate) fn render(f: ClockFn) -> String {
+    let ts = now_unix();
+    let (year, month, day) = civil_from_unix(ts);
+    match f {
+        ClockFn::Now => {
+            let secs_of_day = ts.rem_euclid(86_400);
+            format!(
+                "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+                secs_of_day / 3600,
+                secs_of_day % 3600 / 60,
+                secs_of_day % 60,
+            )
+        }
+        ClockFn::Today => format!("{year:04}-{month:02}-{day:02}"),
+    }
+}
+
+fn now_unix() -> i64 {
+    if let Some(pinned) = *PINNED.read().unwrap() {
+        return pinned;
+    }
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// Unix seconds → proleptic Gregorian (year, month, day) in UTC.
+fn civil_from_unix(ts: i64) -> (i64, u32, u32) {
+    let days = ts.div_euclid(86_400);
+    // Days-to-civil conversion over 400-year eras (146097 days each).
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = era * 400 + yoe + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_template;
+    use serde_json::json;
+
+    #[test]
+    fn pinned_clock_renders_deterministically() {
+        set_clock(1_735_689_600); // 2025-01-01T00:00:00Z
+        let out = render_template("{{ now() }} / {{ today() }}", &json!({})).unwrap();
+        clear_clock();
+        assert_eq!(out, "2025-01-01T00:00:00Z / 2025-01-01");
+    }
+
+    #[test]
+    fn unpinned_clock_still_renders_the_shape() {
+        // No exact assertion: other tests may pin the clock concurrently.
+        let out = render_template("{{ now() }}", &json!({})).unwrap();
+        assert_eq!(out.len(), 20, "{out}");
+        assert!(out.ends_with('Z') && out.as_bytes()[10] == b'T', "{out}");
+    }
+
+    #[test]
+    fn only_the_clock_functions_get_call_syntax() {
+        let err = render_template("{{ rand() }}", &json!({})).unwrap_err();
+        assert!(err.to_string().contains("invalid expression"), "{err}");
+    }
+
+    #[test]
+    fn civil_conversion_handles_era_boundaries() {
+        assert_eq!(civil_from_unix(0), (1970, 1, 1));
+        assert_eq!(civil_from_unix(951_782_400), (2000, 2, 29));
+        assert_eq!(civil_from_unix(-86_400), (1969, 12, 31));
+    }
+}
@@ -0,0 +1,430 @@
+//! Code generation from prompt schemas.
+//!
+//! The prompt file is the single source of truth for a prompt's I/O contract;
+//! generators here project that contract into other languages so frontends
+//! don't hand-maintain parallel type definitions. Currently: TypeScript
+//! `.d.ts` declarations and Zod validators.
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+
+impl PromptDefinition {
+    /// Emit a TypeScript declaration for this prompt's `inputs` and `output`
+    /// schemas: `interface <Name>Inputs` / `interface <Name>Output` (type
+    /// aliases when the schema is not a plain object). Schemas that are
+    /// absent are omitted; a prompt with neither produces an empty string.
+    pub fn typescript_declarations(&self) -> String {
+        let type_base = pascal_case(&self.name);
+        let mut out = String::new();
+        if let Some(inputs) = &self.inputs {
+            emit_declaration(&mut out, &format!("{type_base}Inputs"), inputs);
+        }
+        if let Some(output) = &self.output {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            emit_declaration(&mut out, &format!("{type_base}Output"), output);
+        }
+        out
+    }
+    /// Emit a Zod module for this prompt's `inputs` and `output` schemas:
+    /// `export const <Name>Inputs = z.object({...})` etc., prefixed with the
+    /// `zod` import. Schemas that are absent are omitted; a prompt with
+    /// neither produces an empty string.
+    pub fn zod_schemas(&self) -> String {
+        let type_base = pascal_case(&self.name);
+        let mut decls = String::new();
+        if let Some(inputs) = &self.inputs {
+            decls.push_str(&format!(
+                "export const {type_base}Inputs = {};\n",
+                zod_type(inputs, "")
+            ));
+        }
+        if let Some(output) = &self.output {
+            if !decls.is_empty() {
+                decls.push('\n');
+            }
+            decls.push_str(&format!(
+                "export const {type_base}Output = {};\n",
+                zod_type(output, "")
+            ));
+        }
+        if decls.is_empty() {
+            decls
+        } else {
+            format!("import {{ z }} from \"zod\";\n\n{decls}")
+        }
+    }
+}
+
+/// `summarize-pr` / `summarize_pr` → `SummarizePr`.
+pub(crate) fn pascal_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper_next = true;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if upper_next {
+                out.extend(c.to_uppercase());
+            } else {
+                out.push(c);
+            }
+            upper_next = false;
+        } else {
+            upper_next = true;
+        }
+    }
+    out
+}
+
+fn emit_declaration(out: &mut String, name: &str, schema: &Value) {
+    if let Some(description) = schema.get("description").and_then(Value::as_str) {
+        emit_doc_comment(out, "", description);
+    }
+    // Object schemas become interfaces; anything else is a type alias.
+    if is_plain_object(schema) {
+        out.push_str(&format!("export interface {name} "));
+        emit_object_body(out, schema, "");
+        out.push('\n');
+    } else {
+        out.push_str(&format!("export type {name} = {};\n", ts_type(schema, "")));
+    }
+}
+
+fn is_plain_object(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("object")
+        && schema.get("enum").is_none()
+        && !["oneOf", "anyOf", "allOf"].iter().any(|k| schema.get(*k).is_some())
+}
+
+fn emit_doc_comment(out: &mut String, indent: &str, text: &str) {
+    out.push_str(&format!("{indent}/** {} */\n", text.trim()));
+}
+
+/// Emit `{ prop: T; ... }` for an object schema, multi-line.
+fn emit_object_body(out: &mut String, schema: &Value, indent: &str) {
+    let inner = format!("{indent}  ");
+    out.push_str("{\n");
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+        for (prop, prop_schema) in props {
+            if let Some(description) = prop_schema.get("description").and_then(Value::as_str) {
+                emit_doc_comment(out, &inner, description);
+            }
+            let optional = if required.contains(&prop.as_str()) { "" } else { "?" };
+            out.push_str(&format!(
+                "{inner}{}{optional}: {};\n",
+                property_key(prop),
+                ts_type(prop_schema, &inner)
+            ));
+        }
+    }
+    // Index signatures only for *explicit* extra-property schemas; the
+    // implicit JSON Schema default (open) would make every interface noisy.
+    match schema.get("additionalProperties") {
+        Some(Value::Bool(true)) => {
+            out.push_str(&format!("{inner}[key: string]: unknown;\n"));
+        }
+        Some(extra) if !extra.is_boolean() => {
+            out.push_str(&format!("{inner}[key: string]: {};\n", ts_type(extra, &inner)));
+        }
+        _ => {}
+    }
+    out.push_str(&format!("{indent}}}"));
+}
+
+/// Quote property names that are not valid TS identifiers.
+fn property_key(name: &str) -> String {
+    let valid = !name.is_empty()
+        && name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        name.to_string()
+    } else {
+        serde_json::to_string(name).expect("strings serialize")
+    }
+}
+
+/// Render a schema as a TypeScript type expression.
+fn ts_type(schema: &Value, indent: &str) -> String {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(literal));
+    }
+    if let Some(value) = schema.get("const") {
+        return literal(value);
+    }
+    for (key, sep) in [("oneOf", " | "), ("anyOf", " | "), ("allOf", " & ")] {
+        if let Some(variants) = schema.get(key).and_then(Value::as_array) {
+            return variants
+                .iter()
+                .map(|v| ts_type(v, indent))
+                .collect::<Vec<_>>()
+                .join(sep);
+        }
+    }
+    match schema.get("type") {
+        Some(Value::String(t)) => scalar_or_composite(t, schema, indent),
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|t| scalar_or_composite(t, schema, indent)),
+        ),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn scalar_or_composite(type_name: &str, schema: &Value, indent: &str) -> String {
+    match type_name {
+        "string" => "string".to_string(),
+        "number" | "integer" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => {
+            let item = schema
+                .get("items")
+                .map(|items| ts_type(items, indent))
+                .unwrap_or_else(|| "unknown".to_string());
+            // Parenthesize unions so `A | B[]` doesn't change meaning.
+            if item.contains(' ') {
+                format!("({item})[]")
+            } else {
+                format!("{item}[]")
+            }
+        }
+        "object" => {
+            let mut out = String::new();
+            emit_object_body(&mut out, schema, indent);
+            out
+        }
+        other => {
+            debug_assert!(false, "unknown schema type {other}");
+            "unknown".to_string()
+        }
+    }
+}
+
+fn union(parts: impl Iterator<Item = String>) -> String {
+    let joined: Vec<String> = parts.collect();
+    if joined.is_empty() {
+        "never".to_string()
+    } else {
+        joined.join(" | ")
+    }
+}
+
+fn literal(value: &Value) -> String {
+    serde_json::to_string(value).expect("JSON values serialize")
+}
+
+/// Render a schema as a Zod expression.
+fn zod_type(schema: &Value, indent: &str) -> String {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        // z.enum() takes strings only; fall back to a literal union otherwise.
+        if values.iter().all(Value::is_string) {
+            let joined: Vec<String> = values.iter().map(literal).collect();
+            return format!("z.enum([{}])", joined.join(", "));
+        }
+        return zod_union(values.iter().map(|v| format!("z.literal({})", literal(v))));
+    }
+    if let Some(value) = schema.get("const") {
+        return format!("z.literal({})", literal(value));
+    }
+    if let Some(variants) = schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))
+        .and_then(Value::as_array)
+    {
+        return zod_union(variants.iter().map(|v| zod_type(v, indent)));
+    }
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        let mut iter = parts.iter().map(|v| zod_type(v, indent));
+        let first = iter.next().unwrap_or_else(|| "z.unknown()".to_string());
+        return iter.fold(first, |acc, part| format!("{acc}.and({part})"));
+    }
+    match schema.get("type") {
+        Some(Value::String(t)) => zod_scalar_or_composite(t, schema, indent),
+        Some(Value::Array(types)) => zod_union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|t| zod_scalar_or_composite(t, schema, indent)),
+        ),
+        _ => "z.unknown()".to_string(),
+    }
+}
+
+fn zod_scalar_or_composite(type_name: &str, schema: &Value, indent: &str) -> String {
+    match type_name {
+        "string" => "z.string()".to_string(),
+        "number" => "z.number()".to_string(),
+        "integer" => "z.number().int()".to_string(),
+        "boolean" => "z.boolean()".to_string(),
+        "null" => "z.null()".to_string(),
+        "array" => {
+            let item = schema
+                .get("items")
+                .map(|items| zod_type(items, indent))
+                .unwrap_or_else(|| "z.unknown()".to_string());
+            format!("z.array({item})")
+        }
+        "object" => zod_object(schema, indent),
+        other => {
+            debug_assert!(false, "unknown schema type {other}");
+            "z.unknown()".to_string()
+        }
+    }
+}
+
+fn zod_object(schema: &Value, indent: &str) -> String {
+    let inner = format!("{indent}  ");
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let mut out = String::from("z.object({\n");
+    if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+        for (prop, prop_schema) in props {
+            let mut expr = zod_type(prop_schema, &inner);
+            if let Some(description) = prop_schema.get("description").and_then(Value::as_str) {
+                expr.push_str(&format!(".describe({})", literal(&Value::String(description.into()))));
+            }
+            if !required.contains(&prop.as_str()) {
+                expr.push_str(".optional()");
+            }
+            out.push_str(&format!("{inner}{}: {expr},\n", property_key(prop)));
+        }
+    }
+    out.push_str(&format!("{indent}}})"));
+    match schema.get("additionalProperties") {
+        Some(Value::Bool(false)) => out.push_str(".strict()"),
+        Some(Value::Bool(true)) => out.push_str(".passthrough()"),
+        Some(extra) => out.push_str(&format!(".catchall({})", zod_type(extra, indent))),
+        None => {}
+    }
+    out
+}
+
+fn zod_union(parts: impl Iterator<Item = String>) -> String {
+    let mut joined: Vec<String> = parts.collect();
+    match joined.len() {
+        0 => "z.never()".to_string(),
+        1 => joined.remove(0),
+        _ => format!("z.union([{}])", joined.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    const SOURCE: &str = r#"---
+name: summarize-pr
+inputs:
+  type: object
+  description: What to summarize.
+  properties:
+    diff:
+      type: string
+      description: Unified diff text.
+    max_points: { type: integer }
+    style:
+      enum: [terse, detailed]
+    labels:
+      type: array
+      items: { type: string }
+  required: [diff]
+  additionalProperties: false
+output:
+  type: object
+  properties:
+    summary: { type: string }
+    score: { type: [number, "null"] }
+  required: [summary]
+  additionalProperties: true
+---
+{{ diff }}"#;
+
+    #[test]
+    fn emits_interfaces_for_object_schemas() {
+        let ts = parse(SOURCE).unwrap().typescript_declarations();
+        assert!(ts.contains("/** What to summarize. */"), "{ts}");
+        assert!(ts.contains("export interface SummarizePrInputs {"), "{ts}");
+        assert!(ts.contains("  /** Unified diff text. */\n  diff: string;"), "{ts}");
+        assert!(ts.contains("  max_points?: number;"), "{ts}");
+        assert!(ts.contains("  style?: \"terse\" | \"detailed\";"), "{ts}");
+        assert!(ts.contains("  labels?: string[];"), "{ts}");
+        assert!(ts.contains("export interface SummarizePrOutput {"), "{ts}");
+        assert!(ts.contains("  score?: number | null;"), "{ts}");
+        // Inputs schema is closed, output schema is explicitly open.
+        let inputs_block = &ts[..ts.find("SummarizePrOutput").unwrap()];
+        assert!(!inputs_block.contains("[key: string]"), "{ts}");
+        assert!(ts.contains("  [key: string]: unknown;"), "{ts}");
+    }
+
+    #[test]
+    fn non_object_schemas_become_type_aliases() {
+        let def = parse("---\nname: pick\noutput:\n  enum: [a, b]\n---\nbody").unwrap();
+        assert_eq!(
+            def.typescript_declarations(),
+            "export type PickOutput = \"a\" | \"b\";\n"
+        );
+    }
+
+    #[test]
+    fn no_schemas_means_no_output() {
+        let def = parse("---\nname: chat\n---\nbody").unwrap();
+        assert_eq!(def.typescript_declarations(), "");
+    }
+
+    #[test]
+    fn emits_zod_validators() {
+        let zod = parse(SOURCE).unwrap().zod_schemas();
+        assert!(zod.starts_with("import { z } from \"zod\";\n"), "{zod}");
+        assert!(zod.contains("export const SummarizePrInputs = z.object({"), "{zod}");
+        assert!(
+            zod.contains("  diff: z.string().describe(\"Unified diff text.\"),"),
+            "{zod}"
+        );
+        assert!(zod.contains("  max_points: z.number().int().optional(),"), "{zod}");
+        assert!(zod.contains("  style: z.enum([\"terse\", \"detailed\"]).optional(),"), "{zod}");
+        assert!(zod.contains("  labels: z.array(z.string()).optional(),"), "{zod}");
+        assert!(zod.contains("}).strict();"), "{zod}");
+        assert!(zod.contains("export const SummarizePrOutput = z.object({"), "{zod}");
+        assert!(
+            zod.contains("  score: z.union([z.number(), z.null()]).optional(),"),
+            "{zod}"
+        );
+        assert!(zod.contains("}).passthrough();"), "{zod}");
+    }
+
+    #[test]
+    fn zod_handles_non_object_schemas() {
+        let def = parse("---\nname: pick\noutput:\n  enum: [a, 2]\n---\nbody").unwrap();
+        assert!(
+            def.zod_schemas()
+                .contains("export const PickOutput = z.union([z.literal(\"a\"), z.literal(2)]);"),
+            "{}",
+            def.zod_schemas()
+        );
+        assert_eq!(parse("---\nname: chat\n---\nbody").unwrap().zod_schemas(), "");
+    }
+
+    #[test]
+    fn awkward_property_names_are_quoted() {
+        let def = parse(
+            "---\nname: x\ninputs:\n  type: object\n  properties:\n    \"content-type\": { type: string }\n---\nbody",
+        )
+        .unwrap();
+        assert!(
+            def.typescript_declarations().contains("\"content-type\"?: string;"),
+            "{}",
+            def.typescript_declarations()
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Schema-directed input coercion.
+//!
+//! Host environments frequently deliver inputs as strings — env vars, form
+//! fields, CLI args — so `"5"` arrives where the schema declares an integer.
+//! [`coerce_inputs`] nudges string values toward their declared types before
+//! validation. It never invents data and never discards it: a value that
+//! cannot be coerced is passed through unchanged so validation reports the
+//! real mismatch.
+
+use serde_json::{Map, Value};
+
+/// Coerce string values in `data` toward the types declared by `schema`.
+///
+/// Supported conversions, applied recursively through `properties` / `items`:
+/// - `"5"` → `5` for `integer`, `"1.5"` → `1.5` for `number`
+/// - `"true"` / `"false"` (case-insensitive) → booleans
+/// - `"a, b, c"` → `["a", "b", "c"]` for `array`, with elements coerced
+///   against the `items` schema
+/// - `""` and `"null"` → `null` where the schema allows `null`
+///
+/// A `type` list (`["integer", "null"]`) tries each candidate in order.
+pub fn coerce_inputs(schema: &Value, data: &Value) -> Value {
+    coerce_value(schema, data)
+}
+
+fn coerce_value(schema: &Value, value: &Value) -> Value {
+    match value {
+        Value::String(s) => coerce_string(schema, s),
+        Value::Object(map) => {
+            let props = schema.get("properties").and_then(Value::as_object);
+            let coerced: Map<String, Value> = map
+                .iter()
+                .map(|(k, v)| {
+                    let sub = props.and_then(|p| p.get(k));
+                    match sub {
+                        Some(sub) => (k.clone(), coerce_value(sub, v)),
+                        None => (k.clone(), v.clone()),
+                    }
+                })
+                .collect();
+            Value::Object(coerced)
+        }
+        Value::Array(items) => {
+            let item_schema = schema.get("items");
+            Value::Array(
+                items
+                    .iter()
+                    .map(|v| match item_schema {
+                        Some(s) => coerce_value(s, v),
+                        None => v.clone(),
+                    })
+                    .collect(),
+            )
+        }
+        _ => value.clone(),
+    }
+}
+
+fn coerce_string(schema: &Value, s: &str) -> Value {
+    for ty in declared_types(schema) {
+        if let Some(coerced) = coerce_string_to(schema, s, ty) {
+            return coerced;
+        }
+    }
+    Value::String(s.to_string())
+}
+
+fn declared_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn coerce_string_to(schema: &Value, s: &str, ty: &str) -> Option<Value> {
+    match ty {
+        // Already the declared type; stop trying alternatives.
+        "string" => Some(Value::String(s.to_string())),
+        "integer" => s.trim().parse::<i64>().ok().map(Value::from),
+        "number" => s.trim().parse::<f64>().ok().map(Value::from),
+        "boolean" => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        "null" => match s.trim() {
+            "" | "null" => Some(Value::Null),
+            _ => None,
+        },
+        "array" => {
+            let item_schema = schema.get("items").unwrap_or(&Value::Null);
+            let items = s
+                .split(',')
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .map(|part| coerce_string(item_schema, part))
+                .collect();
+            Some(Value::Array(items))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn coerces_scalars() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer" },
+                "ratio": { "type": "number" },
+                "verbose": { "type": "boolean" }
+            }
+        });
+        let data = json!({ "count": "5", "ratio": "1.5", "verbose": "TRUE" });
+        assert_eq!(
+            coerce_inputs(&schema, &data),
+            json!({ "count": 5, "ratio": 1.5, "verbose": true })
+        );
+    }
+
+    #[test]
+    fn splits_comma_lists_and_coerces_items() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "ids": { "type": "array", "items": { "type": "integer" } }
+            }
+        });
+        assert_eq!(
+            coerce_inputs(&schema, &json!({ "ids": "1, 2,3" })),
+            json!({ "ids": [1, 2, 3] })
+        );
+    }
+
+    #[test]
+    fn unparsable_values_pass_through() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "count": { "type": "integer" } }
+        });
+        let data = json!({ "count": "not a number" });
+        assert_eq!(coerce_inputs(&schema, &data), data);
+    }
+
+    #[test]
+    fn nullable_types_try_each_candidate() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "limit": { "type": ["integer", "null"] } }
+        });
+        assert_eq!(
+            coerce_inputs(&schema, &json!({ "limit": "" })),
+            json!({ "limit": null })
+        );
+        assert_eq!(
+            coerce_inputs(&schema, &json!({ "limit": "7" })),
+            json!({ "limit": 7 })
+        );
+    }
+
+    #[test]
+    fn leaves_typed_values_and_undeclared_keys_alone() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "count": { "type": "integer" } }
+        });
+        let data = json!({ "count": 5, "extra": "true" });
+        assert_eq!(coerce_inputs(&schema, &data), data);
+    }
+
+    #[test]
+    fn recurses_into_nested_objects() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "opts": {
+                    "type": "object",
+                    "properties": { "depth": { "type": "integer" } }
+                }
+            }
+        });
+        assert_eq!(
+            coerce_inputs(&schema, &json!({ "opts": { "depth": "3" } })),
+            json!({ "opts": { "depth": 3 } })
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Compatibility classification between two versions of a prompt.
+//!
+//! Deployment tooling diffs the old and new definition before rollout:
+//! breaking changes (a new required input, a removed output field, a type
+//! change) should block or force a major version bump, while additive ones
+//! can ship silently.
+
+use serde_json::{Map, Value};
+
+use crate::definition::PromptDefinition;
+
+/// How a single change affects existing callers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Existing callers keep working.
+    Compatible,
+    /// Existing callers (or consumers of the output) can break.
+    Breaking,
+}
+
+/// One classified difference between two definitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A new input property. Breaking when required.
+    InputAdded { path: String, required: bool },
+    /// An input property was removed.
+    InputRemoved { path: String },
+    /// An optional input became required.
+    InputNowRequired { path: String },
+    /// A required input became optional.
+    InputNowOptional { path: String },
+    /// An input property's declared `type` changed.
+    InputTypeChanged { path: String, from: String, to: String },
+    /// An input enum lost values; callers sending them now fail validation.
+    InputEnumNarrowed { path: String, removed: Vec<String> },
+    /// An input enum gained values; existing callers are unaffected.
+    InputEnumWidened { path: String, added: Vec<String> },
+    /// A new output property appeared.
+    OutputAdded { path: String },
+    /// An output property was removed; consumers reading it break.
+    OutputRemoved { path: String },
+    /// An output property's declared `type` changed.
+    OutputTypeChanged { path: String, from: String, to: String },
+    /// An output enum gained values consumers may not handle.
+    OutputEnumWidened { path: String, added: Vec<String> },
+    /// An output enum lost values; consumers handling them stay correct.
+    OutputEnumNarrowed { path: String, removed: Vec<String> },
+    /// The target client/model changed.
+    ClientChanged { from: String, to: String },
+}
+
+impl Change {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Change::InputAdded { required: true, .. }
+            | Change::InputNowRequired { .. }
+            | Change::InputTypeChanged { .. }
+            | Change::InputEnumNarrowed { .. }
+            | Change::OutputRemoved { .. }
+            | Change::OutputTypeChanged { .. }
+            | Change::OutputEnumWidened { .. } => Severity::Breaking,
+            _ => Severity::Compatible,
+        }
+    }
+}
+
+/// The full classification produced by [`check_compatibility`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    pub changes: Vec<Change>,
+}
+
+impl CompatibilityReport {
+    /// True when any change would break existing callers.
+    pub fn is_breaking(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|c| c.severity() == Severity::Breaking)
+    }
+
+    pub fn breaking(&self) -> impl Iterator<Item = &Change> {
+        self.changes
+            .iter()
+            .filter(|c| c.severity() == Severity::Breaking)
+    }
+}
+
+/// Classify the differences between two versions of a prompt definition.
+pub fn check_compatibility(old: &PromptDefinition, new: &PromptDefinition) -> CompatibilityReport {
+    let mut report = CompatibilityReport::default();
+
+    if old.client != new.client {
+        report.changes.push(Change::ClientChanged {
+            from: old.client.clone().unwrap_or_default(),
+            to: new.client.clone().unwrap_or_default(),
+        });
+    }
+
+    diff_inputs(
+        old.inputs.as_ref(),
+        new.inputs.as_ref(),
+        "",
+        &mut report.changes,
+    );
+    diff_outputs(
+        old.output.as_ref(),
+        new.output.as_ref(),
+        "",
+        &mut report.changes,
+    );
+    report
+}
+
+/// Classify changes between two `inputs` schemas directly, for CI gates that
+/// compare schemas without whole definitions.
+pub fn check_input_compatibility(old: &Value, new: &Value) -> CompatibilityReport {
+    let mut report = CompatibilityReport::default();
+    diff_inputs(Some(old), Some(new), "", &mut report.changes);
+    report
+}
+
+/// [`check_input_compatibility`], for `output` schemas (where widening an
+/// enum — not narrowing it — is the breaking direction).
+pub fn check_output_compatibility(old: &Value, new: &Value) -> CompatibilityReport {
+    let mut report = CompatibilityReport::default();
+    diff_outputs(Some(old), Some(new), "", &mut report.changes);
+    report
+}
+
+fn properties(schema: Option<&Value>) -> Map<String, Value> {
+    schema
+        .and_then(|s| s.get("properties"))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn required(schema: Option<&Value>) -> Vec<String> {
+    schema
+        .and_then(|s| s.get("required"))
+        .and_then(Value::as_array)
+        .map(|a| {
+            a.iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn type_of(schema: &Value) -> String {
+    match schema.get("type") {
+        Some(Value::String(t)) => t.clone(),
+        Some(other) => other.to_string(),
+        None => "any".to_string(),
+    }
+}
+
+/// `enum` values present in `a` but not `b`, rendered as JSON literals.
+fn enum_difference(a: &Value, b: &Value) -> Vec<String> {
+    let values = |schema: &Value| -> Vec<Value> {
+        schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let other = values(b);
+    values(a)
+        .into_iter()
+        .filter(|v| !other.contains(v))
+        .map(|v| serde_json::to_string(&v).unwrap_or_default())
+        .collect()
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}.{name}")
+    }
+}
+
+fn diff_inputs(old: Option<&Value>, new: Option<&Value>, prefix: &str, out: &mut Vec<Change>) {
+    let old_props = properties(old);
+    let new_props = properties(new);
+    let old_required = required(old);
+    let new_required = required(new);
+
+    for (name, new_schema) in &new_props {
+        let path = join(prefix, name);
+        match old_props.get(name) {
+            None => out.push(Change::InputAdded {
+                path,
+                required: new_required.contains(name),
+            }),
+            Some(old_schema) => {
+                let (from, to) = (type_of(old_schema), type_of(new_schema));
+                if from != to {
+                    out.push(Change::InputTypeChanged { path, from, to });
+                } else {
+                    match (old_required.contains(name), new_required.contains(name)) {
+                        (false, true) => out.push(Change::InputNowRequired { path: path.clone() }),
+                        (true, false) => out.push(Change::InputNowOptional { path: path.clone() }),
+                        _ => {}
+                    }
+                    let removed = enum_difference(old_schema, new_schema);
+                    if !removed.is_empty() {
+                        out.push(Change::InputEnumNarrowed {
+                            path: path.clone(),
+                            removed,
+                        });
+                    }
+                    let added = enum_difference(new_schema, old_schema);
+                    if !added.is_empty() {
+                        out.push(Change::InputEnumWidened {
+                            path: path.clone(),
+                            added,
+                        });
+                    }
+                    diff_inputs(Some(old_schema), Some(new_schema), &path, out);
+                }
+            }
+        }
+    }
+    for name in old_props.keys() {
+        if !new_props.contains_key(name) {
+            out.push(Change::InputRemoved {
+                path: join(prefix, name),
+            });
+        }
+    }
+}
+
+fn diff_outputs(old: Option<&Value>, new: Option<&Value>, prefix: &str, out: &mut Vec<Change>) {
+    let old_props = properties(old);
+    let new_props = properties(new);
+
+    for (name, new_schema) in &new_props {
+        let path = join(prefix, name);
+        match old_props.get(name) {
+            None => out.push(Change::OutputAdded { path }),
+            Some(old_schema) => {
+                let (from, to) = (type_of(old_schema), type_of(new_schema));
+                if from != to {
+                    out.push(Change::OutputTypeChanged { path, from, to });
+                } else {
+                    let added = enum_difference(new_schema, old_schema);
+                    if !added.is_empty() {
+                        out.push(Change::OutputEnumWidened {
+                            path: path.clone(),
+                            added,
+                        });
+                    }
+                    let removed = enum_difference(old_schema, new_schema);
+                    if !removed.is_empty() {
+                        out.push(Change::OutputEnumNarrowed {
+                            path: path.clone(),
+                            removed,
+                        });
+                    }
+                    diff_outputs(Some(old_schema), Some(new_schema), &path, out);
+                }
+            }
+        }
+    }
+    for name in old_props.keys() {
+        if !new_props.contains_key(name) {
+            out.push(Change::OutputRemoved {
+                path: join(prefix, name),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn def(frontmatter: &str) -> PromptDefinition {
+        parse(&format!("---\nname: x\n{frontmatter}\n---\nbody")).unwrap()
+    }
+
+    #[test]
+    fn new_required_input_is_breaking() {
+        let old = def("inputs:\n  type: object\n  properties:\n    a: { type: string }");
+        let new = def(
+            "inputs:\n  type: object\n  properties:\n    a: { type: string }\n    b: { type: string }\n  required: [b]",
+        );
+        let report = check_compatibility(&old, &new);
+        assert!(report.is_breaking());
+        assert_eq!(
+            report.changes,
+            vec![Change::InputAdded {
+                path: "b".into(),
+                required: true
+            }]
+        );
+    }
+
+    #[test]
+    fn optional_input_addition_is_compatible() {
+        let old = def("inputs:\n  type: object\n  properties:\n    a: { type: string }");
+        let new = def(
+            "inputs:\n  type: object\n  properties:\n    a: { type: string }\n    b: { type: string }",
+        );
+        assert!(!check_compatibility(&old, &new).is_breaking());
+    }
+
+    #[test]
+    fn removed_output_and_type_changes_are_breaking() {
+        let old = def(
+            "inputs:\n  type: object\n  properties:\n    a: { type: string }\noutput:\n  type: object\n  properties:\n    summary: { type: string }",
+        );
+        let new = def(
+            "inputs:\n  type: object\n  properties:\n    a: { type: integer }\noutput:\n  type: object\n  properties: {}",
+        );
+        let report = check_compatibility(&old, &new);
+        let breaking: Vec<_> = report.breaking().collect();
+        assert_eq!(breaking.len(), 2, "{report:?}");
+        assert!(matches!(breaking[0], Change::InputTypeChanged { .. }));
+        assert!(matches!(breaking[1], Change::OutputRemoved { .. }));
+    }
+
+    #[test]
+    fn nested_properties_are_diffed() {
+        let old = def(
+            "inputs:\n  type: object\n  properties:\n    opts:\n      type: object\n      properties:\n        depth: { type: integer }",
+        );
+        let new = def(
+            "inputs:\n  type: object\n  properties:\n    opts:\n      type: object\n      properties:\n        depth: { type: string }",
+        );
+        let report = check_compatibility(&old, &new);
+        assert_eq!(
+            report.changes,
+            vec![Change::InputTypeChanged {
+                path: "opts.depth".into(),
+                from: "integer".into(),
+                to: "string".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn enum_narrowing_direction_depends_on_the_schema_role() {
+        use serde_json::json;
+
+        let wide = json!({
+            "type": "object",
+            "properties": { "style": { "enum": ["terse", "detailed", "bullet"] } }
+        });
+        let narrow = json!({
+            "type": "object",
+            "properties": { "style": { "enum": ["terse", "detailed"] } }
+        });
+
+        // Inputs: losing values breaks callers, gaining them does not.
+        let report = check_input_compatibility(&wide, &narrow);
+        assert!(report.is_breaking());
+        assert_eq!(
+            report.changes,
+            vec![Change::InputEnumNarrowed {
+                path: "style".into(),
+                removed: vec!["\"bullet\"".into()]
+            }]
+        );
+        assert!(!check_input_compatibility(&narrow, &wide).is_breaking());
+
+        // Outputs: gaining values breaks consumers, losing them does not.
+        let report = check_output_compatibility(&narrow, &wide);
+        assert!(report.is_breaking());
+        assert!(matches!(
+            report.changes[0],
+            Change::OutputEnumWidened { .. }
+        ));
+        assert!(!check_output_compatibility(&wide, &narrow).is_breaking());
+    }
+
+    #[test]
+    fn version_field_round_trips() {
+        let d = def("version: 1.2.0");
+        assert_eq!(d.version.as_deref(), Some("1.2.0"));
+    }
+}
@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::PromptError;
+use crate::schema;
+use crate::template;
+
+/// A parsed prompt file: frontmatter contract + body template.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromptDefinition {
+    /// Unique prompt name (registry key).
+    pub name: String,
+    /// Path to a parent prompt file this one builds on, resolved relative to
+    /// this file and merged by [`crate::parse_file`]; cleared after merging.
+    /// Child fields win, `inputs`/`output` deep-merge, `tools` union (with
+    /// `-tool` removals), and `{{ super() }}` in the body splices the
+    /// parent's body in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Prompt version, e.g. `1.2.0`. Compared by deployment tooling together
+    /// with [`crate::check_compatibility`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Human-readable summary shown in pickers and docs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Prompt kind: `llm` (the default), `agent`, or `workflow`.
+    /// Agents must declare `output` plus `tools` or `max_turns`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub prompt_type: Option<String>,
+    /// Target client/model identifier, e.g. `anthropic/claude-sonnet-4`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    /// Sampling temperature, `0.0..=2.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Nucleus sampling cutoff, `0.0..=1.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    /// Generation cap in tokens, at least 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Agent loop cap in turns, at least 1. Only meaningful for
+    /// `type: agent`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    /// Stop sequences; each must be non-empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    /// Size caps on the rendered prompt; see [`crate::Budget`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<crate::budget::Budget>,
+    /// JSON Schema describing the template inputs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Value>,
+    /// JSON Schema describing the expected structured output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    /// How strictly the `output` schema binds: in `json` mode (the default)
+    /// [`Self::validate_output`] enforces it, in `text`/`markdown` modes it
+    /// is advisory — documentation for the model, never an error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_mode: Option<OutputMode>,
+    /// Optional system message template, rendered with the same inputs as
+    /// the body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// Few-shot examples emitted as user/assistant message pairs before the
+    /// rendered body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<Example>>,
+    /// Tools available to this prompt; templates may branch on
+    /// `tools.<name>`. See [`crate::Tool`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<crate::tools::Tool>>,
+    /// Embedded golden test cases, executed with [`Self::run_tests`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tests: Option<Vec<crate::golden::TestCase>>,
+    /// Whitespace rewrites applied to the body/system/locale templates at
+    /// parse time. See [`crate::WhitespaceControl`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub whitespace: Option<crate::whitespace::WhitespaceControl>,
+    /// Locale-tagged body overrides, e.g. `de` or `de-CH`; see
+    /// [`Self::render_locale`] for the fallback chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locales: Option<std::collections::BTreeMap<String, String>>,
+    /// The Markdown body template.
+    #[serde(default)]
+    pub body: String,
+}
+
+/// How a prompt's `output` schema is enforced at runtime. Declared as
+/// `output_mode:` in frontmatter; see [`PromptDefinition::output_mode`].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// The response must parse as JSON and validate against `output`.
+    #[default]
+    Json,
+    /// Free-form text; `output` is advisory.
+    Text,
+    /// Free-form Markdown; `output` is advisory.
+    Markdown,
+}
+
+/// A static few-shot example from frontmatter.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Example {
+    /// Example user turn. Non-string values are serialized as JSON.
+    pub user: Value,
+    /// Example assistant turn. Non-string values are serialized as JSON,
+    /// which is how structured-output examples are written.
+    pub assistant: Value,
+}
+
+/// One chat message ready to send to a provider.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+    /// Media content blocks attached to this turn; populated on the final
+    /// user message from `image`/`file` typed inputs. Empty for text-only
+    /// prompts. See [`crate::Attachment`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<crate::media::Attachment>,
+}
+
+impl Message {
+    fn new(role: &str, content: impl Into<String>) -> Self {
+        Message {
+            role: role.to_string(),
+            content: content.into(),
+            attachments: Vec::new(),
+        }
+    }
+}
+
+fn value_as_content(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl PromptDefinition {
+    /// Parse a prompt file. Equivalent to [`crate::parse`].
+    pub fn parse(source: &str) -> Result<Self, PromptError> {
+        crate::parser::parse(source)
+    }
+
+    /// Validate `data` against the `inputs` schema, then render the body.
+    ///
+    /// Prompts without an `inputs` schema skip validation and render directly.
+    pub fn render(&self, data: &Value) -> Result<String, PromptError> {
+        if let Some(inputs) = &self.inputs {
+            crate::media::validate_inputs(inputs, data)?;
+        }
+        let ctx = crate::tools::context_with_tools(self, data);
+        template::render_template(&self.body, &ctx)
+    }
+
+    /// Render the prompt as the chat-message array a provider consumes:
+    /// the rendered `system` template (if any), the `examples` as
+    /// user/assistant pairs, then the rendered body as the final user turn.
+    ///
+    /// Inputs are validated against the `inputs` schema exactly as in
+    /// [`Self::render`].
+    pub fn render_messages(&self, data: &Value) -> Result<Vec<Message>, PromptError> {
+        if let Some(inputs) = &self.inputs {
+            crate::media::validate_inputs(inputs, data)?;
+        }
+        let ctx = crate::tools::context_with_tools(self, data);
+        let mut messages = Vec::new();
+        if let Some(system) = &self.system {
+            messages.push(Message::new(
+                "system",
+                template::render_template(system, &ctx)?,
+            ));
+        }
+        for example in self.examples.as_deref().unwrap_or_default() {
+            messages.push(Message::new("user", value_as_content(&example.user)));
+            messages.push(Message::new(
+                "assistant",
+                value_as_content(&example.assistant),
+            ));
+        }
+        let mut user = Message::new("user", template::render_template(&self.body, &ctx)?);
+        user.attachments = crate::media::attachments(self, data);
+        messages.push(user);
+        Ok(messages)
+    }
+
+    /// [`Self::render`], delivered incrementally: each chunk (text run or
+    /// substituted value) goes to `on_chunk` as it is produced, so large
+    /// prompts can be counted or transmitted before rendering finishes.
+    pub fn render_stream(
+        &self,
+        data: &Value,
+        on_chunk: impl FnMut(&str),
+    ) -> Result<(), PromptError> {
+        if let Some(inputs) = &self.inputs {
+            crate::media::validate_inputs(inputs, data)?;
+        }
+        let ctx = crate::tools::context_with_tools(self, data);
+        template::render_template_stream(&self.body, &ctx, on_chunk)
+    }
+
+    /// Coerce stringly-typed inputs toward the `inputs` schema.
+    ///
+    /// See [`crate::coerce_inputs`]. Identity when no schema is declared.
+    pub fn coerce_inputs(&self, data: &Value) -> Value {
+        match &self.inputs {
+            Some(inputs) => crate::coerce::coerce_inputs(inputs, data),
+            None => data.clone(),
+        }
+    }
+
+    /// The declared `output_mode`, defaulting to [`OutputMode::Json`], so
+    /// every runtime resolves the default the same way.
+    pub fn effective_output_mode(&self) -> OutputMode {
+        self.output_mode.unwrap_or_default()
+    }
+
+    /// Validate a model response against the `output` schema, if declared.
+    ///
+    /// In `text`/`markdown` output modes the schema is advisory and this
+    /// always succeeds.
+    pub fn validate_output(&self, data: &Value) -> Result<(), PromptError> {
+        if self.effective_output_mode() != OutputMode::Json {
+            return Ok(());
+        }
+        match &self.output {
+            Some(output) => schema::validate_json(output, data),
+            None => Ok(()),
+        }
+    }
+
+    /// Tolerantly recover structured output from raw model text and validate
+    /// it against the `output` schema. See [`crate::extract_output`].
+    pub fn extract_output(&self, raw: &str) -> Result<Value, crate::ExtractError> {
+        let empty = Value::Object(serde_json::Map::new());
+        crate::extract::extract_output(self.output.as_ref().unwrap_or(&empty), raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SOURCE: &str = r#"---
+name: greet
+description: Greet someone
+inputs:
+  type: object
+  properties:
+    who: { type: string }
+  required: [who]
+output:
+  type: object
+  properties:
+    greeting: { type: string }
+  required: [greeting]
+---
+Hello {{ who }}!"#;
+
+    #[test]
+    fn render_validates_then_substitutes() {
+        let def = PromptDefinition::parse(SOURCE).unwrap();
+        assert_eq!(def.render(&json!({ "who": "world" })).unwrap(), "Hello world!");
+        assert!(matches!(
+            def.render(&json!({})).unwrap_err(),
+            PromptError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn render_messages_orders_system_examples_body() {
+        let def = PromptDefinition::parse(
+            r#"---
+name: greet
+system: "You greet people in {{ lang }}."
+examples:
+  - user: Bob
+    assistant: Hello Bob!
+  - user: { name: Eve }
+    assistant: { greeting: Hello Eve! }
+---
+Greet {{ who }}."#,
+        )
+        .unwrap();
+        let messages = def
+            .render_messages(&json!({ "lang": "English", "who": "world" }))
+            .unwrap();
+        let pairs: Vec<(&str, &str)> = messages
+            .iter()
+            .map(|m| (m.role.as_str(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("system", "You greet people in English."),
+                ("user", "Bob"),
+                ("assistant", "Hello Bob!"),
+                ("user", r#"{"name":"Eve"}"#),
+                ("assistant", r#"{"greeting":"Hello Eve!"}"#),
+                ("user", "Greet world."),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_output_uses_declared_schema() {
+        let def = PromptDefinition::parse(SOURCE).unwrap();
+        assert_eq!(def.effective_output_mode(), OutputMode::Json);
+        assert!(def.validate_output(&json!({ "greeting": "hi" })).is_ok());
+        assert!(def.validate_output(&json!({})).is_err());
+    }
+
+    #[test]
+    fn non_json_output_modes_make_the_schema_advisory() {
+        let def = PromptDefinition::parse(
+            "---\nname: x\noutput_mode: markdown\noutput:\n  type: object\n  required: [summary]\n---\nbody",
+        )
+        .unwrap();
+        assert_eq!(def.effective_output_mode(), OutputMode::Markdown);
+        assert!(def.validate_output(&json!({})).is_ok());
+
+        let err = PromptDefinition::parse("---\nname: x\noutput_mode: xml\n---\nbody").unwrap_err();
+        assert!(matches!(err, PromptError::Frontmatter(_)), "{err}");
+    }
+}
@@ -0,0 +1,212 @@
+//! Semantic diff between two prompt definitions.
+//!
+//! Change-review tooling and audit logs want structure, not a textual
+//! frontmatter diff: which inputs appeared, which frontmatter fields moved,
+//! which tools came and went, which body lines changed. Schema changes reuse
+//! the classification from [`crate::check_compatibility`], so severity comes
+//! for free.
+
+use serde_json::Value;
+
+use crate::compat::{self, CompatibilityReport};
+use crate::definition::PromptDefinition;
+
+/// A frontmatter field whose value differs between the two versions.
+/// `None` means the field is absent on that side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// One line-level body edit, in body order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineChange {
+    Added(String),
+    Removed(String),
+}
+
+/// The structured difference produced by [`diff_definitions`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DefinitionDiff {
+    /// Classified `inputs`/`output`/client changes, with severity.
+    pub schemas: CompatibilityReport,
+    /// Other frontmatter fields that changed (version, temperature, ...).
+    pub fields: Vec<FieldChange>,
+    pub tools_added: Vec<String>,
+    pub tools_removed: Vec<String>,
+    /// Line-level body edits (LCS diff; unchanged lines are omitted).
+    pub body: Vec<LineChange>,
+}
+
+impl DefinitionDiff {
+    /// True when the two definitions are semantically identical.
+    pub fn is_empty(&self) -> bool {
+        self.schemas.changes.is_empty()
+            && self.fields.is_empty()
+            && self.tools_added.is_empty()
+            && self.tools_removed.is_empty()
+            && self.body.is_empty()
+    }
+}
+
+/// Keys covered by the schema/tool/body sections rather than generic field
+/// comparison.
+const STRUCTURAL_KEYS: &[&str] = &["body", "inputs", "output", "tools", "client"];
+
+/// Compute the structural differences between two versions of a prompt.
+pub fn diff_definitions(old: &PromptDefinition, new: &PromptDefinition) -> DefinitionDiff {
+    let mut diff = DefinitionDiff {
+        schemas: compat::check_compatibility(old, new),
+        ..Default::default()
+    };
+
+    // Generic field comparison over the serialized frontmatter, so new
+    // definition fields participate without touching this module.
+    let old_fields = as_map(old);
+    let new_fields = as_map(new);
+    let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    for key in keys {
+        if STRUCTURAL_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let (old_value, new_value) = (old_fields.get(key), new_fields.get(key));
+        if old_value != new_value {
+            diff.fields.push(FieldChange {
+                field: key.clone(),
+                old: old_value.cloned(),
+                new: new_value.cloned(),
+            });
+        }
+    }
+
+    let old_tools = tool_names(old);
+    let new_tools = tool_names(new);
+    diff.tools_added = new_tools
+        .iter()
+        .filter(|t| !old_tools.contains(t))
+        .cloned()
+        .collect();
+    diff.tools_removed = old_tools
+        .into_iter()
+        .filter(|t| !new_tools.contains(t))
+        .collect();
+
+    diff.body = diff_lines(&old.body, &new.body);
+    diff
+}
+
+fn as_map(def: &PromptDefinition) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(def) {
+        Ok(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
+fn tool_names(def: &PromptDefinition) -> Vec<String> {
+    def.tools
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .map(|t| t.name.clone())
+        .collect()
+}
+
+/// Minimal LCS line diff: added/removed lines in order, context omitted.
+/// Prompt bodies are small, so the quadratic table is fine.
+fn diff_lines(old: &str, new: &str) -> Vec<LineChange> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let (n, m) = (old_lines.len(), new_lines.len());
+
+    // lcs[i][j] = LCS length of old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_lines[i] == new_lines[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            edits.push(LineChange::Removed(old_lines[i].to_string()));
+            i += 1;
+        } else {
+            edits.push(LineChange::Added(new_lines[j].to_string()));
+            j += 1;
+        }
+    }
+    edits.extend(old_lines[i..].iter().map(|l| LineChange::Removed(l.to_string())));
+    edits.extend(new_lines[j..].iter().map(|l| LineChange::Added(l.to_string())));
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn identical_definitions_diff_empty() {
+        let def = parse("---\nname: x\nversion: 1.0.0\n---\nbody").unwrap();
+        assert!(diff_definitions(&def, &def).is_empty());
+    }
+
+    #[test]
+    fn captures_field_tool_and_body_changes() {
+        let old = parse(
+            "---\nname: x\nversion: 1.0.0\ntemperature: 0.2\ntools: [web_search]\n---\nline one\nline two",
+        )
+        .unwrap();
+        let new = parse(
+            "---\nname: x\nversion: 1.1.0\ntools: [code_exec]\n---\nline one\nline 2",
+        )
+        .unwrap();
+        let diff = diff_definitions(&old, &new);
+
+        assert_eq!(diff.tools_added, vec!["code_exec"]);
+        assert_eq!(diff.tools_removed, vec!["web_search"]);
+        assert_eq!(
+            diff.body,
+            vec![
+                LineChange::Removed("line two".into()),
+                LineChange::Added("line 2".into()),
+            ]
+        );
+
+        let fields: Vec<&str> = diff.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, vec!["temperature", "version"]);
+        let version = diff.fields.iter().find(|f| f.field == "version").unwrap();
+        assert_eq!(version.old, Some(serde_json::json!("1.0.0")));
+        assert_eq!(version.new, Some(serde_json::json!("1.1.0")));
+        let temperature = diff.fields.iter().find(|f| f.field == "temperature").unwrap();
+        assert_eq!(temperature.new, None);
+    }
+
+    #[test]
+    fn schema_changes_carry_compat_severity() {
+        let old = parse(
+            "---\nname: x\ninputs:\n  type: object\n  properties:\n    a: { type: string }\n---\nbody",
+        )
+        .unwrap();
+        let new = parse(
+            "---\nname: x\ninputs:\n  type: object\n  properties:\n    a: { type: integer }\n---\nbody",
+        )
+        .unwrap();
+        let diff = diff_definitions(&old, &new);
+        assert!(diff.schemas.is_breaking());
+        assert!(!diff.is_empty());
+    }
+}
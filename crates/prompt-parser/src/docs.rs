@@ -0,0 +1,234 @@
+//! Markdown documentation generated from a parsed definition.
+//!
+//! Docs pages and pickers render this instead of hand-written summaries, so
+//! documentation can never drift from the prompt file it describes.
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+
+impl PromptDefinition {
+    /// A markdown summary of this prompt: title, description, model
+    /// parameters, an inputs table (dotted paths, types, required/default,
+    /// descriptions), the output shape, and declared tools. Sections without
+    /// content are omitted.
+    pub fn render_docs(&self) -> String {
+        let mut out = format!("# {}\n", self.name);
+        if let Some(version) = &self.version {
+            out.push_str(&format!("\n*Version {version}*\n"));
+        }
+        if let Some(description) = &self.description {
+            out.push_str(&format!("\n{description}\n"));
+        }
+
+        let mut facts = Vec::new();
+        if let Some(client) = &self.client {
+            facts.push(format!("**Client:** `{client}`"));
+        }
+        if let Some(t) = self.temperature {
+            facts.push(format!("**Temperature:** {t}"));
+        }
+        if let Some(max) = self.max_tokens {
+            facts.push(format!("**Max tokens:** {max}"));
+        }
+        if !facts.is_empty() {
+            out.push_str(&format!("\n{}\n", facts.join(" · ")));
+        }
+
+        if let Some(inputs) = &self.inputs {
+            let rows = field_rows(inputs);
+            if !rows.is_empty() {
+                out.push_str("\n## Inputs\n\n");
+                out.push_str("| Name | Type | Required | Default | Description |\n");
+                out.push_str("| --- | --- | --- | --- | --- |\n");
+                for row in rows {
+                    out.push_str(&row.to_markdown(true));
+                }
+            }
+        }
+
+        if let Some(output) = &self.output {
+            out.push_str("\n## Output\n\n");
+            let rows = field_rows(output);
+            if rows.is_empty() {
+                out.push_str(&format!("`{}`\n", type_label(output)));
+            } else {
+                out.push_str("| Name | Type | Description |\n");
+                out.push_str("| --- | --- | --- |\n");
+                for row in rows {
+                    out.push_str(&row.to_markdown(false));
+                }
+            }
+        }
+
+        if let Some(tools) = &self.tools {
+            out.push_str("\n## Tools\n\n");
+            for tool in tools {
+                match &tool.description {
+                    Some(description) => {
+                        out.push_str(&format!("- `{}` — {description}\n", tool.name));
+                    }
+                    None => out.push_str(&format!("- `{}`\n", tool.name)),
+                }
+            }
+        }
+        out
+    }
+}
+
+struct FieldRow {
+    path: String,
+    type_label: String,
+    required: bool,
+    default: Option<String>,
+    description: String,
+}
+
+impl FieldRow {
+    fn to_markdown(&self, with_required: bool) -> String {
+        let cells = if with_required {
+            vec![
+                format!("`{}`", self.path),
+                self.type_label.clone(),
+                if self.required { "yes" } else { "no" }.to_string(),
+                self.default.clone().unwrap_or_else(|| "—".to_string()),
+                self.description.clone(),
+            ]
+        } else {
+            vec![
+                format!("`{}`", self.path),
+                self.type_label.clone(),
+                self.description.clone(),
+            ]
+        };
+        format!("| {} |\n", cells.join(" | "))
+    }
+}
+
+/// Flatten an object schema's properties into dotted-path rows.
+fn field_rows(schema: &Value) -> Vec<FieldRow> {
+    let mut rows = Vec::new();
+    collect_rows(schema, "", &mut rows);
+    rows
+}
+
+fn collect_rows(schema: &Value, prefix: &str, rows: &mut Vec<FieldRow>) {
+    let Some(props) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    for (name, prop) in props {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}.{name}")
+        };
+        rows.push(FieldRow {
+            type_label: type_label(prop),
+            required: required.contains(&name.as_str()),
+            default: prop.get("default").map(|d| format!("`{d}`")),
+            description: prop
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                // Keep the table intact if someone writes a multi-line description.
+                .replace('\n', " "),
+            path: path.clone(),
+        });
+        collect_rows(prop, &path, rows);
+    }
+}
+
+/// Short type rendering for a table cell: `string`, `string[]`,
+/// `"a" \| "b"`, `object`.
+fn type_label(schema: &Value) -> String {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return values
+            .iter()
+            .map(|v| format!("`{v}`"))
+            .collect::<Vec<_>>()
+            .join(" \\| ");
+    }
+    match schema.get("type") {
+        Some(Value::String(t)) if t == "array" => {
+            let item = schema
+                .get("items")
+                .map(type_label)
+                .unwrap_or_else(|| "any".to_string());
+            format!("{item}[]")
+        }
+        Some(Value::String(t)) => t.clone(),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" \\| "),
+        _ => "any".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    const SOURCE: &str = r#"---
+name: summarize
+version: 1.0.0
+description: Summarize a document.
+client: anthropic/claude-sonnet-4
+max_tokens: 512
+tools:
+  - name: web_search
+    description: Look things up
+inputs:
+  type: object
+  properties:
+    document:
+      type: string
+      description: The text to summarize.
+    opts:
+      type: object
+      properties:
+        style:
+          enum: [terse, detailed]
+          default: terse
+  required: [document]
+output:
+  type: object
+  properties:
+    summary: { type: string }
+---
+{{ document }}"#;
+
+    #[test]
+    fn renders_all_sections() {
+        let docs = parse(SOURCE).unwrap().render_docs();
+        assert!(docs.starts_with("# summarize\n"), "{docs}");
+        assert!(docs.contains("*Version 1.0.0*"), "{docs}");
+        assert!(docs.contains("Summarize a document."), "{docs}");
+        assert!(docs.contains("**Client:** `anthropic/claude-sonnet-4` · **Max tokens:** 512"), "{docs}");
+        assert!(docs.contains("| `document` | string | yes | — | The text to summarize. |"), "{docs}");
+        assert!(docs.contains("| `opts.style` | `\"terse\"` \\| `\"detailed\"` | no | `\"terse\"` |"), "{docs}");
+        assert!(docs.contains("## Output"), "{docs}");
+        assert!(docs.contains("| `summary` | string |  |"), "{docs}");
+        assert!(docs.contains("- `web_search` — Look things up"), "{docs}");
+    }
+
+    #[test]
+    fn sections_without_content_are_omitted() {
+        let docs = parse("---\nname: bare\n---\nbody").unwrap().render_docs();
+        assert_eq!(docs, "# bare\n");
+    }
+
+    #[test]
+    fn scalar_output_schema_shows_its_type() {
+        let docs = parse("---\nname: x\noutput:\n  type: string\n---\nbody")
+            .unwrap()
+            .render_docs();
+        assert!(docs.contains("## Output\n\n`string`\n"), "{docs}");
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+
+use thiserror::Error;
+
+/// Which render sandbox limit was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderLimitKind {
+    /// Total `#each` iterations across the whole render.
+    LoopIterations,
+    /// Nesting depth of `#if`/`#each` blocks.
+    RecursionDepth,
+    /// Rendered output size in bytes.
+    OutputBytes,
+}
+
+impl fmt::Display for RenderLimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RenderLimitKind::LoopIterations => "loop iterations",
+            RenderLimitKind::RecursionDepth => "recursion depth",
+            RenderLimitKind::OutputBytes => "output bytes",
+        })
+    }
+}
+
+/// Errors surfaced by parsing, validation, and rendering.
+#[derive(Debug, Error)]
+pub enum PromptError {
+    /// The file does not start with a `---` frontmatter fence.
+    #[error("prompt file is missing YAML frontmatter (expected leading `---`)")]
+    MissingFrontmatter,
+
+    /// The frontmatter fence was opened but never closed.
+    #[error("unterminated frontmatter (no closing `---`)")]
+    UnterminatedFrontmatter,
+
+    /// The frontmatter is not valid YAML or is missing required keys.
+    #[error("invalid frontmatter: {0}")]
+    Frontmatter(String),
+
+    /// The `client` field is malformed, an unknown alias, or names an
+    /// unregistered provider.
+    #[error("invalid client `{client}`: {message}")]
+    Client { client: String, message: String },
+
+    /// A declared `inputs`/`output` schema is not a valid JSON Schema.
+    #[error("invalid schema for `{field}`: {message}")]
+    Schema { field: String, message: String },
+
+    /// A prompt or referenced schema file could not be read.
+    #[error("cannot read `{path}`: {message}")]
+    Io { path: String, message: String },
+
+    /// Data failed validation against a schema. Each violation carries its
+    /// instance/schema paths and keyword; `Display` joins them with `; `.
+    #[error("validation failed: {}", crate::schema::format_violations(.0))]
+    Validation(Vec<crate::schema::ValidationViolation>),
+
+    /// The body template is malformed (unclosed block, bad expression, ...).
+    #[error("template error: {0}")]
+    Template(String),
+
+    /// Rendering hit a sandbox limit (see `RenderLimits`).
+    #[error("render limit exceeded: {kind} (limit {limit})")]
+    LimitExceeded { kind: RenderLimitKind, limit: usize },
+
+    /// A template expression referenced a variable absent from the render data.
+    #[error("unknown template variable `{0}`")]
+    UnknownVariable(String),
+
+    /// A registry render (or `{{> name}}` reference) named a prompt that is
+    /// not registered.
+    #[error("unknown prompt `{0}`")]
+    UnknownPrompt(String),
+
+    /// `extends` resolution revisited a file already on the chain.
+    #[error("extends cycle: {}", .chain.join(" -> "))]
+    ExtendsCycle { chain: Vec<String> },
+
+    /// The rendered prompt exceeded a declared `budget:` cap
+    /// (see `PromptDefinition::enforce_budget`).
+    #[error("budget exceeded: {used} {kind} over a limit of {limit}")]
+    BudgetExceeded {
+        kind: crate::budget::BudgetKind,
+        used: usize,
+        limit: usize,
+    },
+
+    /// A declared tool the caller-supplied `ToolRegistry` does not provide.
+    #[error("unknown tool `{tool}`; registry provides: {}", .available.join(", "))]
+    UnknownTool { tool: String, available: Vec<String> },
+}
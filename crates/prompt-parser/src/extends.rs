@@ -0,0 +1,341 @@
+//! Prompt inheritance via `extends`.
+//!
+//! A prompt may build on another file:
+//!
+//! ```yaml
+//! extends: ./base.prompt.md
+//! ```
+//!
+//! [`crate::parse_file`] loads the parent (itself fully parsed, so chains
+//! work) and merges it under the child with explicit rules — inheritance is
+//! only useful when it is predictable:
+//!
+//! - **Scalars** (`client`, `temperature`, `system`, …): child wins when
+//!   set, inherited otherwise.
+//! - **`inputs`/`output`**: deep-merged — nested objects merge key-by-key
+//!   with the child winning conflicts, `required` lists union, any other
+//!   array is replaced by the child's.
+//! - **`tools`**: unioned by name, child description winning; the child
+//!   removes an inherited tool by listing `-tool`.
+//! - **`locales`**: merged per locale tag, child wins.
+//! - **`body`**: an empty child body inherits the parent's; otherwise the
+//!   child's body is used, with every `{{ super() }}` replaced by the
+//!   parent's body so children can wrap instead of restate.
+//!
+//! Like external schema paths, `extends` is resolved relative to the prompt
+//! file and only by [`crate::parse_file`]; the merged definition no longer
+//! carries the field.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// Inheritance chains longer than this fail rather than resolve; real
+/// hierarchies are a handful of levels, anything deeper is a mistake.
+pub(crate) const MAX_EXTENDS_DEPTH: usize = 32;
+
+/// Load and merge the parent named by `def.extends`, if any. Called by
+/// [`crate::parser::parse_file`] before validation; clears `extends`. The
+/// chain of files currently resolving travels along so cycles and runaway
+/// depth fail with a dedicated error instead of overflowing the stack.
+pub(crate) fn resolve_extends(
+    def: &mut PromptDefinition,
+    base_dir: &Path,
+    chain: &mut Vec<std::path::PathBuf>,
+) -> Result<(), PromptError> {
+    let Some(relative) = def.extends.take() else {
+        return Ok(());
+    };
+    let parent = crate::parser::parse_file_with_chain(&base_dir.join(relative), chain)?;
+    // Expand the child's shorthand first so both sides merge as full schemas.
+    crate::shorthand::expand_shorthand(def)?;
+    merge(def, parent);
+    Ok(())
+}
+
+fn merge(child: &mut PromptDefinition, parent: PromptDefinition) {
+    macro_rules! inherit {
+        ($($field:ident),+ $(,)?) => {
+            $(if child.$field.is_none() { child.$field = parent.$field; })+
+        };
+    }
+    inherit!(
+        version,
+        description,
+        prompt_type,
+        client,
+        temperature,
+        top_p,
+        max_tokens,
+        max_turns,
+        stop,
+        output_mode,
+        system,
+        examples,
+        tests,
+        whitespace,
+    );
+
+    child.inputs = merge_schema(parent.inputs, child.inputs.take());
+    child.output = merge_schema(parent.output, child.output.take());
+    child.tools = merge_tools(parent.tools, child.tools.take());
+
+    match (&mut child.locales, parent.locales) {
+        (Some(locales), Some(inherited)) => {
+            for (tag, body) in inherited {
+                locales.entry(tag).or_insert(body);
+            }
+        }
+        (locales @ None, inherited) => *locales = inherited,
+        _ => {}
+    }
+
+    child.body = if child.body.trim().is_empty() {
+        parent.body
+    } else {
+        splice_super(&child.body, &parent.body)
+    };
+}
+
+fn merge_schema(parent: Option<Value>, child: Option<Value>) -> Option<Value> {
+    match (parent, child) {
+        (Some(parent), Some(child)) => Some(deep_merge(parent, child)),
+        (parent, child) => child.or(parent),
+    }
+}
+
+/// Child-wins deep merge: objects recurse, `required` lists union, anything
+/// else is taken from the child.
+fn deep_merge(base: Value, overlay: Value) -> Value {
+    match (base, overlay) {
+        (Value::Object(mut base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(existing) if key == "required" => union_required(existing, value),
+                    Some(existing) => deep_merge(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+fn union_required(base: Value, overlay: Value) -> Value {
+    let (Value::Array(mut base), Value::Array(overlay)) = (base, overlay.clone()) else {
+        return overlay;
+    };
+    for entry in overlay {
+        if !base.contains(&entry) {
+            base.push(entry);
+        }
+    }
+    Value::Array(base)
+}
+
+fn merge_tools(
+    parent: Option<Vec<crate::tools::Tool>>,
+    child: Option<Vec<crate::tools::Tool>>,
+) -> Option<Vec<crate::tools::Tool>> {
+    let (parent, child) = match (parent, child) {
+        (Some(parent), Some(child)) => (parent, child),
+        (parent, child) => return child.or(parent),
+    };
+    let removals: Vec<&str> = child
+        .iter()
+        .filter_map(|t| t.name.strip_prefix('-'))
+        .collect();
+    let mut merged: Vec<crate::tools::Tool> = parent
+        .iter()
+        .filter(|t| !removals.contains(&t.name.as_str()))
+        .cloned()
+        .collect();
+    for tool in child {
+        if tool.name.starts_with('-') {
+            continue;
+        }
+        match merged.iter_mut().find(|t| t.name == tool.name) {
+            Some(existing) => *existing = tool,
+            None => merged.push(tool),
+        }
+    }
+    Some(merged)
+}
+
+/// Replace every `{{ super() }}` tag in `child` with `parent`, verbatim.
+fn splice_super(child: &str, parent: &str) -> String {
+    let mut out = String::with_capacity(child.len());
+    let mut rest = child;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let after_tag = start + 2 + end + 2;
+        if rest[start + 2..start + 2 + end].trim() == "super()" {
+            out.push_str(&rest[..start]);
+            out.push_str(parent);
+        } else {
+            out.push_str(&rest[..after_tag]);
+        }
+        rest = &rest[after_tag..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PromptError, parse, parse_file};
+    use serde_json::json;
+
+    fn write(dir: &std::path::Path, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn temp_dir(label: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "prompt-parser-extends-{label}-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const PARENT: &str = "---\nname: base\nclient: anthropic/claude-sonnet-4\ntemperature: 0.2\ntools: [web_search, code_exec]\ninputs:\n  query: string\n---\nAnswer: {{ query }}";
+
+    #[test]
+    fn scalars_inherit_and_child_wins() {
+        let dir = temp_dir("scalars");
+        write(&dir, "base.prompt.md", PARENT);
+        let child = write(
+            &dir,
+            "child.prompt.md",
+            "---\nname: child\nextends: ./base.prompt.md\ntemperature: 0.9\n---\n",
+        );
+        let def = parse_file(child).unwrap();
+        assert_eq!(def.name, "child");
+        assert_eq!(def.client.as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(def.temperature, Some(0.9));
+        // Empty child body inherits the parent's.
+        assert_eq!(def.body, "Answer: {{ query }}");
+    }
+
+    #[test]
+    fn inputs_deep_merge_and_required_unions() {
+        let dir = temp_dir("inputs");
+        write(&dir, "base.prompt.md", PARENT);
+        let child = write(
+            &dir,
+            "child.prompt.md",
+            "---\nname: child\nextends: ./base.prompt.md\ninputs:\n  locale: string\n---\n{{ query }} {{ locale }}",
+        );
+        let def = parse_file(child).unwrap();
+        let inputs = def.inputs.unwrap();
+        assert_eq!(inputs["properties"]["query"], json!({ "type": "string" }));
+        assert_eq!(inputs["properties"]["locale"], json!({ "type": "string" }));
+        assert_eq!(inputs["required"], json!(["query", "locale"]));
+    }
+
+    #[test]
+    fn tools_union_with_dash_removals() {
+        let dir = temp_dir("tools");
+        write(&dir, "base.prompt.md", PARENT);
+        let child = write(
+            &dir,
+            "child.prompt.md",
+            "---\nname: child\nextends: ./base.prompt.md\ntools: [-code_exec, browser]\n---\nbody",
+        );
+        let def = parse_file(child).unwrap();
+        let names: Vec<&str> = def
+            .tools
+            .as_deref()
+            .unwrap()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, ["web_search", "browser"]);
+    }
+
+    #[test]
+    fn super_splices_the_parent_body() {
+        let dir = temp_dir("super");
+        write(&dir, "base.prompt.md", PARENT);
+        let child = write(
+            &dir,
+            "child.prompt.md",
+            "---\nname: child\nextends: ./base.prompt.md\n---\nContext first.\n{{ super() }}\nThen cite sources.",
+        );
+        let def = parse_file(child).unwrap();
+        assert_eq!(
+            def.body,
+            "Context first.\nAnswer: {{ query }}\nThen cite sources."
+        );
+    }
+
+    #[test]
+    fn cycles_fail_with_the_chain_spelled_out() {
+        let dir = temp_dir("cycle");
+        write(
+            &dir,
+            "a.prompt.md",
+            "---\nname: a\nextends: ./b.prompt.md\n---\nbody",
+        );
+        let b = write(
+            &dir,
+            "b.prompt.md",
+            "---\nname: b\nextends: ./a.prompt.md\n---\nbody",
+        );
+        let err = parse_file(&b).unwrap_err();
+        let PromptError::ExtendsCycle { chain } = &err else {
+            panic!("{err}");
+        };
+        assert_eq!(chain.len(), 3, "{chain:?}");
+        assert!(chain[0].ends_with("b.prompt.md") && chain[2].ends_with("b.prompt.md"));
+
+        // Self-reference is the two-entry degenerate case.
+        let selfish = write(
+            &dir,
+            "self.prompt.md",
+            "---\nname: s\nextends: ./self.prompt.md\n---\nbody",
+        );
+        assert!(matches!(
+            parse_file(&selfish).unwrap_err(),
+            PromptError::ExtendsCycle { .. }
+        ));
+    }
+
+    #[test]
+    fn runaway_depth_is_capped() {
+        let dir = temp_dir("depth");
+        write(&dir, "p0.prompt.md", "---\nname: p0\n---\nbody");
+        let mut last = std::path::PathBuf::new();
+        for i in 1..=40 {
+            last = write(
+                &dir,
+                &format!("p{i}.prompt.md"),
+                &format!("---\nname: p{i}\nextends: ./p{}.prompt.md\n---\nbody", i - 1),
+            );
+        }
+        let err = parse_file(&last).unwrap_err();
+        assert!(
+            matches!(err, PromptError::Frontmatter(ref m) if m.contains("exceeds")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn extends_requires_parse_file() {
+        let err = parse("---\nname: x\nextends: ./base.prompt.md\n---\nbody").unwrap_err();
+        assert!(
+            matches!(err, PromptError::Frontmatter(ref m) if m.contains("parse_file")),
+            "{err}"
+        );
+    }
+}
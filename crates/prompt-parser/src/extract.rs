@@ -0,0 +1,300 @@
+//! Tolerant extraction of structured output from raw model text.
+//!
+//! Models wrap JSON in code fences, preface it with prose, and emit
+//! almost-JSON (trailing commas, single quotes). [`extract_output`] recovers
+//! the payload where it can and reports precisely what it could not.
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::error::PromptError;
+use crate::schema;
+
+/// Why extraction failed, in decreasing order of progress made.
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    /// Nothing in the text looked like JSON.
+    #[error("no JSON candidate found in model output")]
+    NoCandidate,
+
+    /// A candidate was found but would not parse, even after repair.
+    #[error("JSON candidate failed to parse after repair: {message}")]
+    Parse {
+        /// The candidate text (truncated for error readability).
+        candidate: String,
+        /// The parser error from the repaired candidate.
+        message: String,
+    },
+
+    /// Valid JSON was recovered but it violates the output schema.
+    #[error("recovered JSON failed output schema validation: {0}")]
+    Validation(#[source] PromptError),
+}
+
+/// Recover a JSON value from `raw` and validate it against `schema`.
+///
+/// Candidates are tried in order: fenced code blocks, then the first balanced
+/// `{...}`/`[...]` span, then the whole trimmed text. Each candidate is parsed
+/// as-is first and with repairs (trailing commas removed, single-quoted
+/// strings and unquoted object keys rewritten) second.
+pub fn extract_output(schema: &Value, raw: &str) -> Result<Value, ExtractError> {
+    let candidates = collect_candidates(raw);
+    if candidates.is_empty() {
+        return Err(ExtractError::NoCandidate);
+    }
+
+    let mut last_error = None;
+    for candidate in &candidates {
+        match parse_lenient(candidate) {
+            Ok(value) => {
+                return schema::validate_json(schema, &value)
+                    .map(|()| value)
+                    .map_err(ExtractError::Validation);
+            }
+            Err(message) => last_error = Some((candidate.as_str(), message)),
+        }
+    }
+    let (candidate, message) = last_error.expect("candidates is non-empty");
+    Err(ExtractError::Parse {
+        candidate: truncate(candidate, 200).to_string(),
+        message,
+    })
+}
+
+fn collect_candidates(raw: &str) -> Vec<String> {
+    let mut candidates = Vec::new();
+    candidates.extend(fenced_blocks(raw));
+    if let Some(span) = balanced_span(raw) {
+        candidates.push(span.to_string());
+    }
+    let trimmed = raw.trim();
+    if !trimmed.is_empty() && (trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        candidates.push(trimmed.to_string());
+    }
+    candidates.dedup();
+    candidates
+}
+
+/// Contents of ```-fenced blocks, ignoring the info string (` ```json `).
+fn fenced_blocks(raw: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut rest = raw;
+    while let Some(open) = rest.find("```") {
+        let after = &rest[open + 3..];
+        let Some(newline) = after.find('\n') else { break };
+        let body = &after[newline + 1..];
+        let Some(close) = body.find("```") else { break };
+        let block = body[..close].trim();
+        if !block.is_empty() {
+            blocks.push(block.to_string());
+        }
+        rest = &body[close + 3..];
+    }
+    blocks
+}
+
+/// The first balanced `{...}` or `[...]` span, quote-aware.
+fn balanced_span(raw: &str) -> Option<&str> {
+    let start = raw.find(['{', '['])?;
+    let bytes = raw.as_bytes();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &b) in bytes.iter().enumerate().skip(start) {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Some(&raw[start..=i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_lenient(candidate: &str) -> Result<Value, String> {
+    if let Ok(value) = serde_json::from_str(candidate) {
+        return Ok(value);
+    }
+    let repaired = repair(candidate);
+    serde_json::from_str(&repaired).map_err(|e| e.to_string())
+}
+
+/// Rewrite common almost-JSON into JSON: single-quoted strings become
+/// double-quoted, unquoted object keys are quoted, and trailing commas before
+/// `}`/`]` are dropped. Operates with a small scanner so content inside
+/// legitimate strings is never touched.
+fn repair(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push('"');
+                copy_string(&mut chars, &mut out, '"');
+            }
+            '\'' => {
+                out.push('"');
+                copy_string(&mut chars, &mut out, '\'');
+            }
+            ',' => {
+                // Drop the comma if the next non-whitespace closes a container.
+                let mut lookahead = chars.clone();
+                let next = loop {
+                    match lookahead.next() {
+                        Some(w) if w.is_whitespace() => continue,
+                        other => break other,
+                    }
+                };
+                if !matches!(next, Some('}' | ']')) {
+                    out.push(',');
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                // Possibly an unquoted key: collect the identifier and peek
+                // for a `:`. Keywords (true/false/null) stay bare.
+                let mut ident = String::from(c);
+                while let Some(&n) = chars.peek() {
+                    if n.is_alphanumeric() || n == '_' {
+                        ident.push(n);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let mut lookahead = chars.clone();
+                let next = loop {
+                    match lookahead.next() {
+                        Some(w) if w.is_whitespace() => continue,
+                        other => break other,
+                    }
+                };
+                if next == Some(':') && !matches!(ident.as_str(), "true" | "false" | "null") {
+                    out.push('"');
+                    out.push_str(&ident);
+                    out.push('"');
+                } else {
+                    out.push_str(&ident);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Copy a string literal delimited by `quote`, emitting JSON double-quote
+/// syntax. Handles escapes; inner `"` get escaped when converting from `'`.
+fn copy_string(chars: &mut std::iter::Peekable<std::str::Chars>, out: &mut String, quote: char) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(&n) = chars.peek() {
+                    chars.next();
+                    if n == '\'' {
+                        // \' is not a JSON escape; emit the bare quote.
+                        out.push('\'');
+                    } else {
+                        out.push('\\');
+                        out.push(n);
+                    }
+                }
+            }
+            c if c == quote => {
+                out.push('"');
+                return;
+            }
+            '"' => out.push_str("\\\""),
+            c => out.push(c),
+        }
+    }
+}
+
+fn truncate(s: &str, max: usize) -> &str {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": { "summary": { "type": "string" } },
+            "required": ["summary"]
+        })
+    }
+
+    #[test]
+    fn extracts_from_code_fence_with_prose() {
+        let raw = "Here's the result you asked for:\n```json\n{\"summary\": \"ok\"}\n```\nHope that helps!";
+        assert_eq!(
+            extract_output(&schema(), raw).unwrap(),
+            json!({ "summary": "ok" })
+        );
+    }
+
+    #[test]
+    fn extracts_balanced_span_from_prose() {
+        let raw = "The answer is {\"summary\": \"embedded\"} as requested.";
+        assert_eq!(
+            extract_output(&schema(), raw).unwrap(),
+            json!({ "summary": "embedded" })
+        );
+    }
+
+    #[test]
+    fn repairs_trailing_commas_and_single_quotes() {
+        let raw = "{'summary': 'it\\'s done',}";
+        assert_eq!(
+            extract_output(&schema(), raw).unwrap(),
+            json!({ "summary": "it's done" })
+        );
+    }
+
+    #[test]
+    fn repairs_unquoted_keys() {
+        let raw = "{summary: \"bare key\", }";
+        assert_eq!(
+            extract_output(&schema(), raw).unwrap(),
+            json!({ "summary": "bare key" })
+        );
+    }
+
+    #[test]
+    fn no_candidate_is_distinguished_from_parse_failure() {
+        assert!(matches!(
+            extract_output(&schema(), "no json here at all").unwrap_err(),
+            ExtractError::NoCandidate
+        ));
+        assert!(matches!(
+            extract_output(&schema(), "{this is : not recoverable ][").unwrap_err(),
+            ExtractError::Parse { .. }
+        ));
+    }
+
+    #[test]
+    fn schema_violations_surface_as_validation() {
+        let err = extract_output(&schema(), "{\"other\": 1}").unwrap_err();
+        assert!(matches!(err, ExtractError::Validation(_)));
+        assert!(err.to_string().contains("validation"));
+    }
+}
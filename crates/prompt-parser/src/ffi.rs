@@ -0,0 +1,485 @@
+//! C API surface, consumed by libsmithers (Zig).
+//!
+//! Conventions mirror the libsmithers header: every call returns a
+//! heap-allocated NUL-terminated JSON envelope (`{"ok":true,...}` or
+//! `{"ok":false,"error":"..."}`) that the caller must release with
+//! [`prompt_parser_free`].
+
+use std::ffi::{CStr, CString, c_char};
+
+use serde_json::{Value, json};
+
+use crate::definition::PromptDefinition;
+
+/// Hand an owned string across the boundary without any way to panic.
+/// Serialized JSON can't contain a raw NUL (serde escapes control
+/// characters as `\u0000`), but an FFI return path must not be able to
+/// abort the host process on the assumption holding — if a NUL does get
+/// here, escape it the way the JSON layer would have.
+fn into_c(text: String) -> *mut c_char {
+    let c = match CString::new(text) {
+        Ok(c) => c,
+        Err(err) => {
+            let escaped = String::from_utf8_lossy(&err.into_vec()).replace('\0', "\\u0000");
+            CString::new(escaped).expect("interior NULs were just escaped")
+        }
+    };
+    c.into_raw()
+}
+
+fn envelope_ok(payload: Value) -> *mut c_char {
+    let mut obj = json!({ "ok": true });
+    if let (Value::Object(dst), Value::Object(src)) = (&mut obj, payload) {
+        dst.extend(src);
+    }
+    into_c(obj.to_string())
+}
+
+fn envelope_err(message: impl std::fmt::Display) -> *mut c_char {
+    let obj = json!({ "ok": false, "error": message.to_string() });
+    into_c(obj.to_string())
+}
+
+/// # Safety
+/// `ptr` must be a valid NUL-terminated string or null.
+unsafe fn arg_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, String> {
+    if ptr.is_null() {
+        return Err(format!("`{name}` is null"));
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| format!("`{name}` is not valid UTF-8"))
+}
+
+/// Parse a prompt file. Returns `{"ok":true,"definition":{...}}`.
+///
+/// # Safety
+/// `source` must be a valid NUL-terminated string or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_parse(source: *const c_char) -> *mut c_char {
+    let source = match unsafe { arg_str(source, "source") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    match PromptDefinition::parse(source) {
+        Ok(def) => match serde_json::to_value(&def) {
+            Ok(v) => envelope_ok(json!({ "definition": v })),
+            Err(e) => envelope_err(e),
+        },
+        Err(e) => envelope_err(e),
+    }
+}
+
+/// Parse a batch of prompt files in one call.
+///
+/// `files_json` is a JSON array of `{"path": "...", "content": "..."}`
+/// objects. Returns `{"ok":true,"results":[...]}` where each result carries
+/// the file's `path` plus either `"definition"` or `"error"` — one bad file
+/// does not fail the batch. This lets the Zig host load a prompt directory
+/// without one FFI round trip per file.
+///
+/// # Safety
+/// `files_json` must be a valid NUL-terminated string or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_parse_many(files_json: *const c_char) -> *mut c_char {
+    let files = match unsafe { arg_str(files_json, "files_json") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+
+    #[derive(serde::Deserialize)]
+    struct File {
+        path: String,
+        content: String,
+    }
+    let files: Vec<File> = match serde_json::from_str(files) {
+        Ok(f) => f,
+        Err(e) => {
+            return envelope_err(format!(
+                "`files_json` must be an array of {{path, content}} objects: {e}"
+            ));
+        }
+    };
+
+    let results: Vec<Value> = files
+        .iter()
+        .map(|file| match PromptDefinition::parse(&file.content) {
+            Ok(def) => match serde_json::to_value(&def) {
+                Ok(v) => json!({ "path": file.path, "ok": true, "definition": v }),
+                Err(e) => json!({ "path": file.path, "ok": false, "error": e.to_string() }),
+            },
+            Err(e) => json!({ "path": file.path, "ok": false, "error": e.to_string() }),
+        })
+        .collect();
+    envelope_ok(json!({ "results": results }))
+}
+
+/// Parse and render in one call. `inputs_json` is a JSON object.
+/// Returns `{"ok":true,"rendered":"..."}`.
+///
+/// # Safety
+/// Both pointers must be valid NUL-terminated strings or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_render(
+    source: *const c_char,
+    inputs_json: *const c_char,
+) -> *mut c_char {
+    let source = match unsafe { arg_str(source, "source") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    let inputs = match unsafe { arg_str(inputs_json, "inputs_json") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    let inputs: Value = match serde_json::from_str(inputs) {
+        Ok(v) => v,
+        Err(e) => return envelope_err(format!("`inputs_json` is not valid JSON: {e}")),
+    };
+    let def = match PromptDefinition::parse(source) {
+        Ok(d) => d,
+        Err(e) => return envelope_err(e),
+    };
+    match def.render(&inputs) {
+        Ok(rendered) => envelope_ok(json!({ "rendered": rendered })),
+        Err(e) => envelope_err(e),
+    }
+}
+
+/// Parse, render, and return the provider-ready chat messages:
+/// `{"ok":true,"messages":[{"role":"system","content":"..."}, ...]}`.
+/// See [`PromptDefinition::render_messages`] for message ordering.
+///
+/// # Safety
+/// Both pointers must be valid NUL-terminated strings or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_render_messages(
+    content: *const c_char,
+    inputs_json: *const c_char,
+) -> *mut c_char {
+    let content = match unsafe { arg_str(content, "content") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    let inputs = match unsafe { arg_str(inputs_json, "inputs_json") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    let inputs: Value = match serde_json::from_str(inputs) {
+        Ok(v) => v,
+        Err(e) => return envelope_err(format!("`inputs_json` is not valid JSON: {e}")),
+    };
+    let def = match PromptDefinition::parse(content) {
+        Ok(d) => d,
+        Err(e) => return envelope_err(e),
+    };
+    match def.render_messages(&inputs) {
+        Ok(messages) => match serde_json::to_value(&messages) {
+            Ok(v) => envelope_ok(json!({ "messages": v })),
+            Err(e) => envelope_err(e),
+        },
+        Err(e) => envelope_err(e),
+    }
+}
+
+/// Validate a JSON document against a JSON Schema.
+///
+/// Returns `{"ok":true,"valid":true}` or
+/// `{"ok":true,"valid":false,"violations":[{"instance_path":...,
+/// "schema_path":...,"keyword":...,"expected":...,"actual":...,
+/// "message":...}, ...]}` so the host can highlight the exact offending
+/// field. A schema that does not compile is an `"ok":false` envelope.
+///
+/// # Safety
+/// Both pointers must be valid NUL-terminated strings or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_validate_json(
+    schema_json: *const c_char,
+    data_json: *const c_char,
+) -> *mut c_char {
+    let schema = match unsafe { arg_str(schema_json, "schema_json") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    let data = match unsafe { arg_str(data_json, "data_json") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    let schema: Value = match serde_json::from_str(schema) {
+        Ok(v) => v,
+        Err(e) => return envelope_err(format!("`schema_json` is not valid JSON: {e}")),
+    };
+    let data: Value = match serde_json::from_str(data) {
+        Ok(v) => v,
+        Err(e) => return envelope_err(format!("`data_json` is not valid JSON: {e}")),
+    };
+    match crate::schema::validate_json(&schema, &data) {
+        Ok(()) => envelope_ok(json!({ "valid": true })),
+        Err(crate::PromptError::Validation(violations)) => match serde_json::to_value(&violations)
+        {
+            Ok(v) => envelope_ok(json!({ "valid": false, "violations": v })),
+            Err(e) => envelope_err(e),
+        },
+        Err(e) => envelope_err(e),
+    }
+}
+
+/// Opaque handle to a directory-backed prompt registry. Created by
+/// [`prompt_parser_registry_open`], released by
+/// [`prompt_parser_registry_close`]; the Zig server manages its prompt
+/// library through one handle instead of parsing files itself.
+pub struct PromptRegistryHandle {
+    dir: std::path::PathBuf,
+    registry: crate::PromptRegistry,
+}
+
+/// Open a registry handle for a prompt directory. Does not touch the
+/// filesystem yet — call [`prompt_parser_registry_scan`] to load it.
+/// Returns null only for a null/invalid `dir` argument.
+///
+/// # Safety
+/// `dir` must be a valid NUL-terminated string or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_registry_open(
+    dir: *const c_char,
+) -> *mut PromptRegistryHandle {
+    let Ok(dir) = (unsafe { arg_str(dir, "dir") }) else {
+        return std::ptr::null_mut();
+    };
+    Box::into_raw(Box::new(PromptRegistryHandle {
+        dir: std::path::PathBuf::from(dir),
+        registry: crate::PromptRegistry::new(),
+    }))
+}
+
+/// # Safety
+/// `handle` must come from [`prompt_parser_registry_open`] and not be closed.
+unsafe fn registry_handle<'a>(
+    handle: *mut PromptRegistryHandle,
+) -> Result<&'a mut PromptRegistryHandle, String> {
+    if handle.is_null() {
+        return Err("`handle` is null".into());
+    }
+    Ok(unsafe { &mut *handle })
+}
+
+/// (Re-)scan the handle's directory, replacing the loaded set. Returns
+/// `{"ok":true,"files":[{"path":...,"name":...}|{"path":...,"error":...}]}`
+/// — one broken file does not fail the scan. Call again to pick up changes.
+///
+/// # Safety
+/// `handle` must come from [`prompt_parser_registry_open`] and not be closed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_registry_scan(
+    handle: *mut PromptRegistryHandle,
+) -> *mut c_char {
+    let handle = match unsafe { registry_handle(handle) } {
+        Ok(h) => h,
+        Err(e) => return envelope_err(e),
+    };
+    let mut fresh = crate::PromptRegistry::new();
+    match fresh.load_dir(&handle.dir) {
+        Ok(files) => {
+            handle.registry = fresh;
+            match serde_json::to_value(&files) {
+                Ok(v) => envelope_ok(json!({ "files": v })),
+                Err(e) => envelope_err(e),
+            }
+        }
+        Err(e) => envelope_err(e),
+    }
+}
+
+/// List loaded prompt names, sorted. Returns `{"ok":true,"names":[...]}`.
+///
+/// # Safety
+/// `handle` must come from [`prompt_parser_registry_open`] and not be closed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_registry_names(
+    handle: *mut PromptRegistryHandle,
+) -> *mut c_char {
+    let handle = match unsafe { registry_handle(handle) } {
+        Ok(h) => h,
+        Err(e) => return envelope_err(e),
+    };
+    envelope_ok(json!({ "names": handle.registry.names() }))
+}
+
+/// Fetch one loaded definition. Returns `{"ok":true,"definition":{...}}`.
+///
+/// # Safety
+/// `handle` must come from [`prompt_parser_registry_open`] and not be
+/// closed; `name` must be a valid NUL-terminated string or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_registry_get(
+    handle: *mut PromptRegistryHandle,
+    name: *const c_char,
+) -> *mut c_char {
+    let handle = match unsafe { registry_handle(handle) } {
+        Ok(h) => h,
+        Err(e) => return envelope_err(e),
+    };
+    let name = match unsafe { arg_str(name, "name") } {
+        Ok(s) => s,
+        Err(e) => return envelope_err(e),
+    };
+    let Some(def) = handle.registry.get(name) else {
+        return envelope_err(crate::PromptError::UnknownPrompt(name.to_string()));
+    };
+    match serde_json::to_value(def) {
+        Ok(v) => envelope_ok(json!({ "definition": v })),
+        Err(e) => envelope_err(e),
+    }
+}
+
+/// Release a registry handle.
+///
+/// # Safety
+/// `handle` must come from [`prompt_parser_registry_open`] and not be
+/// closed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_registry_close(handle: *mut PromptRegistryHandle) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Release a string returned by any `prompt_parser_*` call.
+///
+/// # Safety
+/// `ptr` must have been returned by this library and not freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prompt_parser_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(f: impl FnOnce() -> *mut c_char) -> Value {
+        let ptr = f();
+        let s = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        unsafe { prompt_parser_free(ptr) };
+        serde_json::from_str(&s).unwrap()
+    }
+
+    #[test]
+    fn parse_round_trips_through_c_strings() {
+        let src = CString::new("---\nname: x\n---\nbody").unwrap();
+        let v = call(|| unsafe { prompt_parser_parse(src.as_ptr()) });
+        assert_eq!(v["ok"], true);
+        assert_eq!(v["definition"]["name"], "x");
+    }
+
+    #[test]
+    fn null_and_errors_become_envelopes() {
+        let v = call(|| unsafe { prompt_parser_parse(std::ptr::null()) });
+        assert_eq!(v["ok"], false);
+
+        let src = CString::new("no frontmatter").unwrap();
+        let v = call(|| unsafe { prompt_parser_parse(src.as_ptr()) });
+        assert_eq!(v["ok"], false);
+        assert!(v["error"].as_str().unwrap().contains("frontmatter"));
+    }
+
+    #[test]
+    fn interior_nul_bytes_never_abort_the_caller() {
+        // The JSON path: a rendered value carrying a NUL crosses the
+        // boundary escaped, not truncated, and nothing panics.
+        let src = CString::new("---\nname: x\n---\nHi {{ who }}").unwrap();
+        let inputs = CString::new(r#"{"who":"a\u0000b"}"#).unwrap();
+        let v = call(|| unsafe { prompt_parser_render(src.as_ptr(), inputs.as_ptr()) });
+        assert_eq!(v["ok"], true);
+        assert_eq!(v["rendered"].as_str().unwrap(), "Hi a\u{0}b");
+
+        // The defensive path: a raw NUL handed to the converter directly
+        // comes back escaped, not truncated.
+        let ptr = into_c("raw\0nul".to_string());
+        let s = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        unsafe { prompt_parser_free(ptr) };
+        assert_eq!(s, "raw\\u0000nul");
+    }
+
+    #[test]
+    fn parse_many_reports_per_file_results() {
+        let files = CString::new(
+            r#"[
+                {"path": "a.md", "content": "---\nname: a\n---\nbody"},
+                {"path": "b.md", "content": "no frontmatter"}
+            ]"#,
+        )
+        .unwrap();
+        let v = call(|| unsafe { prompt_parser_parse_many(files.as_ptr()) });
+        assert_eq!(v["ok"], true);
+        let results = v["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ok"], true);
+        assert_eq!(results[0]["definition"]["name"], "a");
+        assert_eq!(results[1]["ok"], false);
+        assert_eq!(results[1]["path"], "b.md");
+    }
+
+    #[test]
+    fn validate_json_reports_structured_violations() {
+        let schema =
+            CString::new(r#"{"type":"object","properties":{"n":{"type":"integer"}}}"#).unwrap();
+        let good = CString::new(r#"{"n":1}"#).unwrap();
+        let v = call(|| unsafe { prompt_parser_validate_json(schema.as_ptr(), good.as_ptr()) });
+        assert_eq!(v["ok"], true);
+        assert_eq!(v["valid"], true);
+
+        let bad = CString::new(r#"{"n":"x"}"#).unwrap();
+        let v = call(|| unsafe { prompt_parser_validate_json(schema.as_ptr(), bad.as_ptr()) });
+        assert_eq!(v["ok"], true);
+        assert_eq!(v["valid"], false);
+        let violation = &v["violations"][0];
+        assert_eq!(violation["instance_path"], "/n");
+        assert_eq!(violation["keyword"], "type");
+        assert_eq!(violation["actual"], "x");
+    }
+
+    #[test]
+    fn registry_handle_scans_lists_and_fetches() {
+        let dir = std::env::temp_dir().join(format!("prompt-parser-ffi-reg-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.prompt.md"), "---\nname: a\n---\nbody").unwrap();
+        std::fs::write(dir.join("broken.prompt.md"), "no frontmatter").unwrap();
+
+        let dir_c = CString::new(dir.to_str().unwrap()).unwrap();
+        let handle = unsafe { prompt_parser_registry_open(dir_c.as_ptr()) };
+        assert!(!handle.is_null());
+
+        let v = call(|| unsafe { prompt_parser_registry_scan(handle) });
+        assert_eq!(v["ok"], true);
+        let files = v["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["name"], "a");
+        assert!(files[1]["error"].as_str().unwrap().contains("frontmatter"));
+
+        let v = call(|| unsafe { prompt_parser_registry_names(handle) });
+        assert_eq!(v["names"], json!(["a"]));
+
+        let name = CString::new("a").unwrap();
+        let v = call(|| unsafe { prompt_parser_registry_get(handle, name.as_ptr()) });
+        assert_eq!(v["ok"], true);
+        assert_eq!(v["definition"]["name"], "a");
+
+        let missing = CString::new("zz").unwrap();
+        let v = call(|| unsafe { prompt_parser_registry_get(handle, missing.as_ptr()) });
+        assert_eq!(v["ok"], false);
+
+        unsafe { prompt_parser_registry_close(handle) };
+    }
+
+    #[test]
+    fn render_returns_rendered_body() {
+        let src = CString::new("---\nname: x\n---\nHi {{ who }}").unwrap();
+        let inputs = CString::new(r#"{"who":"there"}"#).unwrap();
+        let v = call(|| unsafe { prompt_parser_render(src.as_ptr(), inputs.as_ptr()) });
+        assert_eq!(v["ok"], true);
+        assert_eq!(v["rendered"], "Hi there");
+    }
+}
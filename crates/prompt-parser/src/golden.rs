@@ -0,0 +1,195 @@
+//! Embedded golden tests.
+//!
+//! A prompt file may carry its own regression tests in a `tests:`
+//! frontmatter block, so prompt edits are tested like code:
+//!
+//! ```yaml
+//! tests:
+//!   - name: greets by name
+//!     inputs: { who: world }
+//!     expect: "Hello world!"
+//!   - name: rejects missing input
+//!     inputs: {}
+//!     expect_error: validation
+//! ```
+//!
+//! Each case renders the body with its `inputs` and checks either the exact
+//! rendered text (`expect`) or that rendering fails with an error containing
+//! the `expect_error` substring. A case with neither just asserts the render
+//! succeeds.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// One golden case from a `tests:` frontmatter block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestCase {
+    /// Case name, unique within the prompt.
+    pub name: String,
+    /// Render inputs; defaults to `{}`.
+    #[serde(default = "empty_object")]
+    pub inputs: Value,
+    /// Expected rendered body, compared exactly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<String>,
+    /// Substring the render error must contain (case-insensitive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_error: Option<String>,
+}
+
+fn empty_object() -> Value {
+    Value::Object(serde_json::Map::new())
+}
+
+/// Outcome of one executed [`TestCase`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    pub name: String,
+    /// `None` on pass, otherwise a human-readable failure reason.
+    pub failure: Option<String>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Reject cases that could never be meaningful, at parse/build time.
+pub(crate) fn validate_cases(def: &PromptDefinition) -> Result<(), PromptError> {
+    let Some(cases) = &def.tests else {
+        return Ok(());
+    };
+    let mut seen = std::collections::HashSet::new();
+    for case in cases {
+        if case.name.trim().is_empty() {
+            return Err(PromptError::Frontmatter(
+                "test cases must have a non-empty `name`".into(),
+            ));
+        }
+        if !seen.insert(case.name.as_str()) {
+            return Err(PromptError::Frontmatter(format!(
+                "duplicate test case name `{}`",
+                case.name
+            )));
+        }
+        if case.expect.is_some() && case.expect_error.is_some() {
+            return Err(PromptError::Frontmatter(format!(
+                "test case `{}` sets both `expect` and `expect_error`",
+                case.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl PromptDefinition {
+    /// The embedded test cases, in declaration order.
+    pub fn test_cases(&self) -> &[TestCase] {
+        self.tests.as_deref().unwrap_or_default()
+    }
+
+    /// Execute every embedded test case against this definition.
+    pub fn run_tests(&self) -> Vec<CaseResult> {
+        self.test_cases()
+            .iter()
+            .map(|case| CaseResult {
+                name: case.name.clone(),
+                failure: self.run_case(case),
+            })
+            .collect()
+    }
+
+    fn run_case(&self, case: &TestCase) -> Option<String> {
+        match (self.render(&case.inputs), &case.expect_error) {
+            (Ok(rendered), None) => match &case.expect {
+                Some(expected) if *expected != rendered => Some(format!(
+                    "rendered output differs\n  expected: {expected:?}\n  actual:   {rendered:?}"
+                )),
+                _ => None,
+            },
+            (Ok(_), Some(needle)) => {
+                Some(format!("expected an error containing {needle:?}, but rendering succeeded"))
+            }
+            (Err(err), Some(needle)) => {
+                if err.to_string().to_lowercase().contains(&needle.to_lowercase()) {
+                    None
+                } else {
+                    Some(format!("error {err:?} does not contain {needle:?}"))
+                }
+            }
+            (Err(err), None) => Some(format!("render failed: {err}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PromptError, parse};
+
+    const SOURCE: &str = r#"---
+name: greet
+inputs:
+  type: object
+  properties:
+    who: { type: string }
+  required: [who]
+tests:
+  - name: greets by name
+    inputs: { who: world }
+    expect: "Hello world!"
+  - name: rejects missing input
+    inputs: {}
+    expect_error: validation
+  - name: renders at all
+    inputs: { who: x }
+---
+Hello {{ who }}!"#;
+
+    #[test]
+    fn runs_embedded_cases() {
+        let def = parse(SOURCE).unwrap();
+        assert_eq!(def.test_cases().len(), 3);
+        let results = def.run_tests();
+        assert!(results.iter().all(|r| r.passed()), "{results:?}");
+    }
+
+    #[test]
+    fn reports_mismatches_with_both_sides() {
+        let source = SOURCE.replace("Hello world!", "Goodbye world!");
+        let results = parse(&source).unwrap().run_tests();
+        let failed: Vec<_> = results.iter().filter(|r| !r.passed()).collect();
+        assert_eq!(failed.len(), 1, "{results:?}");
+        assert_eq!(failed[0].name, "greets by name");
+        let reason = failed[0].failure.as_deref().unwrap();
+        assert!(reason.contains("Goodbye world!") && reason.contains("Hello world!"), "{reason}");
+    }
+
+    #[test]
+    fn unexpected_success_and_wrong_errors_fail() {
+        let source = r#"---
+name: t
+tests:
+  - name: wants an error
+    expect_error: validation
+---
+static"#;
+        let results = parse(source).unwrap().run_tests();
+        assert!(!results[0].passed(), "{results:?}");
+    }
+
+    #[test]
+    fn malformed_test_blocks_fail_at_parse() {
+        for bad in [
+            "tests:\n  - name: \"\"\n",
+            "tests:\n  - name: a\n  - name: a\n",
+            "tests:\n  - name: a\n    expect: x\n    expect_error: y\n",
+        ] {
+            let err = parse(&format!("---\nname: t\n{bad}---\nbody")).unwrap_err();
+            assert!(matches!(err, PromptError::Frontmatter(_)), "{bad}: {err}");
+        }
+    }
+}
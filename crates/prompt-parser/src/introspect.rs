@@ -0,0 +1,254 @@
+//! Template introspection: which variables does a body reference, and do they
+//! line up with the declared `inputs` schema?
+//!
+//! Tooling (linters, the registry, docs generation) uses this to catch drift
+//! between frontmatter and body without rendering anything.
+
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::template::Node;
+
+/// Extract the set of variable paths a body template references.
+///
+/// Paths are dotted, as written (`user.name`). Inside `{{#each items}}`,
+/// `this`-relative references are rewritten against the iterated path:
+/// `this.id` becomes `items.id`, a bare `{{ this }}` becomes `items`. Paths
+/// that do not start with `this` are reported as written, since they resolve
+/// against the enclosing scope.
+pub fn extract_template_variables(body: &str) -> Result<BTreeSet<String>, PromptError> {
+    let nodes = crate::cache::template_nodes(body)?;
+    let mut vars = BTreeSet::new();
+    collect(&nodes, None, &mut vars);
+    Ok(vars)
+}
+
+fn collect(nodes: &[Node], each_path: Option<&str>, vars: &mut BTreeSet<String>) {
+    for node in nodes {
+        match node {
+            Node::Text(_) => {}
+            Node::Var(path) => {
+                vars.insert(resolve(path, each_path));
+            }
+            Node::If {
+                path,
+                then_nodes,
+                else_nodes,
+            } => {
+                vars.insert(resolve(path, each_path));
+                collect(then_nodes, each_path, vars);
+                collect(else_nodes, each_path, vars);
+            }
+            Node::Each { path, body } => {
+                let path = resolve(path, each_path);
+                vars.insert(path.clone());
+                collect(body, Some(&path), vars);
+            }
+            // A partial's variables belong to the referenced prompt, which
+            // validates its own inputs; only the argument path is ours.
+            Node::Partial { arg, .. } => {
+                if let Some(path) = arg {
+                    vars.insert(resolve(path, each_path));
+                }
+            }
+            // Clock functions read no inputs.
+            Node::Clock(_) => {}
+        }
+    }
+}
+
+fn resolve(path: &str, each_path: Option<&str>) -> String {
+    match (each_path, path) {
+        (Some(base), "this") => base.to_string(),
+        (Some(base), _) => match path.strip_prefix("this.") {
+            Some(rest) => format!("{base}.{rest}"),
+            None => path.to_string(),
+        },
+        (None, _) => path.to_string(),
+    }
+}
+
+/// Drift between a body template and its declared `inputs` schema.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VariableCoverage {
+    /// Paths referenced in the body that the schema does not declare.
+    pub undeclared: BTreeSet<String>,
+    /// Top-level schema properties the body never references.
+    pub unused: BTreeSet<String>,
+}
+
+impl VariableCoverage {
+    /// True when frontmatter and body agree.
+    pub fn is_clean(&self) -> bool {
+        self.undeclared.is_empty() && self.unused.is_empty()
+    }
+}
+
+/// Cross-check the body's referenced variables against the `inputs` schema.
+///
+/// Prompts without an `inputs` schema report every referenced variable as
+/// undeclared (unless the body references nothing). Schemas that leave objects
+/// open (`additionalProperties` not `false`, or no `properties`) accept any
+/// deeper path beneath them.
+pub fn check_input_coverage(def: &PromptDefinition) -> Result<VariableCoverage, PromptError> {
+    let vars = extract_template_variables(&def.body)?;
+    let empty = Value::Object(serde_json::Map::new());
+    let schema = def.inputs.as_ref().unwrap_or(&empty);
+
+    let mut coverage = VariableCoverage::default();
+    for var in &vars {
+        // `tools.*` resolves against the declared tools, not the inputs.
+        if def.tools.is_some() && (var == "tools" || var.starts_with("tools.")) {
+            continue;
+        }
+        let segments: Vec<&str> = var.split('.').collect();
+        if !path_declared(schema, &segments) {
+            coverage.undeclared.insert(var.clone());
+        }
+    }
+    if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+        for name in props.keys() {
+            let referenced = vars
+                .iter()
+                .any(|v| v == name || v.starts_with(&format!("{name}.")));
+            if !referenced {
+                coverage.unused.insert(name.clone());
+            }
+        }
+    }
+    Ok(coverage)
+}
+
+/// Can `segments` resolve against `schema`? Arrays are traversed transparently
+/// (`items.0.id` and the each-rewritten `items.id` both match an array of
+/// objects with an `id` property).
+fn path_declared(schema: &Value, segments: &[&str]) -> bool {
+    let Some(first) = segments.first() else {
+        return true;
+    };
+    if let Some(items) = schema.get("items") {
+        return if first.parse::<usize>().is_ok() {
+            path_declared(items, &segments[1..])
+        } else {
+            path_declared(items, segments)
+        };
+    }
+    // An unknown property falls through: an open object still accepts it.
+    if let Some(sub) = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .and_then(|props| props.get(*first))
+    {
+        return path_declared(sub, &segments[1..]);
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        // Scalar schema with a remaining path: cannot resolve.
+        Some("string" | "number" | "integer" | "boolean" | "null") => false,
+        // Object (or untyped) schema: open unless additionalProperties: false.
+        _ => schema.get("additionalProperties") != Some(&Value::Bool(false)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extracts_nested_and_block_paths() {
+        let vars = extract_template_variables(
+            "{{ user.name }} {{#if verbose}}{{ detail }}{{/if}} {{#each items}}{{ this.id }}{{/each}}",
+        )
+        .unwrap();
+        let expect: BTreeSet<String> =
+            ["user.name", "verbose", "detail", "items", "items.id"]
+                .into_iter()
+                .map(String::from)
+                .collect();
+        assert_eq!(vars, expect);
+    }
+
+    #[test]
+    fn bare_this_maps_to_the_each_path() {
+        let vars = extract_template_variables("{{#each tags}}{{ this }}{{/each}}").unwrap();
+        assert_eq!(vars, BTreeSet::from(["tags".to_string()]));
+    }
+
+    fn def_with(inputs: Value, body: &str) -> PromptDefinition {
+        PromptDefinition {
+            name: "t".into(),
+            inputs: Some(inputs),
+            body: body.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_undeclared_and_unused() {
+        let def = def_with(
+            json!({
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "who": { "type": "string" },
+                    "tone": { "type": "string" }
+                }
+            }),
+            "Hello {{ who }}, {{ missing }}",
+        );
+        let cov = check_input_coverage(&def).unwrap();
+        assert_eq!(cov.undeclared, BTreeSet::from(["missing".to_string()]));
+        assert_eq!(cov.unused, BTreeSet::from(["tone".to_string()]));
+        assert!(!cov.is_clean());
+    }
+
+    #[test]
+    fn arrays_resolve_through_items() {
+        let def = def_with(
+            json!({
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "items": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "properties": { "id": { "type": "integer" } }
+                        }
+                    }
+                }
+            }),
+            "{{#each items}}{{ this.id }}{{/each}} {{ items.0.id }}",
+        );
+        assert!(check_input_coverage(&def).unwrap().is_clean());
+    }
+
+    #[test]
+    fn open_objects_accept_any_deeper_path() {
+        let def = def_with(
+            json!({
+                "type": "object",
+                "properties": { "meta": { "type": "object" } }
+            }),
+            "{{ meta.anything.goes }}",
+        );
+        assert!(check_input_coverage(&def).unwrap().undeclared.is_empty());
+    }
+
+    #[test]
+    fn scalar_with_trailing_path_is_undeclared() {
+        let def = def_with(
+            json!({
+                "type": "object",
+                "properties": { "who": { "type": "string" } }
+            }),
+            "{{ who.name }}",
+        );
+        let cov = check_input_coverage(&def).unwrap();
+        assert_eq!(cov.undeclared, BTreeSet::from(["who.name".to_string()]));
+    }
+}
@@ -0,0 +1,103 @@
+//! prompt-parser — parses and renders Smithers prompt files.
+//!
+//! A prompt file is Markdown with YAML frontmatter:
+//!
+//! ```markdown
+//! ---
+//! name: summarize
+//! description: Summarize a document
+//! client: anthropic/claude-sonnet-4
+//! inputs:
+//!   type: object
+//!   properties:
+//!     document: { type: string }
+//!   required: [document]
+//! output:
+//!   type: object
+//!   properties:
+//!     summary: { type: string }
+//! ---
+//! Summarize the following document:
+//!
+//! {{ document }}
+//! ```
+//!
+//! The frontmatter declares the I/O contract (JSON Schema), the body is the
+//! template. Rendering validates inputs against the schema, then substitutes
+//! `{{ path }}` expressions; `{{#if}}`/`{{#each}}` blocks are supported.
+//!
+//! Compiled as a static library and linked into libsmithers; the C surface
+//! lives in [`ffi`].
+
+mod budget;
+mod builder;
+pub mod cache;
+mod clients;
+mod clock;
+mod codegen;
+mod coerce;
+mod compat;
+mod definition;
+mod diff;
+mod docs;
+mod error;
+mod extends;
+mod extract;
+mod golden;
+mod introspect;
+mod locale;
+mod media;
+mod parser;
+mod partial;
+mod pricing;
+mod registry;
+mod resolve;
+mod schema;
+mod secret;
+mod shorthand;
+mod template;
+mod tokens;
+mod tools;
+mod watch;
+mod whitespace;
+mod writer;
+
+pub mod ffi;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use budget::{Budget, BudgetKind, BudgetOverrun, BudgetReport};
+pub use builder::PromptDefinitionBuilder;
+pub use clients::{ClientId, register_alias, register_provider, resolve_client};
+pub use clock::{clear_clock, set_clock};
+pub use coerce::coerce_inputs;
+pub use compat::{
+    Change, CompatibilityReport, Severity, check_compatibility, check_input_compatibility,
+    check_output_compatibility,
+};
+pub use definition::{Example, Message, OutputMode, PromptDefinition};
+pub use diff::{DefinitionDiff, FieldChange, LineChange, diff_definitions};
+pub use error::{PromptError, RenderLimitKind};
+pub use extract::{ExtractError, extract_output};
+pub use golden::{CaseResult, TestCase};
+pub use introspect::{VariableCoverage, check_input_coverage, extract_template_variables};
+pub use media::{Attachment, MediaKind, MediaSource};
+pub use parser::{parse, parse_file, parse_with_env, parse_with_tools};
+pub use partial::{Diagnostic, DiagnosticSeverity, PartialParse, parse_partial};
+pub use pricing::{
+    CostEstimate, ModelPricing, clear_pricing_overrides, estimate_cost, pricing_for, set_pricing,
+};
+pub use registry::{FileResult, PromptRegistry};
+pub use schema::{
+    SchemaDraft, ValidationOptions, ValidationViolation, validate_json, validate_json_with,
+};
+pub use template::{
+    RenderLimits, render_template, render_template_stream, render_template_stream_with,
+    render_template_with,
+};
+pub use tokens::{BpeTokenCounter, TokenCounter};
+pub use tools::{Tool, ToolRegistry};
+pub use watch::{ChangeKind, RegistryChange, WatchedRegistry};
+pub use whitespace::{WhitespaceControl, apply_whitespace_control};
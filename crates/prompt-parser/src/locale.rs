@@ -0,0 +1,127 @@
+//! Locale variants within a prompt file.
+//!
+//! Multilingual deployments shouldn't fork every prompt per language. A
+//! `locales:` frontmatter map carries body overrides keyed by BCP 47-style
+//! tags; the schemas, examples, and model parameters stay shared:
+//!
+//! ```yaml
+//! locales:
+//!   de: "Hallo {{ who }}!"
+//!   de-CH: "Grüezi {{ who }}!"
+//! ```
+//!
+//! Lookup walks the fallback chain `de-CH-1996` → `de-CH` → `de` → default
+//! body, so regional variants only override what actually differs.
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::template;
+
+impl PromptDefinition {
+    /// The body template used for `locale`: the most specific `locales`
+    /// entry on the tag's fallback chain, or the default body. Tags match
+    /// case-insensitively, as BCP 47 requires.
+    pub fn body_for_locale(&self, locale: &str) -> &str {
+        if let Some(locales) = &self.locales {
+            let mut tag = locale;
+            loop {
+                if let Some(body) = locales
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(tag))
+                    .map(|(_, body)| body)
+                {
+                    return body;
+                }
+                match tag.rsplit_once('-') {
+                    Some((parent, _)) => tag = parent,
+                    None => break,
+                }
+            }
+        }
+        &self.body
+    }
+
+    /// [`Self::render`] against the body selected by [`Self::body_for_locale`].
+    pub fn render_locale(&self, data: &Value, locale: &str) -> Result<String, PromptError> {
+        if let Some(inputs) = &self.inputs {
+            crate::media::validate_inputs(inputs, data)?;
+        }
+        let ctx = crate::tools::context_with_tools(self, data);
+        template::render_template(self.body_for_locale(locale), &ctx)
+    }
+}
+
+/// Check `locales` entries at parse/build time: plausible tags, templates
+/// that compile.
+pub(crate) fn validate_locales(def: &PromptDefinition) -> Result<(), PromptError> {
+    let Some(locales) = &def.locales else {
+        return Ok(());
+    };
+    for (tag, body) in locales {
+        let well_formed = !tag.is_empty()
+            && tag.split('-').all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric())
+            });
+        if !well_formed {
+            return Err(PromptError::Frontmatter(format!(
+                "invalid locale tag `{tag}`"
+            )));
+        }
+        template::parse_template(body)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PromptError, parse};
+    use serde_json::json;
+
+    const SOURCE: &str = r#"---
+name: greet
+inputs:
+  type: object
+  properties:
+    who: { type: string }
+  required: [who]
+locales:
+  de: "Hallo {{ who }}!"
+  de-CH: "Grüezi {{ who }}!"
+---
+Hello {{ who }}!"#;
+
+    #[test]
+    fn walks_the_fallback_chain() {
+        let def = parse(SOURCE).unwrap();
+        let data = json!({ "who": "world" });
+        assert_eq!(def.render_locale(&data, "de-CH").unwrap(), "Grüezi world!");
+        assert_eq!(def.render_locale(&data, "de-CH-1996").unwrap(), "Grüezi world!");
+        assert_eq!(def.render_locale(&data, "de-AT").unwrap(), "Hallo world!");
+        assert_eq!(def.render_locale(&data, "DE").unwrap(), "Hallo world!");
+        assert_eq!(def.render_locale(&data, "fr").unwrap(), "Hello world!");
+        assert_eq!(def.render(&data).unwrap(), "Hello world!");
+    }
+
+    #[test]
+    fn locale_renders_still_validate_inputs() {
+        let def = parse(SOURCE).unwrap();
+        assert!(matches!(
+            def.render_locale(&json!({}), "de").unwrap_err(),
+            PromptError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn locale_blocks_are_checked_at_parse() {
+        assert!(matches!(
+            parse("---\nname: x\nlocales:\n  \"d!e\": body\n---\nbody").unwrap_err(),
+            PromptError::Frontmatter(_)
+        ));
+        assert!(matches!(
+            parse("---\nname: x\nlocales:\n  de: \"{{#if a}}unclosed\"\n---\nbody").unwrap_err(),
+            PromptError::Template(_)
+        ));
+    }
+}
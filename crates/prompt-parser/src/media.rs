@@ -0,0 +1,287 @@
+//! `image` and `file` input types.
+//!
+//! JSON Schema has no notion of media, so prompt inputs may declare the
+//! extension types `image` and `file`:
+//!
+//! ```yaml
+//! inputs:
+//!   type: object
+//!   properties:
+//!     photo: { type: image }
+//!     report: { type: file }
+//! ```
+//!
+//! A media-typed input accepts a *content reference*: a path, an `http(s)`
+//! URL, a `data:` URI, or an object with `path`, `url`, or
+//! `base64`+`media_type`. Before compilation the extension type is rewritten
+//! into an equivalent standard schema, so validation works everywhere the
+//! `inputs` schema does. [`crate::PromptDefinition::render_messages`] turns
+//! the supplied references into [`Attachment`] content blocks on the final
+//! user turn; actually loading bytes (reading paths, fetching URLs) is the
+//! provider layer's job.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::schema;
+
+/// Which extension type an input was declared as.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Image,
+    File,
+}
+
+/// Where the bytes of a media input live. The parser classifies references;
+/// it never dereferences them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaSource {
+    /// Local filesystem path, relative to wherever the caller decides.
+    Path(String),
+    /// `http://` or `https://` URL.
+    Url(String),
+    /// Inline bytes, from a `data:` URI or a `{ base64, media_type }` object.
+    Base64 { media_type: String, data: String },
+}
+
+/// One non-text content block for the provider message array.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Attachment {
+    /// The input property this block came from.
+    pub name: String,
+    pub kind: MediaKind,
+    pub source: MediaSource,
+}
+
+/// What a media-typed input validates as once the extension type is
+/// rewritten away.
+fn reference_schema(description: Option<&Value>) -> Value {
+    let mut schema = json!({
+        "anyOf": [
+            { "type": "string", "minLength": 1 },
+            {
+                "type": "object",
+                "properties": { "path": { "type": "string", "minLength": 1 } },
+                "required": ["path"],
+                "additionalProperties": false
+            },
+            {
+                "type": "object",
+                "properties": { "url": { "type": "string", "minLength": 1 } },
+                "required": ["url"],
+                "additionalProperties": false
+            },
+            {
+                "type": "object",
+                "properties": {
+                    "base64": { "type": "string", "minLength": 1 },
+                    "media_type": { "type": "string", "minLength": 1 }
+                },
+                "required": ["base64", "media_type"],
+                "additionalProperties": false
+            }
+        ]
+    });
+    if let Some(description) = description {
+        schema["description"] = description.clone();
+    }
+    schema
+}
+
+fn media_kind(schema: &Value) -> Option<MediaKind> {
+    match schema.get("type").and_then(Value::as_str) {
+        Some("image") => Some(MediaKind::Image),
+        Some("file") => Some(MediaKind::File),
+        _ => None,
+    }
+}
+
+fn contains_media_type(schema: &Value) -> bool {
+    match schema {
+        Value::Object(map) => {
+            media_kind(schema).is_some() || map.values().any(contains_media_type)
+        }
+        Value::Array(items) => items.iter().any(contains_media_type),
+        _ => false,
+    }
+}
+
+fn rewrite(schema: &mut Value) {
+    if media_kind(schema).is_some() {
+        *schema = reference_schema(schema.get("description"));
+        return;
+    }
+    match schema {
+        Value::Object(map) => map.values_mut().for_each(rewrite),
+        Value::Array(items) => items.iter_mut().for_each(rewrite),
+        _ => {}
+    }
+}
+
+/// Replace `image`/`file` extension types with [`reference_schema`] so the
+/// result compiles as standard JSON Schema. Borrows when there is nothing
+/// to rewrite, which is the common case.
+pub(crate) fn sanitize_schema(schema: &Value) -> Cow<'_, Value> {
+    if !contains_media_type(schema) {
+        return Cow::Borrowed(schema);
+    }
+    let mut owned = schema.clone();
+    rewrite(&mut owned);
+    Cow::Owned(owned)
+}
+
+/// Validate `data` against an `inputs` schema that may use media types.
+pub(crate) fn validate_inputs(inputs: &Value, data: &Value) -> Result<(), PromptError> {
+    schema::validate_json(&sanitize_schema(inputs), data)
+}
+
+/// Top-level media-typed input properties, in schema order.
+fn media_properties(def: &PromptDefinition) -> Vec<(String, MediaKind)> {
+    let Some(properties) = def
+        .inputs
+        .as_ref()
+        .and_then(|s| s.get("properties"))
+        .and_then(Value::as_object)
+    else {
+        return Vec::new();
+    };
+    properties
+        .iter()
+        .filter_map(|(name, schema)| media_kind(schema).map(|kind| (name.clone(), kind)))
+        .collect()
+}
+
+fn classify(value: &Value) -> Option<MediaSource> {
+    match value {
+        Value::String(s) => Some(classify_str(s)),
+        Value::Object(map) => {
+            if let Some(Value::String(path)) = map.get("path") {
+                Some(MediaSource::Path(path.clone()))
+            } else if let Some(Value::String(url)) = map.get("url") {
+                Some(MediaSource::Url(url.clone()))
+            } else if let (Some(Value::String(data)), Some(Value::String(media_type))) =
+                (map.get("base64"), map.get("media_type"))
+            {
+                Some(MediaSource::Base64 {
+                    media_type: media_type.clone(),
+                    data: data.clone(),
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn classify_str(s: &str) -> MediaSource {
+    if s.starts_with("http://") || s.starts_with("https://") {
+        return MediaSource::Url(s.to_string());
+    }
+    if let Some(rest) = s.strip_prefix("data:")
+        && let Some((media_type, data)) = rest.split_once(";base64,")
+    {
+        return MediaSource::Base64 {
+            media_type: media_type.to_string(),
+            data: data.to_string(),
+        };
+    }
+    MediaSource::Path(s.to_string())
+}
+
+/// Content blocks for every media-typed input present in `data`. Called
+/// after input validation, so supplied values are well-formed references.
+pub(crate) fn attachments(def: &PromptDefinition, data: &Value) -> Vec<Attachment> {
+    media_properties(def)
+        .into_iter()
+        .filter_map(|(name, kind)| {
+            let source = classify(data.get(&name)?)?;
+            Some(Attachment { name, kind, source })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use serde_json::json;
+
+    const SOURCE: &str = r#"---
+name: vision
+inputs:
+  type: object
+  properties:
+    photo: { type: image }
+    report: { type: file }
+    caption: { type: string }
+  required: [photo]
+---
+Describe {{ caption }}."#;
+
+    #[test]
+    fn media_inputs_validate_as_content_references() {
+        let def = parse(SOURCE).unwrap();
+        for photo in [
+            json!("shots/cat.png"),
+            json!("https://example.com/cat.png"),
+            json!("data:image/png;base64,AAAA"),
+            json!({ "url": "https://example.com/cat.png" }),
+            json!({ "base64": "AAAA", "media_type": "image/png" }),
+        ] {
+            let data = json!({ "photo": photo, "caption": "the cat" });
+            assert!(def.render(&data).is_ok(), "{photo}");
+        }
+        let err = def
+            .render(&json!({ "photo": 42, "caption": "the cat" }))
+            .unwrap_err();
+        assert!(matches!(err, crate::PromptError::Validation(_)));
+    }
+
+    #[test]
+    fn render_messages_attaches_content_blocks() {
+        let def = parse(SOURCE).unwrap();
+        let messages = def
+            .render_messages(&json!({
+                "photo": "data:image/png;base64,AAAA",
+                "report": { "url": "https://example.com/q3.pdf" },
+                "caption": "the cat",
+            }))
+            .unwrap();
+        let user = messages.last().unwrap();
+        assert_eq!(user.content, "Describe the cat.");
+
+        let photo = user.attachments.iter().find(|a| a.name == "photo").unwrap();
+        assert_eq!(photo.kind, MediaKind::Image);
+        assert_eq!(
+            photo.source,
+            MediaSource::Base64 {
+                media_type: "image/png".into(),
+                data: "AAAA".into(),
+            }
+        );
+
+        let report = user.attachments.iter().find(|a| a.name == "report").unwrap();
+        assert_eq!(report.kind, MediaKind::File);
+        assert_eq!(
+            report.source,
+            MediaSource::Url("https://example.com/q3.pdf".into())
+        );
+        assert_eq!(user.attachments.len(), 2);
+    }
+
+    #[test]
+    fn text_only_schemas_are_not_rewritten() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "who": { "type": "string" } }
+        });
+        assert!(matches!(sanitize_schema(&schema), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,464 @@
+//! Frontmatter splitting and prompt file parsing.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::schema;
+use crate::template;
+
+/// Parse a prompt file into a [`PromptDefinition`].
+///
+/// Validates as much as possible up front: the frontmatter must be valid YAML
+/// with a `name`, any declared `inputs`/`output` schemas must compile, and the
+/// body template must be well-formed.
+pub fn parse(source: &str) -> Result<PromptDefinition, PromptError> {
+    parse_with_env(source, &[])
+}
+
+/// [`parse`] with `${ENV_VAR}` interpolation in frontmatter values.
+///
+/// Only variables in `env_allowlist` interpolate, so deployment-specific
+/// model names can come from the environment without prompt files gaining
+/// access to arbitrary secrets. Referencing a variable outside the allowlist,
+/// or an allowlisted variable that is unset, is a frontmatter error. The body
+/// is never interpolated.
+pub fn parse_with_env(
+    source: &str,
+    env_allowlist: &[&str],
+) -> Result<PromptDefinition, PromptError> {
+    let (frontmatter, body) = split_frontmatter(source)?;
+    let frontmatter = interpolate_env(frontmatter, env_allowlist)?;
+
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&frontmatter)
+        .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+    // Round-trip through serde_json so schemas are plain JSON values.
+    let json: Value = serde_json::to_value(&yaml)
+        .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+    let mut def: PromptDefinition = serde_json::from_value(json)
+        .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+    def.body = body.to_string();
+    crate::shorthand::attach_comment_descriptions(&mut def, &frontmatter);
+    finish_definition(&mut def)?;
+    tracing::debug!(name = %def.name, "parsed prompt definition");
+    Ok(def)
+}
+
+/// [`parse`], then cross-check the declared tools against the host's
+/// [`crate::ToolRegistry`].
+///
+/// Frontmatter validation only proves the prompt is internally consistent;
+/// this also proves the runtime implements every tool the prompt declares,
+/// so a renamed or unknown tool fails here instead of as a dispatch failure
+/// mid-agent-run.
+pub fn parse_with_tools(
+    source: &str,
+    tools: &crate::tools::ToolRegistry,
+) -> Result<PromptDefinition, PromptError> {
+    let def = parse(source)?;
+    tools.check(&def)?;
+    Ok(def)
+}
+
+/// Parse a prompt file from disk.
+///
+/// Unlike [`parse`], this resolves external schema references —
+/// `inputs: ./schemas/task.schema.json` and `$ref`s to other files, with
+/// optional `#/json/pointer` fragments — relative to the prompt file's
+/// directory, inlining the referenced documents.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<PromptDefinition, PromptError> {
+    parse_file_with_chain(path.as_ref(), &mut Vec::new())
+}
+
+/// [`parse_file`] tracking the chain of files currently being resolved, so
+/// self- or mutually-extending prompts fail with the cycle spelled out
+/// instead of recursing until the stack overflows.
+pub(crate) fn parse_file_with_chain(
+    path: &Path,
+    chain: &mut Vec<std::path::PathBuf>,
+) -> Result<PromptDefinition, PromptError> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if chain.contains(&canonical) {
+        let mut cycle: Vec<String> = chain.iter().map(|p| p.display().to_string()).collect();
+        cycle.push(canonical.display().to_string());
+        return Err(PromptError::ExtendsCycle { chain: cycle });
+    }
+    if chain.len() >= crate::extends::MAX_EXTENDS_DEPTH {
+        return Err(PromptError::Frontmatter(format!(
+            "`extends` chain exceeds {} files",
+            crate::extends::MAX_EXTENDS_DEPTH
+        )));
+    }
+    chain.push(canonical);
+    let result = parse_file_inner(path, chain);
+    chain.pop();
+    result
+}
+
+fn parse_file_inner(
+    path: &Path,
+    chain: &mut Vec<std::path::PathBuf>,
+) -> Result<PromptDefinition, PromptError> {
+    let source = std::fs::read_to_string(path).map_err(|e| PromptError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+
+    let (frontmatter, body) = split_frontmatter(&source)?;
+    let yaml: serde_yaml::Value = serde_yaml::from_str(frontmatter)
+        .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+    let json: Value = serde_json::to_value(&yaml)
+        .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+    let mut def: PromptDefinition = serde_json::from_value(json)
+        .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+    def.body = body.to_string();
+    crate::shorthand::attach_comment_descriptions(&mut def, frontmatter);
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    crate::extends::resolve_extends(&mut def, base_dir, chain)?;
+    crate::resolve::resolve_external_schemas(&mut def, base_dir)?;
+    finish_definition(&mut def)?;
+    Ok(def)
+}
+
+/// Validate and normalize a definition, however it was constructed: name
+/// present, model parameters in range, client resolved, schemas compiling,
+/// templates well-formed. Shared by the parser and the builder.
+pub(crate) fn finish_definition(def: &mut PromptDefinition) -> Result<(), PromptError> {
+    if def.name.trim().is_empty() {
+        return Err(PromptError::Frontmatter("`name` must be non-empty".into()));
+    }
+    // Like external schema paths, inheritance needs a file to resolve
+    // against; parse_file merges the parent before we get here.
+    if def.extends.is_some() {
+        return Err(PromptError::Frontmatter(
+            "`extends` is only resolved by parse_file".into(),
+        ));
+    }
+
+    crate::whitespace::apply_to_definition(def);
+
+    validate_model_parameters(def)?;
+    validate_prompt_type(def)?;
+    crate::budget::validate_budget(def)?;
+
+    // Resolve aliases and reject unknown providers here, not at request time.
+    if let Some(client) = &def.client {
+        def.client = Some(crate::clients::resolve_client(client)?.to_string());
+    }
+
+    crate::shorthand::expand_shorthand(def)?;
+
+    let options = schema::ValidationOptions::default();
+    for (field, schema) in [("inputs", &def.inputs), ("output", &def.output)] {
+        if let Some(schema) = schema {
+            // File-path schemas only make sense with a file to resolve
+            // against; parse_file inlines them before we get here.
+            if schema.is_string() {
+                return Err(PromptError::Schema {
+                    field: field.to_string(),
+                    message: "external schema paths are only resolved by parse_file".into(),
+                });
+            }
+            // Inputs may use the `image`/`file` extension types; compile the
+            // standard-schema rewrite, same as validation will.
+            let schema = if field == "inputs" {
+                crate::media::sanitize_schema(schema)
+            } else {
+                std::borrow::Cow::Borrowed(schema)
+            };
+            crate::cache::validator(field, &schema, &options)?;
+        }
+    }
+
+    crate::golden::validate_cases(def)?;
+    crate::locale::validate_locales(def)?;
+    crate::tools::validate_tools(def)?;
+
+    // Surface template syntax errors at parse/build time, not first render.
+    template::parse_template(&def.body)?;
+    if let Some(system) = &def.system {
+        template::parse_template(system)?;
+    }
+    Ok(())
+}
+
+/// Replace `${VAR}` references in the frontmatter text with environment
+/// values, for allowlisted names only.
+fn interpolate_env(frontmatter: &str, allowlist: &[&str]) -> Result<String, PromptError> {
+    if !frontmatter.contains("${") {
+        return Ok(frontmatter.to_string());
+    }
+    let mut out = String::with_capacity(frontmatter.len());
+    let mut rest = frontmatter;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            return Err(PromptError::Frontmatter(
+                "unterminated `${` in frontmatter".into(),
+            ));
+        };
+        let name = &after[..end];
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            || name.is_empty()
+        {
+            return Err(PromptError::Frontmatter(format!(
+                "invalid environment reference `${{{name}}}`"
+            )));
+        }
+        if !allowlist.contains(&name) {
+            return Err(PromptError::Frontmatter(format!(
+                "environment variable `{name}` is not in the allowlist"
+            )));
+        }
+        let value = std::env::var(name).map_err(|_| {
+            PromptError::Frontmatter(format!("environment variable `{name}` is not set"))
+        })?;
+        out.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Known values for the `type:` field.
+const PROMPT_TYPES: &[&str] = &["llm", "agent", "workflow"];
+
+/// `type:` is a closed enum, and agents carry extra obligations: they loop,
+/// so they need a termination lever (`tools` to finish with, or `max_turns`),
+/// and their result feeds tooling, so `output` is mandatory.
+pub(crate) fn validate_prompt_type(def: &PromptDefinition) -> Result<(), PromptError> {
+    match def.prompt_type.as_deref() {
+        None => {}
+        Some(t) if !PROMPT_TYPES.contains(&t) => {
+            return Err(PromptError::Frontmatter(format!(
+                "unknown `type` `{t}` (expected one of {})",
+                PROMPT_TYPES.join(", ")
+            )));
+        }
+        Some("agent") => {
+            if def.tools.is_none() && def.max_turns.is_none() {
+                return Err(PromptError::Frontmatter(
+                    "`type: agent` requires `tools` or `max_turns`".into(),
+                ));
+            }
+            if def.output.is_none() {
+                return Err(PromptError::Frontmatter(
+                    "`type: agent` requires an `output` schema".into(),
+                ));
+            }
+        }
+        Some(_) => {}
+    }
+    Ok(())
+}
+
+/// Range-check the sampling/generation parameters declared in frontmatter.
+pub(crate) fn validate_model_parameters(def: &PromptDefinition) -> Result<(), PromptError> {
+    if let Some(t) = def.temperature
+        && !(0.0..=2.0).contains(&t)
+    {
+        return Err(PromptError::Frontmatter(format!(
+            "`temperature` must be within 0.0..=2.0, got {t}"
+        )));
+    }
+    if let Some(p) = def.top_p
+        && !(0.0..=1.0).contains(&p)
+    {
+        return Err(PromptError::Frontmatter(format!(
+            "`top_p` must be within 0.0..=1.0, got {p}"
+        )));
+    }
+    if def.max_tokens == Some(0) {
+        return Err(PromptError::Frontmatter(
+            "`max_tokens` must be at least 1".into(),
+        ));
+    }
+    if def.max_turns == Some(0) {
+        return Err(PromptError::Frontmatter(
+            "`max_turns` must be at least 1".into(),
+        ));
+    }
+    if let Some(stop) = &def.stop
+        && stop.iter().any(String::is_empty)
+    {
+        return Err(PromptError::Frontmatter(
+            "`stop` sequences must be non-empty".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Split `---\n<yaml>\n---\n<body>`; returns (frontmatter, body).
+pub(crate) fn split_frontmatter(source: &str) -> Result<(&str, &str), PromptError> {
+    let rest = source
+        .strip_prefix("---\n")
+        .or_else(|| source.strip_prefix("---\r\n"))
+        .ok_or(PromptError::MissingFrontmatter)?;
+    for (idx, _) in rest.match_indices("\n---") {
+        let after = &rest[idx + 4..];
+        // The closing fence must occupy the whole line.
+        let (fence_end, body) = match after.strip_prefix('\n') {
+            Some(body) => (idx, body),
+            None => match after.strip_prefix("\r\n") {
+                Some(body) => (idx, body),
+                None if after.is_empty() => (idx, ""),
+                None => continue,
+            },
+        };
+        return Ok((&rest[..fence_end], body));
+    }
+    Err(PromptError::UnterminatedFrontmatter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_minimal_prompt() {
+        let def = parse("---\nname: hello\n---\nHi there").unwrap();
+        assert_eq!(def.name, "hello");
+        assert_eq!(def.body, "Hi there");
+        assert!(def.inputs.is_none());
+    }
+
+    #[test]
+    fn missing_frontmatter_is_rejected() {
+        assert!(matches!(
+            parse("just a body").unwrap_err(),
+            PromptError::MissingFrontmatter
+        ));
+        assert!(matches!(
+            parse("---\nname: x\nno closing fence").unwrap_err(),
+            PromptError::UnterminatedFrontmatter
+        ));
+    }
+
+    #[test]
+    fn name_is_required() {
+        assert!(matches!(
+            parse("---\ndescription: no name\n---\nbody").unwrap_err(),
+            PromptError::Frontmatter(_)
+        ));
+    }
+
+    #[test]
+    fn client_aliases_normalize_and_unknown_providers_fail() {
+        let def = parse("---\nname: x\nclient: claude-sonnet\n---\nbody").unwrap();
+        assert_eq!(def.client.as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert!(matches!(
+            parse("---\nname: x\nclient: acme/model-x\n---\nbody").unwrap_err(),
+            PromptError::Client { .. }
+        ));
+    }
+
+    #[test]
+    fn parses_model_parameters() {
+        let def = parse(
+            "---\nname: x\ntemperature: 0.2\ntop_p: 0.9\nmax_tokens: 1024\nstop: [\"\\n\\n\"]\n---\nbody",
+        )
+        .unwrap();
+        assert_eq!(def.temperature, Some(0.2));
+        assert_eq!(def.top_p, Some(0.9));
+        assert_eq!(def.max_tokens, Some(1024));
+        assert_eq!(def.stop.as_deref(), Some(&["\n\n".to_string()][..]));
+    }
+
+    #[test]
+    fn model_parameters_are_range_checked() {
+        for bad in [
+            "temperature: 2.5",
+            "top_p: 1.5",
+            "max_tokens: 0",
+            "stop: [\"\"]",
+        ] {
+            let err = parse(&format!("---\nname: x\n{bad}\n---\nbody")).unwrap_err();
+            assert!(
+                matches!(err, PromptError::Frontmatter(_)),
+                "{bad}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn prompt_type_is_a_closed_enum_with_agent_rules() {
+        assert!(parse("---\nname: x\ntype: llm\n---\nbody").is_ok());
+        assert!(parse("---\nname: x\ntype: workflow\n---\nbody").is_ok());
+
+        let err = parse("---\nname: x\ntype: chat\n---\nbody").unwrap_err();
+        assert!(err.to_string().contains("llm, agent, workflow"), "{err}");
+
+        // Agents need a termination lever and an output contract.
+        let err = parse(
+            "---\nname: x\ntype: agent\noutput:\n  type: object\n---\nbody",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("`tools` or `max_turns`"), "{err}");
+        let err = parse("---\nname: x\ntype: agent\nmax_turns: 5\n---\nbody").unwrap_err();
+        assert!(err.to_string().contains("`output`"), "{err}");
+        assert!(parse(
+            "---\nname: x\ntype: agent\nmax_turns: 5\noutput:\n  type: object\n---\nbody"
+        )
+        .is_ok());
+        assert!(parse(
+            "---\nname: x\ntype: agent\ntools: [finish]\noutput:\n  type: object\n---\nbody"
+        )
+        .is_ok());
+
+        assert!(matches!(
+            parse("---\nname: x\nmax_turns: 0\n---\nbody").unwrap_err(),
+            PromptError::Frontmatter(_)
+        ));
+    }
+
+    #[test]
+    fn invalid_inputs_schema_fails_at_parse() {
+        let err = parse("---\nname: x\ninputs:\n  type: 42\n---\nbody").unwrap_err();
+        assert!(matches!(err, PromptError::Schema { ref field, .. } if field == "inputs"));
+    }
+
+    #[test]
+    fn template_errors_fail_at_parse() {
+        assert!(matches!(
+            parse("---\nname: x\n---\n{{#if a}}unclosed").unwrap_err(),
+            PromptError::Template(_)
+        ));
+    }
+
+    #[test]
+    fn env_interpolation_respects_the_allowlist() {
+        // SAFETY: test-only; no other test reads this variable.
+        unsafe { std::env::set_var("PROMPT_PARSER_TEST_MODEL", "anthropic/claude-sonnet-4") };
+
+        let source = "---\nname: x\nclient: ${PROMPT_PARSER_TEST_MODEL}\n---\nbody ${NOT_TOUCHED}";
+        let def = parse_with_env(source, &["PROMPT_PARSER_TEST_MODEL"]).unwrap();
+        assert_eq!(def.client.as_deref(), Some("anthropic/claude-sonnet-4"));
+        // The body is never interpolated.
+        assert_eq!(def.body, "body ${NOT_TOUCHED}");
+
+        // Outside the allowlist: refused, with the variable named.
+        let err = parse_with_env(source, &[]).unwrap_err();
+        assert!(err.to_string().contains("PROMPT_PARSER_TEST_MODEL"), "{err}");
+
+        // Allowlisted but unset: also an error.
+        let unset = "---\nname: x\ndescription: ${PROMPT_PARSER_TEST_UNSET}\n---\nbody";
+        let err = parse_with_env(unset, &["PROMPT_PARSER_TEST_UNSET"]).unwrap_err();
+        assert!(err.to_string().contains("not set"), "{err}");
+
+        // Plain parse never interpolates.
+        assert!(parse(source).is_err());
+    }
+
+    #[test]
+    fn fence_inside_yaml_string_is_not_a_terminator() {
+        let def = parse("---\nname: x\ndescription: \"a --- b\"\n---\nbody").unwrap();
+        assert_eq!(def.description.as_deref(), Some("a --- b"));
+        assert_eq!(def.body, "body");
+    }
+}
@@ -0,0 +1,229 @@
+//! Partial parsing with accumulated diagnostics.
+//!
+//! [`crate::parse`] is all-or-nothing, which is right for runtime but useless
+//! for an editor holding a half-written file: one bad line hides every other
+//! problem. [`parse_partial`] keeps whatever it can recover — frontmatter
+//! fields even when the template fails to compile, the body even when the
+//! YAML is broken — and reports everything wrong at once.
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::schema::ValidationOptions;
+use crate::{parser, template};
+
+/// How bad a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+    /// The definition cannot be used as written.
+    Error,
+    /// Suspicious but renderable, e.g. frontmatter/body drift.
+    Warning,
+}
+
+/// One problem found while partially parsing a prompt file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+/// The result of [`parse_partial`]: a best-effort definition plus everything
+/// that is wrong with it.
+#[derive(Debug, Clone, Default)]
+pub struct PartialParse {
+    /// As much of the definition as could be recovered. Not validated —
+    /// render it only when there are no error diagnostics.
+    pub definition: PromptDefinition,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl PartialParse {
+    /// True when a strict [`crate::parse`] would have succeeded.
+    pub fn is_valid(&self) -> bool {
+        !self
+            .diagnostics
+            .iter()
+            .any(|d| d.severity == DiagnosticSeverity::Error)
+    }
+
+    fn error(&mut self, message: impl std::fmt::Display) {
+        self.diagnostics.push(Diagnostic {
+            severity: DiagnosticSeverity::Error,
+            message: message.to_string(),
+        });
+    }
+
+    fn warning(&mut self, message: impl std::fmt::Display) {
+        self.diagnostics.push(Diagnostic {
+            severity: DiagnosticSeverity::Warning,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Parse as much of `source` as possible, accumulating diagnostics instead
+/// of stopping at the first error.
+pub fn parse_partial(source: &str) -> PartialParse {
+    let mut result = PartialParse::default();
+
+    // Recover the frontmatter/body split even when the fences are broken.
+    let (frontmatter, body) = match parser::split_frontmatter(source) {
+        Ok(split) => split,
+        Err(e @ PromptError::MissingFrontmatter) => {
+            result.error(e);
+            ("", source)
+        }
+        Err(e) => {
+            // Unterminated: everything after the opening fence is
+            // frontmatter-in-progress; there is no body yet.
+            result.error(e);
+            let rest = source
+                .strip_prefix("---\n")
+                .or_else(|| source.strip_prefix("---\r\n"))
+                .unwrap_or(source);
+            (rest, "")
+        }
+    };
+
+    if !frontmatter.is_empty() {
+        match serde_yaml::from_str::<serde_yaml::Value>(frontmatter)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| serde_json::to_value(&yaml).map_err(|e| e.to_string()))
+            .and_then(|json| {
+                serde_json::from_value::<PromptDefinition>(json).map_err(|e| e.to_string())
+            }) {
+            Ok(def) => result.definition = def,
+            Err(e) => result.error(format!("invalid frontmatter: {e}")),
+        }
+    }
+    result.definition.body = body.to_string();
+    let def = result.definition.clone();
+
+    if def.name.trim().is_empty() {
+        result.error("`name` must be non-empty");
+    }
+    if let Err(e) = parser::validate_model_parameters(&def) {
+        result.error(e);
+    }
+    if let Err(e) = parser::validate_prompt_type(&def) {
+        result.error(e);
+    }
+    if let Some(client) = &def.client {
+        match crate::clients::resolve_client(client) {
+            Ok(resolved) => result.definition.client = Some(resolved.to_string()),
+            Err(e) => result.error(e),
+        }
+    }
+
+    let options = ValidationOptions::default();
+    for (field, schema) in [("inputs", &def.inputs), ("output", &def.output)] {
+        if let Some(schema) = schema {
+            let schema = if field == "inputs" {
+                crate::media::sanitize_schema(schema)
+            } else {
+                std::borrow::Cow::Borrowed(schema)
+            };
+            if let Err(e) = crate::cache::validator(field, &schema, &options) {
+                result.error(e);
+            }
+        }
+    }
+
+    if let Err(e) = crate::golden::validate_cases(&def) {
+        result.error(e);
+    }
+    if let Err(e) = crate::locale::validate_locales(&def) {
+        result.error(e);
+    }
+    if let Err(e) = crate::tools::validate_tools(&def) {
+        result.error(e);
+    }
+
+    let mut body_ok = true;
+    if let Err(e) = template::parse_template(&def.body) {
+        result.error(e);
+        body_ok = false;
+    }
+    if let Some(system) = &def.system
+        && let Err(e) = template::parse_template(system)
+    {
+        result.error(format!("system: {e}"));
+    }
+
+    // Drift between body and schema renders fine but is usually a mistake.
+    if body_ok && let Ok(coverage) = crate::check_input_coverage(&def) {
+        for var in &coverage.undeclared {
+            result.warning(format!("body references undeclared input `{var}`"));
+        }
+        for name in &coverage.unused {
+            result.warning(format!("declared input `{name}` is never referenced"));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_files_have_no_diagnostics() {
+        let result = parse_partial("---\nname: x\n---\nHi {{ who }}");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+        assert_eq!(result.definition.name, "x");
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    fn broken_template_still_yields_frontmatter() {
+        let result = parse_partial(
+            "---\nname: x\ndescription: Hello\ntemperature: 9\n---\n{{#if a}}unclosed",
+        );
+        assert!(!result.is_valid());
+        assert_eq!(result.definition.name, "x");
+        assert_eq!(result.definition.description.as_deref(), Some("Hello"));
+        let messages: Vec<&str> = result
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert!(messages.iter().any(|m| m.contains("temperature")), "{messages:?}");
+        assert!(messages.iter().any(|m| m.contains("unclosed")), "{messages:?}");
+    }
+
+    #[test]
+    fn broken_yaml_still_yields_the_body() {
+        let result = parse_partial("---\nname: [unclosed\n---\nThe body");
+        assert!(!result.is_valid());
+        assert_eq!(result.definition.body, "The body");
+    }
+
+    #[test]
+    fn missing_and_unterminated_fences_are_recovered() {
+        let result = parse_partial("just a body");
+        assert!(!result.is_valid());
+        assert_eq!(result.definition.body, "just a body");
+
+        // Unterminated fence: the YAML written so far still parses.
+        let result = parse_partial("---\nname: x\ndescription: wip");
+        assert!(!result.is_valid());
+        assert_eq!(result.definition.name, "x");
+        assert_eq!(result.definition.description.as_deref(), Some("wip"));
+        assert_eq!(result.definition.body, "");
+    }
+
+    #[test]
+    fn coverage_drift_is_a_warning() {
+        let result = parse_partial(
+            "---\nname: x\ninputs:\n  type: object\n  additionalProperties: false\n  properties:\n    a: { type: string }\n---\n{{ b }}",
+        );
+        assert!(result.is_valid());
+        let warnings: Vec<&Diagnostic> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == DiagnosticSeverity::Warning)
+            .collect();
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+    }
+}
@@ -0,0 +1,181 @@
+//! Per-model pricing and cost estimation.
+//!
+//! Prices change faster than releases ship, so the built-in table is only a
+//! default: hosts override entries at runtime ([`set_pricing`]) from their own
+//! config. Lookup is by `client` string, exact match first, then longest
+//! prefix so dated model ids (`anthropic/claude-sonnet-4-20250514`) inherit
+//! the family entry.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// USD per million tokens, split by direction.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// The result of [`estimate_cost`], in USD.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub input_cost: f64,
+    /// Worst case: assumes the full output budget is used.
+    pub max_output_cost: f64,
+}
+
+impl CostEstimate {
+    pub fn total(&self) -> f64 {
+        self.input_cost + self.max_output_cost
+    }
+}
+
+fn default_table() -> HashMap<String, ModelPricing> {
+    let entry = |i, o| ModelPricing {
+        input_per_mtok: i,
+        output_per_mtok: o,
+    };
+    HashMap::from([
+        ("anthropic/claude-opus-4".to_string(), entry(15.0, 75.0)),
+        ("anthropic/claude-sonnet-4".to_string(), entry(3.0, 15.0)),
+        ("anthropic/claude-haiku-3.5".to_string(), entry(0.8, 4.0)),
+        ("openai/gpt-4o".to_string(), entry(2.5, 10.0)),
+        ("openai/gpt-4o-mini".to_string(), entry(0.15, 0.6)),
+        ("openai/gpt-4.1".to_string(), entry(2.0, 8.0)),
+        ("openai/o1".to_string(), entry(15.0, 60.0)),
+        ("openai/o3-mini".to_string(), entry(1.1, 4.4)),
+    ])
+}
+
+static OVERRIDES: LazyLock<RwLock<HashMap<String, ModelPricing>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+static DEFAULTS: LazyLock<HashMap<String, ModelPricing>> = LazyLock::new(default_table);
+
+/// Install or replace the pricing for a client string. Overrides win over the
+/// built-in table and participate in prefix matching.
+pub fn set_pricing(client: &str, pricing: ModelPricing) {
+    OVERRIDES
+        .write()
+        .unwrap()
+        .insert(client.to_string(), pricing);
+}
+
+/// Remove every runtime override, restoring the built-in table.
+pub fn clear_pricing_overrides() {
+    OVERRIDES.write().unwrap().clear();
+}
+
+/// Pricing for a client string: exact match first, then the longest prefix
+/// entry. `None` when the model is unknown to both tables.
+pub fn pricing_for(client: &str) -> Option<ModelPricing> {
+    let overrides = OVERRIDES.read().unwrap();
+    let candidates = overrides
+        .iter()
+        .map(|(k, v)| (k, v, true))
+        .chain(DEFAULTS.iter().map(|(k, v)| (k, v, false)));
+    candidates
+        .filter(|(key, ..)| client == key.as_str() || client.starts_with(&format!("{key}-")))
+        // Most specific entry wins; an override beats a default of equal length.
+        .max_by_key(|(key, _, is_override)| (key.len(), *is_override))
+        .map(|(_, pricing, _)| *pricing)
+}
+
+/// Estimate cost in USD for `prompt_tokens` in and up to `max_output_tokens`
+/// out. `None` when the client has no pricing entry.
+pub fn estimate_cost(
+    client: &str,
+    prompt_tokens: usize,
+    max_output_tokens: usize,
+) -> Option<CostEstimate> {
+    let pricing = pricing_for(client)?;
+    Some(CostEstimate {
+        input_cost: prompt_tokens as f64 * pricing.input_per_mtok / 1_000_000.0,
+        max_output_cost: max_output_tokens as f64 * pricing.output_per_mtok / 1_000_000.0,
+    })
+}
+
+impl PromptDefinition {
+    /// Estimate the cost of one execution: counted prompt tokens in, the
+    /// frontmatter `max_tokens` budget out. `None` when `client` is missing
+    /// or has no pricing entry.
+    pub fn estimate_cost(&self, data: &Value) -> Result<Option<CostEstimate>, PromptError> {
+        let Some(client) = self.client.as_deref() else {
+            return Ok(None);
+        };
+        let prompt_tokens = self.count_tokens(data)?;
+        let max_output = self.max_tokens.unwrap_or(0) as usize;
+        Ok(estimate_cost(client, prompt_tokens, max_output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn exact_and_prefix_lookup() {
+        let sonnet = pricing_for("anthropic/claude-sonnet-4").unwrap();
+        assert_eq!(sonnet.input_per_mtok, 3.0);
+        // Dated ids inherit the family entry.
+        let dated = pricing_for("anthropic/claude-sonnet-4-20250514").unwrap();
+        assert_eq!(dated, sonnet);
+        assert!(pricing_for("acme/unknown-model").is_none());
+        // Prefix matching requires a `-` boundary; `gpt-4o` must not match
+        // a hypothetical `gpt-4o2`.
+        assert!(pricing_for("openai/gpt-4o2").is_none());
+    }
+
+    #[test]
+    fn estimates_scale_with_tokens() {
+        // openai/o1 is untouched by the override test, so this is race-free
+        // under the parallel runner.
+        let est = estimate_cost("openai/o1", 1_000_000, 500_000).unwrap();
+        assert_eq!(est.input_cost, 15.0);
+        assert_eq!(est.max_output_cost, 30.0);
+        assert_eq!(est.total(), 45.0);
+    }
+
+    #[test]
+    fn overrides_win_and_clear() {
+        set_pricing(
+            "acme/frontier-1",
+            ModelPricing {
+                input_per_mtok: 1.0,
+                output_per_mtok: 2.0,
+            },
+        );
+        assert!(pricing_for("acme/frontier-1").is_some());
+        set_pricing(
+            "openai/gpt-4o",
+            ModelPricing {
+                input_per_mtok: 99.0,
+                output_per_mtok: 99.0,
+            },
+        );
+        assert_eq!(pricing_for("openai/gpt-4o").unwrap().input_per_mtok, 99.0);
+        clear_pricing_overrides();
+        assert_eq!(pricing_for("openai/gpt-4o").unwrap().input_per_mtok, 2.5);
+        assert!(pricing_for("acme/frontier-1").is_none());
+    }
+
+    #[test]
+    fn definition_estimate_uses_client_and_max_tokens() {
+        let def = PromptDefinition::parse(
+            "---\nname: t\nclient: anthropic/claude-sonnet-4\nmax_tokens: 1000\n---\nhello",
+        )
+        .unwrap();
+        let est = def.estimate_cost(&json!({})).unwrap().unwrap();
+        assert!(est.input_cost > 0.0);
+        assert_eq!(est.max_output_cost, 1000.0 * 15.0 / 1_000_000.0);
+
+        let no_client = PromptDefinition::parse("---\nname: t\n---\nhello").unwrap();
+        assert!(no_client.estimate_cost(&json!({})).unwrap().is_none());
+    }
+}
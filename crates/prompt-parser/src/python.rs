@@ -0,0 +1,63 @@
+//! pyo3 bindings for Python data/eval pipelines.
+//!
+//! Exposes the same prompt format the Rust/Zig runtime consumes, so Python
+//! tooling never reimplements parsing. Compiled only with the `python`
+//! feature (typically via maturin):
+//!
+//! ```sh
+//! maturin build -m crates/prompt-parser/Cargo.toml --features python
+//! ```
+//!
+//! Definitions and messages cross the boundary as plain dicts/lists via
+//! pythonize; errors raise `ValueError` with the library's messages.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+
+fn py_err(e: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+fn from_py(data: &Bound<'_, PyAny>) -> PyResult<Value> {
+    pythonize::depythonize(data).map_err(py_err)
+}
+
+/// Parse a prompt file; returns the definition as a dict.
+#[pyfunction]
+fn parse_prompt_file(py: Python<'_>, source: &str) -> PyResult<PyObject> {
+    let def = PromptDefinition::parse(source).map_err(py_err)?;
+    Ok(pythonize::pythonize(py, &def).map_err(py_err)?.unbind())
+}
+
+/// Parse, validate `inputs` against the schema, and render the body.
+#[pyfunction]
+fn render(source: &str, inputs: &Bound<'_, PyAny>) -> PyResult<String> {
+    let def = PromptDefinition::parse(source).map_err(py_err)?;
+    def.render(&from_py(inputs)?).map_err(py_err)
+}
+
+/// Parse, validate, and render the provider-ready message list.
+#[pyfunction]
+fn render_messages(py: Python<'_>, source: &str, inputs: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let def = PromptDefinition::parse(source).map_err(py_err)?;
+    let messages = def.render_messages(&from_py(inputs)?).map_err(py_err)?;
+    Ok(pythonize::pythonize(py, &messages).map_err(py_err)?.unbind())
+}
+
+/// Validate `data` against `schema` (newest draft). Raises on violation.
+#[pyfunction]
+fn validate(schema: &Bound<'_, PyAny>, data: &Bound<'_, PyAny>) -> PyResult<()> {
+    crate::schema::validate_json(&from_py(schema)?, &from_py(data)?).map_err(py_err)
+}
+
+#[pymodule]
+fn prompt_parser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_prompt_file, m)?)?;
+    m.add_function(wrap_pyfunction!(render, m)?)?;
+    m.add_function(wrap_pyfunction!(render_messages, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    Ok(())
+}
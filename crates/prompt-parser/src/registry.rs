@@ -0,0 +1,249 @@
+//! The prompt registry: named definitions and sub-prompt composition.
+//!
+//! Prompt libraries are built from small composable pieces: a body may
+//! reference another registered prompt with `{{> refine_answer}}` (passing
+//! the current render data through) or `{{> refine_answer ctx}}` (passing the
+//! value at `ctx` as the sub-prompt's inputs). The referenced prompt
+//! validates its own `inputs` schema and renders with its own declared tools,
+//! exactly as if rendered directly; circular references are an error.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::template::{self, PartialRenderer, RenderLimits};
+
+/// A set of named prompt definitions that can reference each other.
+#[derive(Debug, Clone, Default)]
+pub struct PromptRegistry {
+    prompts: HashMap<String, PromptDefinition>,
+    limits: RenderLimits,
+}
+
+impl PromptRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// [`PromptRegistry::new`] with explicit sandbox limits, applied to each
+    /// prompt render in a composition tree.
+    pub fn with_limits(limits: RenderLimits) -> Self {
+        PromptRegistry {
+            prompts: HashMap::new(),
+            limits,
+        }
+    }
+
+    /// Register a definition under its name, replacing any previous one.
+    pub fn register(&mut self, def: PromptDefinition) {
+        self.prompts.insert(def.name.clone(), def);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PromptDefinition> {
+        self.prompts.get(name)
+    }
+
+    /// Unregister a definition, returning it if it was present.
+    pub fn remove(&mut self, name: &str) -> Option<PromptDefinition> {
+        self.prompts.remove(name)
+    }
+
+    /// Registered prompt names, sorted for stable output.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.prompts.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Parse every `.md` file under `dir` (recursively, in sorted order) and
+    /// register the results.
+    ///
+    /// One broken file does not fail the scan: each file's outcome is
+    /// reported in the returned list and only the unreadable-directory case
+    /// is an error. Files go through [`crate::parse_file`], so `extends` and
+    /// external schema references resolve; a later file registering an
+    /// already-taken name replaces the earlier definition.
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<Vec<FileResult>, PromptError> {
+        let mut files = Vec::new();
+        collect_md_files(dir.as_ref(), &mut files)?;
+        files.sort();
+        let mut results = Vec::with_capacity(files.len());
+        for path in files {
+            let (name, error) = match crate::parser::parse_file(&path) {
+                Ok(def) => {
+                    let name = def.name.clone();
+                    self.register(def);
+                    (Some(name), None)
+                }
+                Err(e) => (None, Some(e.to_string())),
+            };
+            results.push(FileResult {
+                path: path.display().to_string(),
+                name,
+                error,
+            });
+        }
+        tracing::debug!(
+            files = results.len(),
+            errors = results.iter().filter(|r| r.error.is_some()).count(),
+            "scanned prompt directory"
+        );
+        Ok(results)
+    }
+
+    /// Render the named prompt, resolving `{{> ...}}` references against
+    /// this registry.
+    pub fn render(&self, name: &str, data: &Value) -> Result<String, PromptError> {
+        let resolver = RegistryPartials {
+            registry: self,
+            stack: RefCell::new(Vec::new()),
+        };
+        resolver.render_partial(name, data)
+    }
+}
+
+/// One file's outcome from [`PromptRegistry::load_dir`]: the registered
+/// prompt name on success, the parse error otherwise.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileResult {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn collect_md_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), PromptError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| PromptError::Io {
+        path: dir.display().to_string(),
+        message: e.to_string(),
+    })?;
+    for entry in entries {
+        let entry = entry.map_err(|e| PromptError::Io {
+            path: dir.display().to_string(),
+            message: e.to_string(),
+        })?;
+        let path = entry.path();
+        if path.is_dir() {
+            collect_md_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Resolves partials against a registry, tracking the chain of prompts
+/// currently rendering so cycles fail instead of recursing forever.
+struct RegistryPartials<'a> {
+    registry: &'a PromptRegistry,
+    stack: RefCell<Vec<String>>,
+}
+
+impl PartialRenderer for RegistryPartials<'_> {
+    fn render_partial(&self, name: &str, data: &Value) -> Result<String, PromptError> {
+        let def = self
+            .registry
+            .get(name)
+            .ok_or_else(|| PromptError::UnknownPrompt(name.to_string()))?;
+        if self.stack.borrow().iter().any(|entry| entry == name) {
+            return Err(PromptError::Template(format!(
+                "circular prompt reference `{name}` (via {})",
+                self.stack.borrow().join(" > ")
+            )));
+        }
+
+        if let Some(inputs) = &def.inputs {
+            crate::media::validate_inputs(inputs, data)?;
+        }
+        let ctx = crate::tools::context_with_tools(def, data);
+
+        self.stack.borrow_mut().push(name.to_string());
+        let rendered =
+            template::render_template_partials(&def.body, &ctx, &self.registry.limits, self);
+        self.stack.borrow_mut().pop();
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use serde_json::json;
+
+    fn registry_with(sources: &[&str]) -> PromptRegistry {
+        let mut registry = PromptRegistry::new();
+        for source in sources {
+            registry.register(parse(source).unwrap());
+        }
+        registry
+    }
+
+    #[test]
+    fn partials_render_inline_with_their_own_validation() {
+        let registry = registry_with(&[
+            "---\nname: header\ninputs:\n  type: object\n  properties:\n    title: { type: string }\n  required: [title]\n---\n# {{ title }}",
+            "---\nname: page\n---\n{{> header}}\n\nBody for {{ title }}.",
+        ]);
+
+        assert_eq!(
+            registry.render("page", &json!({ "title": "Hi" })).unwrap(),
+            "# Hi\n\nBody for Hi."
+        );
+        // The sub-prompt's schema rejects data the outer prompt accepted.
+        assert!(matches!(
+            registry.render("page", &json!({})).unwrap_err(),
+            PromptError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn argument_path_selects_the_sub_prompt_inputs() {
+        let registry = registry_with(&[
+            "---\nname: user-line\n---\n{{ name }} <{{ email }}>",
+            "---\nname: report\n---\n{{#each users}}{{> user-line this}}\n{{/each}}",
+        ]);
+        let data = json!({ "users": [
+            { "name": "Ada", "email": "ada@example.com" },
+            { "name": "Bob", "email": "bob@example.com" }
+        ]});
+        assert_eq!(
+            registry.render("report", &data).unwrap(),
+            "Ada <ada@example.com>\nBob <bob@example.com>\n"
+        );
+    }
+
+    #[test]
+    fn unknown_and_circular_references_error() {
+        let registry = registry_with(&["---\nname: a\n---\n{{> b}}"]);
+        assert!(matches!(
+            registry.render("a", &json!({})).unwrap_err(),
+            PromptError::UnknownPrompt(name) if name == "b"
+        ));
+        assert!(matches!(
+            registry.render("nope", &json!({})).unwrap_err(),
+            PromptError::UnknownPrompt(_)
+        ));
+
+        let registry = registry_with(&[
+            "---\nname: a\n---\n{{> b}}",
+            "---\nname: b\n---\n{{> a}}",
+        ]);
+        let err = registry.render("a", &json!({})).unwrap_err();
+        assert!(err.to_string().contains("circular"), "{err}");
+    }
+
+    #[test]
+    fn partials_outside_a_registry_are_rejected() {
+        let def = parse("---\nname: x\n---\n{{> other}}").unwrap();
+        assert!(matches!(
+            def.render(&json!({})).unwrap_err(),
+            PromptError::Template(_)
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+//! External schema file resolution.
+//!
+//! Large shared schemas shouldn't be duplicated inline in YAML shorthand, so
+//! a prompt parsed from disk (via [`crate::parse_file`]) may declare
+//! `inputs: ./schemas/task.schema.json`, and any schema node may use
+//! `$ref: ./other.schema.json` (optionally with a `#/json/pointer` fragment).
+//! Paths resolve relative to the file containing them; internal `#/...` refs
+//! are left for the validator. Resolution inlines the referenced document, so
+//! the rest of the crate only ever sees self-contained schemas.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// Resolve `inputs`/`output` declared as file paths and external `$ref`s,
+/// relative to `base_dir` (the prompt file's directory).
+pub(crate) fn resolve_external_schemas(
+    def: &mut PromptDefinition,
+    base_dir: &Path,
+) -> Result<(), PromptError> {
+    for (field, slot) in [("inputs", &mut def.inputs), ("output", &mut def.output)] {
+        if let Some(schema) = slot.take() {
+            let mut stack = Vec::new();
+            *slot = Some(resolve_value(field, schema, base_dir, &mut stack)?);
+        }
+    }
+    Ok(())
+}
+
+fn resolve_value(
+    field: &str,
+    value: Value,
+    base_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Value, PromptError> {
+    match value {
+        // `inputs: ./schemas/task.schema.json` — the whole schema is a file.
+        Value::String(reference) => load_external(field, &reference, base_dir, stack),
+        other => resolve_refs(field, other, base_dir, stack),
+    }
+}
+
+/// Walk a schema, inlining every external `$ref`.
+fn resolve_refs(
+    field: &str,
+    value: Value,
+    base_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Value, PromptError> {
+    match value {
+        Value::Object(obj) => {
+            if let Some(reference) = obj.get("$ref").and_then(Value::as_str)
+                && !reference.starts_with('#')
+            {
+                // External ref: the whole node is replaced by the target.
+                return load_external(field, reference, base_dir, stack);
+            }
+            let mut out = serde_json::Map::with_capacity(obj.len());
+            for (key, child) in obj {
+                out.insert(key, resolve_refs(field, child, base_dir, stack)?);
+            }
+            Ok(Value::Object(out))
+        }
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| resolve_refs(field, item, base_dir, stack))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        other => Ok(other),
+    }
+}
+
+/// Load `path[#/pointer]` relative to `base_dir`, then resolve refs inside it
+/// relative to *its* directory. `stack` holds the chain of files currently
+/// being loaded, for cycle detection.
+fn load_external(
+    field: &str,
+    reference: &str,
+    base_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Value, PromptError> {
+    let (path_part, fragment) = match reference.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (reference, None),
+    };
+    let path = base_dir.join(path_part);
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+    if stack.contains(&canonical) {
+        return Err(PromptError::Schema {
+            field: field.to_string(),
+            message: format!("circular schema reference through `{}`", path.display()),
+        });
+    }
+
+    let text = std::fs::read_to_string(&path).map_err(|e| PromptError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+    let document: Value = if matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml" | "yml")
+    ) {
+        serde_yaml::from_str::<serde_yaml::Value>(&text)
+            .map_err(|e| schema_error(field, &path, &e.to_string()))
+            .and_then(|yaml| {
+                serde_json::to_value(yaml).map_err(|e| schema_error(field, &path, &e.to_string()))
+            })?
+    } else {
+        serde_json::from_str(&text).map_err(|e| schema_error(field, &path, &e.to_string()))?
+    };
+
+    let target = match fragment {
+        None | Some("") => document,
+        Some(pointer) => document
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| schema_error(field, &path, &format!("no value at `#{pointer}`")))?,
+    };
+
+    stack.push(canonical);
+    let parent = path.parent().unwrap_or(base_dir);
+    let resolved = resolve_refs(field, target, parent, stack);
+    stack.pop();
+    resolved
+}
+
+fn schema_error(field: &str, path: &Path, message: &str) -> PromptError {
+    PromptError::Schema {
+        field: field.to_string(),
+        message: format!("{}: {message}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PromptError, parse_file};
+
+    fn write(dir: &std::path::Path, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn temp_dir(label: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("prompt-parser-{label}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn whole_schema_from_file() {
+        let dir = temp_dir("whole");
+        write(
+            &dir,
+            "schemas/task.schema.json",
+            r#"{ "type": "object", "properties": { "title": { "type": "string" } }, "required": ["title"] }"#,
+        );
+        let prompt = write(
+            &dir,
+            "task.prompt.md",
+            "---\nname: task\ninputs: ./schemas/task.schema.json\n---\n{{ title }}",
+        );
+
+        let def = parse_file(&prompt).unwrap();
+        assert!(def.render(&serde_json::json!({ "title": "x" })).is_ok());
+        assert!(matches!(
+            def.render(&serde_json::json!({})).unwrap_err(),
+            PromptError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn field_refs_resolve_with_fragments_and_nesting() {
+        let dir = temp_dir("refs");
+        write(
+            &dir,
+            "schemas/common.schema.json",
+            r#"{ "definitions": { "id": { "$ref": "./id.schema.json" } } }"#,
+        );
+        // Nested ref resolves relative to common.schema.json, not the prompt.
+        write(&dir, "schemas/id.schema.json", r#"{ "type": "integer" }"#);
+        let prompt = write(
+            &dir,
+            "lookup.prompt.md",
+            "---\nname: lookup\ninputs:\n  type: object\n  properties:\n    id:\n      $ref: ./schemas/common.schema.json#/definitions/id\n---\n{{ id }}",
+        );
+
+        let def = parse_file(&prompt).unwrap();
+        assert_eq!(
+            def.inputs.as_ref().unwrap().pointer("/properties/id/type"),
+            Some(&serde_json::json!("integer"))
+        );
+    }
+
+    #[test]
+    fn circular_references_are_detected() {
+        let dir = temp_dir("cycle");
+        write(&dir, "a.schema.json", r#"{ "$ref": "./b.schema.json" }"#);
+        write(&dir, "b.schema.json", r#"{ "$ref": "./a.schema.json" }"#);
+        let prompt = write(
+            &dir,
+            "c.prompt.md",
+            "---\nname: c\ninputs: ./a.schema.json\n---\nbody",
+        );
+
+        let err = parse_file(&prompt).unwrap_err();
+        assert!(matches!(err, PromptError::Schema { .. }), "{err}");
+        assert!(err.to_string().contains("circular"), "{err}");
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        let dir = temp_dir("missing");
+        let prompt = write(
+            &dir,
+            "m.prompt.md",
+            "---\nname: m\ninputs: ./nope.schema.json\n---\nbody",
+        );
+        assert!(matches!(
+            parse_file(&prompt).unwrap_err(),
+            PromptError::Io { .. }
+        ));
+    }
+
+    #[test]
+    fn string_schemas_require_a_file_context() {
+        // Plain parse() has no directory to resolve against.
+        let err =
+            crate::parse("---\nname: x\ninputs: ./task.schema.json\n---\nbody").unwrap_err();
+        assert!(matches!(err, PromptError::Schema { .. }), "{err}");
+    }
+}
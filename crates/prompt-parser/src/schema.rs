@@ -0,0 +1,261 @@
+//! JSON Schema compilation and validation.
+
+use jsonschema::{Draft, Validator};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::PromptError;
+
+/// One schema violation, with enough structure for a host to highlight the
+/// exact offending field instead of showing a joined string.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationViolation {
+    /// JSON pointer into the validated data, e.g. `/items/0/id`.
+    pub instance_path: String,
+    /// JSON pointer into the schema that rejected it.
+    pub schema_path: String,
+    /// The failing keyword (`type`, `required`, `enum`, ...).
+    pub keyword: String,
+    /// What the schema wanted, when the keyword makes that expressible.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    /// The offending value.
+    pub actual: Value,
+    /// Human-readable description of this one violation.
+    pub message: String,
+}
+
+/// The legacy `; `-joined rendering, used by `Display` on
+/// [`PromptError::Validation`].
+pub(crate) fn format_violations(violations: &[ValidationViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| {
+            if v.instance_path.is_empty() {
+                v.message.clone()
+            } else {
+                format!("{}: {}", v.instance_path, v.message)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// JSON Schema draft used when the schema has no `$schema` declaration.
+///
+/// An explicit `$schema` in the document always wins; the draft here only
+/// picks the dialect for undeclared schemas, which is what prompt frontmatter
+/// overwhelmingly contains.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaDraft {
+    Draft7,
+    Draft201909,
+    /// The newest supported draft; the default. Generated schemas
+    /// increasingly rely on `$defs` and `prefixItems`, which older drafts
+    /// mishandle.
+    #[default]
+    Draft202012,
+}
+
+impl From<SchemaDraft> for Draft {
+    fn from(draft: SchemaDraft) -> Self {
+        match draft {
+            SchemaDraft::Draft7 => Draft::Draft7,
+            SchemaDraft::Draft201909 => Draft::Draft201909,
+            SchemaDraft::Draft202012 => Draft::Draft202012,
+        }
+    }
+}
+
+/// Options controlling schema validation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// Dialect for schemas without `$schema`. Defaults to the newest draft.
+    pub draft: SchemaDraft,
+    /// Enforce `format` keywords (email, uri, date-time, uuid, ...) as
+    /// assertions instead of treating them as annotations. Off by default,
+    /// matching the specification.
+    pub assert_formats: bool,
+}
+
+/// Compile a schema, surfacing compilation problems as a [`PromptError::Schema`].
+pub(crate) fn compile(
+    field: &str,
+    schema: &Value,
+    options: &ValidationOptions,
+) -> Result<Validator, PromptError> {
+    let mut builder = jsonschema::options();
+    // `with_draft` overrides even an explicit `$schema`; only force the
+    // dialect when the schema leaves it undeclared.
+    if schema.get("$schema").is_none() {
+        builder.with_draft(options.draft.into());
+    }
+    builder.should_validate_formats(options.assert_formats);
+    builder
+        .build(schema)
+        .map_err(|e| PromptError::Schema {
+            field: field.to_string(),
+            message: e.to_string(),
+        })
+}
+
+/// Validate `data` against `schema` with default options (newest draft).
+/// On failure every violation is collected into
+/// [`PromptError::Validation`] as structured [`ValidationViolation`]s.
+pub fn validate_json(schema: &Value, data: &Value) -> Result<(), PromptError> {
+    validate_json_with(schema, data, &ValidationOptions::default())
+}
+
+/// [`validate_json`] with explicit [`ValidationOptions`].
+pub fn validate_json_with(
+    schema: &Value,
+    data: &Value,
+    options: &ValidationOptions,
+) -> Result<(), PromptError> {
+    let validator = crate::cache::validator("schema", schema, options)?;
+    let violations: Vec<ValidationViolation> = validator
+        .iter_errors(data)
+        .map(|e| {
+            let schema_path = e.schema_path.to_string();
+            let keyword = schema_path.rsplit('/').next().unwrap_or("").to_string();
+            ValidationViolation {
+                instance_path: e.instance_path.to_string(),
+                expected: expected_of(&e.kind),
+                actual: e.instance.clone().into_owned(),
+                message: e.to_string(),
+                schema_path,
+                keyword,
+            }
+        })
+        .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(PromptError::Validation(violations))
+    }
+}
+
+/// What the failing keyword wanted, for the kinds where that is a short
+/// printable thing. Everything else is already spelled out in `message`.
+fn expected_of(kind: &jsonschema::error::ValidationErrorKind) -> Option<String> {
+    use jsonschema::error::ValidationErrorKind as Kind;
+    match kind {
+        Kind::Type { kind } => Some(format!("{kind:?}")),
+        Kind::Enum { options } => Some(options.to_string()),
+        Kind::Required { property } => Some(property.to_string()),
+        Kind::Constant { expected_value } => Some(expected_value.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn accepts_valid_data() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"]
+        });
+        assert!(validate_json(&schema, &json!({ "name": "x" })).is_ok());
+    }
+
+    #[test]
+    fn collects_all_violations() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": "string" },
+                "b": { "type": "integer" }
+            },
+            "required": ["a", "b"]
+        });
+        let err = validate_json(&schema, &json!({ "a": 1, "b": "x" })).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("/a"), "{msg}");
+        assert!(msg.contains("/b"), "{msg}");
+    }
+
+    #[test]
+    fn violations_are_structured() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "n": { "type": "integer" } },
+            "required": ["name"]
+        });
+        let err = validate_json(&schema, &json!({ "n": "five" })).unwrap_err();
+        let PromptError::Validation(violations) = err else {
+            panic!("expected Validation, got {err}");
+        };
+        assert_eq!(violations.len(), 2, "{violations:?}");
+
+        let type_violation = violations.iter().find(|v| v.keyword == "type").unwrap();
+        assert_eq!(type_violation.instance_path, "/n");
+        assert_eq!(type_violation.schema_path, "/properties/n/type");
+        assert_eq!(type_violation.actual, json!("five"));
+        assert!(type_violation.expected.as_deref().is_some_and(|e| e.contains("nteger")));
+
+        let required = violations.iter().find(|v| v.keyword == "required").unwrap();
+        assert_eq!(required.instance_path, "");
+        assert_eq!(required.expected.as_deref(), Some("\"name\""));
+    }
+
+    #[test]
+    fn rejects_invalid_schema() {
+        let err = validate_json(&json!({ "type": 42 }), &json!({})).unwrap_err();
+        assert!(matches!(err, PromptError::Schema { .. }));
+    }
+
+    #[test]
+    fn default_draft_understands_prefix_items() {
+        // `prefixItems` is 2020-12 vocabulary; under Draft 7 it is ignored
+        // and the invalid tuple below would pass.
+        let schema = json!({ "prefixItems": [{ "type": "integer" }] });
+        let data = json!(["not an int"]);
+        assert!(validate_json(&schema, &data).is_err());
+        let draft7 = ValidationOptions {
+            draft: SchemaDraft::Draft7,
+            ..Default::default()
+        };
+        assert!(validate_json_with(&schema, &data, &draft7).is_ok());
+    }
+
+    #[test]
+    fn formats_are_annotations_unless_asserted() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "contact": { "type": "string", "format": "email" },
+                "when": { "type": "string", "format": "date-time" }
+            }
+        });
+        let data = json!({ "contact": "not-an-email", "when": "yesterday" });
+        // Spec behavior: formats annotate, they do not reject.
+        assert!(validate_json(&schema, &data).is_ok());
+
+        let asserting = ValidationOptions {
+            assert_formats: true,
+            ..Default::default()
+        };
+        let err = validate_json_with(&schema, &data, &asserting).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("/contact"), "{msg}");
+        assert!(msg.contains("/when"), "{msg}");
+
+        let ok = json!({ "contact": "a@b.example", "when": "2026-08-31T12:00:00Z" });
+        assert!(validate_json_with(&schema, &ok, &asserting).is_ok());
+    }
+
+    #[test]
+    fn explicit_schema_declaration_wins() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "string"
+        });
+        assert!(validate_json(&schema, &json!("ok")).is_ok());
+        assert!(validate_json(&schema, &json!(1)).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Secret-marked inputs and redacted rendering.
+//!
+//! Credentials flow through prompts — an API key interpolated into a curl
+//! example, a token in a connection string — and every transcript or log
+//! line the host writes is a place for them to leak. An input is marked in
+//! shorthand (`api_key: string (secret)`) or directly in JSON Schema
+//! (`secret: true` on the property), and [`PromptDefinition::render_redacted`]
+//! renders with each secret value replaced by a `[redacted:path]`
+//! placeholder. The real render path is untouched; redaction is what hosts
+//! use for anything they persist.
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+use crate::template;
+
+/// Dotted paths of every `secret: true` property in the `inputs` schema.
+fn secret_paths(schema: &Value, prefix: &str, out: &mut Vec<String>) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (name, property) in properties {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}.{name}")
+        };
+        if property.get("secret").and_then(Value::as_bool) == Some(true) {
+            out.push(path.clone());
+        }
+        secret_paths(property, &path, out);
+    }
+}
+
+fn redact_at(data: &mut Value, path: &str) {
+    let mut current = data;
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        let Some(obj) = current.as_object_mut() else {
+            return;
+        };
+        let Some(value) = obj.get_mut(segment) else {
+            return;
+        };
+        if segments.peek().is_none() {
+            *value = Value::String(format!("[redacted:{path}]"));
+            return;
+        }
+        current = value;
+    }
+}
+
+impl PromptDefinition {
+    /// `data` with every secret-marked input replaced by its
+    /// `[redacted:path]` placeholder. Identity when nothing is marked.
+    pub fn redact_inputs(&self, data: &Value) -> Value {
+        let mut paths = Vec::new();
+        if let Some(inputs) = &self.inputs {
+            secret_paths(inputs, "", &mut paths);
+        }
+        let mut redacted = data.clone();
+        for path in &paths {
+            redact_at(&mut redacted, path);
+        }
+        redacted
+    }
+
+    /// [`Self::render`] for transcripts and logs: the real `data` is
+    /// validated against the `inputs` schema, but secret-marked values
+    /// render as placeholders so the output never contains raw credentials.
+    pub fn render_redacted(&self, data: &Value) -> Result<String, PromptError> {
+        if let Some(inputs) = &self.inputs {
+            crate::media::validate_inputs(inputs, data)?;
+        }
+        let redacted = self.redact_inputs(data);
+        let ctx = crate::tools::context_with_tools(self, &redacted);
+        template::render_template(&self.body, &ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use serde_json::json;
+
+    const SOURCE: &str = "---\nname: deploy\ninputs:\n  host: string\n  api_key: string (secret)  # deployment credential\n---\ncurl -H 'Authorization: {{ api_key }}' https://{{ host }}/";
+
+    #[test]
+    fn shorthand_secret_flag_marks_the_property() {
+        let def = parse(SOURCE).unwrap();
+        let property = &def.inputs.as_ref().unwrap()["properties"]["api_key"];
+        assert_eq!(property["secret"], json!(true));
+        assert_eq!(property["description"], json!("deployment credential"));
+    }
+
+    #[test]
+    fn redacted_render_replaces_secret_values_only() {
+        let def = parse(SOURCE).unwrap();
+        let data = json!({ "host": "api.example.com", "api_key": "sk-live-123" });
+        assert_eq!(
+            def.render_redacted(&data).unwrap(),
+            "curl -H 'Authorization: [redacted:api_key]' https://api.example.com/"
+        );
+        // The real render path is untouched.
+        assert!(def.render(&data).unwrap().contains("sk-live-123"));
+    }
+
+    #[test]
+    fn nested_schema_secrets_redact_too() {
+        let def = parse(
+            "---\nname: x\ninputs:\n  type: object\n  properties:\n    auth:\n      type: object\n      properties:\n        token: { type: string, secret: true }\n---\n{{ auth.token }}",
+        )
+        .unwrap();
+        let redacted = def.redact_inputs(&json!({ "auth": { "token": "t0ps3cret" } }));
+        assert_eq!(redacted["auth"]["token"], json!("[redacted:auth.token]"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Typed shorthand for `inputs`/`output` schemas.
+//!
+//! Full JSON Schema is overkill for most prompts, so a flat mapping of
+//! `name: type` expands into an object schema:
+//!
+//! ```yaml
+//! inputs:
+//!   query: string  # the user's search query
+//!   tags: string[]
+//!   limit: integer?
+//! ```
+//!
+//! Types are `string`, `number`, `integer`, `boolean`, `object`, `array`,
+//! `any`, and the media types `image`/`file`; `[]` nests arrays and a
+//! trailing `?` makes the field optional. Everything else is required and
+//! the expanded schema is closed (`additionalProperties: false`).
+//!
+//! A trailing `# description` lands in the property's `description`, which
+//! tool-calling providers surface to the model. YAML strips unquoted
+//! comments before we see them, so the parser recovers them from the raw
+//! frontmatter text; quoting the whole value works too.
+//!
+//! A mapping that uses any schema keyword (`type`, `properties`, `$ref`, …)
+//! is treated as JSON Schema, untouched. A shorthand field can therefore not
+//! be named `type` — spell that one out as a full schema.
+
+use serde_json::{Map, Value, json};
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// Keys that mark a mapping as real JSON Schema rather than shorthand.
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "type",
+    "properties",
+    "items",
+    "required",
+    "enum",
+    "const",
+    "anyOf",
+    "oneOf",
+    "allOf",
+    "not",
+    "$ref",
+    "$schema",
+    "additionalProperties",
+    "patternProperties",
+    "description",
+];
+
+fn is_shorthand(value: &Value) -> bool {
+    let Some(map) = value.as_object() else {
+        return false;
+    };
+    !map.is_empty()
+        && map.keys().all(|k| !SCHEMA_KEYWORDS.contains(&k.as_str()))
+        && map.values().all(Value::is_string)
+}
+
+/// Parse one `type[]…? (flags) # description` spec into a property schema,
+/// plus whether the field is required.
+fn expand_spec(spec: &str) -> Result<(Value, bool), String> {
+    let (typepart, description) = match spec.split_once('#') {
+        Some((t, d)) => (t.trim(), Some(d.trim())),
+        None => (spec.trim(), None),
+    };
+    let (typepart, flags) = match typepart.strip_suffix(')') {
+        Some(rest) => match rest.rsplit_once('(') {
+            Some((t, flags)) => (t.trim_end(), Some(flags)),
+            None => return Err(format!("unbalanced parentheses in `{typepart}`")),
+        },
+        None => (typepart, None),
+    };
+    let mut secret = false;
+    for flag in flags.iter().flat_map(|f| f.split(',')) {
+        match flag.trim() {
+            "secret" => secret = true,
+            other => return Err(format!("unknown flag `{other}`")),
+        }
+    }
+    let (typepart, required) = match typepart.strip_suffix('?') {
+        Some(t) => (t.trim_end(), false),
+        None => (typepart, true),
+    };
+    let mut depth = 0usize;
+    let mut base = typepart;
+    while let Some(inner) = base.strip_suffix("[]") {
+        depth += 1;
+        base = inner;
+    }
+    let mut schema = match base {
+        "string" | "number" | "integer" | "boolean" | "object" | "array" | "image" | "file" => {
+            json!({ "type": base })
+        }
+        "any" => json!({}),
+        other => return Err(format!("unknown shorthand type `{other}`")),
+    };
+    for _ in 0..depth {
+        schema = json!({ "type": "array", "items": schema });
+    }
+    if secret {
+        schema["secret"] = json!(true);
+    }
+    if let Some(description) = description
+        && !description.is_empty()
+    {
+        schema["description"] = json!(description);
+    }
+    Ok((schema, required))
+}
+
+/// Expand shorthand `inputs`/`output` mappings into full object schemas.
+/// Called by [`crate::parser::finish_definition`] before schema compilation;
+/// full-schema and string (external path) forms pass through untouched.
+pub(crate) fn expand_shorthand(def: &mut PromptDefinition) -> Result<(), PromptError> {
+    for (field, schema) in [("inputs", &mut def.inputs), ("output", &mut def.output)] {
+        let Some(value) = schema else { continue };
+        if !is_shorthand(value) {
+            continue;
+        }
+        let specs = value.as_object().expect("is_shorthand checked");
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for (name, spec) in specs {
+            let spec = spec.as_str().expect("is_shorthand checked");
+            let (property, is_required) = expand_spec(spec).map_err(|message| {
+                PromptError::Schema {
+                    field: field.to_string(),
+                    message: format!("`{name}`: {message}"),
+                }
+            })?;
+            if is_required {
+                required.push(name.clone());
+            }
+            properties.insert(name.clone(), property);
+        }
+        let mut expanded = json!({
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": false,
+        });
+        if !required.is_empty() {
+            expanded["required"] = json!(required);
+        }
+        *value = expanded;
+    }
+    Ok(())
+}
+
+/// Re-attach unquoted `# description` comments that YAML stripped: scan the
+/// raw `inputs:`/`output:` blocks and append each trailing comment to the
+/// corresponding shorthand spec. No-op for full-schema forms.
+pub(crate) fn attach_comment_descriptions(def: &mut PromptDefinition, frontmatter: &str) {
+    for (key, schema) in [("inputs", &mut def.inputs), ("output", &mut def.output)] {
+        let Some(value) = schema else { continue };
+        if !is_shorthand(value) {
+            continue;
+        }
+        let specs = value.as_object_mut().expect("is_shorthand checked");
+        for (name, comment) in block_comments(frontmatter, key) {
+            if let Some(Value::String(spec)) = specs.get_mut(&name)
+                && !spec.contains('#')
+            {
+                spec.push_str("  # ");
+                spec.push_str(&comment);
+            }
+        }
+    }
+}
+
+/// `field name → trailing comment` for the flat entries of a top-level block.
+fn block_comments(frontmatter: &str, key: &str) -> Vec<(String, String)> {
+    let mut comments = Vec::new();
+    let mut in_block = false;
+    for line in frontmatter.lines() {
+        if !line.starts_with([' ', '\t']) {
+            in_block = line
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.starts_with(':'));
+            continue;
+        }
+        if !in_block {
+            continue;
+        }
+        let Some((name, rest)) = line.trim_start().split_once(':') else {
+            continue;
+        };
+        let Some((_, comment)) = rest.split_once('#') else {
+            continue;
+        };
+        let (name, comment) = (name.trim(), comment.trim());
+        if !name.is_empty() && !comment.is_empty() {
+            comments.push((name.to_string(), comment.to_string()));
+        }
+    }
+    comments
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PromptError, parse};
+    use serde_json::json;
+
+    #[test]
+    fn shorthand_expands_to_a_closed_object_schema() {
+        let def = parse(
+            "---\nname: search\ninputs:\n  query: string\n  tags: string[]\n  limit: integer?\n---\n{{ query }}",
+        )
+        .unwrap();
+        assert_eq!(
+            def.inputs,
+            Some(json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "limit": { "type": "integer" },
+                },
+                "required": ["query", "tags"],
+                "additionalProperties": false,
+            }))
+        );
+        // Closed and required are enforced.
+        assert!(def.render(&json!({ "tags": [] })).is_err());
+        assert!(
+            def.render(&json!({ "query": "x", "tags": [], "extra": 1 }))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn trailing_comments_become_descriptions() {
+        let def = parse(
+            "---\nname: search\ninputs:\n  query: string  # the user's search query\n  limit: \"integer? # page size\"\n---\n{{ query }}",
+        )
+        .unwrap();
+        let properties = &def.inputs.as_ref().unwrap()["properties"];
+        assert_eq!(
+            properties["query"]["description"],
+            json!("the user's search query")
+        );
+        assert_eq!(properties["limit"]["description"], json!("page size"));
+    }
+
+    #[test]
+    fn full_schemas_are_left_alone() {
+        let def = parse("---\nname: x\ninputs:\n  type: object\n---\nbody").unwrap();
+        assert_eq!(def.inputs, Some(json!({ "type": "object" })));
+    }
+
+    #[test]
+    fn unknown_shorthand_types_fail_at_parse() {
+        let err = parse("---\nname: x\ninputs:\n  q: sring\n---\nbody").unwrap_err();
+        assert!(
+            matches!(err, PromptError::Schema { ref field, ref message }
+                if field == "inputs" && message.contains("sring")),
+            "{err}"
+        );
+    }
+}
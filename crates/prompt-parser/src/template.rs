@@ -0,0 +1,610 @@
+//! The body template engine.
+//!
+//! Deliberately small: `{{ path }}` substitution with dotted paths into the
+//! input object, plus `{{#if path}} ... {{else}} ... {{/if}}` and
+//! `{{#each path}} ... {{/each}}` blocks. Inside an `each` block, `this`
+//! refers to the current element and bare paths resolve against the element
+//! first, then the enclosing scope.
+
+use serde_json::Value;
+
+use crate::error::{PromptError, RenderLimitKind};
+
+/// Sandbox limits applied while rendering, so a malicious or buggy prompt
+/// cannot hang or OOM the host process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderLimits {
+    /// Total `#each` iterations across the whole render.
+    pub max_loop_iterations: usize,
+    /// Nesting depth of `#if`/`#each` blocks.
+    pub max_depth: usize,
+    /// Rendered output size in bytes.
+    pub max_output_bytes: usize,
+}
+
+impl Default for RenderLimits {
+    fn default() -> Self {
+        RenderLimits {
+            max_loop_iterations: 10_000,
+            max_depth: 64,
+            max_output_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// Renders `{{> name}}` references. Implemented by [`crate::PromptRegistry`];
+/// plain template renders have no resolver and reject partials.
+pub(crate) trait PartialRenderer {
+    fn render_partial(&self, name: &str, data: &Value) -> Result<String, PromptError>;
+}
+
+struct RenderContext<'a> {
+    limits: &'a RenderLimits,
+    partials: Option<&'a dyn PartialRenderer>,
+    iterations: usize,
+    bytes: usize,
+}
+
+impl RenderContext<'_> {
+    fn exceeded(kind: RenderLimitKind, limit: usize) -> PromptError {
+        PromptError::LimitExceeded { kind, limit }
+    }
+
+    /// Count `text` against the output budget, then hand it to the sink.
+    fn emit(
+        &mut self,
+        text: &str,
+        out: &mut dyn FnMut(&str),
+    ) -> Result<(), PromptError> {
+        self.bytes += text.len();
+        if self.bytes > self.limits.max_output_bytes {
+            return Err(Self::exceeded(
+                RenderLimitKind::OutputBytes,
+                self.limits.max_output_bytes,
+            ));
+        }
+        out(text);
+        Ok(())
+    }
+}
+
+/// A parsed template node.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Text(String),
+    /// `{{ path }}` — dotted path into the render data.
+    Var(String),
+    If {
+        path: String,
+        then_nodes: Vec<Node>,
+        else_nodes: Vec<Node>,
+    },
+    Each {
+        path: String,
+        body: Vec<Node>,
+    },
+    /// `{{> name}}` / `{{> name path}}` — render another registered prompt
+    /// inline, with the value at `path` (default: the root data) as its
+    /// inputs.
+    Partial {
+        name: String,
+        arg: Option<String>,
+    },
+    /// `{{ now() }}` / `{{ today() }}` — see [`crate::clock`].
+    Clock(crate::clock::ClockFn),
+}
+
+/// One lexed `{{ ... }}` tag or a literal text run.
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Var(String),
+    OpenIf(String),
+    Else,
+    CloseIf,
+    OpenEach(String),
+    CloseEach,
+    Partial { name: String, arg: Option<String> },
+    Clock(crate::clock::ClockFn),
+}
+
+fn lex(source: &str) -> Result<Vec<Token>, PromptError> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            return Err(PromptError::Template(format!(
+                "unterminated `{{{{` near `{}`",
+                truncate(&rest[start..], 30)
+            )));
+        };
+        let tag = after[..end].trim();
+        tokens.push(parse_tag(tag)?);
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+fn parse_tag(tag: &str) -> Result<Token, PromptError> {
+    if let Some(path) = tag.strip_prefix("#if") {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(PromptError::Template("`#if` requires a path".into()));
+        }
+        return Ok(Token::OpenIf(validate_path(path)?));
+    }
+    if let Some(path) = tag.strip_prefix("#each") {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(PromptError::Template("`#each` requires a path".into()));
+        }
+        return Ok(Token::OpenEach(validate_path(path)?));
+    }
+    if let Some(reference) = tag.strip_prefix('>') {
+        let mut parts = reference.split_whitespace();
+        let Some(name) = parts.next() else {
+            return Err(PromptError::Template("`>` requires a prompt name".into()));
+        };
+        let arg = parts.next().map(validate_path).transpose()?;
+        if parts.next().is_some() {
+            return Err(PromptError::Template(format!(
+                "`>` takes at most one argument path, got `{reference}`"
+            )));
+        }
+        return Ok(Token::Partial {
+            name: validate_path(name)?,
+            arg,
+        });
+    }
+    match tag {
+        "else" => Ok(Token::Else),
+        "/if" => Ok(Token::CloseIf),
+        "/each" => Ok(Token::CloseEach),
+        "now()" => Ok(Token::Clock(crate::clock::ClockFn::Now)),
+        "today()" => Ok(Token::Clock(crate::clock::ClockFn::Today)),
+        _ => Ok(Token::Var(validate_path(tag)?)),
+    }
+}
+
+fn validate_path(path: &str) -> Result<String, PromptError> {
+    let valid = !path.is_empty()
+        && path.split('.').all(|seg| {
+            !seg.is_empty()
+                && seg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+    if valid {
+        Ok(path.to_string())
+    } else {
+        Err(PromptError::Template(format!(
+            "invalid expression `{path}`"
+        )))
+    }
+}
+
+/// Parse a template body into a node tree.
+pub(crate) fn parse_template(source: &str) -> Result<Vec<Node>, PromptError> {
+    let tokens = lex(source)?;
+    let mut iter = tokens.into_iter();
+    let (nodes, terminator) = parse_nodes(&mut iter, None)?;
+    debug_assert!(terminator.is_none());
+    Ok(nodes)
+}
+
+/// Returns the parsed nodes plus the block-terminating token that stopped the
+/// scan (`else`, `/if`, `/each`), if any.
+fn parse_nodes(
+    iter: &mut std::vec::IntoIter<Token>,
+    inside: Option<&str>,
+) -> Result<(Vec<Node>, Option<Token>), PromptError> {
+    let mut nodes = Vec::new();
+    while let Some(token) = iter.next() {
+        match token {
+            Token::Text(t) => nodes.push(Node::Text(t)),
+            Token::Var(p) => nodes.push(Node::Var(p)),
+            Token::Partial { name, arg } => nodes.push(Node::Partial { name, arg }),
+            Token::Clock(f) => nodes.push(Node::Clock(f)),
+            Token::OpenIf(path) => {
+                let (then_nodes, stop) = parse_nodes(iter, Some("if"))?;
+                let else_nodes = match stop {
+                    Some(Token::Else) => {
+                        let (else_nodes, stop) = parse_nodes(iter, Some("if"))?;
+                        match stop {
+                            Some(Token::CloseIf) => else_nodes,
+                            _ => {
+                                return Err(PromptError::Template(format!(
+                                    "unclosed `#if {path}`"
+                                )));
+                            }
+                        }
+                    }
+                    Some(Token::CloseIf) => Vec::new(),
+                    _ => {
+                        return Err(PromptError::Template(format!("unclosed `#if {path}`")));
+                    }
+                };
+                nodes.push(Node::If {
+                    path,
+                    then_nodes,
+                    else_nodes,
+                });
+            }
+            Token::OpenEach(path) => {
+                let (body, stop) = parse_nodes(iter, Some("each"))?;
+                match stop {
+                    Some(Token::CloseEach) => {}
+                    _ => {
+                        return Err(PromptError::Template(format!("unclosed `#each {path}`")));
+                    }
+                }
+                nodes.push(Node::Each { path, body });
+            }
+            Token::Else | Token::CloseIf if inside == Some("if") => {
+                return Ok((nodes, Some(token)));
+            }
+            Token::CloseEach if inside == Some("each") => {
+                return Ok((nodes, Some(token)));
+            }
+            Token::Else => {
+                return Err(PromptError::Template("`{{else}}` outside `#if`".into()));
+            }
+            Token::CloseIf => {
+                return Err(PromptError::Template("`{{/if}}` without `#if`".into()));
+            }
+            Token::CloseEach => {
+                return Err(PromptError::Template("`{{/each}}` without `#each`".into()));
+            }
+        }
+    }
+    if inside.is_some() {
+        return Err(PromptError::Template("unclosed block".into()));
+    }
+    Ok((nodes, None))
+}
+
+/// Render a template body against a JSON object of inputs, with default
+/// [`RenderLimits`].
+///
+/// Parsed templates are cached by content hash; see [`crate::cache`].
+pub fn render_template(source: &str, data: &Value) -> Result<String, PromptError> {
+    render_template_with(source, data, &RenderLimits::default())
+}
+
+/// [`render_template`] with explicit sandbox limits.
+pub fn render_template_with(
+    source: &str,
+    data: &Value,
+    limits: &RenderLimits,
+) -> Result<String, PromptError> {
+    let mut out = String::with_capacity(source.len());
+    render_template_stream_with(source, data, limits, |chunk| out.push_str(chunk))?;
+    Ok(out)
+}
+
+/// Render incrementally, handing each chunk (a literal text run or one
+/// substituted value) to `on_chunk` as it is produced, with default
+/// [`RenderLimits`].
+///
+/// The host can start token counting or transmission before the full render
+/// completes. On error the chunks already delivered are a prefix of the
+/// output; nothing is retracted.
+pub fn render_template_stream(
+    source: &str,
+    data: &Value,
+    on_chunk: impl FnMut(&str),
+) -> Result<(), PromptError> {
+    render_template_stream_with(source, data, &RenderLimits::default(), on_chunk)
+}
+
+/// [`render_template_stream`] with explicit sandbox limits.
+pub fn render_template_stream_with(
+    source: &str,
+    data: &Value,
+    limits: &RenderLimits,
+    mut on_chunk: impl FnMut(&str),
+) -> Result<(), PromptError> {
+    let nodes = crate::cache::template_nodes(source)?;
+    let mut ctx = RenderContext {
+        limits,
+        partials: None,
+        iterations: 0,
+        bytes: 0,
+    };
+    render_nodes(&mut ctx, &nodes, &[data], 0, &mut on_chunk)
+}
+
+/// Render with a resolver for `{{> name}}` references. Only the registry
+/// calls this; standalone renders treat partials as errors.
+pub(crate) fn render_template_partials(
+    source: &str,
+    data: &Value,
+    limits: &RenderLimits,
+    partials: &dyn PartialRenderer,
+) -> Result<String, PromptError> {
+    let nodes = crate::cache::template_nodes(source)?;
+    let mut out = String::with_capacity(source.len());
+    let mut ctx = RenderContext {
+        limits,
+        partials: Some(partials),
+        iterations: 0,
+        bytes: 0,
+    };
+    render_nodes(&mut ctx, &nodes, &[data], 0, &mut |chunk| out.push_str(chunk))?;
+    Ok(out)
+}
+
+fn render_nodes(
+    ctx: &mut RenderContext<'_>,
+    nodes: &[Node],
+    scopes: &[&Value],
+    depth: usize,
+    out: &mut dyn FnMut(&str),
+) -> Result<(), PromptError> {
+    if depth > ctx.limits.max_depth {
+        return Err(RenderContext::exceeded(
+            RenderLimitKind::RecursionDepth,
+            ctx.limits.max_depth,
+        ));
+    }
+    for node in nodes {
+        match node {
+            Node::Text(t) => ctx.emit(t, out)?,
+            Node::Var(path) => {
+                let value = lookup(scopes, path)
+                    .ok_or_else(|| PromptError::UnknownVariable(path.clone()))?;
+                ctx.emit(&stringify(value), out)?;
+            }
+            Node::If {
+                path,
+                then_nodes,
+                else_nodes,
+            } => {
+                let branch = if lookup(scopes, path).is_some_and(truthy) {
+                    then_nodes
+                } else {
+                    else_nodes
+                };
+                render_nodes(ctx, branch, scopes, depth + 1, out)?;
+            }
+            Node::Each { path, body } => {
+                let value = lookup(scopes, path)
+                    .ok_or_else(|| PromptError::UnknownVariable(path.clone()))?;
+                let Value::Array(items) = value else {
+                    return Err(PromptError::Template(format!(
+                        "`#each {path}` expects an array"
+                    )));
+                };
+                for item in items {
+                    ctx.iterations += 1;
+                    if ctx.iterations > ctx.limits.max_loop_iterations {
+                        return Err(RenderContext::exceeded(
+                            RenderLimitKind::LoopIterations,
+                            ctx.limits.max_loop_iterations,
+                        ));
+                    }
+                    let mut inner: Vec<&Value> = vec![item];
+                    inner.extend_from_slice(scopes);
+                    render_nodes(ctx, body, &inner, depth + 1, out)?;
+                }
+            }
+            Node::Partial { name, arg } => {
+                let Some(partials) = ctx.partials else {
+                    return Err(PromptError::Template(format!(
+                        "`{{{{> {name}}}}}` requires a registry render"
+                    )));
+                };
+                let data = match arg {
+                    Some(path) => lookup(scopes, path)
+                        .ok_or_else(|| PromptError::UnknownVariable(path.clone()))?,
+                    // Bare `{{> name}}` passes the root render data through.
+                    None => scopes.last().copied().unwrap_or(&Value::Null),
+                };
+                let rendered = partials.render_partial(name, data)?;
+                ctx.emit(&rendered, out)?;
+            }
+            Node::Clock(f) => ctx.emit(&crate::clock::render(*f), out)?,
+        }
+    }
+    Ok(())
+}
+
+/// Resolve a dotted path against the scope stack (innermost first).
+/// `this` names the innermost scope itself.
+fn lookup<'a>(scopes: &[&'a Value], path: &str) -> Option<&'a Value> {
+    let mut segments = path.split('.').peekable();
+    let first = *segments.peek()?;
+    if first == "this" {
+        segments.next();
+        return walk(scopes.first()?, segments);
+    }
+    for scope in scopes {
+        if let Some(v) = walk(scope, segments.clone()) {
+            return Some(v);
+        }
+    }
+    None
+}
+
+fn walk<'a, 'b>(
+    mut value: &'a Value,
+    segments: impl Iterator<Item = &'b str>,
+) -> Option<&'a Value> {
+    for seg in segments {
+        value = match value {
+            Value::Object(map) => map.get(seg)?,
+            Value::Array(items) => items.get(seg.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn stringify(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(_) | Value::Object(_) => {
+            serde_json::to_string(value).unwrap_or_default()
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn truncate(s: &str, max: usize) -> &str {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn substitutes_nested_paths() {
+        let data = json!({ "user": { "name": "Ada" }, "n": 2 });
+        let out = render_template("Hi {{ user.name }}, n={{ n }}", &data).unwrap();
+        assert_eq!(out, "Hi Ada, n=2");
+    }
+
+    #[test]
+    fn if_else_branches() {
+        let data = json!({ "verbose": false, "name": "x" });
+        let out =
+            render_template("{{#if verbose}}long{{else}}short{{/if}}", &data).unwrap();
+        assert_eq!(out, "short");
+    }
+
+    #[test]
+    fn each_iterates_with_this() {
+        let data = json!({ "items": [{ "id": 1 }, { "id": 2 }] });
+        let out = render_template("{{#each items}}[{{ this.id }}]{{/each}}", &data).unwrap();
+        assert_eq!(out, "[1][2]");
+    }
+
+    #[test]
+    fn limits_cap_iterations_depth_and_output() {
+        use crate::error::RenderLimitKind;
+
+        let items = json!({ "items": (0..100).collect::<Vec<_>>() });
+        let tight = RenderLimits {
+            max_loop_iterations: 10,
+            ..Default::default()
+        };
+        let err =
+            render_template_with("{{#each items}}x{{/each}}", &items, &tight).unwrap_err();
+        assert!(matches!(
+            err,
+            PromptError::LimitExceeded {
+                kind: RenderLimitKind::LoopIterations,
+                limit: 10
+            }
+        ));
+
+        let deep = format!(
+            "{}x{}",
+            "{{#if a}}".repeat(5),
+            "{{/if}}".repeat(5)
+        );
+        let shallow = RenderLimits {
+            max_depth: 3,
+            ..Default::default()
+        };
+        let err = render_template_with(&deep, &json!({ "a": true }), &shallow).unwrap_err();
+        assert!(matches!(
+            err,
+            PromptError::LimitExceeded {
+                kind: RenderLimitKind::RecursionDepth,
+                ..
+            }
+        ));
+
+        let small = RenderLimits {
+            max_output_bytes: 16,
+            ..Default::default()
+        };
+        let err = render_template_with(
+            "{{#each items}}0123456789{{/each}}",
+            &items,
+            &small,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            PromptError::LimitExceeded {
+                kind: RenderLimitKind::OutputBytes,
+                ..
+            }
+        ));
+
+        // Defaults are generous enough for ordinary prompts.
+        assert!(render_template("{{#each items}}x{{/each}}", &items).is_ok());
+    }
+
+    #[test]
+    fn streaming_yields_chunks_in_order() {
+        let data = json!({ "items": ["a", "b"], "who": "Ada" });
+        let mut chunks = Vec::new();
+        render_template_stream("Hi {{ who }}: {{#each items}}<{{ this }}>{{/each}}", &data, |c| {
+            chunks.push(c.to_string());
+        })
+        .unwrap();
+        assert_eq!(chunks, vec!["Hi ", "Ada", ": ", "<", "a", ">", "<", "b", ">"]);
+        assert_eq!(chunks.concat(), "Hi Ada: <a><b>");
+    }
+
+    #[test]
+    fn streaming_stops_at_the_output_limit() {
+        let small = RenderLimits {
+            max_output_bytes: 4,
+            ..Default::default()
+        };
+        let mut seen = String::new();
+        let err = render_template_stream_with("abcd{{ x }}", &json!({ "x": "y" }), &small, |c| {
+            seen.push_str(c);
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            PromptError::LimitExceeded {
+                kind: RenderLimitKind::OutputBytes,
+                ..
+            }
+        ));
+        // Delivered chunks are a prefix of the would-be output.
+        assert_eq!(seen, "abcd");
+    }
+
+    #[test]
+    fn unknown_variable_errors() {
+        let err = render_template("{{ missing }}", &json!({})).unwrap_err();
+        assert!(matches!(err, PromptError::UnknownVariable(p) if p == "missing"));
+    }
+
+    #[test]
+    fn unclosed_block_errors() {
+        let err = render_template("{{#if a}}x", &json!({ "a": true })).unwrap_err();
+        assert!(matches!(err, PromptError::Template(_)));
+    }
+}
@@ -0,0 +1,162 @@
+//! Token counting for rendered prompts.
+//!
+//! Budget checks happen before a request is sent, so the count has to come
+//! from the client side. [`TokenCounter`] keeps the mechanism pluggable; the
+//! default is a real BPE count via tiktoken vocabularies, selected per model
+//! from the `client` field. Providers without a published tokenizer are
+//! approximated with `cl100k_base`, which is close enough for budgeting.
+
+use std::sync::LazyLock;
+
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// Counts tokens in a piece of text. Implementations must be cheap to call
+/// repeatedly; expensive setup belongs in construction.
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Which embedded BPE vocabulary to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Vocab {
+    Cl100k,
+    O200k,
+}
+
+static CL100K: LazyLock<tiktoken_rs::CoreBPE> =
+    LazyLock::new(|| tiktoken_rs::cl100k_base().expect("embedded cl100k vocabulary loads"));
+static O200K: LazyLock<tiktoken_rs::CoreBPE> =
+    LazyLock::new(|| tiktoken_rs::o200k_base().expect("embedded o200k vocabulary loads"));
+
+/// The default [`TokenCounter`]: BPE over an embedded tiktoken vocabulary.
+#[derive(Debug, Clone, Copy)]
+pub struct BpeTokenCounter {
+    vocab: Vocab,
+}
+
+impl BpeTokenCounter {
+    /// Counter with the `cl100k_base` vocabulary.
+    pub fn cl100k() -> Self {
+        BpeTokenCounter {
+            vocab: Vocab::Cl100k,
+        }
+    }
+
+    /// Counter with the `o200k_base` vocabulary (current OpenAI models).
+    pub fn o200k() -> Self {
+        BpeTokenCounter { vocab: Vocab::O200k }
+    }
+
+    /// Pick a vocabulary for a `client` string (`provider/model`).
+    ///
+    /// Current OpenAI models (`gpt-4o`, `gpt-5`, `o1`...) use `o200k_base`;
+    /// everything else, including Anthropic models, is approximated with
+    /// `cl100k_base`.
+    pub fn for_client(client: Option<&str>) -> Self {
+        let Some((provider, model)) = client.and_then(|c| c.split_once('/')) else {
+            return Self::cl100k();
+        };
+        let modern_openai = ["gpt-4o", "gpt-4.1", "gpt-5", "o1", "o3", "o4"]
+            .iter()
+            .any(|prefix| model.starts_with(prefix));
+        if provider == "openai" && modern_openai {
+            Self::o200k()
+        } else {
+            Self::cl100k()
+        }
+    }
+}
+
+impl TokenCounter for BpeTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        let bpe = match self.vocab {
+            Vocab::Cl100k => &*CL100K,
+            Vocab::O200k => &*O200K,
+        };
+        bpe.encode_with_special_tokens(text).len()
+    }
+}
+
+impl PromptDefinition {
+    /// Token count of the fully rendered prompt (all messages), using the
+    /// default BPE counter selected from the `client` field.
+    pub fn count_tokens(&self, data: &Value) -> Result<usize, PromptError> {
+        self.count_tokens_with(data, &BpeTokenCounter::for_client(self.client.as_deref()))
+    }
+
+    /// [`Self::count_tokens`] with a caller-supplied counter.
+    pub fn count_tokens_with(
+        &self,
+        data: &Value,
+        counter: &dyn TokenCounter,
+    ) -> Result<usize, PromptError> {
+        let messages = self.render_messages(data)?;
+        Ok(messages.iter().map(|m| counter.count(&m.content)).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bpe_counts_are_plausible() {
+        let counter = BpeTokenCounter::cl100k();
+        let n = counter.count("The quick brown fox jumps over the lazy dog.");
+        assert!((5..=15).contains(&n), "got {n}");
+        assert_eq!(counter.count(""), 0);
+    }
+
+    #[test]
+    fn vocab_selection_follows_the_client() {
+        assert_eq!(
+            BpeTokenCounter::for_client(Some("openai/gpt-4o")).vocab,
+            Vocab::O200k
+        );
+        assert_eq!(
+            BpeTokenCounter::for_client(Some("openai/gpt-3.5-turbo")).vocab,
+            Vocab::Cl100k
+        );
+        assert_eq!(
+            BpeTokenCounter::for_client(Some("anthropic/claude-sonnet-4")).vocab,
+            Vocab::Cl100k
+        );
+        assert_eq!(BpeTokenCounter::for_client(None).vocab, Vocab::Cl100k);
+    }
+
+    #[test]
+    fn count_tokens_covers_all_messages() {
+        let def = PromptDefinition::parse(
+            "---\nname: t\nsystem: Be brief.\n---\nSummarize {{ doc }}",
+        )
+        .unwrap();
+        let total = def.count_tokens(&json!({ "doc": "a document" })).unwrap();
+        let body_only = def
+            .count_tokens_with(
+                &json!({ "doc": "a document" }),
+                &BpeTokenCounter::cl100k(),
+            )
+            .unwrap();
+        assert!(total > 0);
+        assert_eq!(total, body_only);
+    }
+
+    #[test]
+    fn custom_counters_plug_in() {
+        struct CharCounter;
+        impl TokenCounter for CharCounter {
+            fn count(&self, text: &str) -> usize {
+                text.chars().count()
+            }
+        }
+        let def = PromptDefinition::parse("---\nname: t\n---\nabcd").unwrap();
+        assert_eq!(
+            def.count_tokens_with(&json!({}), &CharCounter).unwrap(),
+            4
+        );
+    }
+}
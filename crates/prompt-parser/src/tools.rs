@@ -0,0 +1,255 @@
+//! Declared tools and tool-aware rendering.
+//!
+//! A prompt that adapts its instructions to available capabilities declares
+//! them in frontmatter:
+//!
+//! ```yaml
+//! tools:
+//!   - web_search
+//!   - name: code_exec
+//!     description: Run code in a sandbox
+//! ```
+//!
+//! Rendering injects a `tools` object into the context so the body can branch
+//! with `{{#if tools.web_search}}...{{/if}}`. References to tools the
+//! frontmatter does not declare are a parse-time error, so a renamed tool
+//! can't silently dead-code half the prompt.
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// One declared tool from a `tools:` frontmatter block. YAML shorthand
+/// (`- web_search`) and the full map form both parse.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "ToolSpec")]
+pub struct Tool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ToolSpec {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default)]
+        description: Option<String>,
+    },
+}
+
+impl From<ToolSpec> for Tool {
+    fn from(spec: ToolSpec) -> Self {
+        match spec {
+            ToolSpec::Name(name) => Tool {
+                name,
+                description: None,
+            },
+            ToolSpec::Full { name, description } => Tool { name, description },
+        }
+    }
+}
+
+/// The tools a host can actually dispatch.
+///
+/// Frontmatter validation only proves a prompt is internally consistent; a
+/// prompt can still declare a tool the runtime never implemented, which
+/// otherwise surfaces as a dispatch failure mid-agent-run. Hosts build a
+/// registry of what they provide and parse with [`crate::parse_with_tools`],
+/// or [`Self::check`] an already-parsed definition.
+#[derive(Debug, Clone, Default)]
+pub struct ToolRegistry {
+    names: BTreeSet<String>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool the host implements.
+    pub fn register(&mut self, name: impl Into<String>) -> &mut Self {
+        self.names.insert(name.into());
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// Registered names, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// Every tool the definition declares must be registered.
+    pub fn check(&self, def: &PromptDefinition) -> Result<(), PromptError> {
+        for tool in def.tools.as_deref().unwrap_or_default() {
+            if !self.names.contains(&tool.name) {
+                return Err(PromptError::UnknownTool {
+                    tool: tool.name.clone(),
+                    available: self.names.iter().cloned().collect(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for ToolRegistry {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        ToolRegistry {
+            names: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// The render context, extended with `tools.<name>: true` for every declared
+/// tool. Borrowed unchanged when the prompt declares none. Injection happens
+/// after input validation, so closed `inputs` schemas are unaffected.
+pub(crate) fn context_with_tools<'a>(def: &PromptDefinition, data: &'a Value) -> Cow<'a, Value> {
+    let Some(tools) = &def.tools else {
+        return Cow::Borrowed(data);
+    };
+    let mut obj = match data {
+        Value::Object(obj) => obj.clone(),
+        _ => serde_json::Map::new(),
+    };
+    let mut flags = serde_json::Map::with_capacity(tools.len());
+    for tool in tools {
+        flags.insert(tool.name.clone(), Value::Bool(true));
+    }
+    obj.insert("tools".to_string(), Value::Object(flags));
+    Cow::Owned(Value::Object(obj))
+}
+
+/// Parse/build-time checks: well-formed unique names, and every `tools.*`
+/// reference in the body/system/locale templates names a declared tool.
+pub(crate) fn validate_tools(def: &PromptDefinition) -> Result<(), PromptError> {
+    let declared: Vec<&str> = def
+        .tools
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect();
+
+    let mut seen = std::collections::HashSet::new();
+    for name in &declared {
+        let well_formed = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !well_formed {
+            return Err(PromptError::Frontmatter(format!("invalid tool name `{name}`")));
+        }
+        if !seen.insert(*name) {
+            return Err(PromptError::Frontmatter(format!("duplicate tool `{name}`")));
+        }
+    }
+
+    let mut templates: Vec<&str> = vec![&def.body];
+    templates.extend(def.system.as_deref());
+    if let Some(locales) = &def.locales {
+        templates.extend(locales.values().map(String::as_str));
+    }
+    for body in templates {
+        for var in crate::introspect::extract_template_variables(body)? {
+            if let Some(tool) = var.strip_prefix("tools.") {
+                let tool = tool.split('.').next().unwrap_or(tool);
+                if !declared.contains(&tool) {
+                    return Err(PromptError::Frontmatter(format!(
+                        "template references undeclared tool `{tool}`"
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToolRegistry;
+    use crate::{PromptError, parse};
+    use serde_json::json;
+
+    const SOURCE: &str = r#"---
+name: research
+tools:
+  - web_search
+  - name: code_exec
+    description: Run code in a sandbox
+---
+{{#if tools.web_search}}Search the web first.{{else}}Use your training data.{{/if}}"#;
+
+    #[test]
+    fn declared_tools_are_truthy_in_the_context() {
+        let def = parse(SOURCE).unwrap();
+        assert_eq!(def.tools.as_ref().unwrap().len(), 2);
+        assert_eq!(
+            def.tools.as_ref().unwrap()[1].description.as_deref(),
+            Some("Run code in a sandbox")
+        );
+        assert_eq!(def.render(&json!({})).unwrap(), "Search the web first.");
+    }
+
+    #[test]
+    fn undeclared_tool_references_fail_at_parse() {
+        let err = parse(
+            "---\nname: x\ntools: [web_search]\n---\n{{#if tools.web_serach}}typo{{/if}}",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("web_serach"), "{err}");
+
+        // No tools block at all: any tools.* reference is undeclared.
+        assert!(parse("---\nname: x\n---\n{{#if tools.a}}b{{/if}}").is_err());
+    }
+
+    #[test]
+    fn tool_declarations_are_checked() {
+        for bad in ["tools: [\"\"]", "tools: [a, a]", "tools: [\"b d\"]"] {
+            let err = parse(&format!("---\nname: x\n{bad}\n---\nbody")).unwrap_err();
+            assert!(matches!(err, PromptError::Frontmatter(_)), "{bad}: {err}");
+        }
+    }
+
+    #[test]
+    fn registry_cross_check_rejects_unimplemented_tools() {
+        let registry: ToolRegistry = ["web_search", "code_exec"].into_iter().collect();
+        let source = "---\nname: x\ntools: [web_search, browser]\n---\nbody";
+        let err = crate::parse_with_tools(source, &registry).unwrap_err();
+        assert!(
+            matches!(err, PromptError::UnknownTool { ref tool, .. } if tool == "browser"),
+            "{err}"
+        );
+        assert!(err.to_string().contains("code_exec, web_search"), "{err}");
+
+        let ok = "---\nname: x\ntools: [web_search]\n---\nbody";
+        assert!(crate::parse_with_tools(ok, &registry).is_ok());
+    }
+
+    #[test]
+    fn injection_does_not_break_closed_input_schemas() {
+        let def = parse(
+            r#"---
+name: x
+tools: [web_search]
+inputs:
+  type: object
+  properties:
+    q: { type: string }
+  required: [q]
+  additionalProperties: false
+---
+{{ q }}{{#if tools.web_search}} (search){{/if}}"#,
+        )
+        .unwrap();
+        assert_eq!(def.render(&json!({ "q": "hi" })).unwrap(), "hi (search)");
+    }
+}
@@ -0,0 +1,62 @@
+//! wasm-bindgen surface for the web app.
+//!
+//! Lets the SolidJS frontend lint and preview prompt files entirely
+//! client-side — no server round trip, no native addon. Compiled only with
+//! the `wasm` feature:
+//!
+//! ```sh
+//! wasm-pack build crates/prompt-parser --features wasm
+//! ```
+//!
+//! Errors become thrown JS strings carrying the same messages as
+//! [`crate::PromptError`].
+
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+use crate::definition::PromptDefinition;
+
+fn js_err(e: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+fn js_inputs(inputs: JsValue) -> Result<Value, JsValue> {
+    serde_wasm_bindgen::from_value(inputs).map_err(js_err)
+}
+
+/// Parse a prompt file; returns the definition as a plain JS object.
+#[wasm_bindgen(js_name = parsePrompt)]
+pub fn parse_prompt(source: &str) -> Result<JsValue, JsValue> {
+    let def = PromptDefinition::parse(source).map_err(js_err)?;
+    serde_wasm_bindgen::to_value(&def).map_err(js_err)
+}
+
+/// Parse, validate inputs, and render the body.
+#[wasm_bindgen(js_name = renderPrompt)]
+pub fn render_prompt(source: &str, inputs: JsValue) -> Result<String, JsValue> {
+    let def = PromptDefinition::parse(source).map_err(js_err)?;
+    def.render(&js_inputs(inputs)?).map_err(js_err)
+}
+
+/// Parse, validate inputs, and render the provider-ready message array.
+#[wasm_bindgen(js_name = renderMessages)]
+pub fn render_messages(source: &str, inputs: JsValue) -> Result<JsValue, JsValue> {
+    let def = PromptDefinition::parse(source).map_err(js_err)?;
+    let messages = def.render_messages(&js_inputs(inputs)?).map_err(js_err)?;
+    serde_wasm_bindgen::to_value(&messages).map_err(js_err)
+}
+
+/// Validate `data` against `schema` (default options: newest draft).
+#[wasm_bindgen(js_name = validateJson)]
+pub fn validate_json(schema: JsValue, data: JsValue) -> Result<(), JsValue> {
+    let schema = js_inputs(schema)?;
+    let data = js_inputs(data)?;
+    crate::schema::validate_json(&schema, &data).map_err(js_err)
+}
+
+/// Referenced template variables of a body, as an array of dotted paths.
+#[wasm_bindgen(js_name = extractTemplateVariables)]
+pub fn extract_template_variables(body: &str) -> Result<JsValue, JsValue> {
+    let vars = crate::introspect::extract_template_variables(body).map_err(js_err)?;
+    serde_wasm_bindgen::to_value(&vars.into_iter().collect::<Vec<_>>()).map_err(js_err)
+}
@@ -0,0 +1,291 @@
+//! Hot reload for directory-backed registries.
+//!
+//! The agent server keeps its prompt library loaded for the lifetime of the
+//! process; restarting it to pick up a prompt edit defeats the point of
+//! prompts-as-files. [`PromptRegistry::watch`] loads a directory and then
+//! keeps it loaded: a filesystem watcher re-parses changed `.md` files,
+//! drops the content caches, and emits [`RegistryChange`] events — added,
+//! updated, or removed, with the parse error when the new revision is
+//! broken. A broken edit keeps the last good definition registered so
+//! in-flight renders don't start failing mid-edit.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::error::PromptError;
+use crate::registry::PromptRegistry;
+
+/// What happened to one prompt file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Updated,
+    Removed,
+}
+
+/// One change event from [`PromptRegistry::watch`].
+#[derive(Debug, Clone)]
+pub struct RegistryChange {
+    pub kind: ChangeKind,
+    pub path: String,
+    /// The prompt name, when the file (still) parses.
+    pub name: Option<String>,
+    /// The parse error for a broken revision; the previous definition stays
+    /// registered.
+    pub error: Option<String>,
+}
+
+struct WatchState {
+    registry: PromptRegistry,
+    /// Which registered name each file currently provides, for rename and
+    /// removal bookkeeping.
+    names_by_path: HashMap<PathBuf, String>,
+}
+
+/// A registry kept in sync with its directory. Dropping it stops the
+/// watcher.
+pub struct WatchedRegistry {
+    state: Arc<Mutex<WatchState>>,
+    changes: Receiver<RegistryChange>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl PromptRegistry {
+    /// Load `dir` (as in [`Self::load_dir`]), then watch it: edits re-parse,
+    /// caches invalidate, and change events queue up for
+    /// [`WatchedRegistry::next_change`].
+    pub fn watch(mut self, dir: impl AsRef<Path>) -> Result<WatchedRegistry, PromptError> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut names_by_path = HashMap::new();
+        for file in self.load_dir(&dir)? {
+            if let Some(name) = file.name {
+                names_by_path.insert(PathBuf::from(file.path), name);
+            }
+        }
+
+        let state = Arc::new(Mutex::new(WatchState {
+            registry: self,
+            names_by_path,
+        }));
+        let (tx, changes) = std::sync::mpsc::channel();
+        let handler_state = Arc::clone(&state);
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                let Ok(event) = result else { return };
+                handle_event(&handler_state, &event, &tx);
+            })
+            .map_err(|e| watch_error(&dir, e))?;
+        watcher
+            .watch(&dir, RecursiveMode::Recursive)
+            .map_err(|e| watch_error(&dir, e))?;
+
+        Ok(WatchedRegistry {
+            state,
+            changes,
+            _watcher: watcher,
+        })
+    }
+}
+
+impl WatchedRegistry {
+    /// Run `f` against the current registry.
+    pub fn with_registry<R>(&self, f: impl FnOnce(&PromptRegistry) -> R) -> R {
+        f(&self.state.lock().unwrap().registry)
+    }
+
+    /// The next queued change, waiting up to `timeout` for one to arrive.
+    pub fn next_change(&self, timeout: Duration) -> Option<RegistryChange> {
+        self.changes.recv_timeout(timeout).ok()
+    }
+}
+
+fn watch_error(dir: &Path, e: notify::Error) -> PromptError {
+    PromptError::Io {
+        path: dir.display().to_string(),
+        message: e.to_string(),
+    }
+}
+
+fn handle_event(
+    state: &Arc<Mutex<WatchState>>,
+    event: &notify::Event,
+    tx: &Sender<RegistryChange>,
+) {
+    use notify::EventKind;
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+    for path in &event.paths {
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        // Stale cache entries are only wasted memory (keys are content
+        // hashes), but an editing session churns them fast; drop them.
+        crate::cache::invalidate();
+        let change = if path.exists() {
+            upsert(state, path)
+        } else {
+            remove(state, path)
+        };
+        if let Some(change) = change {
+            let _ = tx.send(change);
+        }
+    }
+}
+
+fn upsert(state: &Arc<Mutex<WatchState>>, path: &Path) -> Option<RegistryChange> {
+    let parsed = crate::parser::parse_file(path);
+    let mut state = state.lock().unwrap();
+    let known = state.names_by_path.contains_key(path);
+    let kind = if known {
+        ChangeKind::Updated
+    } else {
+        ChangeKind::Added
+    };
+    match parsed {
+        Ok(def) => {
+            let name = def.name.clone();
+            if let Some(previous) = state.names_by_path.insert(path.to_path_buf(), name.clone())
+                && previous != name
+            {
+                // The file now provides a different prompt; retire the old one.
+                state.registry.remove(&previous);
+            }
+            state.registry.register(def);
+            Some(RegistryChange {
+                kind,
+                path: path.display().to_string(),
+                name: Some(name),
+                error: None,
+            })
+        }
+        // Broken revision: report it, keep the last good definition.
+        Err(e) => Some(RegistryChange {
+            kind,
+            path: path.display().to_string(),
+            name: state.names_by_path.get(path).cloned(),
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+fn remove(state: &Arc<Mutex<WatchState>>, path: &Path) -> Option<RegistryChange> {
+    let mut state = state.lock().unwrap();
+    let name = state.names_by_path.remove(path)?;
+    state.registry.remove(&name);
+    Some(RegistryChange {
+        kind: ChangeKind::Removed,
+        path: path.display().to_string(),
+        name: Some(name),
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "prompt-parser-watch-{label}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Filesystem events are asynchronous and may arrive split (a create
+    /// before the content lands), so tests poll the observable state with a
+    /// deadline and drain events into `seen` along the way.
+    fn wait_until(
+        watched: &WatchedRegistry,
+        seen: &mut Vec<RegistryChange>,
+        mut done: impl FnMut(&WatchedRegistry, &[RegistryChange]) -> bool,
+    ) -> bool {
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while std::time::Instant::now() < deadline {
+            while let Some(change) = watched.next_change(Duration::from_millis(50)) {
+                seen.push(change);
+            }
+            if done(watched, seen) {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn edits_flow_through_as_change_events() {
+        let dir = temp_dir("events");
+        std::fs::write(dir.join("a.prompt.md"), "---\nname: a\n---\nv1").unwrap();
+        let watched = PromptRegistry::new().watch(&dir).unwrap();
+        assert_eq!(watched.with_registry(|r| r.render("a", &json!({})).unwrap()), "v1");
+
+        let mut seen = Vec::new();
+        let new = dir.join("b.prompt.md");
+        std::fs::write(&new, "---\nname: b\n---\nhello").unwrap();
+        assert!(
+            wait_until(&watched, &mut seen, |w, _| {
+                w.with_registry(|r| r.get("b").is_some())
+            }),
+            "addition never landed: {seen:?}"
+        );
+        assert_eq!(watched.with_registry(|r| r.render("b", &json!({})).unwrap()), "hello");
+        assert!(
+            seen.iter()
+                .any(|c| c.kind == ChangeKind::Added && c.name.as_deref() == Some("b")),
+            "{seen:?}"
+        );
+
+        std::fs::write(&new, "---\nname: b\n---\nhello again").unwrap();
+        assert!(
+            wait_until(&watched, &mut seen, |w, _| {
+                w.with_registry(|r| r.render("b", &json!({})).unwrap()) == "hello again"
+            }),
+            "update never landed: {seen:?}"
+        );
+
+        std::fs::remove_file(&new).unwrap();
+        assert!(
+            wait_until(&watched, &mut seen, |w, _| {
+                w.with_registry(|r| r.get("b").is_none())
+            }),
+            "removal never landed: {seen:?}"
+        );
+        assert!(
+            seen.iter().any(|c| c.kind == ChangeKind::Removed),
+            "{seen:?}"
+        );
+    }
+
+    #[test]
+    fn broken_edits_report_diagnostics_and_keep_the_last_good_definition() {
+        let dir = temp_dir("broken");
+        let path = dir.join("a.prompt.md");
+        std::fs::write(&path, "---\nname: a\n---\ngood").unwrap();
+        let watched = PromptRegistry::new().watch(&dir).unwrap();
+
+        let mut seen = Vec::new();
+        std::fs::write(&path, "no frontmatter").unwrap();
+        assert!(
+            wait_until(&watched, &mut seen, |_, seen| {
+                seen.iter().any(|c| {
+                    c.error.as_deref().is_some_and(|e| e.contains("frontmatter"))
+                })
+            }),
+            "diagnostic never arrived: {seen:?}"
+        );
+        // The last good definition still renders.
+        assert_eq!(watched.with_registry(|r| r.render("a", &json!({})).unwrap()), "good");
+    }
+}
@@ -0,0 +1,279 @@
+//! Whitespace control for template bodies.
+//!
+//! Templates written with readable indentation shouldn't leak that
+//! indentation into the model input. A `whitespace:` frontmatter block (or
+//! the `whitespace: true` shorthand enabling everything) opts in:
+//!
+//! ```yaml
+//! whitespace:
+//!   trim_blocks: true    # drop the newline right after a block tag
+//!   lstrip_blocks: true  # drop indentation before a block tag on its own line
+//!   dedent: true         # strip the common leading indentation of the body
+//! ```
+//!
+//! Control is applied as a source rewrite when the definition is parsed or
+//! built, so caching, rendering, and streaming are untouched. The rewrite is
+//! idempotent, which keeps `parse → to_markdown → parse` round-trips stable.
+
+use serde::{Deserialize, Serialize};
+
+use crate::definition::PromptDefinition;
+
+/// Which whitespace rewrites to apply to the body templates.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "ControlSpec")]
+pub struct WhitespaceControl {
+    /// Remove the first newline after `#if`/`else`/`/if`/`#each`/`/each` tags.
+    #[serde(default)]
+    pub trim_blocks: bool,
+    /// Remove leading indentation on lines that hold only a block tag.
+    #[serde(default)]
+    pub lstrip_blocks: bool,
+    /// Strip the common leading indentation of all non-blank lines.
+    #[serde(default)]
+    pub dedent: bool,
+}
+
+impl WhitespaceControl {
+    /// Everything on — what `whitespace: true` means in frontmatter.
+    pub fn all() -> Self {
+        WhitespaceControl {
+            trim_blocks: true,
+            lstrip_blocks: true,
+            dedent: true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ControlSpec {
+    Shorthand(bool),
+    Full {
+        #[serde(default)]
+        trim_blocks: bool,
+        #[serde(default)]
+        lstrip_blocks: bool,
+        #[serde(default)]
+        dedent: bool,
+    },
+}
+
+impl From<ControlSpec> for WhitespaceControl {
+    fn from(spec: ControlSpec) -> Self {
+        match spec {
+            ControlSpec::Shorthand(true) => WhitespaceControl::all(),
+            ControlSpec::Shorthand(false) => WhitespaceControl::default(),
+            ControlSpec::Full {
+                trim_blocks,
+                lstrip_blocks,
+                dedent,
+            } => WhitespaceControl {
+                trim_blocks,
+                lstrip_blocks,
+                dedent,
+            },
+        }
+    }
+}
+
+/// Apply the enabled rewrites to one template source.
+pub fn apply_whitespace_control(source: &str, control: &WhitespaceControl) -> String {
+    let mut text = source.to_string();
+    if control.dedent {
+        text = dedent(&text);
+    }
+    if control.lstrip_blocks {
+        text = lstrip_blocks(&text);
+    }
+    if control.trim_blocks {
+        text = trim_blocks(&text);
+    }
+    text
+}
+
+/// Rewrite every template the definition carries, in place.
+pub(crate) fn apply_to_definition(def: &mut PromptDefinition) {
+    let Some(control) = def.whitespace else {
+        return;
+    };
+    def.body = apply_whitespace_control(&def.body, &control);
+    if let Some(system) = &def.system {
+        def.system = Some(apply_whitespace_control(system, &control));
+    }
+    if let Some(locales) = &mut def.locales {
+        for body in locales.values_mut() {
+            *body = apply_whitespace_control(body, &control);
+        }
+    }
+}
+
+fn dedent(source: &str) -> String {
+    let common = source
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    if common == 0 {
+        return source.to_string();
+    }
+    let mut lines: Vec<&str> = source
+        .split('\n')
+        .map(|line| line.get(common..).unwrap_or(""))
+        .collect();
+    // split/join keeps the trailing-newline shape of the input.
+    if source.ends_with('\n') {
+        lines.pop();
+        lines.join("\n") + "\n"
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn is_block_tag(tag: &str) -> bool {
+    let tag = tag.trim();
+    tag.starts_with('#') || tag.starts_with('/') || tag == "else"
+}
+
+/// A line whose only content is block tags loses its leading indentation.
+fn lstrip_blocks(source: &str) -> String {
+    let mut lines: Vec<String> = source
+        .split('\n')
+        .map(|line| {
+            let stripped = line.trim_start_matches([' ', '\t']);
+            if stripped.starts_with("{{") && line_is_only_tags(stripped, is_block_tag) {
+                stripped.to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if source.ends_with('\n') {
+        lines.pop();
+        lines.join("\n") + "\n"
+    } else {
+        lines.join("\n")
+    }
+}
+
+/// Is the line nothing but `{{ ... }}` tags (accepted by `pred`) and spaces?
+fn line_is_only_tags(mut rest: &str, pred: fn(&str) -> bool) -> bool {
+    loop {
+        rest = rest.trim_start_matches([' ', '\t']);
+        if rest.is_empty() {
+            return true;
+        }
+        let Some(after) = rest.strip_prefix("{{") else {
+            return false;
+        };
+        let Some(end) = after.find("}}") else {
+            return false;
+        };
+        if !pred(&after[..end]) {
+            return false;
+        }
+        rest = &after[end + 2..];
+    }
+}
+
+/// Drop the newline immediately following a block tag.
+fn trim_blocks(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            // Malformed tag: leave it for the template parser to report.
+            break;
+        };
+        out.push_str(&rest[..start + 2 + end + 2]);
+        rest = &after[end + 2..];
+        if is_block_tag(&after[..end]) {
+            rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n')).unwrap_or(rest);
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use serde_json::json;
+
+    #[test]
+    fn rewrites_compose_to_clean_output() {
+        let source = r#"---
+name: list
+whitespace: true
+---
+    {{#each items}}
+    - {{ this }}
+    {{/each}}"#;
+        let def = parse(source).unwrap();
+        assert_eq!(
+            def.render(&json!({ "items": ["a", "b"] })).unwrap(),
+            "- a\n- b\n"
+        );
+    }
+
+    #[test]
+    fn individual_flags_are_selectable() {
+        let control = WhitespaceControl {
+            trim_blocks: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_whitespace_control("{{#if a}}\nx\n{{/if}}\n", &control),
+            "{{#if a}}x\n{{/if}}"
+        );
+
+        let control = WhitespaceControl {
+            lstrip_blocks: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_whitespace_control("  {{#if a}}\n  x\n  {{/if}}", &control),
+            "{{#if a}}\n  x\n{{/if}}"
+        );
+
+        let control = WhitespaceControl {
+            dedent: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_whitespace_control("  a\n\n    b\n", &control),
+            "a\n\n  b\n"
+        );
+    }
+
+    #[test]
+    fn variable_tags_are_not_block_tags() {
+        let control = WhitespaceControl::all();
+        // `{{ x }}` on its own line keeps its newline and indentation.
+        assert_eq!(
+            apply_whitespace_control("{{#if a}}\n  {{ x }}\n{{/if}}", &control),
+            "{{#if a}}  {{ x }}\n{{/if}}"
+        );
+    }
+
+    #[test]
+    fn rewrites_are_idempotent() {
+        let control = WhitespaceControl::all();
+        let once = apply_whitespace_control("  {{#each xs}}\n  - {{ this }}\n  {{/each}}\n", &control);
+        assert_eq!(apply_whitespace_control(&once, &control), once);
+    }
+
+    #[test]
+    fn round_trips_through_markdown() {
+        let def = parse(
+            "---\nname: x\nwhitespace:\n  trim_blocks: true\n---\n{{#if a}}\nyes{{/if}}",
+        )
+        .unwrap();
+        assert_eq!(def.body, "{{#if a}}yes{{/if}}");
+        let reparsed = parse(&def.to_markdown().unwrap()).unwrap();
+        assert_eq!(def, reparsed);
+    }
+}
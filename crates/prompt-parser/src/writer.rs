@@ -0,0 +1,129 @@
+//! Serializing a [`PromptDefinition`] back to a `.prompt.md` file.
+//!
+//! Tooling edits prompts programmatically — bump a version, add a tool — and
+//! should not shred the file while doing it. Keys are emitted in the
+//! canonical frontmatter order regardless of struct or map ordering, so a
+//! parse → edit → write cycle produces minimal diffs. YAML comments are not
+//! representable in the parsed definition and are therefore not preserved;
+//! tooling that must keep comments should patch the source text instead.
+
+use serde_json::Value;
+use serde_yaml::Mapping;
+
+use crate::definition::PromptDefinition;
+use crate::error::PromptError;
+
+/// Canonical frontmatter key order for emitted files.
+const KEY_ORDER: &[&str] = &[
+    "name",
+    "extends",
+    "version",
+    "description",
+    "type",
+    "client",
+    "temperature",
+    "top_p",
+    "max_tokens",
+    "max_turns",
+    "stop",
+    "budget",
+    "whitespace",
+    "system",
+    "tools",
+    "examples",
+    "inputs",
+    "output",
+    "output_mode",
+    "tests",
+    "locales",
+];
+
+impl PromptDefinition {
+    /// Serialize back to prompt-file form: ordered YAML frontmatter between
+    /// `---` fences, then the body verbatim.
+    ///
+    /// Round-trips: `parse(def.to_markdown())` yields an equal definition.
+    pub fn to_markdown(&self) -> Result<String, PromptError> {
+        let json = serde_json::to_value(self)
+            .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+        let Value::Object(mut fields) = json else {
+            unreachable!("PromptDefinition serializes as an object");
+        };
+        fields.remove("body");
+
+        let mut mapping = Mapping::new();
+        for key in KEY_ORDER {
+            if let Some(value) = fields.remove(*key) {
+                mapping.insert(
+                    serde_yaml::Value::String((*key).to_string()),
+                    yaml_from_json(value),
+                );
+            }
+        }
+        // Anything the canonical list doesn't know yet goes last, in order.
+        for (key, value) in fields {
+            mapping.insert(serde_yaml::Value::String(key), yaml_from_json(value));
+        }
+
+        let frontmatter = serde_yaml::to_string(&mapping)
+            .map_err(|e| PromptError::Frontmatter(e.to_string()))?;
+        Ok(format!("---\n{frontmatter}---\n{}", self.body))
+    }
+}
+
+fn yaml_from_json(value: Value) -> serde_yaml::Value {
+    serde_yaml::to_value(value).expect("JSON values are representable in YAML")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    const SOURCE: &str = r#"---
+name: greet
+version: 1.2.0
+description: Greet someone
+client: anthropic/claude-sonnet-4
+temperature: 0.2
+max_tokens: 256
+system: Be warm.
+inputs:
+  type: object
+  properties:
+    who: { type: string }
+  required: [who]
+output:
+  type: object
+  properties:
+    greeting: { type: string }
+---
+Hello {{ who }}!"#;
+
+    #[test]
+    fn round_trips_through_markdown() {
+        let def = parse(SOURCE).unwrap();
+        let emitted = def.to_markdown().unwrap();
+        let reparsed = parse(&emitted).unwrap();
+        assert_eq!(def, reparsed);
+    }
+
+    #[test]
+    fn keys_follow_canonical_order() {
+        let def = parse(SOURCE).unwrap();
+        let emitted = def.to_markdown().unwrap();
+        let positions: Vec<usize> = ["name:", "version:", "client:", "max_tokens:", "inputs:"]
+            .iter()
+            .map(|k| emitted.find(k).unwrap_or_else(|| panic!("missing {k}")))
+            .collect();
+        let mut sorted = positions.clone();
+        sorted.sort_unstable();
+        assert_eq!(positions, sorted, "{emitted}");
+    }
+
+    #[test]
+    fn body_is_verbatim() {
+        let def = parse(SOURCE).unwrap();
+        let emitted = def.to_markdown().unwrap();
+        assert!(emitted.ends_with("---\nHello {{ who }}!"), "{emitted}");
+    }
+}